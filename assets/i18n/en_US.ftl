@@ -1,6 +1,7 @@
 title = En
 readme = A terminal and gui e-book reader for linux and windows，support epub, haodoo.
 copy-content = Copy content
+copy-image = Copy image
 lookup-dictionary = Lookup dictionary
 add-bookmark = Add bookmark
 settings-render-label = Render mode
@@ -16,8 +17,36 @@ file-open-title = Open e-book
 folder-open = Open book folder
 folder-open-title = Open e-book folder
 history = Recently reading
+open-containing-folder = Open containing folder
+opds-catalog = OPDS catalog
+opds-back = Back
+opds-more = More...
+opds-loading = Loading...
+loading-book = Loading { $filename } ...
+opds-downloading = Downloading...
+opds-not-configured = No OPDS catalog url configured
+opds-no-cache-dir = Can not find cache dir
+sync-position-title = Reading position synced
+sync-position-detail = A newer reading position saved by other device found, jump to it?
+continue-reading-title = Continue reading the latest book?
+external-link-title = Open external link?
+external-link-open = Open
+external-link-always = Always open
 reload = Reload content
 book-info = Book information
+book-info-language = Language: { $lang }
+reading-time-chapter = Chapter reading time: about { $minutes } min
+reading-time-book = Book reading time: about { $minutes } min
+print-page = Print page: { $page }
+location-copied = Location copied: { $location }
+file-size = Size: { $size } { $unit }
+file-modified = Modified: { $time }
+file-created = Created: { $time }
+date-time-format = %m/%d/%Y %H:%M
+export-annotations = Export annotations
+export-annotations-title = Export annotations to Markdown
+no-annotation = No annotation to export, select text first
+annotations-exported = Annotations exported to { $path }
 dark-theme = Dark theme
 lang = Language
 search-hint = Search
@@ -25,12 +54,66 @@ with-custom-color = Book custom color
 with-custom-font = Book custom font
 custom-style = Custom styles
 invalid-style = Invalid style: { $error }
-strip-empty-lines = No empty lines
+empty-lines = Blank lines
+empty-lines-keep = Keep all
+empty-lines-collapse = Collapse to one
+empty-lines-strip = Strip all
+image-interpolation = Image quality
+image-interpolation-nearest = Nearest (pixel art)
+image-interpolation-bilinear = Bilinear
+image-interpolation-hyper = Hyper (slow)
 scroll-for-page = Mouse scroll for page
+click-zones = Click left/right side for page, middle for fullscreen
+smooth-theme = Fade colors when switching theme
+drop-cap = Drop cap for large first letter
+hyphenation = Hyphenate long words at line end
+show-page-numbers = Show print page numbers
+strip-invisible-chars = Strip soft hyphens and zero width spaces
+page-footnotes = Show footnotes at bottom of pages
+reflow-text = Join hard wrapped lines of plain text
+book-reflow-text = Join hard wrapped lines of current book
+reflow-text-global = Global setting
+reflow-text-on = Join
+reflow-text-off = Keep line breaks
+chapter-number-by-toc = Chapter number for goto means TOC entry
+running-header = Show section title above the text
+font-family = Font family
+font-family-placeholder = Override fonts of books, e.g. OpenDyslexic
+highlight-words = Highlight words
+highlight-words-placeholder = Words always highlighted, separated by comma
+dyslexia-mode = Dyslexia friendly mode, wider spacing and font
+focus-mode = Focus mode, dim paragraphs except the top one
+invert-images = Invert images in dark theme
+upscale-images = Enlarge small images to fit the view
+always-open-external-link = Always open external links without confirmation
+external-image-viewer = Open clicked images with external viewer
+empty-launch = When started without book
+empty-launch-readme = Show readme
+empty-launch-library = Show recent books
+empty-launch-last-book = Open last book
+empty-launch-blank = Show blank page
+empty-launch-prompt = Open a book from the toolbar, or drop a book file here
+image-zoom-in = Zoom in
+image-zoom-out = Zoom out
+image-original-size = Original size
+image-fit = Fit
+image-open-external = Open externally
 tab-chapter = Chapter
 tab-dictionary = Dictionary
 tab-find = Search in book
+tab-notes = Notes
 default-font-size = Default font size
+leading-chars = Leading spaces of paragraphs
+book-leading-chars = Leading spaces of current book
+book-language = Language of current book
+book-language-detected = Detected: { $lang }
+book-language-unknown = Unknown, e.g. en-US
+book-encoding = Text encoding of current book
+book-encoding-detect = Detect automatically, e.g. GBK
+invalid-encoding = Unknown text encoding
+leading-chars-book-default = Book default
+leading-chars-global = Global setting
+leading-chars-off = None
 color-color = Default color
 color-background = Default background
 color-highlight = Highlight color
@@ -38,18 +121,30 @@ color-highlight-background = Highlight background
 color-link = Link color
 color-matched = Color for matched text
 color-matched-background = Background for matched text
+image-background = Fill background behind images
+color-image-background = Background for images
 invalid-default-font-size = Invalid default font size
 font-files = Font files
 dictionary-file = Dictionary file(ifo)
 cache-dictionary = Cache dictionaries data
 select-by-dictionary = Double click to select by dictionary lookup
 ignore-font-weight = Ignore font weight for rendering
+ignore-book-fonts = Ignore fonts embedded in books
 settings-dialog-title = Settings
 settings-dialog = Show settings dialog
+reload-config = Reload config file
+config-reloaded = Config file reloaded
+failed-reload-config = Failed reload config file: { $error }
+about = About
+about-license = License: { $license }
+about-homepage = Homepage: { $url }
+about-features = Features: { $features }
+about-copy = Copy
 ok-title = Ok
 cancel-title = Cancel
 add-title = Add
 remove-title = Remove
+move-up-title = Move up
 invalid-path = Invalid { $title }: { $path }
 filter-chapter = Filter chapters
 
@@ -61,10 +156,21 @@ found-chapter-title = Chapter {$index}
 
 dictionary-no-definition = No definition in dictionary
 failed-load-reading = Failed load reading book info：{ $error }
+drm-protected = This book is protected by DRM and can not be opened
 
 custom-style-dialog-title = Set custom styles
+style-library = Style library
+style-name-placeholder = Style name
+style-save = Save to library
+style-name-empty = Style name is empty
+style-delete = Delete from library
+style-set-default = Set as default for new books
 goto-dialog-title = Goto line
 goto-placeholder = Line number
+goto-page-dialog-title = Goto print page
+goto-page-placeholder = Page number
+goto-chapter-dialog-title = Goto chapter
+goto-chapter-placeholder = Chapter number
 invalid-input-title = Invalid input
 invalid-format = Invalid format
 need-restart = (Need restart)
@@ -1,6 +1,7 @@
 title = 漢
 readme = 可以在linux以及windows上使用的桌面阅读器，支持epub，haodoo。
 copy-content = 复制内容
+copy-image = 复制图片
 lookup-dictionary = 查阅字典
 add-bookmark = 增加书签
 settings-render-label = 渲染方式
@@ -16,8 +17,36 @@ file-open-title = 选择书籍
 folder-open = 选择书籍目录
 folder-open-title = 选择书籍目录
 history = 最近阅读
+open-containing-folder = 打开所在文件夹
+opds-catalog = OPDS书库
+opds-back = 返回
+opds-more = 更多...
+opds-loading = 加载中...
+loading-book = 正在加载 { $filename } ...
+opds-downloading = 下载中...
+opds-not-configured = 未设置OPDS书库地址
+opds-no-cache-dir = 找不到缓存目录
+sync-position-title = 阅读位置同步
+sync-position-detail = 发现其他设备保存的更新的阅读位置，是否跳转？
+continue-reading-title = 继续阅读上次的书籍？
+external-link-title = 打开外部链接？
+external-link-open = 打开
+external-link-always = 总是打开
 reload = 重新加载
 book-info = 书籍信息
+book-info-language = 语言：{ $lang }
+reading-time-chapter = 本章阅读时间：约{ $minutes }分钟
+reading-time-book = 全书阅读时间：约{ $minutes }分钟
+print-page = 纸书页码：{ $page }
+location-copied = 已复制位置：{ $location }
+file-size = 大小：{ $size } { $unit }
+file-modified = 修改时间：{ $time }
+file-created = 创建时间：{ $time }
+date-time-format = %Y年%m月%d日 %H:%M
+export-annotations = 导出标注
+export-annotations-title = 导出标注为Markdown
+no-annotation = 无可导出的标注，请先选择文本
+annotations-exported = 标注已导出到{ $path }
 dark-theme = 夜光模式
 lang = 语言
 search-hint = 查找
@@ -25,12 +54,66 @@ with-custom-color = 使用书籍设定颜色
 with-custom-font = 使用书籍设定字体
 custom-style = 自定义样式
 invalid-style = 样式错误：{ $error }
-strip-empty-lines = 隐藏空行
+empty-lines = 空行
+empty-lines-keep = 全部保留
+empty-lines-collapse = 连续空行只保留一行
+empty-lines-strip = 全部隐藏
+image-interpolation = 图片质量
+image-interpolation-nearest = 最近邻（像素画）
+image-interpolation-bilinear = 双线性
+image-interpolation-hyper = 最高质量（较慢）
 scroll-for-page = 鼠标滚动页
+click-zones = 点击左右两侧翻页，中间切换全屏
+smooth-theme = 切换主题时渐变颜色
+drop-cap = 首字下沉
+hyphenation = 行尾长单词断字
+show-page-numbers = 显示纸书页码
+strip-invisible-chars = 去除软连字符和零宽空格
+page-footnotes = 在页面底部显示脚注
+reflow-text = 合并纯文本中的硬换行
+book-reflow-text = 合并当前书籍中的硬换行
+reflow-text-global = 全局设置
+reflow-text-on = 合并
+reflow-text-off = 保留换行
+chapter-number-by-toc = 跳转章节序号按目录计算
+running-header = 在正文上方显示章节标题
+font-family = 字体
+font-family-placeholder = 替代书籍字体，如 OpenDyslexic
+highlight-words = 高亮词语
+highlight-words-placeholder = 始终高亮的词语，以逗号分隔
+dyslexia-mode = 阅读障碍友好模式，加宽间距并替换字体
+focus-mode = 专注模式，淡化页首段落以外的内容
+invert-images = 深色主题下反色显示图片
+upscale-images = 放大较小的图片以适应视图
+always-open-external-link = 打开外部链接时不再确认
+external-image-viewer = 点击图片时使用外部程序查看
+empty-launch = 未指定书籍启动时
+empty-launch-readme = 显示说明
+empty-launch-library = 显示最近阅读书架
+empty-launch-last-book = 打开最后阅读的书籍
+empty-launch-blank = 显示空白页
+empty-launch-prompt = 请从工具栏打开书籍，或将书籍文件拖放到这里
+image-zoom-in = 放大
+image-zoom-out = 缩小
+image-original-size = 原始大小
+image-fit = 适应窗口
+image-open-external = 外部打开
 tab-chapter = 章节
 tab-dictionary = 字典
 tab-find = 全文查找
+tab-notes = 笔记
 default-font-size = 缺省字体大小
+leading-chars = 段首缩进字数
+book-leading-chars = 当前书籍段首缩进字数
+book-language = 当前书籍语言
+book-language-detected = 检测到：{ $lang }
+book-language-unknown = 未知，如 zh-CN
+book-encoding = 当前书籍文本编码
+book-encoding-detect = 自动检测，如 GBK
+invalid-encoding = 未知的文本编码
+leading-chars-book-default = 书籍缺省
+leading-chars-global = 全局设置
+leading-chars-off = 无
 color-color = 缺省颜色
 color-background = 缺省背景色
 color-highlight = 高亮颜色
@@ -38,18 +121,30 @@ color-highlight-background = 高亮背景色
 color-link = 链接颜色
 color-matched = 快速检索匹配颜色
 color-matched-background = 快速检索匹配背景
+image-background = 图片下方填充背景
+color-image-background = 图片背景
 invalid-default-font-size = 缺省字体大小设置错误
 font-files = 字体文件
 dictionary-file = 字典文件(ifo)
 cache-dictionary = 缓存字典数据
 select-by-dictionary = 基于字典双击选词
 ignore-font-weight = 渲染时忽略字体粗体设置
+ignore-book-fonts = 忽略书籍内嵌字体
 settings-dialog-title = 设置
 settings-dialog = 参数设置
+reload-config = 重新加载配置文件
+config-reloaded = 配置文件已重新加载
+failed-reload-config = 重新加载配置文件失败：{ $error }
+about = 关于
+about-license = 许可证：{ $license }
+about-homepage = 主页：{ $url }
+about-features = 功能：{ $features }
+about-copy = 复制
 ok-title = 确定
 cancel-title = 取消
 add-title = 新增
 remove-title = 删除
+move-up-title = 上移
 invalid-path = 错误的{ $title }: { $path }
 filter-chapter = 过滤章节标题
 
@@ -61,10 +156,21 @@ found-chapter-title = 第{$index}章
 
 dictionary-no-definition = 无字典解释
 failed-load-reading = 加载文件信息失败：{ $error }
+drm-protected = 此书受 DRM 保护，无法打开
 
 custom-style-dialog-title = 设定自定义样式
+style-library = 样式库
+style-name-placeholder = 样式名称
+style-save = 保存到样式库
+style-name-empty = 样式名称为空
+style-delete = 从样式库删除
+style-set-default = 设为新书默认样式
 goto-dialog-title = 跳转到
 goto-placeholder = 行号
+goto-page-dialog-title = 跳转到纸书页码
+goto-page-placeholder = 页码
+goto-chapter-dialog-title = 跳转到章节
+goto-chapter-placeholder = 章节序号
 invalid-input-title = 输入错误
 invalid-format = 格式错误
 need-restart = (需重启)
@@ -4,7 +4,7 @@ use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Write};
 use std::fs::OpenOptions;
 use std::io::Read;
-use std::ops::Range;
+use std::ops::{AddAssign, Range};
 use std::slice::Iter;
 
 use anyhow::{anyhow, Result};
@@ -18,16 +18,16 @@ use crate::book::txt::TxtLoader;
 #[cfg(feature = "gui")]
 use crate::color::{Color32, Colors};
 use crate::common::TraceInfo;
-use crate::common::{byte_index_for_char, char_index_for_byte, Position};
-use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::common::{byte_index_for_char, char_index_for_byte, is_cjk, Position};
+use crate::config::{BookLoadingInfo, ReadingInfo, ReadingSpeed};
 use crate::container::BookContent;
 use crate::container::BookContent::{Buf, File, Path};
 use crate::controller::{HighlightInfo, HighlightMode};
 #[cfg(feature = "gui")]
 use crate::gui::HtmlFonts;
 #[cfg(feature = "gui")]
-use crate::html_parser::{BlockStyle, BorderLines, FontScale, FontWeight, TextDecoration};
-use crate::html_parser::{ImageStyle, TextStyle};
+use crate::html_parser::{BlockStyle, BorderLines, FontScale, FontWeight, TextDecoration, TextTransform, VerticalAlign};
+use crate::html_parser::{ContentOptions, ImageStyle, TextStyle};
 use crate::terminal::Listable;
 
 mod epub;
@@ -35,8 +35,13 @@ mod txt;
 mod html;
 mod haodoo;
 
+#[cfg(feature = "gui")]
+pub(crate) use epub::extracted_epub_container;
+
 pub const EMPTY_CHAPTER_CONTENT: &str = "No content.";
 pub const IMAGE_CHAR: char = '🖼';
+/// invisible line break opportunity, like <wbr>
+pub const ZERO_WIDTH_SPACE: char = '\u{200B}';
 
 /// this array is sorted, modify carefully
 pub const TEXT_SELECTION_SPLITTER: [char; 92] = [
@@ -179,11 +184,30 @@ pub struct CharStyle<'a> {
 	pub link: Option<(usize, &'a Range<usize>)>,
 	pub image: Option<&'a ImageStyle>,
 	pub title: Option<&'a String>,
+	pub text_transform: Option<TextTransform>,
+	pub nowrap: bool,
+	pub vertical_align: Option<VerticalAlign>,
 }
 
 pub struct Line {
 	chars: Vec<char>,
 	styles: Vec<(TextStyle, Range<usize>)>,
+	// indent level for blocks, like blockquote
+	indent: u8,
+	// line-height as multiple of font size
+	line_height: Option<f32>,
+	// first line indent in em, None for default leading space
+	text_indent: Option<f32>,
+	// space before the block in em, only for the first block
+	margin_top: f32,
+	// space after the block in em
+	margin_bottom: f32,
+	// start a new page with this line in paged mode
+	page_break: bool,
+	// serial of the footnote containing this line, for page footnotes
+	footnote: Option<usize>,
+	// (char offset, first line of the footnote) of footnote references
+	footnote_refs: Vec<(usize, usize)>,
 }
 
 pub enum SearchError {
@@ -222,7 +246,7 @@ impl Line {
 	#[inline]
 	fn with_chars(chars: Vec<char>) -> Self
 	{
-		Line { chars, styles: vec![] }
+		Line { chars, styles: vec![], indent: 0, line_height: None, text_indent: None, margin_top: 0.0, margin_bottom: 0.0, page_break: false, footnote: None, footnote_refs: vec![] }
 	}
 
 	pub fn new(str: &str) -> Self
@@ -284,6 +308,111 @@ impl Line {
 		self.chars.is_empty()
 	}
 
+	#[inline]
+	pub fn indent(&self) -> u8
+	{
+		self.indent
+	}
+
+	#[inline]
+	pub fn set_indent(&mut self, indent: u8)
+	{
+		self.indent = indent;
+	}
+
+	#[inline]
+	#[allow(unused)]
+	pub fn line_height(&self) -> Option<f32>
+	{
+		self.line_height
+	}
+
+	#[inline]
+	pub fn set_line_height(&mut self, line_height: Option<f32>)
+	{
+		self.line_height = line_height;
+	}
+
+	#[inline]
+	#[allow(unused)]
+	pub fn text_indent(&self) -> Option<f32>
+	{
+		self.text_indent
+	}
+
+	#[inline]
+	pub fn set_text_indent(&mut self, text_indent: Option<f32>)
+	{
+		self.text_indent = text_indent;
+	}
+
+	#[inline]
+	pub fn margin_top(&self) -> f32
+	{
+		self.margin_top
+	}
+
+	#[inline]
+	#[allow(unused)]
+	pub fn margin_bottom(&self) -> f32
+	{
+		self.margin_bottom
+	}
+
+	#[inline]
+	pub fn page_break(&self) -> bool
+	{
+		self.page_break
+	}
+
+	#[inline]
+	pub fn set_page_break(&mut self, page_break: bool)
+	{
+		self.page_break = page_break;
+	}
+
+	#[inline]
+	pub fn footnote(&self) -> Option<usize>
+	{
+		self.footnote
+	}
+
+	#[inline]
+	pub fn set_footnote(&mut self, footnote: Option<usize>)
+	{
+		self.footnote = footnote;
+	}
+
+	#[inline]
+	pub fn footnote_refs(&self) -> &Vec<(usize, usize)>
+	{
+		&self.footnote_refs
+	}
+
+	#[inline]
+	pub fn add_footnote_ref(&mut self, offset: usize, footnote_line: usize)
+	{
+		self.footnote_refs.push((offset, footnote_line));
+	}
+
+	/// collapse with existing margin, like css does
+	#[inline]
+	pub fn add_margin_top(&mut self, margin: f32)
+	{
+		if margin > self.margin_top {
+			self.margin_top = margin;
+		}
+	}
+
+	/// collapse with existing margin, like css does
+	#[inline]
+	pub fn add_margin_bottom(&mut self, margin: f32)
+	{
+		if margin > self.margin_bottom {
+			self.margin_bottom = margin;
+		}
+	}
+
 	#[inline]
 	#[allow(unused)]
 	pub fn is_blank(&self) -> bool
@@ -305,6 +434,21 @@ impl Line {
 		}
 	}
 
+	/// char for rendering, with text-transform applied, source chars keep untouched
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn render_char_at(&self, index: usize, transform: &Option<TextTransform>) -> Option<char>
+	{
+		let ch = self.char_at(index)?;
+		match transform {
+			Some(transform) => {
+				let prev = if index == 0 { None } else { self.char_at(index - 1) };
+				Some(transform.apply(ch, prev))
+			}
+			None => Some(ch),
+		}
+	}
+
 	#[inline]
 	pub fn iter(&self) -> Iter<char>
 	{
@@ -343,9 +487,9 @@ impl Line {
 		Ok(())
 	}
 
-	pub fn link_iter<F, T>(&self, forward: bool, f: F) -> Option<T>
+	pub fn link_iter<F, T>(&self, forward: bool, mut f: F) -> Option<T>
 	where
-		F: Fn(Link) -> (bool, Option<T>),
+		F: FnMut(Link) -> (bool, Option<T>),
 	{
 		let range = 0..self.styles.len();
 		let indeies: Vec<usize> = if forward {
@@ -418,7 +562,11 @@ impl Line {
 				TextStyle::Image { .. } |
 				TextStyle::Color(..) |
 				TextStyle::BackgroundColor(..) |
-				TextStyle::Title(..) => {}
+				TextStyle::Title(..) |
+				TextStyle::TextTransform(..) |
+				TextStyle::NoWrap |
+				TextStyle::Mark |
+				TextStyle::VerticalAlign(..) => {}
 			}
 		}
 	}
@@ -437,6 +585,9 @@ impl Line {
 			link: None,
 			image: None,
 			title: None,
+			text_transform: None,
+			nowrap: false,
+			vertical_align: None,
 		};
 		let mut new_color = None;
 		for (index, (style, range)) in self.styles.iter().enumerate().rev() {
@@ -458,6 +609,12 @@ impl Line {
 					TextStyle::Color(color) => if custom_color { new_color = Some(color.clone()) },
 					TextStyle::BackgroundColor(color) => if custom_color { char_style.background = Some(color.clone()) },
 					TextStyle::Title(title) => char_style.title = Some(title),
+					TextStyle::TextTransform(transform) => char_style.text_transform = Some(*transform),
+					TextStyle::NoWrap => char_style.nowrap = true,
+					TextStyle::VerticalAlign(align) => char_style.vertical_align = Some(*align),
+					TextStyle::Mark => if char_style.background.is_none() {
+						char_style.background = Some(colors.highlight_background.clone())
+					},
 					TextStyle::Decoration(_) => {}
 				}
 			}
@@ -475,10 +632,14 @@ impl Line {
 		if TEXT_SELECTION_SPLITTER.binary_search(pointer_char).is_ok() {
 			return Some((offset, offset));
 		}
+		// CJK run not joined with other text
+		let cjk = is_cjk(*pointer_char);
+		let splitter = |ch: &char| TEXT_SELECTION_SPLITTER.binary_search(ch).is_ok()
+			|| (cjk && !is_cjk(*ch));
 
 		let mut from = offset;
 		for idx in (0..offset).rev() {
-			if TEXT_SELECTION_SPLITTER.binary_search(&self.chars[idx]).is_ok() {
+			if splitter(&self.chars[idx]) {
 				break;
 			}
 			from = idx;
@@ -486,7 +647,7 @@ impl Line {
 
 		let mut to = offset;
 		while let Some(ch) = self.chars.get(to + 1) {
-			if TEXT_SELECTION_SPLITTER.binary_search(ch).is_ok() {
+			if splitter(ch) {
 				break;
 			}
 			to += 1;
@@ -494,6 +655,28 @@ impl Line {
 		Some((from, to))
 	}
 
+	/// start offsets of all occurrences of the word, ascii case ignored
+	#[allow(unused)]
+	pub fn find_all(&self, word: &[char]) -> Vec<usize>
+	{
+		let mut found = vec![];
+		if word.is_empty() {
+			return found;
+		}
+		let mut offset = 0;
+		while offset + word.len() <= self.chars.len() {
+			if self.chars[offset..offset + word.len()].iter()
+				.zip(word)
+				.all(|(a, b)| a.eq_ignore_ascii_case(b)) {
+				found.push(offset);
+				offset += word.len();
+			} else {
+				offset += 1;
+			}
+		}
+		found
+	}
+
 	#[allow(unused)]
 	pub fn sub_str(&self, target: &mut String, range: Range<usize>) {
 		target.clear();
@@ -501,6 +684,28 @@ impl Line {
 			target.push(self.chars[idx]);
 		}
 	}
+
+	/// count words for alphabetic text and chars for CJK text
+	pub fn word_count(&self) -> WordCount
+	{
+		let mut count = WordCount::default();
+		let mut in_word = false;
+		for ch in &self.chars {
+			let ch = *ch;
+			if is_cjk(ch) {
+				count.chars += 1;
+				in_word = false;
+			} else if ch.is_alphanumeric() {
+				if !in_word {
+					count.words += 1;
+					in_word = true;
+				}
+			} else if ch != '\'' && ch != '’' && ch != '-' {
+				in_word = false;
+			}
+		}
+		count
+	}
 }
 
 impl Default for Line {
@@ -533,6 +738,101 @@ impl PartialEq for Line {
 	}
 }
 
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct WordCount {
+	pub words: usize,
+	pub chars: usize,
+}
+
+impl WordCount {
+	/// estimated reading time in minutes, rounded up
+	pub fn minutes(&self, speed: &ReadingSpeed) -> usize
+	{
+		let wpm = cmp::max(speed.words_per_minute, 1) as f32;
+		let cpm = cmp::max(speed.chars_per_minute, 1) as f32;
+		let minutes = self.words as f32 / wpm + self.chars as f32 / cpm;
+		minutes.ceil() as usize
+	}
+}
+
+impl AddAssign for WordCount {
+	#[inline]
+	fn add_assign(&mut self, rhs: Self)
+	{
+		self.words += rhs.words;
+		self.chars += rhs.chars;
+	}
+}
+
+pub(crate) fn lines_word_count(lines: &Vec<Line>) -> WordCount
+{
+	let mut count = WordCount::default();
+	for line in lines {
+		count += line.word_count();
+	}
+	count
+}
+
+#[inline]
+pub(crate) fn lines_char_count(lines: &Vec<Line>) -> usize
+{
+	lines.iter().map(|line| line.len()).sum()
+}
+
+/// options of searching text in book
+#[derive(Clone, Debug)]
+#[derive(Clone)]
+pub struct SearchOptions {
+	pub case_sensitive: bool,
+	/// pattern is regular expression, otherwise plain text
+	pub regex: bool,
+	pub whole_word: bool,
+}
+
+impl Default for SearchOptions {
+	#[inline]
+	fn default() -> Self
+	{
+		SearchOptions { case_sensitive: true, regex: true, whole_word: false }
+	}
+}
+
+impl SearchOptions {
+	pub fn build_regex(&self, pattern: &str) -> Result<Regex>
+	{
+		let mut pattern = if self.regex {
+			Cow::Borrowed(pattern)
+		} else {
+			fancy_regex::escape(pattern)
+		};
+		if self.whole_word {
+			pattern = Cow::Owned(format!(r"\b(?:{})\b", pattern));
+		}
+		if !self.case_sensitive {
+			pattern = Cow::Owned(format!("(?i){}", pattern));
+		}
+		Ok(Regex::new(&pattern)?)
+	}
+}
+
+/// all matches in lines, (start, end) with exclusive end offset
+pub fn search_lines(lines: &Vec<Line>, regex: &Regex) -> Vec<(Position, Position)>
+{
+	let mut found = vec![];
+	for (idx, line) in lines.iter().enumerate() {
+		let mut start = 0;
+		while start < line.len() {
+			let Some(range) = line.search_pattern_once(regex, Some(start), None, false) else {
+				break;
+			};
+			found.push((Position::new(idx, range.start), Position::new(idx, range.end)));
+			// skip empty match
+			start = if range.end > range.start { range.end } else { range.end + 1 };
+		}
+	}
+	found
+}
+
 pub enum LoadingChapter {
 	Index(usize),
 	Last,
@@ -596,16 +896,34 @@ pub trait Book {
 	fn toc_iterator(&self) -> Option<Box<dyn Iterator<Item=TocInfo> + '_>> { None }
 	#[inline]
 	fn toc_position(&mut self, _toc_index: usize) -> Option<TraceInfo> { None }
+	/// print page label at the position
+	#[inline]
+	fn page_label(&self, _line: usize, _offset: usize) -> Option<&str> { None }
+	#[inline]
+	fn page_position(&mut self, _label: &str) -> Option<TraceInfo> { None }
 	fn lines(&self) -> &Vec<Line>;
 	#[inline]
 	fn leading_space(&self) -> usize { 2 }
 	#[inline]
 	fn link_position(&mut self, _line: usize, _link_index: usize) -> Option<TraceInfo> { None }
+	/// position of the element with the id, may be prefixed by the file like "text/ch1.xhtml#id"
+	#[inline]
+	fn anchor_position(&mut self, _anchor: &str) -> Option<TraceInfo> { None }
 	// (absolute path, content)
 	#[inline]
 	fn image<'a>(&'a self, _href: &'a str) -> Option<ImageData<'a>> { None }
+	/// cover image of the book
+	#[inline]
+	fn cover(&self) -> Option<ImageData> { None }
 	#[inline]
 	fn font_family_names(&self) -> Option<&IndexSet<String>> { None }
+	/// language code of the book, like "en-US"
+	#[inline]
+	fn language(&self) -> Option<&str> { None }
+	/// text encoding can be set by user, false for books declaring their own
+	#[inline]
+	#[cfg(feature = "gui")]
+	fn encoding_overridable(&self) -> bool { false }
 	#[inline]
 	#[cfg(feature = "gui")]
 	fn color_customizable(&self) -> bool { false }
@@ -621,6 +939,67 @@ pub trait Book {
 	#[inline]
 	#[cfg(feature = "gui")]
 	fn block_styles(&self) -> Option<&Vec<BlockStyle>> { None }
+	/// parse one of the chapters following current one into cache,
+	/// return false if nothing left to prefetch
+	#[inline]
+	fn prefetch_chapter(&mut self) -> bool { false }
+	/// switch options of parsed content,
+	/// return true if content reloaded
+	#[inline]
+	fn set_content_options(&mut self, _options: ContentOptions) -> Result<bool> { Ok(false) }
+	/// word count of current chapter
+	#[inline]
+	fn word_count(&self) -> WordCount
+	{
+		lines_word_count(self.lines())
+	}
+	/// word count of whole book, None if not available
+	#[inline]
+	fn total_word_count(&mut self) -> Option<WordCount>
+	{
+		if self.chapter_count() == 1 {
+			Some(self.word_count())
+		} else {
+			None
+		}
+	}
+	/// char count of each chapter, None if not available
+	#[inline]
+	fn chapter_char_counts(&mut self) -> Option<Vec<usize>>
+	{
+		if self.chapter_count() == 1 {
+			Some(vec![lines_char_count(self.lines())])
+		} else {
+			None
+		}
+	}
+	/// search chapters from the chapter index, return the first chapter
+	/// with matches and the matches in it, the book may switch to that
+	/// chapter, the current chapter is restored if nothing found
+	fn search_chapter(&mut self, from: usize, regex: &Regex)
+		-> Result<Option<(usize, Vec<(Position, Position)>)>>
+	{
+		let current = self.current_chapter();
+		let mut result = Ok(None);
+		for chapter in from..self.chapter_count() {
+			match self.goto_chapter(chapter) {
+				Ok(Some(chapter)) => {
+					let found = search_lines(self.lines(), regex);
+					if !found.is_empty() {
+						return Ok(Some((chapter, found)));
+					}
+				}
+				Ok(None) => break,
+				Err(e) => {
+					result = Err(e);
+					break;
+				}
+			}
+		}
+		// restore even if failed searching
+		self.goto_chapter(current)?;
+		result
+	}
 
 	fn range_highlight(&self, from: Position, to: Position)
 		-> Option<HighlightInfo>
@@ -684,6 +1063,7 @@ pub trait Book {
 
 pub struct BookLoader {
 	loaders: Vec<Box<dyn Loader>>,
+	content_options: ContentOptions,
 }
 
 pub(crate) trait Loader {
@@ -699,17 +1079,17 @@ pub(crate) trait Loader {
 		false
 	}
 	fn load_file(&self, filename: &str, mut file: std::fs::File,
-		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let mut content: Vec<u8> = Vec::new();
 		file.read_to_end(&mut content)?;
-		self.load_buf(filename, content, loading_chapter, loading)
+		self.load_buf(filename, content, loading_chapter, loading, options)
 	}
 
 	fn load_buf(&self, filename: &str, content: Vec<u8>,
-		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>;
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>;
 }
 
 impl BookLoader {
@@ -735,22 +1115,30 @@ impl BookLoader {
 		false
 	}
 
+	/// options for parsing books loaded later
+	#[inline]
+	pub fn set_content_options(&mut self, options: ContentOptions)
+	{
+		self.content_options = options;
+	}
+
 	pub fn load(&self, filename: &str, content: BookContent,
 		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		for loader in self.loaders.iter() {
 			if loader.support(filename) {
+				let options = loading.content_options(&self.content_options);
 				let (book, mut reading) = match content {
 					File(filepath) => {
 						let file = OpenOptions::new().read(true).open(filepath)?;
-						loader.load_file(filename, file, loading_chapter, loading)?
+						loader.load_file(filename, file, loading_chapter, loading, options)?
 					}
 					Path(filepath) => {
 						let file = OpenOptions::new().read(true).open(filepath)?;
-						loader.load_file(filename, file, loading_chapter, loading)?
+						loader.load_file(filename, file, loading_chapter, loading, options)?
 					}
-					Buf(buf) => loader.load_buf(filename, buf, loading_chapter, loading)?,
+					Buf(buf) => loader.load_buf(filename, buf, loading_chapter, loading, options)?,
 				};
 				reading.chapter = book.current_chapter();
 				let lines = book.lines();
@@ -776,7 +1164,7 @@ impl Default for BookLoader {
 		loaders.push(Box::new(EpubLoader::new()));
 		loaders.push(Box::new(HtmlLoader::new()));
 		loaders.push(Box::new(HaodooLoader::new()));
-		BookLoader { loaders }
+		BookLoader { loaders, content_options: Default::default() }
 	}
 }
 
@@ -815,6 +1203,36 @@ impl ChapterError
 	}
 }
 
+/// book content protected by DRM, can not be decrypted
+pub struct DrmError {
+	algorithm: String,
+}
+
+impl Debug for DrmError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		f.write_str(&format!("DRM protected: {}", self.algorithm))
+	}
+}
+
+impl Display for DrmError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+	{
+		f.write_str(&format!("Book is protected by DRM ({}), can not be opened.", self.algorithm))
+	}
+}
+
+impl Error for DrmError {}
+
+impl DrmError
+{
+	#[inline]
+	pub fn anyhow(algorithm: String) -> anyhow::Error
+	{
+		anyhow::Error::new(DrmError { algorithm })
+	}
+}
+
 fn find_pattern(line: &str, chars: usize, regex: &Regex, start_offset: usize, rev: bool) -> Option<Range<usize>>
 {
 	let m = if rev {
@@ -826,3 +1244,91 @@ fn find_pattern(line: &str, chars: usize, regex: &Regex, start_offset: usize, re
 	let match_end = char_index_for_byte(&line, chars, m.end()).unwrap();
 	Some(Range { start: match_start + start_offset, end: match_end + start_offset })
 }
+
+#[cfg(test)]
+mod tests {
+	use anyhow::Result;
+	use crate::book::{Book, Line, SearchOptions};
+
+	struct ChaptersBook {
+		chapters: Vec<Vec<Line>>,
+		current: usize,
+	}
+
+	impl Book for ChaptersBook {
+		fn chapter_count(&self) -> usize
+		{
+			self.chapters.len()
+		}
+
+		fn goto_chapter(&mut self, chapter_index: usize) -> Result<Option<usize>>
+		{
+			if chapter_index >= self.chapters.len() {
+				return Ok(None);
+			}
+			self.current = chapter_index;
+			Ok(Some(chapter_index))
+		}
+
+		fn current_chapter(&self) -> usize
+		{
+			self.current
+		}
+
+		fn lines(&self) -> &Vec<Line>
+		{
+			&self.chapters[self.current]
+		}
+	}
+
+	/// search from the chapter, return the chapter found, and
+	/// (line, start, end) of matches, with chapter of the book after searching
+	fn search(pattern: &str, options: &SearchOptions, from: usize)
+		-> (Option<(usize, Vec<(usize, usize, usize)>)>, usize)
+	{
+		let mut book = ChaptersBook {
+			chapters: vec![
+				vec![Line::new("The cat sat"), Line::new("concatenate")],
+				vec![Line::new("CAT and cat")],
+			],
+			current: 1,
+		};
+		let regex = options.build_regex(pattern).unwrap();
+		let found = book.search_chapter(from, &regex).unwrap()
+			.map(|(chapter, found)| (chapter, found.iter()
+				.map(|(start, end)| (start.line, start.offset, end.offset))
+				.collect()));
+		(found, book.current_chapter())
+	}
+
+	#[test]
+	fn test_search_chapter()
+	{
+		let options = SearchOptions::default();
+		// stop at the first chapter with matches
+		assert_eq!(search("cat", &options, 0), (Some((0, vec![(0, 4, 7), (1, 3, 6)])), 0));
+		assert_eq!(search("cat", &options, 1), (Some((1, vec![(0, 8, 11)])), 1));
+		// current chapter restored if not found
+		assert_eq!(search("dog", &options, 0), (None, 1));
+	}
+
+	#[test]
+	fn test_search_options()
+	{
+		let options = SearchOptions { case_sensitive: false, regex: false, whole_word: true };
+		assert_eq!(search("cat", &options, 0), (Some((0, vec![(0, 4, 7)])), 0));
+		assert_eq!(search("cat", &options, 1), (Some((1, vec![(0, 0, 3), (0, 8, 11)])), 1));
+		assert_eq!(search("c.t", &options, 0), (None, 1));
+	}
+
+	#[test]
+	fn test_word_count()
+	{
+		let count = Line::new("It's a well-known fact, isn't it?").word_count();
+		assert_eq!(count.words, 6);
+		assert_eq!(count.chars, 0);
+		let count = Line::new("你好，world 世界").word_count();
+		assert_eq!(count.words, 1);
+		assert_eq!(count.chars, 4);
+	}
+}
@@ -1,25 +1,28 @@
 use std::borrow::Cow;
-use std::cell::RefCell;
-use std::collections::hash_map::Entry;
+use std::cell::{OnceCell, RefCell};
+use std::cmp;
 use std::collections::HashMap;
 use std::fs;
 use std::io::Cursor;
 use std::io::Read;
 use std::io::Seek;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use anyhow::{anyhow, bail, Result};
 use elsa::FrozenMap;
+use fancy_regex::Regex;
 use indexmap::IndexSet;
 use roxmltree::{Children, ExpandedName, Node};
+#[cfg(feature = "gui")]
+use sha1::{Digest, Sha1};
 use zip::ZipArchive;
 
-use crate::book::{Book, LoadingChapter, ChapterError, Line, Loader, TocInfo, ImageData};
+use crate::book::{Book, LoadingChapter, ChapterError, DrmError, Line, Loader, TocInfo, ImageData, WordCount, lines_char_count, lines_word_count, search_lines};
 #[cfg(feature = "gui")]
 use crate::html_parser::BlockStyle;
-use crate::html_parser::{HtmlContent, HtmlParseOptions, HtmlResolver, parse_xml};
+use crate::html_parser::{ContentOptions, HtmlContent, HtmlFontFaceDesc, HtmlParseOptions, HtmlResolver, parse_xml};
 use crate::list::ListIterator;
-use crate::common::TraceInfo;
+use crate::common::{percent_decode, Position, TraceInfo};
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::{frozen_map_get, html_parser};
 #[cfg(feature = "gui")]
@@ -43,6 +46,12 @@ struct ContentOPF {
 	pub title: String,
 	pub author: Option<String>,
 	pub language: String,
+	/// identifier referenced by package unique-identifier, key of idpf font obfuscation
+	pub unique_identifier: Option<String>,
+	/// urn:uuid identifier, key of adobe font obfuscation
+	pub uuid_identifier: Option<String>,
+	/// full path of cover image
+	pub cover: Option<String>,
 	pub manifest: Manifest,
 	pub spine: Spine,
 	pub toc_id: Option<String>,
@@ -62,6 +71,23 @@ struct NavPoint {
 
 type Chapter = HtmlContent;
 
+// chapters following current one parsed when idle
+const PREFETCH_CHAPTERS: usize = 3;
+const CHAPTER_CACHE_LIMIT: usize = 16;
+const CONTAINER_XML: &str = "META-INF/container.xml";
+const ENCRYPTION_XML: &str = "META-INF/encryption.xml";
+const IDPF_FONT_OBFUSCATION: &str = "http://www.idpf.org/2008/embedding";
+const ADOBE_FONT_OBFUSCATION: &str = "http://ns.adobe.com/pdf/enc#RC";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FontObfuscation {
+	Idpf,
+	Adobe,
+}
+
+/// obfuscated font path in archive -> obfuscation algorithm
+type Obfuscations = HashMap<String, FontObfuscation>;
+
 trait EpubArchive {
 	fn is_encrypted(&self) -> bool;
 	fn content(&self, path: &str) -> Result<Vec<u8>>;
@@ -71,10 +97,15 @@ trait EpubArchive {
 		Ok(String::from_utf8(buf)?)
 	}
 	fn exists(&self, path: &str) -> bool;
+	/// all entry paths in archive
+	fn entry_names(&self) -> Vec<String>;
+	/// entry paths keyed by the lowercase path, built once for the archive
+	fn entry_index(&self) -> &HashMap<String, String>;
 }
 
 struct EpubZipArchive<R: Read + Seek> {
 	zip: RefCell<ZipArchive<R>>,
+	entry_index: OnceCell<HashMap<String, String>>,
 }
 
 impl<R: Read + Seek> EpubZipArchive<R> {
@@ -82,7 +113,7 @@ impl<R: Read + Seek> EpubZipArchive<R> {
 	fn new(reader: R) -> Result<Self>
 	{
 		let zip = ZipArchive::new(reader)?;
-		Ok(EpubZipArchive { zip: RefCell::new(zip) })
+		Ok(EpubZipArchive { zip: RefCell::new(zip), entry_index: OnceCell::new() })
 	}
 }
 
@@ -90,7 +121,7 @@ impl<R: Read + Seek> EpubArchive for EpubZipArchive<R> {
 	#[inline]
 	fn is_encrypted(&self) -> bool
 	{
-		self.zip.borrow().file_names().find(|f| *f == "META-INF/encryption.xml").is_some()
+		self.zip.borrow().file_names().find(|f| *f == ENCRYPTION_XML).is_some()
 	}
 
 	fn content(&self, path: &str) -> Result<Vec<u8>>
@@ -109,10 +140,34 @@ impl<R: Read + Seek> EpubArchive for EpubZipArchive<R> {
 	{
 		self.zip.borrow().index_for_name(path).is_some()
 	}
+
+	fn entry_names(&self) -> Vec<String>
+	{
+		self.zip.borrow().file_names().map(|name| name.to_owned()).collect()
+	}
+
+	#[inline]
+	fn entry_index(&self) -> &HashMap<String, String>
+	{
+		self.entry_index.get_or_init(|| lowercase_index(self.entry_names()))
+	}
+}
+
+/// container.xml of an extracted epub in the folder, loaded as an epub
+#[cfg(feature = "gui")]
+pub(crate) fn extracted_epub_container(folder: &Path) -> Option<PathBuf>
+{
+	let container = folder.join(CONTAINER_XML);
+	if container.is_file() {
+		Some(container)
+	} else {
+		None
+	}
 }
 
 struct EpubExtractedArchive {
 	root: PathBuf,
+	entry_index: OnceCell<HashMap<String, String>>,
 }
 
 impl EpubExtractedArchive {
@@ -130,7 +185,7 @@ impl EpubExtractedArchive {
 		if !root.exists() {
 			bail!("Extracted epub root not exists");
 		}
-		Ok(EpubExtractedArchive { root })
+		Ok(EpubExtractedArchive { root, entry_index: OnceCell::new() })
 	}
 
 	#[inline]
@@ -149,7 +204,7 @@ impl EpubArchive for EpubExtractedArchive {
 	#[inline]
 	fn is_encrypted(&self) -> bool
 	{
-		self.exists("META-INF/encryption.xml")
+		self.exists(ENCRYPTION_XML)
 	}
 
 	#[inline]
@@ -169,20 +224,59 @@ impl EpubArchive for EpubExtractedArchive {
 	{
 		self.target(path).exists()
 	}
+
+	fn entry_names(&self) -> Vec<String>
+	{
+		fn walk(dir: &PathBuf, prefix: &str, names: &mut Vec<String>)
+		{
+			let Ok(entries) = fs::read_dir(dir) else {
+				return;
+			};
+			for entry in entries.flatten() {
+				let name = entry.file_name().to_string_lossy().into_owned();
+				let name = format!("{}{}", prefix, name);
+				let path = entry.path();
+				if path.is_dir() {
+					walk(&path, &format!("{}/", name), names);
+				} else {
+					names.push(name);
+				}
+			}
+		}
+		let mut names = vec![];
+		walk(&self.root, "", &mut names);
+		names
+	}
+
+	#[inline]
+	fn entry_index(&self) -> &HashMap<String, String>
+	{
+		self.entry_index.get_or_init(|| lowercase_index(self.entry_names()))
+	}
 }
 
 struct EpubBook {
 	archive: Box<dyn EpubArchive>,
 	content_opf: ContentOPF,
 	toc: Vec<NavPoint>,
+	// print page numbers from page-list nav
+	page_list: Vec<NavPoint>,
 	chapter_cache: HashMap<usize, Chapter>,
+	// font faces of chapters prefetched, loaded when switched to
+	#[cfg(feature = "gui")]
+	prefetched_fonts: HashMap<usize, Vec<HtmlFontFaceDesc>>,
 	css_cache: FrozenMap<String, String>,
 	images: FrozenMap<String, Vec<u8>>,
 	font_families: IndexSet<String>,
 	chapter_index: usize,
 	#[cfg(feature = "gui")]
 	fonts: HtmlFonts,
+	#[cfg(feature = "gui")]
+	obfuscations: Obfuscations,
 	custom_style: Option<String>,
+	// word count and char count of each chapter, for all chapters parsed once
+	chapter_stats: Option<Vec<(WordCount, usize)>>,
+	content_options: ContentOptions,
 }
 
 pub struct EpubLoader {
@@ -209,7 +303,7 @@ impl Loader for EpubLoader {
 		if filename.to_lowercase().ends_with(".epub") {
 			return true;
 		}
-		if filename.ends_with("META-INF/container.xml") {
+		if Path::new(filename).ends_with(CONTAINER_XML) {
 			return true;
 		}
 		false
@@ -217,8 +311,8 @@ impl Loader for EpubLoader {
 
 	#[inline]
 	fn load_file(&self, filename: &str, file: std::fs::File,
-		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let archive: Box<dyn EpubArchive> = if filename.to_lowercase().ends_with(".epub") {
 			Box::new(EpubZipArchive::new(file)?)
@@ -226,20 +320,20 @@ impl Loader for EpubLoader {
 			Box::new(EpubExtractedArchive::new(filename)?)
 		};
 		let reading = get_reading(loading);
-		let book = EpubBook::new(archive, loading_chapter, &reading.custom_style)?;
+		let book = EpubBook::new(archive, loading_chapter, &reading.custom_style, options)?;
 		Ok((Box::new(book), reading))
 	}
 
 	fn load_buf(&self, filename: &str, content: Vec<u8>,
-		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		if !filename.to_lowercase().ends_with(".epub") {
 			bail!("Not support extracted epub in other container.")
 		}
 		let archive = EpubZipArchive::new(Cursor::new(content))?;
 		let reading = get_reading(loading);
-		let book = EpubBook::new(Box::new(archive), loading_chapter, &reading.custom_style)?;
+		let book = EpubBook::new(Box::new(archive), loading_chapter, &reading.custom_style, options)?;
 		Ok((Box::new(book), reading))
 	}
 }
@@ -300,6 +394,20 @@ impl Book for EpubBook {
 		self.chapter_index
 	}
 
+	/// chapters scanned without switching to them
+	fn search_chapter(&mut self, from: usize, regex: &Regex)
+		-> Result<Option<(usize, Vec<(Position, Position)>)>>
+	{
+		for chapter_index in from..self.chapter_count() {
+			let found = self.chapter_stat(chapter_index,
+				|lines| search_lines(lines, regex))?;
+			if !found.is_empty() {
+				return Ok(Some((chapter_index, found)));
+			}
+		}
+		Ok(None)
+	}
+
 	fn title(&self, line: usize, offset: usize) -> Option<&str> {
 		let toc_index = self.toc_index(line, offset);
 		let toc = self.toc.get(toc_index)?;
@@ -308,41 +416,7 @@ impl Book for EpubBook {
 
 	fn toc_index(&self, line: usize, offset: usize) -> usize
 	{
-		self.chapter_cache
-			.get(&self.chapter_index)
-			.map_or(0, |c| {
-				let toc = &self.toc;
-				let len = toc.len();
-				if len == 0 {
-					return 0;
-				}
-				let mut file_matched = None;
-				let spine = &self.content_opf.spine[self.chapter_index];
-				let manifest = &self.content_opf.manifest[spine];
-				let chapter_href = &manifest.href;
-				for toc_index in 0..len {
-					let np = &toc[toc_index];
-					match &np.src_file {
-						Some(src_file) if chapter_href == src_file => {
-							if let Some(anchor) = &np.src_anchor {
-								if let Some(position) = c.id_position(anchor) {
-									if position.line > line || (position.line == line && position.offset > offset) {
-										break;
-									}
-								}
-							}
-							file_matched = Some(toc_index);
-						}
-						_ => if np.first_chapter_index <= self.chapter_index {
-							file_matched = Some(toc_index);
-						}
-					}
-				}
-				if let Some(the_last_index_found) = file_matched {
-					return the_last_index_found;
-				}
-				0
-			})
+		self.nav_index(&self.toc, line, offset).unwrap_or(0)
 	}
 
 	fn toc_iterator(&self) -> Option<Box<dyn Iterator<Item=TocInfo> + '_>>
@@ -362,6 +436,21 @@ impl Book for EpubBook {
 		self.target_position(Some(&src_file), src_anchor)
 	}
 
+	fn page_label(&self, line: usize, offset: usize) -> Option<&str>
+	{
+		let index = self.nav_index(&self.page_list, line, offset)?;
+		self.page_list[index].label.as_deref()
+	}
+
+	fn page_position(&mut self, label: &str) -> Option<TraceInfo>
+	{
+		let np = self.page_list.iter()
+			.find(|np| np.label.as_deref() == Some(label))?;
+		let src_file = np.src_file.as_ref()?.to_string();
+		let src_anchor = np.src_anchor.clone();
+		self.target_position(Some(&src_file), src_anchor)
+	}
+
 	#[inline]
 	fn lines(&self) -> &Vec<Line>
 	{
@@ -384,17 +473,45 @@ impl Book for EpubBook {
 			self.target_position(None, target_anchor)
 		} else {
 			let path = concat_path_str(cwd, target_file)?;
+			let path = locate_entry(self.archive.as_ref(), &path).unwrap_or(path);
 			self.target_position(Some(&path), target_anchor)
 		}
 	}
 
+	fn anchor_position(&mut self, anchor: &str) -> Option<TraceInfo>
+	{
+		if let Some((file, id)) = anchor.split_once('#') {
+			let path = locate_entry(self.archive.as_ref(), file)
+				.unwrap_or_else(|| file.to_owned());
+			return self.target_position(Some(&path), Some(id.to_owned()));
+		}
+		// current chapter first, then the whole book
+		if let Some(trace) = self.target_position(None, Some(anchor.to_owned())) {
+			return Some(trace);
+		}
+		for chapter_index in 0..self.chapter_count() {
+			let Ok(chapter) = self.load_chapter(chapter_index) else {
+				continue;
+			};
+			if let Some(position) = chapter.id_position(anchor) {
+				return Some(TraceInfo {
+					chapter: chapter_index,
+					line: position.line,
+					offset: position.offset,
+				});
+			}
+		}
+		None
+	}
+
 	fn image<'h>(&'h self, href: &'h str) -> Option<ImageData<'h>>
 	{
 		if let Ok(path) = chapter_path(self.current_chapter(), &self.content_opf) {
 			let cwd = path_cwd(path);
 			let full_path = concat_path_str(cwd, href)?;
 			let bytes = frozen_map_get!(self.images, full_path, true, ||{
-				self.archive.content(&full_path).ok()
+				locate_entry(self.archive.as_ref(), &full_path)
+					.and_then(|path| self.archive.content(&path).ok())
 			})?;
 			Some(ImageData::Borrowed((Cow::Owned(full_path), bytes)))
 		} else {
@@ -402,12 +519,33 @@ impl Book for EpubBook {
 		}
 	}
 
+	fn cover(&self) -> Option<ImageData>
+	{
+		let href = self.content_opf.cover.as_ref()?;
+		let path = href.clone();
+		let bytes = frozen_map_get!(self.images, path, true, ||{
+			self.archive.content(href).ok()
+		})?;
+		Some(ImageData::Borrowed((Cow::Borrowed(href), bytes)))
+	}
+
 	#[inline]
 	fn font_family_names(&self) -> Option<&IndexSet<String>>
 	{
 		Some(&self.font_families)
 	}
 
+	#[inline]
+	fn language(&self) -> Option<&str>
+	{
+		let language = self.content_opf.language.trim();
+		if language.is_empty() {
+			None
+		} else {
+			Some(language)
+		}
+	}
+
 	#[cfg(feature = "gui")]
 	#[inline]
 	fn color_customizable(&self) -> bool
@@ -447,6 +585,59 @@ impl Book for EpubBook {
 			.get(&self.current_chapter())?
 			.block_styles()
 	}
+
+	fn total_word_count(&mut self) -> Option<WordCount>
+	{
+		let mut count = WordCount::default();
+		for (chapter_count, _) in self.chapter_stats() {
+			count += *chapter_count;
+		}
+		Some(count)
+	}
+
+	fn chapter_char_counts(&mut self) -> Option<Vec<usize>>
+	{
+		let counts = self.chapter_stats()
+			.iter()
+			.map(|(_, chars)| *chars)
+			.collect();
+		Some(counts)
+	}
+
+	fn prefetch_chapter(&mut self) -> bool
+	{
+		let last = cmp::min(self.chapter_index + PREFETCH_CHAPTERS,
+			self.chapter_count().saturating_sub(1));
+		for chapter_index in self.chapter_index + 1..=last {
+			if self.chapter_cache.contains_key(&chapter_index) {
+				continue;
+			}
+			// errors reported when the chapter is loaded for reading
+			#[allow(unused)]
+			let Ok((html_content, font_faces)) = self.parse_chapter(chapter_index) else {
+				return false;
+			};
+			self.cache_chapter(chapter_index, html_content);
+			#[cfg(feature = "gui")]
+			self.prefetched_fonts.insert(chapter_index, font_faces);
+			return true;
+		}
+		false
+	}
+
+	fn set_content_options(&mut self, options: ContentOptions) -> Result<bool>
+	{
+		if self.content_options == options {
+			return Ok(false);
+		}
+		self.content_options = options;
+		self.chapter_cache.clear();
+		#[cfg(feature = "gui")]
+		self.prefetched_fonts.clear();
+		self.chapter_stats = None;
+		self.load_chapter(self.chapter_index)?;
+		Ok(true)
+	}
 }
 
 struct EpubResolver<'a> {
@@ -473,13 +664,13 @@ impl<'a> HtmlResolver for EpubResolver<'a>
 
 	fn css(&self, sub: &str) -> Option<(PathBuf, &str)>
 	{
-		let mut full_path = concat_path(self.cwd.clone(), sub);
-		let path = path_str(&full_path)?;
+		let path = concat_path_str(self.cwd.clone(), sub)?;
+		let path = locate_entry(self.archive, &path)?;
+		let cwd = path_cwd(&path);
 		let content = frozen_map_get!(self.css_cache, path, || {
 			self.archive.string( &path).ok()
 		})?;
-		full_path.pop();
-		Some((full_path, content))
+		Some((cwd, content))
 	}
 
 	fn custom_style(&self) -> Option<&str>
@@ -490,12 +681,14 @@ impl<'a> HtmlResolver for EpubResolver<'a>
 
 impl EpubBook {
 	pub fn new(archive: Box<dyn EpubArchive>, loading_chapter: LoadingChapter,
-		custom_style: &Option<String>) -> Result<Self>
+		custom_style: &Option<String>, content_options: ContentOptions) -> Result<Self>
 	{
-		if archive.is_encrypted() {
-			return Err(anyhow!("Encrypted epub."));
-		}
-		let container_text = archive.string("META-INF/container.xml")?;
+		// font obfuscation only is fine, other encryption means DRM
+		#[cfg(feature = "gui")]
+		let obfuscations = archive_obfuscations(archive.as_ref())?;
+		#[cfg(not(feature = "gui"))]
+		archive_obfuscations(archive.as_ref())?;
+		let container_text = archive.string(CONTAINER_XML)?;
 		let doc = parse_xml(&container_text)?;
 		let root = doc.root_element();
 		let rootfiles = get_child(root, "rootfiles").ok_or(anyhow!("invalid container.xml: no rootfiles"))?;
@@ -515,43 +708,26 @@ impl EpubBook {
 				let cwd = path_cwd(href);
 				parse_ncx(&ncx_text, &cwd)?
 			}
-			None => {
-				let mut toc = None;
-				for (_id, item) in &content_opf.manifest {
-					if let Some(properties) = &item.properties {
-						if properties.contains("nav") {
-							let nav_text = archive.string(&item.href)?;
-							let cwd = path_cwd(&item.href);
-							toc = Some(parse_nav_doc(&nav_text, &cwd)?);
-							break;
-						}
-					}
-				}
-				if let Some(toc) = toc {
-					toc
-				} else {
-					return Err(anyhow!("Invalid content.opf file, no ncx or nav"));
-				}
+			None => if let Some(item) = nav_doc_item(&content_opf.manifest) {
+				let nav_text = archive.string(&item.href)?;
+				let cwd = path_cwd(&item.href);
+				parse_nav_doc(&nav_text, &cwd, "toc")?
+			} else {
+				return Err(anyhow!("Invalid content.opf file, no ncx or nav"));
 			}
 		};
+		// page-list is optional, ignore any error
+		let mut page_list = nav_doc_item(&content_opf.manifest)
+			.and_then(|item| {
+				let nav_text = archive.string(&item.href).ok()?;
+				let cwd = path_cwd(&item.href);
+				parse_nav_doc(&nav_text, &cwd, "page-list").ok()
+			})
+			.unwrap_or_default();
 
 		let chapter_count = content_opf.spine.len();
-
-		let mut chapter_index = 0;
-		for np in &mut toc {
-			if let Some(src_file) = &np.src_file {
-				for i in chapter_index..chapter_count {
-					let spine = &content_opf.spine[i];
-					let manifest = &content_opf.manifest[spine];
-					let chapter_href = &manifest.href;
-					if chapter_href == src_file {
-						np.first_chapter_index = i;
-						chapter_index = i;
-						break;
-					}
-				}
-			}
-		}
+		setup_first_chapter_index(&mut toc, &content_opf, archive.as_ref());
+		setup_first_chapter_index(&mut page_list, &content_opf, archive.as_ref());
 
 		let mut chapter_index = match loading_chapter {
 			LoadingChapter::Index(index) => index,
@@ -565,14 +741,21 @@ impl EpubBook {
 			archive,
 			content_opf,
 			toc,
+			page_list,
 			chapter_cache,
+			#[cfg(feature = "gui")]
+			prefetched_fonts: HashMap::new(),
 			chapter_index,
 			css_cache: Default::default(),
 			images: Default::default(),
 			font_families: Default::default(),
 			#[cfg(feature = "gui")]
 			fonts: HtmlFonts::new(),
+			#[cfg(feature = "gui")]
+			obfuscations,
 			custom_style: custom_style.clone(),
+			chapter_stats: None,
+			content_options,
 		};
 		book.load_chapter(chapter_index)?;
 		Ok(book)
@@ -580,37 +763,132 @@ impl EpubBook {
 
 	fn load_chapter(&mut self, chapter_index: usize) -> Result<&Chapter>
 	{
-		let chapter = match self.chapter_cache.entry(chapter_index) {
-			Entry::Occupied(o) => o.into_mut(),
-			Entry::Vacant(v) => {
-				let full_path = chapter_path(chapter_index, &self.content_opf)?;
-				let cwd = path_cwd(full_path);
-				let mut html_str = self.archive.string(full_path)?;
-				if full_path.to_lowercase().ends_with(".xhtml") {
-					html_str = xhtml_to_html(&html_str)?;
-				}
-				let mut resolve = EpubResolver {
-					cwd,
-					archive: self.archive.as_ref(),
-					css_cache: &self.css_cache,
-					custom_style: self.custom_style.as_ref().map(|s| s.as_ref()),
-				};
-				#[allow(unused)]
-					let (html_content, mut font_faces) = html_parser::parse(HtmlParseOptions::new(&html_str)
-					.with_font_family(&mut self.font_families)
-					.with_resolver(&mut resolve))?;
+		if self.chapter_cache.contains_key(&chapter_index) {
+			// fonts of prefetched chapter not loaded yet
+			#[cfg(feature = "gui")]
+			if let Some(font_faces) = self.prefetched_fonts.remove(&chapter_index) {
+				self.reload_fonts(font_faces);
+			}
+		} else {
+			#[allow(unused)]
+			let (html_content, font_faces) = self.parse_chapter(chapter_index)?;
+			self.cache_chapter(chapter_index, html_content);
+			#[cfg(feature = "gui")]
+			self.reload_fonts(font_faces);
+		}
+		Ok(self.chapter_cache.get(&chapter_index).unwrap())
+	}
+
+	fn parse_chapter(&mut self, chapter_index: usize) -> Result<(Chapter, Vec<HtmlFontFaceDesc>)>
+	{
+		let full_path = chapter_path(chapter_index, &self.content_opf)?;
+		let cwd = path_cwd(full_path);
+		let mut html_str = self.archive.string(full_path)?;
+		if full_path.to_lowercase().ends_with(".xhtml") {
+			html_str = xhtml_to_html(&html_str)?;
+		}
+		let mut resolve = EpubResolver {
+			cwd,
+			archive: self.archive.as_ref(),
+			css_cache: &self.css_cache,
+			custom_style: self.custom_style.as_ref().map(|s| s.as_ref()),
+		};
+		html_parser::parse(HtmlParseOptions::new(&html_str)
+			.with_font_family(&mut self.font_families)
+			.with_resolver(&mut resolve)
+			.with_content_options(self.content_options.clone())
+			.with_language(self.content_options.language.as_deref()
+				.unwrap_or(&self.content_opf.language)))
+	}
+
+	#[cfg(feature = "gui")]
+	fn reload_fonts(&mut self, font_faces: Vec<HtmlFontFaceDesc>)
+	{
+		self.fonts.reload(font_faces, |path| {
+			let path_str = path_str(path)?;
+			let path_str = locate_entry(self.archive.as_ref(), &path_str)?;
+			let mut content = self.archive.content(&path_str).ok()?;
+			if let Some(obfuscation) = self.obfuscations.get(&path_str) {
+				deobfuscate_font(&mut content, *obfuscation, &self.content_opf)?;
+			}
+			Some(content)
+		});
+	}
+
+	/// cache the chapter, and drop the farthest ones from
+	/// current chapter when the cache is full
+	fn cache_chapter(&mut self, chapter_index: usize, chapter: Chapter)
+	{
+		while self.chapter_cache.len() >= CHAPTER_CACHE_LIMIT {
+			let current = self.chapter_index;
+			let farthest = self.chapter_cache.keys()
+				.filter(|index| **index != current)
+				.max_by_key(|index| index.abs_diff(current))
+				.cloned();
+			if let Some(farthest) = farthest {
+				self.chapter_cache.remove(&farthest);
 				#[cfg(feature = "gui")]
-				{
-					self.fonts.reload(font_faces, |path| {
-						let path_str = path_str(path)?;
-						let content = self.archive.content(&path_str).ok()?;
-						Some(content)
-					});
+				self.prefetched_fonts.remove(&farthest);
+			} else {
+				break;
+			}
+		}
+		self.chapter_cache.insert(chapter_index, chapter);
+	}
+
+	/// index of the last nav point before the position
+	fn nav_index(&self, nav_points: &[NavPoint], line: usize, offset: usize) -> Option<usize>
+	{
+		let chapter = self.chapter_cache.get(&self.chapter_index)?;
+		let spine = &self.content_opf.spine[self.chapter_index];
+		let manifest = &self.content_opf.manifest[spine];
+		let chapter_href = &manifest.href;
+		let mut file_matched = None;
+		for (index, np) in nav_points.iter().enumerate() {
+			match &np.src_file {
+				Some(src_file) if chapter_href == src_file => {
+					if let Some(anchor) = &np.src_anchor {
+						if let Some(position) = chapter.id_position(anchor) {
+							if position.line > line || (position.line == line && position.offset > offset) {
+								break;
+							}
+						}
+					}
+					file_matched = Some(index);
+				}
+				_ => if np.first_chapter_index <= self.chapter_index {
+					file_matched = Some(index);
 				}
-				v.insert(html_content)
 			}
-		};
-		Ok(chapter)
+		}
+		file_matched
+	}
+
+	/// stats of all chapters, unparsable chapters counted as empty
+	fn chapter_stats(&mut self) -> &Vec<(WordCount, usize)>
+	{
+		if self.chapter_stats.is_none() {
+			let stats = (0..self.chapter_count())
+				.map(|chapter_index| self.chapter_stat(chapter_index, |lines|
+					(lines_word_count(lines), lines_char_count(lines)))
+					.unwrap_or_default())
+				.collect();
+			self.chapter_stats = Some(stats);
+		}
+		self.chapter_stats.as_ref().unwrap()
+	}
+
+	/// statistic of chapter lines, without caching the chapter,
+	/// so fonts of current chapter are kept
+	fn chapter_stat<T, F>(&mut self, chapter_index: usize, f: F) -> Result<T>
+	where
+		F: Fn(&Vec<Line>) -> T,
+	{
+		if let Some(chapter) = self.chapter_cache.get(&chapter_index) {
+			return Ok(f(chapter.lines()));
+		}
+		let (chapter, _) = self.parse_chapter(chapter_index)?;
+		Ok(f(chapter.lines()))
 	}
 
 	fn target_position(&mut self, target_file: Option<&str>, target_anchor: Option<String>) -> Option<TraceInfo>
@@ -712,16 +990,16 @@ fn parse_ncx(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
 
 /// parse Navigation document
 /// according to https://www.w3.org/publishing/epub3/epub-packages.html#sec-package-nav-def
-fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
+fn parse_nav_doc(text: &str, cwd: &PathBuf, nav_type: &str) -> Result<Vec<NavPoint>>
 {
-	fn search_nav<'a, 'i>(element: Node<'a, 'i>, type_name: ExpandedName) -> Option<Node<'a, 'i>>
+	fn search_nav<'a, 'i>(element: Node<'a, 'i>, type_name: ExpandedName, nav_type: &str) -> Option<Node<'a, 'i>>
 	{
 		for child in element.children() {
 			if child.is_element() {
-				if child.has_tag_name("nav") && child.attribute(type_name).map_or(false, |t| t == "toc") {
+				if child.has_tag_name("nav") && child.attribute(type_name).map_or(false, |t| t == nav_type) {
 					return Some(child);
 				}
-				let option = search_nav(child, type_name);
+				let option = search_nav(child, type_name, nav_type);
 				if option.is_some() {
 					return option;
 				}
@@ -788,7 +1066,8 @@ fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
 	let namespace = root.lookup_namespace_uri(Some("epub"))
 		.ok_or(anyhow!("Navigation document without epub namespace"))?;
 	let epub_type_name = ExpandedName::from((namespace, "type"));
-	let nav = search_nav(body, epub_type_name).ok_or(anyhow!("Navigation document without nav of toc"))?;
+	let nav = search_nav(body, epub_type_name, nav_type)
+		.ok_or(anyhow!("Navigation document without nav of {}", nav_type))?;
 	let mut toc = vec![];
 	for child in nav.children() {
 		if child.has_tag_name("ol") {
@@ -803,7 +1082,40 @@ fn parse_nav_doc(text: &str, cwd: &PathBuf) -> Result<Vec<NavPoint>>
 	}
 }
 
-fn parse_manifest(manifest: Node, path: &PathBuf) -> Manifest
+#[inline]
+fn nav_doc_item(manifest: &Manifest) -> Option<&ManifestItem>
+{
+	manifest.values().find(|item| item.properties
+		.as_ref()
+		.map_or(false, |properties| properties.contains("nav")))
+}
+
+fn setup_first_chapter_index(nav_points: &mut Vec<NavPoint>, content_opf: &ContentOPF,
+	archive: &dyn EpubArchive)
+{
+	let chapter_count = content_opf.spine.len();
+	let mut chapter_index = 0;
+	for np in nav_points {
+		if let Some(src_file) = &mut np.src_file {
+			// match the manifest href, which is the located entry
+			if let Some(entry) = locate_entry(archive, src_file) {
+				*src_file = entry;
+			}
+			for i in chapter_index..chapter_count {
+				let spine = &content_opf.spine[i];
+				let manifest = &content_opf.manifest[spine];
+				let chapter_href = &manifest.href;
+				if chapter_href == src_file {
+					np.first_chapter_index = i;
+					chapter_index = i;
+					break;
+				}
+			}
+		}
+	}
+}
+
+fn parse_manifest(manifest: Node, path: &PathBuf, archive: &dyn EpubArchive) -> Manifest
 {
 	manifest
 		.children()
@@ -812,6 +1124,7 @@ fn parse_manifest(manifest: Node, path: &PathBuf) -> Manifest
 				let id = node.attribute("id")?.to_string();
 				let href = node.attribute("href")?;
 				let href = concat_path_str(path.clone(), href)?;
+				let href = locate_entry(archive, &href).unwrap_or(href);
 				return Some((
 					id.clone(),
 					ManifestItem {
@@ -827,6 +1140,24 @@ fn parse_manifest(manifest: Node, path: &PathBuf) -> Manifest
 		.collect::<HashMap<ItemId, ManifestItem>>()
 }
 
+/// epub3 cover-image property, or epub2 cover meta
+fn cover_item<'a>(metadata: Node, manifest: &'a Manifest) -> Option<&'a ManifestItem>
+{
+	manifest.values()
+		.find(|item| item.properties.as_ref()
+			.map_or(false, |properties| properties
+				.split_ascii_whitespace()
+				.any(|property| property == "cover-image")))
+		.or_else(|| {
+			let id = metadata.children()
+				.find(|node| node.tag_name().name() == "meta"
+					&& node.attribute("name") == Some("cover"))?
+				.attribute("content")?;
+			manifest.get(id)
+		})
+		.filter(|item| item.media_type.starts_with("image/"))
+}
+
 #[inline]
 fn parse_spine(spine: Node, manifest: &Manifest, archive: &dyn EpubArchive) -> (Spine, Option<String>)
 {
@@ -868,18 +1199,108 @@ fn parse_content_opf(text: &str, content_opf_dir: &PathBuf, archive: &dyn EpubAr
 	let language = get_child(metadata, "language")
 		.map_or(String::new(), |e| e.text()
 			.map_or(String::new(), |s| s.to_owned()));
-	let manifest = parse_manifest(manifest, content_opf_dir);
+	let identifiers: Vec<Node> = metadata.children()
+		.filter(|node| node.tag_name().name() == "identifier")
+		.collect();
+	let unique_identifier = package.attribute("unique-identifier")
+		.and_then(|id| identifiers.iter().find(|node| node.attribute("id") == Some(id)))
+		.or_else(|| identifiers.first())
+		.and_then(|node| node.text())
+		.map(|text| text.trim().to_owned());
+	let uuid_identifier = identifiers.iter()
+		.filter_map(|node| node.text())
+		.map(|text| text.trim())
+		.find(|text| text.starts_with("urn:uuid:"))
+		.map(|text| text.to_owned());
+	let manifest = parse_manifest(manifest, content_opf_dir, archive);
+	let cover = cover_item(metadata, &manifest)
+		.map(|item| item.href.clone());
 	let (spine, toc_id) = parse_spine(spine, &manifest, archive);
 	Ok(ContentOPF {
 		title,
 		author,
 		language,
+		unique_identifier,
+		uuid_identifier,
+		cover,
 		manifest,
 		spine,
 		toc_id,
 	})
 }
 
+/// obfuscated fonts of the archive, error for DRM
+fn archive_obfuscations(archive: &dyn EpubArchive) -> Result<Obfuscations>
+{
+	if archive.is_encrypted() {
+		let encryption_text = archive.string(ENCRYPTION_XML)?;
+		parse_encryption(&encryption_text)
+	} else {
+		Ok(Obfuscations::new())
+	}
+}
+
+/// parse META-INF/encryption.xml, any encryption other than
+/// font obfuscation is DRM, which can not be handled
+fn parse_encryption(text: &str) -> Result<Obfuscations>
+{
+	let doc = parse_xml(text)?;
+	let mut obfuscations = Obfuscations::new();
+	for data in doc.root_element().children()
+		.filter(|node| node.tag_name().name() == "EncryptedData") {
+		let algorithm = get_child(data, "EncryptionMethod")
+			.and_then(|method| method.attribute("Algorithm"))
+			.unwrap_or("unknown");
+		let obfuscation = match algorithm {
+			IDPF_FONT_OBFUSCATION => FontObfuscation::Idpf,
+			ADOBE_FONT_OBFUSCATION => FontObfuscation::Adobe,
+			_ => return Err(DrmError::anyhow(algorithm.to_owned())),
+		};
+		if let Some(uri) = get_child(data, "CipherData")
+			.and_then(|cipher| get_child(cipher, "CipherReference"))
+			.and_then(|reference| reference.attribute("URI")) {
+			obfuscations.insert(uri.to_owned(), obfuscation);
+		}
+	}
+	Ok(obfuscations)
+}
+
+/// restore obfuscated font data, None if key not available
+/// idpf: xor first 1040 bytes with sha1 of unique identifier, without white spaces
+/// adobe: xor first 1024 bytes with the 16 bytes of uuid identifier
+#[cfg(feature = "gui")]
+fn deobfuscate_font(data: &mut [u8], obfuscation: FontObfuscation,
+	content_opf: &ContentOPF) -> Option<()>
+{
+	let (key, length) = match obfuscation {
+		FontObfuscation::Idpf => {
+			let identifier: String = content_opf.unique_identifier.as_ref()?
+				.chars()
+				.filter(|ch| !matches!(ch, ' ' | '\t' | '\r' | '\n'))
+				.collect();
+			(Sha1::digest(identifier.as_bytes()).to_vec(), 1040)
+		}
+		FontObfuscation::Adobe => {
+			let uuid: String = content_opf.uuid_identifier.as_ref()?
+				.trim_start_matches("urn:uuid:")
+				.chars()
+				.filter(|ch| *ch != '-')
+				.collect();
+			if uuid.len() != 32 || !uuid.is_ascii() {
+				return None;
+			}
+			let key = (0..16)
+				.map(|idx| u8::from_str_radix(&uuid[idx * 2..idx * 2 + 2], 16).ok())
+				.collect::<Option<Vec<u8>>>()?;
+			(key, 1024)
+		}
+	};
+	for (idx, byte) in data.iter_mut().take(length).enumerate() {
+		*byte ^= key[idx % key.len()];
+	}
+	Some(())
+}
+
 fn toc_title(nav_point: &NavPoint) -> &str {
 	let label = match &nav_point.label {
 		Some(label) => label,
@@ -932,6 +1353,33 @@ fn concat_path_str(path: PathBuf, sub_path: &str) -> Option<String>
 	path_str(&concat_path(path, sub_path))
 }
 
+/// find the archive entry of the path, fallback to percent decoded
+/// or letter case ignored matching for sloppy referenced paths
+fn locate_entry(archive: &dyn EpubArchive, path: &str) -> Option<String>
+{
+	if archive.exists(path) {
+		return Some(path.to_owned());
+	}
+	let decoded = percent_decode(path);
+	if decoded != path && archive.exists(&decoded) {
+		Some(decoded.into_owned())
+	} else {
+		archive.entry_index()
+			.get(&decoded.to_lowercase())
+			.cloned()
+	}
+}
+
+/// index entry names by lowercase, the first one kept for conflicts
+fn lowercase_index(names: Vec<String>) -> HashMap<String, String>
+{
+	let mut index = HashMap::with_capacity(names.len());
+	for name in names {
+		index.entry(name.to_lowercase()).or_insert(name);
+	}
+	index
+}
+
 #[inline]
 fn path_cwd(path: &str) -> PathBuf
 {
@@ -957,3 +1405,105 @@ fn get_reading(loading: BookLoadingInfo) -> ReadingInfo
 		reading.custom_font = true;
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use std::cell::OnceCell;
+	use std::collections::HashMap;
+	use anyhow::{anyhow, Result};
+	use crate::book::epub::{EpubArchive, locate_entry, lowercase_index};
+	#[cfg(feature = "gui")]
+	use crate::book::epub::{ContentOPF, deobfuscate_font, FontObfuscation};
+
+	struct NamesArchive {
+		names: Vec<&'static str>,
+		entry_index: OnceCell<HashMap<String, String>>,
+	}
+
+	impl EpubArchive for NamesArchive {
+		fn is_encrypted(&self) -> bool
+		{
+			false
+		}
+
+		fn content(&self, path: &str) -> Result<Vec<u8>>
+		{
+			if self.exists(path) {
+				Ok(path.as_bytes().to_vec())
+			} else {
+				Err(anyhow!("failed load {}", path))
+			}
+		}
+
+		fn exists(&self, path: &str) -> bool
+		{
+			self.names.contains(&path)
+		}
+
+		fn entry_names(&self) -> Vec<String>
+		{
+			self.names.iter().map(|name| name.to_string()).collect()
+		}
+
+		fn entry_index(&self) -> &HashMap<String, String>
+		{
+			self.entry_index.get_or_init(|| lowercase_index(self.entry_names()))
+		}
+	}
+
+	#[test]
+	fn test_locate_entry()
+	{
+		let archive = NamesArchive {
+			names: vec!["OEBPS/Images/my cover.png", "OEBPS/Styles/main.css", "OEBPS/a%20b.html"],
+			entry_index: OnceCell::new(),
+		};
+		assert_eq!(locate_entry(&archive, "OEBPS/Styles/main.css").as_deref(), Some("OEBPS/Styles/main.css"));
+		assert_eq!(locate_entry(&archive, "OEBPS/Images/my%20cover.png").as_deref(), Some("OEBPS/Images/my cover.png"));
+		assert_eq!(locate_entry(&archive, "OEBPS/styles/Main.CSS").as_deref(), Some("OEBPS/Styles/main.css"));
+		assert_eq!(locate_entry(&archive, "OEBPS/images/My%20Cover.png").as_deref(), Some("OEBPS/Images/my cover.png"));
+		// entry name with literal percent-encoding
+		assert_eq!(locate_entry(&archive, "OEBPS/a%20b.html").as_deref(), Some("OEBPS/a%20b.html"));
+		assert_eq!(locate_entry(&archive, "OEBPS/missing.css"), None);
+	}
+
+	#[cfg(feature = "gui")]
+	#[test]
+	fn test_deobfuscate_font()
+	{
+		let content_opf = ContentOPF {
+			title: "test".to_owned(),
+			author: None,
+			language: String::new(),
+			// white spaces ignored for the key
+			unique_identifier: Some(" urn:uuid:0a1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9\n".to_owned()),
+			uuid_identifier: Some("urn:uuid:0a1b2c3d-4e5f-6071-8293-a4b5c6d7e8f9".to_owned()),
+			cover: None,
+			manifest: HashMap::new(),
+			spine: vec![],
+			toc_id: None,
+		};
+
+		// sha1 of the identifier
+		let key = [0x73, 0x2a, 0x5d, 0xe8, 0xf1, 0x72, 0xb1, 0x92, 0x94, 0x7f,
+			0x39, 0x29, 0x29, 0x16, 0x4c, 0xe8, 0xb7, 0x58, 0x24, 0xf0];
+		let mut data = vec![0u8; 1100];
+		deobfuscate_font(&mut data, FontObfuscation::Idpf, &content_opf).unwrap();
+		assert_eq!(&data[..20], &key);
+		assert_eq!(&data[1020..1040], &key);
+		assert!(data[1040..].iter().all(|byte| *byte == 0));
+
+		// bytes of the uuid
+		let key = [0x0a, 0x1b, 0x2c, 0x3d, 0x4e, 0x5f, 0x60, 0x71,
+			0x82, 0x93, 0xa4, 0xb5, 0xc6, 0xd7, 0xe8, 0xf9];
+		let mut data = vec![0u8; 1100];
+		deobfuscate_font(&mut data, FontObfuscation::Adobe, &content_opf).unwrap();
+		assert_eq!(&data[..16], &key);
+		assert_eq!(&data[1008..1024], &key);
+		assert!(data[1024..].iter().all(|byte| *byte == 0));
+
+		// no uuid identifier, no key
+		let content_opf = ContentOPF { uuid_identifier: None, ..content_opf };
+		assert!(deobfuscate_font(&mut data, FontObfuscation::Adobe, &content_opf).is_none());
+	}
+}
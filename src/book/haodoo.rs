@@ -4,11 +4,12 @@ use std::io::{Cursor, Read, Seek, SeekFrom};
 use anyhow::{anyhow, bail, Result};
 use encoding_rs::Encoding;
 
-use crate::book::{Book, LoadingChapter, Line, Loader, TocInfo};
+use crate::book::{Book, LoadingChapter, Line, Loader, TocInfo, WordCount, lines_char_count, lines_word_count};
 use crate::common::{decode_text, detect_charset, txt_lines};
 use crate::list::ListIterator;
 use crate::common::TraceInfo;
 use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::html_parser::ContentOptions;
 
 ///
 // http://www.haodoo.net/?M=hd&P=mPDB22
@@ -88,15 +89,15 @@ impl Loader for HaodooLoader {
 	}
 
 	fn load_file(&self, _filename: &str, file: std::fs::File,
-		loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		_options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		Ok((Box::new(HaodooBook::new(file, loading_chapter)?), loading.get()))
 	}
 
 	fn load_buf(&self, _filename: &str, content: Vec<u8>,
-		chapter_position: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		chapter_position: LoadingChapter, loading: BookLoadingInfo,
+		_options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		Ok((
 			Box::new(HaodooBook::new(Cursor::new(content), chapter_position)?),
@@ -242,6 +243,36 @@ impl<R: Read + Seek + 'static> Book for HaodooBook<R> {
 			| None => panic!("chapter not loaded before using."),
 		}
 	}
+
+	fn total_word_count(&mut self) -> Option<WordCount>
+	{
+		let mut count = WordCount::default();
+		for chapter_index in 0..self.chapter_count() {
+			if self.chapters.get(chapter_index)?.lines.is_none() {
+				let lines = self.load_chapter(chapter_index).ok()?;
+				self.chapters[chapter_index].lines = Some(lines);
+			}
+			if let Some(lines) = &self.chapters[chapter_index].lines {
+				count += lines_word_count(lines);
+			}
+		}
+		Some(count)
+	}
+
+	fn chapter_char_counts(&mut self) -> Option<Vec<usize>>
+	{
+		let mut counts = vec![];
+		for chapter_index in 0..self.chapter_count() {
+			if self.chapters.get(chapter_index)?.lines.is_none() {
+				let lines = self.load_chapter(chapter_index).ok()?;
+				self.chapters[chapter_index].lines = Some(lines);
+			}
+			if let Some(lines) = &self.chapters[chapter_index].lines {
+				counts.push(lines_char_count(lines));
+			}
+		}
+		Some(counts)
+	}
 }
 
 impl<R: Read + Seek> HaodooBook<R> {
@@ -1,6 +1,6 @@
 use std::fs;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use anyhow::Result;
 use elsa::FrozenMap;
@@ -9,7 +9,7 @@ use indexmap::IndexSet;
 use crate::book::{Book, LoadingChapter, Line, Loader, ImageData};
 #[cfg(feature = "gui")]
 use crate::html_parser::BlockStyle;
-use crate::html_parser::{HtmlContent, HtmlParseOptions, HtmlResolver};
+use crate::html_parser::{ContentOptions, HtmlContent, HtmlParseOptions, HtmlResolver};
 use crate::common::{plain_text, TraceInfo};
 use crate::config::{BookLoadingInfo, ReadingInfo};
 use crate::{frozen_map_get, html_parser};
@@ -23,6 +23,10 @@ pub(crate) struct HtmlLoader {
 
 pub(crate) struct HtmlBook {
 	path: Option<PathBuf>,
+	// kept for parsing again when content options changed
+	text: String,
+	resolver: Option<HtmlContentResolver>,
+	content_options: ContentOptions,
 	content: HtmlContent,
 	font_families: IndexSet<String>,
 	#[cfg(feature = "gui")]
@@ -81,74 +85,94 @@ impl Loader for HtmlLoader {
 	}
 
 	fn load_file(&self, _filename: &str, mut file: fs::File,
-		_loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		_loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
-		let filename = loading.filename();
-		let mut cwd = PathBuf::from_str(filename)?;
-		cwd.pop();
 		let mut content: Vec<u8> = Vec::new();
 		file.read_to_end(&mut content)?;
-		let mut font_families = IndexSet::new();
-		let mut text = plain_text(content, false)?;
-		if filename.to_lowercase().ends_with(".xhtml") {
-			text = xhtml_to_html(&text)?;
+		load_local(content, loading, options)
+	}
+
+	fn load_buf(&self, filename: &str, content: Vec<u8>,
+		_loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
+	{
+		// file read already, resources still relative to it
+		if Path::new(filename).is_absolute() {
+			return load_local(content, loading, options);
 		}
 		let reading = get_reading(loading);
-		#[allow(unused)]
-			let (content, mut font_faces) = html_parser::parse(HtmlParseOptions::new(&text)
-			.with_font_family(&mut font_families)
-			.with_resolver(&HtmlContentResolver {
-				cwd: cwd.clone(),
-				css_cache: FrozenMap::new(),
-				custom_style: reading.custom_style.clone(),
-			}))?;
-		#[cfg(feature = "gui")]
-			let book = {
-			let mut fonts = HtmlFonts::new();
-			fonts.reload(font_faces, |path| {
-				let content = fs::read(path).ok()?;
-				Some(content)
-			});
-			HtmlBook {
-				path: Some(cwd),
-				content,
-				font_families,
-				fonts,
-			}
-		};
-		#[cfg(not(feature = "gui"))]
-			let book = HtmlBook {
-			path: Some(cwd.to_owned()),
-			content,
-			font_families,
-		};
+		let text = plain_text(content, false, reading.encoding.as_deref())?;
+		let book = HtmlBook::new(text, None, options)?;
 		Ok((
 			Box::new(book),
-			reading
+			reading,
 		))
 	}
+}
 
-	fn load_buf(&self, _filename: &str, content: Vec<u8>,
-		_loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+/// html file in local file system, with resources relative to it
+fn load_local(content: Vec<u8>, loading: BookLoadingInfo, options: ContentOptions)
+	-> Result<(Box<dyn Book>, ReadingInfo)>
+{
+	let reading = get_reading(loading);
+	let filename = &reading.filename;
+	let mut cwd = PathBuf::from_str(filename)?;
+	cwd.pop();
+	let mut text = plain_text(content, false, reading.encoding.as_deref())?;
+	if filename.to_lowercase().ends_with(".xhtml") {
+		text = xhtml_to_html(&text)?;
+	}
+	let resolver = HtmlContentResolver {
+		cwd,
+		css_cache: FrozenMap::new(),
+		custom_style: reading.custom_style.clone(),
+	};
+	let book = HtmlBook::new(text, Some(resolver), options)?;
+	Ok((
+		Box::new(book),
+		reading
+	))
+}
+
+impl HtmlBook {
+	fn new(text: String, resolver: Option<HtmlContentResolver>,
+		content_options: ContentOptions) -> Result<Self>
 	{
-		let mut font_families = IndexSet::new();
-		let text = plain_text(content, false)?;
-		let (content, _) = html_parser::parse(HtmlParseOptions::new(&text)
-			.with_font_family(&mut font_families))?;
-		let book = HtmlBook {
-			path: None,
-			content,
-			font_families,
+		let mut book = HtmlBook {
+			path: resolver.as_ref().map(|resolver| resolver.cwd.clone()),
+			text,
+			resolver,
+			content_options,
+			content: HtmlContent::empty(),
+			font_families: IndexSet::new(),
 			#[cfg(feature = "gui")]
 			fonts: HtmlFonts::new(),
 		};
-		let reading = get_reading(loading);
-		Ok((
-			Box::new(book),
-			reading,
-		))
+		book.parse()?;
+		Ok(book)
+	}
+
+	fn parse(&mut self) -> Result<()>
+	{
+		let mut options = HtmlParseOptions::new(&self.text)
+			.with_font_family(&mut self.font_families)
+			.with_content_options(self.content_options.clone());
+		if let Some(resolver) = &self.resolver {
+			options = options.with_resolver(resolver);
+		}
+		#[allow(unused)]
+		let (content, font_faces) = html_parser::parse(options)?;
+		self.content = content;
+		// fonts only available for local file
+		#[cfg(feature = "gui")]
+		if self.resolver.is_some() {
+			self.fonts.reload(font_faces, |path| {
+				let content = fs::read(path).ok()?;
+				Some(content)
+			});
+		}
+		Ok(())
 	}
 }
 
@@ -177,6 +201,13 @@ impl Book for HtmlBook {
 		Some(TraceInfo { chapter: 0, line: position.line, offset: position.offset })
 	}
 
+	fn anchor_position(&mut self, anchor: &str) -> Option<TraceInfo>
+	{
+		let anchor = anchor.rsplit('#').next()?;
+		let position = self.content.id_position(anchor)?;
+		Some(TraceInfo { chapter: 0, line: position.line, offset: position.offset })
+	}
+
 	fn image<'h>(&'h self, href: &'h str) -> Option<ImageData<'h>>
 	{
 		if let Some(path) = &self.path {
@@ -194,6 +225,23 @@ impl Book for HtmlBook {
 		Some(&self.font_families)
 	}
 
+	fn set_content_options(&mut self, options: ContentOptions) -> Result<bool>
+	{
+		if self.content_options == options {
+			return Ok(false);
+		}
+		self.content_options = options;
+		self.parse()?;
+		Ok(true)
+	}
+
+	#[cfg(feature = "gui")]
+	#[inline]
+	fn encoding_overridable(&self) -> bool
+	{
+		true
+	}
+
 	#[cfg(feature = "gui")]
 	#[inline]
 	fn color_customizable(&self) -> bool
@@ -1,12 +1,21 @@
+use std::borrow::Cow;
 use anyhow::Result;
 
 use crate::book::{Book, LoadingChapter, Line, Loader};
-use crate::common::plain_text_lines;
+use crate::common::{guess_language, plain_text, reflow_txt_lines, strip_invisible_chars, txt_lines};
 use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::html_parser::ContentOptions;
 
 pub struct TxtBook {
+	text: String,
 	lines: Vec<Line>,
 	leading_space: usize,
+	// only prose text can be reflowed
+	reflowable: bool,
+	reflow: bool,
+	strip_invisible_chars: bool,
+	// guessed from the text, for no metadata in plain text
+	language: Option<&'static str>,
 }
 
 impl Book for TxtBook {
@@ -17,6 +26,39 @@ impl Book for TxtBook {
 	fn leading_space(&self) -> usize {
 		self.leading_space
 	}
+
+	fn language(&self) -> Option<&str> {
+		self.language
+	}
+
+	#[cfg(feature = "gui")]
+	fn encoding_overridable(&self) -> bool {
+		true
+	}
+
+	fn set_content_options(&mut self, options: ContentOptions) -> Result<bool> {
+		let reflow = self.reflowable && options.reflow_text;
+		if reflow == self.reflow && options.strip_invisible_chars == self.strip_invisible_chars {
+			return Ok(false);
+		}
+		self.reflow = reflow;
+		self.strip_invisible_chars = options.strip_invisible_chars;
+		self.lines = book_lines(&self.text, reflow, self.strip_invisible_chars);
+		Ok(true)
+	}
+}
+
+fn book_lines(text: &str, reflow: bool, strip: bool) -> Vec<Line> {
+	let text = if strip {
+		Cow::Owned(strip_invisible_chars(text))
+	} else {
+		Cow::Borrowed(text)
+	};
+	if reflow {
+		reflow_txt_lines(&text)
+	} else {
+		txt_lines(&text)
+	}
 }
 
 pub struct TxtLoader {
@@ -37,16 +79,23 @@ impl Loader for TxtLoader {
 	}
 
 	fn load_buf(&self, filename: &str, content: Vec<u8>,
-		_loading_chapter: LoadingChapter, loading: BookLoadingInfo)
-		-> Result<(Box<dyn Book>, ReadingInfo)>
+		_loading_chapter: LoadingChapter, loading: BookLoadingInfo,
+		options: ContentOptions) -> Result<(Box<dyn Book>, ReadingInfo)>
 	{
-		let lines = plain_text_lines(content)?;
-		let leading_space = if filename.to_lowercase().ends_with(".log") {
+		let reading = loading.get();
+		let text = plain_text(content, false, reading.encoding.as_deref())?;
+		let filename = filename.to_lowercase();
+		let leading_space = if filename.ends_with(".log") {
 			0
 		} else {
 			2
 		};
-		let book = TxtBook { lines, leading_space };
-		Ok((Box::new(book), loading.get()))
+		let reflowable = filename.ends_with(".txt");
+		let reflow = reflowable && options.reflow_text;
+		let strip_invisible_chars = options.strip_invisible_chars;
+		let lines = book_lines(&text, reflow, strip_invisible_chars);
+		let language = guess_language(&text);
+		let book = TxtBook { text, lines, leading_space, reflowable, reflow, strip_invisible_chars, language };
+		Ok((Box::new(book), reading))
 	}
-}
\ No newline at end of file
+}
@@ -123,6 +123,20 @@ impl Color32 {
 		}
 	}
 
+	/// linear interpolation of each channel, t from 0 for self to 1 for other
+	#[cfg(feature = "gui")]
+	pub fn mix(&self, other: &Color32, t: f32) -> Color32
+	{
+		let t = t.clamp(0., 1.);
+		let mut mixed = [0; 4];
+		for (idx, channel) in mixed.iter_mut().enumerate() {
+			let from = self.0[idx] as f32;
+			let to = other.0[idx] as f32;
+			*channel = fast_round(from + (to - from) * t);
+		}
+		Color32(mixed)
+	}
+
 	#[inline(always)]
 	#[cfg(feature = "gui")]
 	pub fn apply(&self, cairo: &CairoContext)
@@ -209,6 +223,9 @@ pub struct Colors
 	pub link: Color32,
 	pub matched_color: Color32,
 	pub matched_background: Color32,
+	// filled behind images, for transparent images, None for the page background
+	#[serde(default)]
+	pub image_background: Option<Color32>,
 }
 
 impl Colors {
@@ -220,6 +237,7 @@ impl Colors {
 		link: Color32::BLUE,
 		matched_color: Color32::BLACK,
 		matched_background: Color32::LIGHT_GRAY,
+		image_background: None,
 	};
 	pub const DEFAULT_BRIGHT: Colors = Colors {
 		color: Color32::BLACK,
@@ -229,5 +247,25 @@ impl Colors {
 		link: Color32::BLUE,
 		matched_color: Color32::BLACK,
 		matched_background: Color32::LIGHT_GRAY,
+		image_background: None,
 	};
+
+	/// colors between self and other, for transition of themes
+	#[cfg(feature = "gui")]
+	pub fn mix(&self, other: &Colors, t: f32) -> Colors
+	{
+		Colors {
+			color: self.color.mix(&other.color, t),
+			background: self.background.mix(&other.background, t),
+			highlight: self.highlight.mix(&other.highlight, t),
+			highlight_background: self.highlight_background.mix(&other.highlight_background, t),
+			link: self.link.mix(&other.link, t),
+			matched_color: self.matched_color.mix(&other.matched_color, t),
+			matched_background: self.matched_background.mix(&other.matched_background, t),
+			image_background: match (&self.image_background, &other.image_background) {
+				(Some(from), Some(to)) => Some(from.mix(to, t)),
+				(_, to) => to.clone(),
+			},
+		}
+	}
 }
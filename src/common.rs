@@ -1,7 +1,7 @@
 use anyhow::Result;
 use chardetng::EncodingDetector;
 use encoding_rs::{Encoding, UTF_8};
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 use std::ops::Range;
 use unicode_width::UnicodeWidthChar;
 
@@ -108,13 +108,21 @@ pub fn length_with_leading(text: &Line, leading_space: usize) -> usize {
 	};
 }
 
-pub(crate) fn plain_text(content: Vec<u8>, full_scan: bool) -> Result<String> {
-	let encoding = detect_charset(&content, full_scan);
+/// decode with the encoding label if given and known, or the detected one
+pub(crate) fn plain_text(content: Vec<u8>, full_scan: bool, encoding: Option<&str>)
+	-> Result<String> {
+	let encoding = encoding
+		.and_then(|label| Encoding::for_label(label.trim().as_bytes()))
+		.unwrap_or_else(|| detect_charset(&content, full_scan));
 	decode_text(content, encoding)
 }
 
 #[inline]
 pub(crate) fn detect_charset(content: &Vec<u8>, full_scan: bool) -> &'static Encoding {
+	// utf-16 never guessed by the detector
+	if let Some((encoding, _)) = Encoding::for_bom(content) {
+		return encoding;
+	}
 	let mut detector = EncodingDetector::new();
 	if detector.feed(content, full_scan) {
 		detector.guess(None, true)
@@ -126,17 +134,68 @@ pub(crate) fn detect_charset(content: &Vec<u8>, full_scan: bool) -> &'static Enc
 #[inline]
 pub(crate) fn decode_text(content: Vec<u8>, encoding: &'static Encoding) -> Result<String> {
 	let text = if encoding.eq(UTF_8) {
-		String::from_utf8(content)?
+		match String::from_utf8(content) {
+			Ok(mut text) => {
+				if text.starts_with('\u{feff}') {
+					text.remove(0);
+				}
+				text
+			}
+			// few broken bytes should not make the whole file unreadable
+			Err(err) => String::from(UTF_8.decode(err.as_bytes()).0),
+		}
 	} else {
+		// bom, if any, is stripped by decode
 		let (cow, ..) = encoding.decode(content.borrow());
 		String::from(cow)
 	};
 	Ok(text)
 }
 
-pub(crate) fn plain_text_lines(content: Vec<u8>) -> Result<Vec<Line>> {
-	let text = plain_text(content, false)?;
-	Ok(txt_lines(&text))
+/// join hard wrapped lines into paragraphs, paragraphs
+/// separated by blank lines or indented lines
+pub(crate) fn reflow_txt_lines(txt: &str) -> Vec<Line> {
+	let mut lines: Vec<Line> = vec![];
+	let mut line = Line::default();
+	for text in txt.split('\n') {
+		let text = text.trim_end();
+		let content = text.trim_start();
+		if content.is_empty() {
+			if !line.is_empty() {
+				lines.push(line);
+				line = Line::default();
+			}
+			lines.push(Line::default());
+			continue;
+		}
+		if !line.is_empty() {
+			if content.len() != text.len() {
+				lines.push(line);
+				line = Line::default();
+			} else {
+				// no space for joining CJK text
+				let last = line.char_at(line.len() - 1).unwrap();
+				let first = content.chars().next().unwrap();
+				if !is_cjk(last) && !is_cjk(first) {
+					line.push(' ');
+				}
+			}
+		}
+		for c in content.chars() {
+			line.push(c);
+		}
+	}
+	lines.push(line);
+	lines
+}
+
+/// soft hyphen, zero width space, word joiner and zero width no-break space,
+/// zero width (non-)joiner are kept for shaping of some scripts
+#[inline]
+pub(crate) fn strip_invisible_chars(text: &str) -> String {
+	text.chars()
+		.filter(|ch| !matches!(ch, '\u{00AD}' | '\u{200B}' | '\u{2060}' | '\u{FEFF}'))
+		.collect()
 }
 
 pub(crate) fn txt_lines(txt: &str) -> Vec<Line> {
@@ -269,6 +328,51 @@ pub fn is_compact_for_han(ch: char) -> bool
 	HAN_COMPACT_CHARS.binary_search(&ch).is_ok()
 }
 
+/// han, kana and their extensions, which are read by chars instead of words
+#[inline]
+pub fn is_cjk(ch: char) -> bool
+{
+	matches!(ch,
+		'\u{3040}'..='\u{30ff}'
+		| '\u{3400}'..='\u{4dbf}'
+		| '\u{4e00}'..='\u{9fff}'
+		| '\u{f900}'..='\u{faff}'
+		| '\u{20000}'..='\u{2fa1f}')
+}
+
+/// guess language of plain text by scripts of the sampled chars,
+/// kana for japanese, hangul for korean, han for chinese,
+/// latin only for english
+pub(crate) fn guess_language(text: &str) -> Option<&'static str>
+{
+	const SAMPLE_CHARS: usize = 4096;
+	let mut latin = 0;
+	let mut han = 0;
+	let mut kana = 0;
+	let mut hangul = 0;
+	let mut others = 0;
+	for ch in text.chars().filter(|ch| ch.is_alphabetic()).take(SAMPLE_CHARS) {
+		match ch {
+			'\u{3040}'..='\u{30ff}' => kana += 1,
+			'\u{1100}'..='\u{11ff}' | '\u{ac00}'..='\u{d7af}' => hangul += 1,
+			_ if is_cjk(ch) => han += 1,
+			_ if ch.is_ascii_alphabetic() => latin += 1,
+			_ => others += 1,
+		}
+	}
+	if kana > 0 && kana * 10 >= han {
+		Some("ja")
+	} else if hangul > 0 && hangul >= han {
+		Some("ko")
+	} else if han > 0 {
+		Some("zh")
+	} else if latin > 0 && others == 0 {
+		Some("en")
+	} else {
+		None
+	}
+}
+
 #[inline]
 pub fn han_render_char(ch: char) -> char
 {
@@ -278,9 +382,45 @@ pub fn han_render_char(ch: char) -> char
 	}
 }
 
+/// decode %XX escapes in url path, invalid escapes kept as is
+pub(crate) fn percent_decode(text: &str) -> Cow<str>
+{
+	if !text.contains('%') {
+		return Cow::Borrowed(text);
+	}
+	let bytes = text.as_bytes();
+	let mut decoded = Vec::with_capacity(bytes.len());
+	let mut i = 0;
+	while i < bytes.len() {
+		if bytes[i] == b'%' && i + 2 < bytes.len() {
+			let hex = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2]));
+			if let (Some(high), Some(low)) = hex {
+				decoded.push(high << 4 | low);
+				i += 3;
+				continue;
+			}
+		}
+		decoded.push(bytes[i]);
+		i += 1;
+	}
+	Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[inline]
+fn hex_value(byte: u8) -> Option<u8>
+{
+	match byte {
+		b'0'..=b'9' => Some(byte - b'0'),
+		b'a'..=b'f' => Some(byte - b'a' + 10),
+		b'A'..=b'F' => Some(byte - b'A' + 10),
+		_ => None,
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use crate::common::{is_overlap, overlap_range};
+	use encoding_rs::UTF_8;
+	use crate::common::{decode_text, guess_language, is_overlap, overlap_range, percent_decode, plain_text, reflow_txt_lines};
 
 	#[test]
 	fn test_is_range_overlap()
@@ -315,4 +455,62 @@ mod tests {
 		assert!(overlap_range(&(10..15), &(8..9)).is_none());
 		assert!(overlap_range(&(10..15), &(15..16)).is_none());
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn test_percent_decode()
+	{
+		assert_eq!(percent_decode("Text/chapter1.html"), "Text/chapter1.html");
+		assert_eq!(percent_decode("Chapter%201.css"), "Chapter 1.css");
+		assert_eq!(percent_decode("%E4%B8%AD%E6%96%87.png"), "中文.png");
+		// invalid escapes kept
+		assert_eq!(percent_decode("100%.css"), "100%.css");
+		assert_eq!(percent_decode("a%zz%2"), "a%zz%2");
+	}
+
+	#[test]
+	fn test_guess_language()
+	{
+		assert_eq!(guess_language("It's a well-known fact."), Some("en"));
+		assert_eq!(guess_language("这是一本中文书。"), Some("zh"));
+		assert_eq!(guess_language("これは日本語の本です。"), Some("ja"));
+		assert_eq!(guess_language("이것은 한국어 책입니다."), Some("ko"));
+		assert_eq!(guess_language("Это русская книга."), None);
+		assert_eq!(guess_language("1234 ..."), None);
+	}
+
+	#[test]
+	fn test_reflow_txt_lines()
+	{
+		let text = "The quick brown\nfox jumps.\n\n  Indented starts\na paragraph.\n  Another one.\n\n中文断行\n接着写\r";
+		let lines: Vec<String> = reflow_txt_lines(text).iter()
+			.map(|line| line.to_string())
+			.collect();
+		assert_eq!(lines, vec![
+			// joined with space
+			"The quick brown fox jumps.",
+			// blank line kept as separator
+			"",
+			// indented line starts a new paragraph
+			"Indented starts a paragraph.",
+			"Another one.",
+			"",
+			// no space for CJK
+			"中文断行接着写",
+		]);
+	}
+
+	#[test]
+	fn test_plain_text()
+	{
+		let utf16: Vec<u8> = [0xFF, 0xFE].into_iter()
+			.chain("中文".encode_utf16().flat_map(|c| c.to_le_bytes()))
+			.collect();
+		assert_eq!(plain_text(utf16, false, None).unwrap(), "中文");
+		let utf8 = b"\xEF\xBB\xBFtext".to_vec();
+		assert_eq!(plain_text(utf8, false, None).unwrap(), "text");
+		let broken = b"ok\xFF".to_vec();
+		assert_eq!(decode_text(broken, UTF_8).unwrap(), "ok\u{FFFD}");
+		let gbk = vec![0xD6, 0xD0, 0xCE, 0xC4];
+		assert_eq!(plain_text(gbk, false, Some("gbk")).unwrap(), "中文");
+	}
+}
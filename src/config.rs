@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -5,11 +6,15 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 #[cfg(feature = "gui")]
+use gtk4::gdk_pixbuf::InterpType;
+#[cfg(feature = "gui")]
 use gtk4::Orientation;
-use rusqlite::{Connection, Row};
+use rusqlite::{Connection, params, Row};
 use serde_derive::{Deserialize, Serialize};
 
+use crate::book::Book;
 use crate::color::Colors;
+use crate::html_parser::{ContentOptions, DEFAULT_TAB_WIDTH};
 #[cfg(feature = "i18n")]
 use crate::i18n;
 use crate::terminal::Listable;
@@ -27,6 +32,18 @@ pub struct ReadingInfo {
 	pub strip_empty_lines: bool,
 	pub custom_style: Option<String>,
 	pub font_size: u8,
+	/// leading chars of paragraphs for this book, None for global setting
+	pub leading_chars: Option<u8>,
+	/// join hard wrapped lines of plain text, None for global setting
+	pub reflow_text: Option<bool>,
+	/// render han(vertical) for this book, None for global setting
+	pub render_han: Option<bool>,
+	/// language code for this book when the metadata is wrong, None for the book's
+	pub language: Option<String>,
+	/// text encoding for this book when detected wrong, None for detecting
+	pub encoding: Option<String>,
+	// last saved time
+	ts: u64,
 }
 
 impl ReadingInfo {
@@ -46,18 +63,72 @@ impl ReadingInfo {
 			strip_empty_lines: false,
 			custom_style: None,
 			font_size: default_font_size(),
+			leading_chars: None,
+			reflow_text: None,
+			render_han: None,
+			language: None,
+			encoding: None,
+			ts: 0,
 		}
 	}
 
+	/// reading of another book in the same container,
+	/// with this book's settings but from the start
 	#[inline]
 	pub fn load_inner_book(&self, inner_book: usize) -> BookLoadingInfo
 	{
-		BookLoadingInfo::ChangeInnerBook(
-			&self.filename,
+		BookLoadingInfo::ChangeInnerBook(ReadingInfo {
 			inner_book,
-			self.row_id,
-			self.custom_style.clone(),
-			self.font_size)
+			chapter: 0,
+			line: 0,
+			position: 0,
+			custom_color: false,
+			custom_font: false,
+			strip_empty_lines: false,
+			ts: 0,
+			..self.clone()
+		})
+	}
+
+	/// leading chars of paragraphs, this book's setting first,
+	/// then the global one, then the book's default
+	#[inline]
+	pub fn leading_chars(&self, global: Option<u8>, book_default: usize) -> usize
+	{
+		self.leading_chars
+			.or(global)
+			.map_or(book_default, |chars| chars as usize)
+	}
+
+	/// render han(vertical) for this book if set, or the global setting
+	#[inline]
+	pub fn render_han(&self, global: bool) -> bool
+	{
+		self.render_han.unwrap_or(global)
+	}
+
+	/// global content options with overrides of this book applied
+	pub fn content_options(&self, global: &ContentOptions) -> ContentOptions
+	{
+		let mut options = global.clone();
+		if let Some(reflow_text) = self.reflow_text {
+			options.reflow_text = reflow_text;
+		}
+		options.language = self.language.clone();
+		options
+	}
+
+	/// language of the book, this book's override first, then the metadata
+	#[inline]
+	pub fn language<'a>(&'a self, book: &'a dyn Book) -> Option<&'a str>
+	{
+		self.language.as_deref().or_else(|| book.language())
+	}
+
+	#[inline]
+	pub fn ts(&self) -> u64
+	{
+		self.ts
 	}
 
 	#[inline]
@@ -88,8 +159,10 @@ impl Listable for ReadingInfo {
 
 #[allow(unused)]
 pub enum BookLoadingInfo<'a> {
-	NewReading(&'a str, usize, usize, u8),
-	ChangeInnerBook(&'a str, usize, i64, Option<String>, u8),
+	// filename, inner book, chapter, font size and custom style
+	NewReading(&'a str, usize, usize, u8, Option<String>),
+	// template reading with settings kept from the previous book
+	ChangeInnerBook(ReadingInfo),
 	History(ReadingInfo),
 	Reload(ReadingInfo),
 }
@@ -100,8 +173,21 @@ impl<'a> BookLoadingInfo<'a> {
 	{
 		match self {
 			BookLoadingInfo::NewReading(filename, ..) => filename,
-			BookLoadingInfo::ChangeInnerBook(filename, ..) => filename,
-			BookLoadingInfo::History(reading) | BookLoadingInfo::Reload(reading) => &reading.filename,
+			BookLoadingInfo::ChangeInnerBook(reading)
+			| BookLoadingInfo::History(reading)
+			| BookLoadingInfo::Reload(reading) => &reading.filename,
+		}
+	}
+
+	/// global content options with overrides of the loading book applied
+	#[inline]
+	pub fn content_options(&self, global: &ContentOptions) -> ContentOptions
+	{
+		match self {
+			BookLoadingInfo::NewReading(..) => global.clone(),
+			BookLoadingInfo::ChangeInnerBook(reading)
+			| BookLoadingInfo::History(reading)
+			| BookLoadingInfo::Reload(reading) => reading.content_options(global),
 		}
 	}
 
@@ -109,7 +195,7 @@ impl<'a> BookLoadingInfo<'a> {
 	pub fn get(self) -> ReadingInfo
 	{
 		match self {
-			BookLoadingInfo::NewReading(filename, inner_book, chapter, font_size) => ReadingInfo {
+			BookLoadingInfo::NewReading(filename, inner_book, chapter, font_size, custom_style) => ReadingInfo {
 				row_id: 0,
 				filename: filename.to_owned(),
 				inner_book,
@@ -119,24 +205,18 @@ impl<'a> BookLoadingInfo<'a> {
 				custom_color: false,
 				custom_font: false,
 				strip_empty_lines: false,
-				custom_style: None,
+				custom_style,
 				font_size,
+				leading_chars: None,
+				reflow_text: None,
+				render_han: None,
+				language: None,
+				encoding: None,
+				ts: 0,
 			},
-			BookLoadingInfo::ChangeInnerBook(filename, inner_book, row_id, custom_style, font_size) =>
-				ReadingInfo {
-					row_id,
-					filename: filename.to_owned(),
-					inner_book,
-					chapter: 0,
-					line: 0,
-					position: 0,
-					custom_color: false,
-					custom_font: false,
-					strip_empty_lines: false,
-					custom_style: custom_style.clone(),
-					font_size,
-				},
-			BookLoadingInfo::History(reading) | BookLoadingInfo::Reload(reading) => reading,
+			BookLoadingInfo::ChangeInnerBook(reading)
+			| BookLoadingInfo::History(reading)
+			| BookLoadingInfo::Reload(reading) => reading,
 		}
 	}
 
@@ -145,7 +225,7 @@ impl<'a> BookLoadingInfo<'a> {
 		where F: FnOnce(&mut ReadingInfo)
 	{
 		match self {
-			BookLoadingInfo::NewReading(filename, inner_book, chapter, font_size) => {
+			BookLoadingInfo::NewReading(filename, inner_book, chapter, font_size, custom_style) => {
 				let mut reading = ReadingInfo {
 					row_id: 0,
 					filename: filename.to_owned(),
@@ -156,26 +236,19 @@ impl<'a> BookLoadingInfo<'a> {
 					custom_color: false,
 					custom_font: false,
 					strip_empty_lines: false,
-					custom_style: None,
+					custom_style,
 					font_size,
+					leading_chars: None,
+					reflow_text: None,
+					render_han: None,
+					language: None,
+					encoding: None,
+					ts: 0,
 				};
 				f(&mut reading);
 				reading
 			}
-			BookLoadingInfo::ChangeInnerBook(filename, inner_book, row_id, custom_style, font_size) => {
-				let mut reading = ReadingInfo {
-					row_id,
-					filename: filename.to_owned(),
-					inner_book,
-					chapter: 0,
-					line: 0,
-					position: 0,
-					custom_color: false,
-					custom_font: false,
-					strip_empty_lines: false,
-					custom_style: custom_style.clone(),
-					font_size,
-				};
+			BookLoadingInfo::ChangeInnerBook(mut reading) => {
 				f(&mut reading);
 				reading
 			}
@@ -227,6 +300,44 @@ impl SidebarPosition {
 	}
 }
 
+/// open the latest reading book when started without file
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContinueReading {
+	Always,
+	/// confirm in gui, same as never in terminal
+	Ask,
+	Never,
+}
+
+impl Default for ContinueReading {
+	#[inline]
+	fn default() -> Self
+	{
+		ContinueReading::Always
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReadingSpeed {
+	#[serde(default = "default_words_per_minute")]
+	pub words_per_minute: usize,
+	/// for CJK text, which is counted by chars instead of words
+	#[serde(default = "default_chars_per_minute")]
+	pub chars_per_minute: usize,
+}
+
+impl Default for ReadingSpeed {
+	#[inline]
+	fn default() -> Self
+	{
+		ReadingSpeed {
+			words_per_minute: default_words_per_minute(),
+			chars_per_minute: default_chars_per_minute(),
+		}
+	}
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 #[cfg(feature = "gui")]
 pub struct GuiConfiguration {
@@ -237,19 +348,284 @@ pub struct GuiConfiguration {
 	pub default_font_size: u8,
 	#[serde(default = "default_font_size")]
 	pub dict_font_size: u8,
+	/// font size change for each zoom in/out
+	#[serde(default = "default_font_size_step")]
+	pub font_size_step: u8,
+	#[serde(default = "default_min_font_size")]
+	pub min_font_size: u8,
+	#[serde(default = "default_max_font_size")]
+	pub max_font_size: u8,
 	pub sidebar_size: u32,
 	#[serde(default)]
 	pub sidebar_position: SidebarPosition,
+	#[serde(default)]
+	pub sidebar_open: bool,
+	/// name of last active sidebar tab
+	#[serde(default)]
+	pub sidebar_tab: Option<String>,
 	#[serde(default = "default_locale")]
 	pub lang: String,
 	pub dictionaries: Vec<PathConfig>,
 	pub cache_dict: bool,
-	pub strip_empty_lines: bool,
+	#[serde(default, alias = "strip_empty_lines", deserialize_with = "deserialize_empty_lines")]
+	pub empty_lines: EmptyLines,
 	pub ignore_font_weight: bool,
+	/// render with user fonts only, ignore fonts embedded in books
+	#[serde(default)]
+	pub ignore_book_fonts: bool,
 	#[serde(default)]
 	pub scroll_for_page: bool,
+	/// click left or right third of the view for paging, middle for fullscreen
+	#[serde(default)]
+	pub click_zones: bool,
+	/// cross-fade colors when switching theme
+	#[serde(default)]
+	pub smooth_theme: bool,
+	/// lines for each wheel notch
+	#[serde(default = "default_scroll_step")]
+	pub scroll_step: u8,
+	/// lines keep visible when paging in scrolling view
+	#[serde(default)]
+	pub page_overlap: u8,
 	#[serde(default)]
 	pub select_by_dictionary: bool,
+	#[serde(default)]
+	pub drop_cap: bool,
+	#[serde(default)]
+	pub hyphenation: bool,
+	/// open external links without confirmation
+	#[serde(default)]
+	pub always_open_external_link: bool,
+	/// open clicked image with external viewer instead of the built-in one
+	#[serde(default)]
+	pub external_image_viewer: bool,
+	/// what to show when started without book
+	#[serde(default, alias = "library_view", deserialize_with = "deserialize_empty_launch")]
+	pub on_empty_launch: EmptyLaunch,
+	/// leading chars of paragraphs for all books, None for book default
+	#[serde(default)]
+	pub leading_chars: Option<u8>,
+	/// show title of current section above the book view
+	#[serde(default)]
+	pub running_header: bool,
+	/// named css snippets for custom style
+	#[serde(default)]
+	pub styles: Vec<NamedStyle>,
+	/// custom style for books opened first time
+	#[serde(default)]
+	pub default_style: Option<String>,
+	/// font family for all text, ignore fonts of books
+	#[serde(default)]
+	pub font_family: Option<String>,
+	/// wider spacing and dyslexia friendly font
+	#[serde(default)]
+	pub dyslexia_mode: bool,
+	#[serde(default = "default_dyslexia_font_family")]
+	pub dyslexia_font_family: String,
+	/// dim lines except the paragraph at the top of the page
+	#[serde(default)]
+	pub focus_mode: bool,
+	/// render footnotes at bottom of the pages referencing them
+	#[serde(default)]
+	pub page_footnotes: bool,
+	/// invert colors of images in dark theme
+	#[serde(default)]
+	pub invert_images: bool,
+	#[serde(default)]
+	pub image_interpolation: ImageInterpolation,
+	/// enlarge images smaller than the view, up to max_image_scale
+	#[serde(default)]
+	pub upscale_images: bool,
+	#[serde(default = "default_max_image_scale")]
+	pub max_image_scale: f32,
+	/// font family for text with lang attribute, keyed by language
+	/// like "zh" or "zh-tw", applied as font family of the book
+	#[serde(default)]
+	pub lang_fonts: HashMap<String, String>,
+	/// start in terminal unless --gui is given
+	#[serde(default)]
+	pub prefer_terminal: bool,
+	/// format of dates in book info, like "%Y-%m-%d %H:%M",
+	/// the format of current locale is used if not set
+	#[serde(default)]
+	pub date_format: Option<String>,
+	/// words always highlighted in books, each with its own color
+	#[serde(default)]
+	pub highlight_words: Vec<String>,
+	#[cfg(feature = "opds")]
+	#[serde(default)]
+	pub opds: Option<OpdsConfiguration>,
+}
+
+/// how blank lines of books rendered
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyLines {
+	Keep,
+	/// keep only the first one of continuous blank lines
+	Collapse,
+	Strip,
+}
+
+#[cfg(feature = "gui")]
+impl Default for EmptyLines {
+	#[inline]
+	fn default() -> Self
+	{
+		EmptyLines::Keep
+	}
+}
+
+#[cfg(feature = "gui")]
+impl EmptyLines {
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			EmptyLines::Keep => "empty-lines-keep",
+			EmptyLines::Collapse => "empty-lines-collapse",
+			EmptyLines::Strip => "empty-lines-strip",
+		}
+	}
+}
+
+/// what to show when started without book
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyLaunch {
+	Readme,
+	/// recent books with covers
+	Library,
+	/// latest book in history
+	LastBook,
+	/// empty view with a prompt for opening book
+	Blank,
+}
+
+#[cfg(feature = "gui")]
+impl Default for EmptyLaunch {
+	#[inline]
+	fn default() -> Self
+	{
+		EmptyLaunch::Readme
+	}
+}
+
+#[cfg(feature = "gui")]
+impl EmptyLaunch {
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			EmptyLaunch::Readme => "empty-launch-readme",
+			EmptyLaunch::Library => "empty-launch-library",
+			EmptyLaunch::LastBook => "empty-launch-last-book",
+			EmptyLaunch::Blank => "empty-launch-blank",
+		}
+	}
+}
+
+/// quality of scaling images
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[cfg(feature = "gui")]
+#[serde(rename_all = "snake_case")]
+pub enum ImageInterpolation {
+	/// blocky but sharp, for pixel art or comics
+	Nearest,
+	Bilinear,
+	/// best quality but slow
+	Hyper,
+}
+
+#[cfg(feature = "gui")]
+impl Default for ImageInterpolation {
+	#[inline]
+	fn default() -> Self
+	{
+		ImageInterpolation::Bilinear
+	}
+}
+
+#[cfg(feature = "gui")]
+impl ImageInterpolation {
+	#[inline]
+	pub fn interp_type(&self) -> InterpType
+	{
+		match self {
+			ImageInterpolation::Nearest => InterpType::Nearest,
+			ImageInterpolation::Bilinear => InterpType::Bilinear,
+			ImageInterpolation::Hyper => InterpType::Hyper,
+		}
+	}
+
+	#[inline]
+	pub fn i18n_key(&self) -> &'static str
+	{
+		match self {
+			ImageInterpolation::Nearest => "image-interpolation-nearest",
+			ImageInterpolation::Bilinear => "image-interpolation-bilinear",
+			ImageInterpolation::Hyper => "image-interpolation-hyper",
+		}
+	}
+}
+
+/// accept the boolean strip_empty_lines of old versions
+#[cfg(feature = "gui")]
+fn deserialize_empty_lines<'de, D>(deserializer: D) -> std::result::Result<EmptyLines, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	use serde::Deserialize;
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum RawEmptyLines {
+		Strip(bool),
+		Mode(EmptyLines),
+	}
+	Ok(match RawEmptyLines::deserialize(deserializer)? {
+		RawEmptyLines::Strip(true) => EmptyLines::Strip,
+		RawEmptyLines::Strip(false) => EmptyLines::Keep,
+		RawEmptyLines::Mode(mode) => mode,
+	})
+}
+
+/// accept the boolean library_view of old versions
+#[cfg(feature = "gui")]
+fn deserialize_empty_launch<'de, D>(deserializer: D) -> std::result::Result<EmptyLaunch, D::Error>
+where
+	D: serde::Deserializer<'de>,
+{
+	use serde::Deserialize;
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum RawEmptyLaunch {
+		Library(bool),
+		Mode(EmptyLaunch),
+	}
+	Ok(match RawEmptyLaunch::deserialize(deserializer)? {
+		RawEmptyLaunch::Library(true) => EmptyLaunch::Library,
+		RawEmptyLaunch::Library(false) => EmptyLaunch::Readme,
+		RawEmptyLaunch::Mode(mode) => mode,
+	})
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[cfg(feature = "gui")]
+pub struct NamedStyle {
+	pub name: String,
+	pub style: String,
+}
+
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[cfg(feature = "opds")]
+pub struct OpdsConfiguration {
+	pub url: String,
+	#[serde(default)]
+	pub username: Option<String>,
+	#[serde(default)]
+	pub password: Option<String>,
 }
 
 #[cfg(feature = "gui")]
@@ -261,21 +637,93 @@ impl Default for GuiConfiguration
 			fonts: vec![],
 			default_font_size: default_font_size(),
 			dict_font_size: default_font_size(),
+			font_size_step: default_font_size_step(),
+			min_font_size: default_min_font_size(),
+			max_font_size: default_max_font_size(),
 			sidebar_size: 300,
 			sidebar_position: Default::default(),
+			sidebar_open: false,
+			sidebar_tab: None,
 			lang: default_locale(),
 			dictionaries: vec![],
 			cache_dict: false,
-			strip_empty_lines: false,
+			empty_lines: EmptyLines::default(),
 			ignore_font_weight: false,
+			ignore_book_fonts: false,
 			scroll_for_page: false,
+			click_zones: false,
+			smooth_theme: false,
+			scroll_step: default_scroll_step(),
+			page_overlap: 0,
 			select_by_dictionary: false,
+			drop_cap: false,
+			hyphenation: false,
+			always_open_external_link: false,
+			external_image_viewer: false,
+			on_empty_launch: EmptyLaunch::default(),
+			leading_chars: None,
+			running_header: false,
+			styles: vec![],
+			default_style: None,
+			font_family: None,
+			dyslexia_mode: false,
+			dyslexia_font_family: default_dyslexia_font_family(),
+			focus_mode: false,
+			page_footnotes: false,
+			invert_images: false,
+			image_interpolation: Default::default(),
+			upscale_images: false,
+			max_image_scale: default_max_image_scale(),
+			lang_fonts: HashMap::new(),
+			prefer_terminal: false,
+			date_format: None,
+			highlight_words: vec![],
+			#[cfg(feature = "opds")]
+			opds: None,
 		}
 	}
 }
 
 #[cfg(feature = "gui")]
 impl GuiConfiguration {
+	/// forced font family for rendering, dyslexia font in dyslexia mode
+	#[inline]
+	pub fn render_font_family(&self) -> Option<String>
+	{
+		if self.dyslexia_mode && !self.dyslexia_font_family.is_empty() {
+			Some(self.dyslexia_font_family.clone())
+		} else {
+			self.font_family.clone()
+		}
+	}
+
+	/// max scale for images smaller than the view, 1 for no upscaling
+	#[inline]
+	pub fn image_scale_limit(&self) -> f32
+	{
+		if self.upscale_images {
+			self.max_image_scale.max(1.0)
+		} else {
+			1.0
+		}
+	}
+
+	/// valid font size range, (min, max)
+	#[inline]
+	pub fn font_size_range(&self) -> (u8, u8)
+	{
+		let min = self.min_font_size.max(1);
+		(min, self.max_font_size.max(min))
+	}
+
+	/// font size after zooming given steps, limited in range
+	pub fn zoom_font_size(&self, font_size: u8, steps: i32) -> u8
+	{
+		let (min, max) = self.font_size_range();
+		let font_size = font_size as i32 + steps * self.font_size_step.max(1) as i32;
+		font_size.clamp(min as i32, max as i32) as u8
+	}
+
 	pub fn curr_colors(&self, dark: bool) -> &Colors
 	{
 		if dark {
@@ -299,6 +747,19 @@ pub struct Configuration {
 	pub render_han: bool,
 	pub dark_theme: bool,
 	history: PathBuf,
+	pub reading_speed: ReadingSpeed,
+	pub sync_dir: Option<PathBuf>,
+	pub show_page_numbers: bool,
+	pub strip_invisible_chars: bool,
+	/// join hard wrapped lines of plain text, can be set per book
+	pub reflow_text: bool,
+	/// spaces for tab in preformatted text
+	pub tab_width: u8,
+	/// chapter number for goto means toc entry, otherwise spine index
+	pub chapter_number_by_toc: bool,
+	/// chars of each stable page, 0 for disabled
+	pub chars_per_page: usize,
+	pub continue_reading: ContinueReading,
 	#[cfg(feature = "gui")]
 	pub gui: GuiConfiguration,
 
@@ -314,6 +775,15 @@ impl Configuration {
 			render_han: self.render_han,
 			dark_theme: self.dark_theme,
 			history: self.history.clone(),
+			reading_speed: self.reading_speed.clone(),
+			sync_dir: self.sync_dir.clone(),
+			show_page_numbers: self.show_page_numbers,
+			strip_invisible_chars: self.strip_invisible_chars,
+			reflow_text: self.reflow_text,
+			tab_width: self.tab_width,
+			chapter_number_by_toc: self.chapter_number_by_toc,
+			chars_per_page: self.chars_per_page,
+			continue_reading: self.continue_reading,
 			#[cfg(feature = "gui")]
 			gui: self.gui.clone(),
 		};
@@ -324,6 +794,81 @@ impl Configuration {
 		Ok(())
 	}
 
+	/// folder of per-book notes, beside the config file
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn notes_dir(&self) -> PathBuf
+	{
+		self.config_file.with_file_name("notes")
+	}
+
+	/// dictionaries and whether to cache them, configured in gui settings
+	#[cfg(feature = "dict")]
+	#[inline]
+	pub fn dictionaries(&self) -> (&[PathConfig], bool)
+	{
+		#[cfg(feature = "gui")]
+		{
+			(&self.gui.dictionaries, self.gui.cache_dict)
+		}
+		#[cfg(not(feature = "gui"))]
+		{
+			(&[], false)
+		}
+	}
+
+	#[inline]
+	pub fn content_options(&self) -> ContentOptions
+	{
+		ContentOptions {
+			show_page_numbers: self.show_page_numbers,
+			strip_invisible_chars: self.strip_invisible_chars,
+			reflow_text: self.reflow_text,
+			tab_width: self.tab_width,
+			#[cfg(feature = "gui")]
+			lang_fonts: self.gui.lang_fonts.clone(),
+			#[cfg(not(feature = "gui"))]
+			lang_fonts: HashMap::new(),
+			#[cfg(feature = "gui")]
+			page_footnotes: self.gui.page_footnotes,
+			#[cfg(not(feature = "gui"))]
+			page_footnotes: false,
+			language: None,
+		}
+	}
+
+	/// read the config file again, without touching current configuration
+	pub fn read_file(&self) -> Result<RawConfig>
+	{
+		let string = fs::read_to_string(&self.config_file)?;
+		Ok(toml::from_str(&string)?)
+	}
+
+	/// replace with the config read from file, keep states changed
+	/// by reading, like theme and sidebar, history db need restart
+	pub fn reload(&mut self, raw_config: RawConfig)
+	{
+		self.orig = raw_config.clone();
+		self.render_han = raw_config.render_han;
+		self.reading_speed = raw_config.reading_speed;
+		self.sync_dir = raw_config.sync_dir;
+		self.show_page_numbers = raw_config.show_page_numbers;
+		self.strip_invisible_chars = raw_config.strip_invisible_chars;
+		self.reflow_text = raw_config.reflow_text;
+		self.tab_width = raw_config.tab_width;
+		self.chapter_number_by_toc = raw_config.chapter_number_by_toc;
+		self.chars_per_page = raw_config.chars_per_page;
+		self.continue_reading = raw_config.continue_reading;
+		#[cfg(feature = "gui")]
+		{
+			let mut gui = raw_config.gui;
+			gui.sidebar_size = self.gui.sidebar_size;
+			gui.sidebar_open = self.gui.sidebar_open;
+			gui.sidebar_tab = self.gui.sidebar_tab.take();
+			self.gui = gui;
+		}
+	}
+
 	#[cfg(feature = "gui")]
 	pub fn curr_theme(&self) -> &Colors
 	{
@@ -349,6 +894,12 @@ impl Configuration {
 			custom_style: row.get(9)?,
 			font_size: row.get::<usize, Option<u8>>(10)?.
 				unwrap_or(default_font_size()),
+			ts: row.get(11)?,
+			leading_chars: row.get(12)?,
+			reflow_text: row.get(13)?,
+			render_han: row.get(14)?,
+			language: row.get(15)?,
+			encoding: row.get(16)?,
 		})
 	}
 
@@ -372,7 +923,12 @@ select row_id,
        strip_empty_lines,
        custom_style,
        font_size,
-       ts
+       ts,
+       leading_chars,
+       reflow_text,
+       render_han,
+       language,
+       encoding
 from history
 where filename = ?
 ")?;
@@ -380,10 +936,11 @@ where filename = ?
 		if let Some(info) = iter.next() {
 			Ok(BookLoadingInfo::History(info?))
 		} else {
+			// new reading with the default custom style
 			#[cfg(feature = "gui")]
-			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, self.gui.default_font_size)) }
+			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, self.gui.default_font_size, self.gui.default_style.clone())) }
 			#[cfg(not(feature = "gui"))]
-			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, default_font_size())) }
+			{ Ok(BookLoadingInfo::NewReading(filename, 0, 0, default_font_size(), None)) }
 		}
 	}
 
@@ -401,7 +958,12 @@ select row_id,
        strip_empty_lines,
        custom_style,
        font_size,
-       ts
+       ts,
+       leading_chars,
+       reflow_text,
+       render_han,
+       language,
+       encoding
 from history
 where row_id = ?
 ")?;
@@ -420,12 +982,14 @@ where row_id = ?
 			self.history_db.execute("
 insert into history (filename, inner_book, chapter, line, position,
                      custom_color, custom_font, strip_empty_lines,
-                     custom_style, font_size, ts)
-values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-", (&reading.filename, reading.inner_book, reading.chapter, reading.line,
+                     custom_style, font_size, ts, leading_chars, reflow_text,
+                     render_han, language, encoding)
+values (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+", params![&reading.filename, reading.inner_book, reading.chapter, reading.line,
 				reading.position, reading.custom_color, reading.custom_font,
 				reading.strip_empty_lines, &reading.custom_style,
-				reading.font_size, ts))?;
+				reading.font_size, ts, reading.leading_chars, reading.reflow_text,
+				reading.render_han, &reading.language, &reading.encoding])?;
 			reading.row_id = self.history_db.last_insert_rowid();
 		} else {
 			self.history_db.execute("
@@ -440,13 +1004,21 @@ set filename          = ?,
     strip_empty_lines = ?,
     custom_style      = ?,
     font_size         = ?,
-    ts                = ?
+    ts                = ?,
+    leading_chars     = ?,
+    reflow_text       = ?,
+    render_han        = ?,
+    language          = ?,
+    encoding          = ?
 where row_id = ?
-", (&reading.filename, reading.inner_book, reading.chapter, reading.line,
+", params![&reading.filename, reading.inner_book, reading.chapter, reading.line,
 				reading.position, reading.custom_color, reading.custom_font,
 				reading.strip_empty_lines, &reading.custom_style,
-				reading.font_size, ts, reading.row_id))?;
+				reading.font_size, ts, reading.leading_chars, reading.reflow_text,
+				reading.render_han, &reading.language, &reading.encoding,
+				reading.row_id])?;
 		}
+		reading.ts = ts;
 		Ok(())
 	}
 }
@@ -480,7 +1052,7 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 				None
 			};
 			let history_db = load_history_db(&raw_config.history)?;
-			if current.is_none() {
+			if current.is_none() && raw_config.continue_reading == ContinueReading::Always {
 				if let Some(latest_reading) = query(&history_db, 1, None, None)?.pop() {
 					current = Some(latest_reading.filename);
 				}
@@ -490,6 +1062,15 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 				render_han: raw_config.render_han,
 				dark_theme: raw_config.dark_theme,
 				history: raw_config.history,
+				reading_speed: raw_config.reading_speed,
+				sync_dir: raw_config.sync_dir,
+				show_page_numbers: raw_config.show_page_numbers,
+				strip_invisible_chars: raw_config.strip_invisible_chars,
+				reflow_text: raw_config.reflow_text,
+				tab_width: raw_config.tab_width,
+				chapter_number_by_toc: raw_config.chapter_number_by_toc,
+				chars_per_page: raw_config.chars_per_page,
+				continue_reading: raw_config.continue_reading,
 				#[cfg(feature = "gui")]
 				gui: raw_config.gui,
 				config_file,
@@ -508,6 +1089,15 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 				render_han: false,
 				dark_theme: false,
 				history: history.clone(),
+				reading_speed: Default::default(),
+				sync_dir: None,
+				show_page_numbers: false,
+				strip_invisible_chars: false,
+				reflow_text: false,
+				tab_width: DEFAULT_TAB_WIDTH,
+				chapter_number_by_toc: false,
+				chars_per_page: 0,
+				continue_reading: Default::default(),
 				#[cfg(feature = "gui")]
 				gui: Default::default(),
 			};
@@ -517,6 +1107,15 @@ pub(super) fn load_config(filename: Option<String>, config_file: PathBuf, config
 				render_han: false,
 				dark_theme: false,
 				history,
+				reading_speed: Default::default(),
+				sync_dir: None,
+				show_page_numbers: false,
+				strip_invisible_chars: false,
+				reflow_text: false,
+				tab_width: DEFAULT_TAB_WIDTH,
+				chapter_number_by_toc: false,
+				chars_per_page: 0,
+				continue_reading: Default::default(),
 				#[cfg(feature = "gui")]
 				gui: Default::default(),
 
@@ -555,7 +1154,67 @@ fn default_font_size() -> u8
 	20
 }
 
-const CURRENT_DB_VERSION: u16 = 2;
+#[inline]
+#[cfg(feature = "gui")]
+fn default_font_size_step() -> u8
+{
+	2
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_min_font_size() -> u8
+{
+	20
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_max_font_size() -> u8
+{
+	50
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_scroll_step() -> u8
+{
+	1
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_dyslexia_font_family() -> String
+{
+	String::from("OpenDyslexic")
+}
+
+#[inline]
+#[cfg(feature = "gui")]
+fn default_max_image_scale() -> f32
+{
+	2.0
+}
+
+#[inline]
+fn default_tab_width() -> u8
+{
+	DEFAULT_TAB_WIDTH
+}
+
+#[inline]
+fn default_words_per_minute() -> usize
+{
+	200
+}
+
+#[inline]
+fn default_chars_per_minute() -> usize
+{
+	400
+}
+
+const CURRENT_DB_VERSION: u16 = 7;
 
 #[inline]
 fn load_history_db(path: &PathBuf) -> Result<Connection>
@@ -582,6 +1241,11 @@ create table history
     custom_style      varchar,
     font_size         unsigned big int,
     ts                unsigned big int,
+    leading_chars     unsigned big int,
+    reflow_text       unsigned big int,
+    render_han        unsigned big int,
+    language          varchar,
+    encoding          varchar,
     unique (filename)
 )", ())?;
 		conn
@@ -612,6 +1276,26 @@ fn upgrade_db(connection: &Connection) -> Result<()>
 		connection.execute("alter table history add font_size unsigned big int", [])?;
 		connection.execute("update info set version = 2", [])?;
 	}
+	if version < 3 {
+		connection.execute("alter table history add leading_chars unsigned big int", [])?;
+		connection.execute("update info set version = 3", [])?;
+	}
+	if version < 4 {
+		connection.execute("alter table history add reflow_text unsigned big int", [])?;
+		connection.execute("update info set version = 4", [])?;
+	}
+	if version < 5 {
+		connection.execute("alter table history add render_han unsigned big int", [])?;
+		connection.execute("update info set version = 5", [])?;
+	}
+	if version < 6 {
+		connection.execute("alter table history add language varchar", [])?;
+		connection.execute("update info set version = 6", [])?;
+	}
+	if version < 7 {
+		connection.execute("alter table history add encoding varchar", [])?;
+		connection.execute("update info set version = 7", [])?;
+	}
 	Ok(())
 }
 
@@ -630,7 +1314,12 @@ select row_id,
        strip_empty_lines,
        custom_style,
        font_size,
-       ts
+       ts,
+       leading_chars,
+       reflow_text,
+       render_han,
+       language,
+       encoding
 from history
 order by ts desc
 ")?;
@@ -686,6 +1375,24 @@ pub struct RawConfig {
 	pub render_han: bool,
 	pub dark_theme: bool,
 	history: PathBuf,
+	#[serde(default)]
+	pub reading_speed: ReadingSpeed,
+	#[serde(default)]
+	pub sync_dir: Option<PathBuf>,
+	#[serde(default)]
+	pub show_page_numbers: bool,
+	#[serde(default)]
+	pub strip_invisible_chars: bool,
+	#[serde(default)]
+	pub reflow_text: bool,
+	#[serde(default = "default_tab_width")]
+	pub tab_width: u8,
+	#[serde(default)]
+	pub chapter_number_by_toc: bool,
+	#[serde(default)]
+	pub chars_per_page: usize,
+	#[serde(default)]
+	pub continue_reading: ContinueReading,
 	#[cfg(feature = "gui")]
 	#[serde(default)]
 	pub gui: GuiConfiguration,
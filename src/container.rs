@@ -40,12 +40,19 @@ impl ContainerManager {
 		Ok(Box::new(DummyContainer::new(&filename)))
 	}
 
+	/// packed or folder books, not a single book file
+	#[allow(unused)]
+	pub fn is_container(&self, filename: &str) -> bool
+	{
+		self.loaders.iter().any(|loader| loader.accept(filename))
+	}
+
 	pub fn load_book(&self, container: &mut Box<dyn Container>, loading: BookLoadingInfo)
 		-> Result<(Box<dyn Book>, ReadingInfo)>
 	{
 		let (book_index, chapter) = match &loading {
-			BookLoadingInfo::NewReading(_, inner_book, chapter, _) => (*inner_book, *chapter),
-			BookLoadingInfo::ChangeInnerBook(_, inner_book, _, _, _) => (*inner_book, 0),
+			BookLoadingInfo::NewReading(_, inner_book, chapter, ..) => (*inner_book, *chapter),
+			BookLoadingInfo::ChangeInnerBook(reading) => (reading.inner_book, 0),
 			BookLoadingInfo::History(reading) | BookLoadingInfo::Reload(reading)
 			=> (reading.inner_book, reading.chapter),
 		};
@@ -125,6 +132,8 @@ impl Clone for BookName {
 // for non pack file as a container with single book
 pub struct DummyContainer {
 	filename: String,
+	// content read already, taken by the first loading
+	content: Option<Vec<u8>>,
 }
 
 impl Container for DummyContainer {
@@ -143,7 +152,11 @@ impl Container for DummyContainer {
 	#[inline]
 	fn book_content(&mut self, _inner_index: usize) -> Result<BookContent>
 	{
-		Ok(BookContent::File(self.filename.clone()))
+		if let Some(content) = self.content.take() {
+			Ok(BookContent::Buf(content))
+		} else {
+			Ok(BookContent::File(self.filename.clone()))
+		}
 	}
 }
 
@@ -152,6 +165,17 @@ impl DummyContainer {
 	{
 		DummyContainer {
 			filename: filename.to_owned(),
+			content: None,
+		}
+	}
+
+	/// single book file with content read already
+	#[allow(unused)]
+	pub fn preloaded(filename: &str, content: Vec<u8>) -> Self
+	{
+		DummyContainer {
+			filename: filename.to_owned(),
+			content: Some(content),
 		}
 	}
 }
@@ -29,7 +29,7 @@ impl ContainerLoader for ZipLoader {
 			}
 			buf.extend_from_slice(zip_file.name_raw());
 		}
-		let names = plain_text(buf, true)?;
+		let names = plain_text(buf, true, None)?;
 		let names = names.split('\n');
 		let mut files = vec![];
 		for (idx, name) in names.enumerate() {
@@ -71,3 +71,101 @@ impl Container for ZipContainer {
 		Ok(BookContent::Buf(content))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+	use std::fs::{self, File};
+	use std::io::{Cursor, Seek, Write};
+	use zip::write::SimpleFileOptions;
+	use zip::{CompressionMethod, ZipWriter};
+	use crate::BookLoader;
+	use crate::config::BookLoadingInfo;
+	use crate::container::{BookContent, ContainerLoader, ContainerManager, load_book, load_container};
+	use crate::container::zip::ZipLoader;
+
+	fn write_entries<W: Write + Seek>(writer: W, entries: &[(&str, &[u8])]) -> W
+	{
+		let mut writer = ZipWriter::new(writer);
+		for (name, content) in entries {
+			// epub mimetype must be stored
+			let options = SimpleFileOptions::default()
+				.compression_method(CompressionMethod::Stored);
+			writer.start_file(*name, options).unwrap();
+			writer.write_all(content).unwrap();
+		}
+		writer.finish().unwrap()
+	}
+
+	fn minimal_epub(text: &str) -> Vec<u8>
+	{
+		let container = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+<rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+		let opf = r#"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="id">urn:uuid:00000000-0000-0000-0000-000000000000</dc:identifier>
+<dc:title>Minimal</dc:title>
+<dc:language>en</dc:language>
+</metadata>
+<manifest>
+<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+<item id="ch1" href="ch1.xhtml" media-type="application/xhtml+xml"/>
+</manifest>
+<spine><itemref idref="ch1"/></spine>
+</package>"#;
+		let nav = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>Nav</title></head>
+<body><nav epub:type="toc"><ol><li><a href="ch1.xhtml">Chapter 1</a></li></ol></nav></body>
+</html>"#;
+		let chapter = format!(r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>Chapter 1</title></head>
+<body><p>{}</p></body>
+</html>"#, text);
+		let entries: [(&str, &[u8]); 5] = [
+			("mimetype", b"application/epub+zip"),
+			("META-INF/container.xml", container.as_bytes()),
+			("OEBPS/content.opf", opf.as_bytes()),
+			("OEBPS/nav.xhtml", nav.as_bytes()),
+			("OEBPS/ch1.xhtml", chapter.as_bytes()),
+		];
+		write_entries(Cursor::new(vec![]), &entries).into_inner()
+	}
+
+	#[test]
+	fn test_zip_of_epubs()
+	{
+		let path = env::temp_dir().join(format!("tbr-epubs-{}.zip", std::process::id()));
+		let first = minimal_epub("first book");
+		let second = minimal_epub("second book");
+		let entries: [(&str, &[u8]); 3] = [
+			("books/b.epub", &second),
+			("notes.bin", b"ignored"),
+			("books/a.epub", &first),
+		];
+		write_entries(File::create(&path).unwrap(), &entries);
+		let filename = path.to_str().unwrap();
+
+		let book_loader = BookLoader::default();
+		let mut container = ZipLoader {}.open(filename, &book_loader).unwrap();
+		let names: Vec<&str> = container.inner_book_names().unwrap()
+			.iter()
+			.map(|name| name.as_ref())
+			.collect();
+		assert_eq!(names, vec!["books/a.epub", "books/b.epub"]);
+		let content = container.book_content(1).unwrap();
+		assert!(matches!(content, BookContent::Buf(buf) if buf == second));
+
+		let container_manager = ContainerManager::default();
+		let mut container = load_container(&container_manager, filename).unwrap();
+		let loading = BookLoadingInfo::NewReading(filename, 1, 0, 0, None);
+		let (book, reading) = load_book(&container_manager, &mut container, loading).unwrap();
+		assert_eq!(reading.inner_book, 1);
+		assert!(book.lines().iter().any(|line| line.to_string().contains("second book")));
+		fs::remove_file(path).unwrap();
+	}
+}
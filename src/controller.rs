@@ -5,10 +5,11 @@ use anyhow::{anyhow, bail, Result};
 use fancy_regex::Regex;
 
 use crate::{ContainerManager, Position};
-use crate::book::{Book, Line};
+use crate::book::{Book, Line, SearchOptions, search_lines, WordCount};
 use crate::common::TraceInfo;
-use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::config::{BookLoadingInfo, ReadingInfo, ReadingSpeed};
 use crate::container::{Container, load_book, load_container};
+use crate::html_parser::ContentOptions;
 
 const TRACE_SIZE: usize = 100;
 
@@ -43,10 +44,26 @@ pub struct HighlightInfo {
 	pub mode: HighlightMode,
 }
 
+/// position to open the book at on start, chapter number start from 1
+#[derive(Clone)]
+pub struct StartTarget {
+	pub chapter: Option<usize>,
+	// element id, may be prefixed by the file
+	pub anchor: Option<String>,
+}
+
 pub struct ReadingStatus<'a> {
 	pub title: Option<&'a str>,
+	// print page label
+	pub page: Option<&'a str>,
 	pub total_line: usize,
 	pub current_line: usize,
+	// estimated minutes for reading current chapter
+	pub reading_minutes: usize,
+	// stable page (current, total)
+	pub pages: Option<(usize, usize)>,
+	// highlighted search match (current, total) in current chapter
+	pub matches: Option<(usize, usize)>,
 }
 
 impl<'a> ReadingStatus<'a> {
@@ -63,10 +80,21 @@ impl<'a> Display for ReadingStatus<'a> {
 	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
 	{
 		if let Some(title) = &self.title {
-			write!(f, "{}({}:{})", title, self.total_line, self.current_line)
+			write!(f, "{}", title)?;
+		}
+		if let Some((current, total)) = &self.pages {
+			write!(f, "({}/{})", current, total)?;
 		} else {
-			write!(f, "({}:{})", self.total_line, self.current_line)
+			write!(f, "({}:{})", self.total_line, self.current_line)?;
+		}
+		if let Some(page) = &self.page {
+			write!(f, " p.{}", page)?;
 		}
+		write!(f, " ~{}min", self.reading_minutes)?;
+		if let Some((current, total)) = &self.matches {
+			write!(f, " match {} of {}", current, total)?;
+		}
+		Ok(())
 	}
 }
 
@@ -78,9 +106,24 @@ pub struct Controller<C, R: Render<C> + ?Sized>
 	pub book: Box<dyn Book>,
 	pub reading: ReadingInfo,
 	pub search_pattern: String,
+	// options of the last search, for searching again
+	search_options: SearchOptions,
 	pub render: Box<R>,
+	pub reading_speed: ReadingSpeed,
+	content_options: ContentOptions,
+	chars_per_page: usize,
+	// start char offset of each chapter, and total chars at last
+	chapter_offsets: Option<Vec<usize>>,
+	// start char offset of each line in the chapter
+	line_offsets: Option<(usize, Vec<usize>)>,
+	// offsets calculated only when needed, after content changed
+	offsets_outdated: bool,
+	// word count of the chapter for reading time, reset when book loaded
+	chapter_word_count: Option<(usize, WordCount)>,
 
 	highlight: Option<HighlightInfo>,
+	// (current, total) of the search highlight
+	search_matches: Option<(usize, usize)>,
 	trace: Vec<TraceInfo>,
 	current_trace: usize,
 	next: Option<Position>,
@@ -88,18 +131,23 @@ pub struct Controller<C, R: Render<C> + ?Sized>
 
 impl<C, R: Render<C> + ?Sized> Controller<C, R>
 {
-	pub fn new(loading: BookLoadingInfo, render: Box<R>, render_context: &mut C) -> Result<Self>
+	pub fn new(loading: BookLoadingInfo, content_options: ContentOptions,
+		render: Box<R>, render_context: &mut C) -> Result<Self>
 	{
-		let container_manager = Default::default();
+		let mut container_manager = ContainerManager::default();
+		// books parsed with the options at loading, no parsing again
+		container_manager.book_loader.set_content_options(content_options.clone());
 		let mut container = load_container(&container_manager, loading.filename())?;
 		let (book, reading) = load_book(&container_manager, &mut container, loading)?;
-		Ok(Controller::from_data(
+		let mut controller = Controller::from_data(
 			reading,
 			container_manager,
 			container,
 			book,
 			render,
-			render_context))
+			render_context);
+		controller.content_options = content_options;
+		Ok(controller)
 	}
 
 	#[inline]
@@ -116,11 +164,20 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 			book,
 			reading,
 			search_pattern: "".to_string(),
+			search_options: Default::default(),
 			trace,
 			current_trace: 0,
 			highlight: None,
+			search_matches: None,
 			next: None,
 			render,
+			reading_speed: Default::default(),
+			content_options: Default::default(),
+			chars_per_page: 0,
+			chapter_offsets: None,
+			line_offsets: None,
+			offsets_outdated: true,
+			chapter_word_count: None,
 		}
 	}
 	#[inline]
@@ -144,6 +201,8 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 	#[inline]
 	pub fn redraw(&mut self, context: &mut C)
 	{
+		self.load_page_offsets();
+		self.load_word_count();
 		let next = self.render.redraw(
 			self.book.as_ref(),
 			self.book.lines(),
@@ -157,6 +216,8 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 	#[inline]
 	pub fn redraw_at(&mut self, line: usize, offset: usize, context: &mut C)
 	{
+		self.load_page_offsets();
+		self.load_word_count();
 		let next = self.render.redraw(
 			self.book.as_ref(),
 			self.book.lines(),
@@ -176,40 +237,229 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		&self.search_pattern
 	}
 
+	/// language of current book, shared by features depending on it
+	#[inline]
+	pub fn language(&self) -> Option<&str>
+	{
+		self.reading.language(self.book.as_ref())
+	}
+
 	pub fn status(&self) -> ReadingStatus
 	{
 		let title = self.book
 			.title(self.reading.line, self.reading.position);
+		let page = self.book
+			.page_label(self.reading.line, self.reading.position);
 		ReadingStatus {
 			title,
+			page,
 			total_line: self.book.lines().len(),
 			current_line: self.reading.line + 1,
+			reading_minutes: self.word_count().minutes(&self.reading_speed),
+			pages: self.current_page().zip(self.total_pages()),
+			matches: match &self.highlight {
+				Some(HighlightInfo { mode: HighlightMode::Search, .. }) => self.search_matches,
+				_ => None,
+			},
+		}
+	}
+
+	/// counted once for each chapter
+	fn load_word_count(&mut self)
+	{
+		let chapter = self.book.current_chapter();
+		if self.chapter_word_count.map_or(true, |(c, _)| c != chapter) {
+			self.chapter_word_count = Some((chapter, self.book.word_count()));
+		}
+	}
+
+	#[inline]
+	fn word_count(&self) -> WordCount
+	{
+		match self.chapter_word_count {
+			Some((chapter, count)) if chapter == self.book.current_chapter() => count,
+			_ => self.book.word_count(),
+		}
+	}
+
+	/// setup chars of stable page, 0 for disabled
+	pub fn set_chars_per_page(&mut self, chars_per_page: usize)
+	{
+		self.chars_per_page = chars_per_page;
+		self.offsets_outdated = true;
+	}
+
+	/// char offsets for stable page, only loaded when page enabled
+	fn load_page_offsets(&mut self)
+	{
+		if self.chars_per_page == 0 {
+			return;
+		}
+		if self.offsets_outdated {
+			self.offsets_outdated = false;
+			self.line_offsets = None;
+			self.chapter_offsets = self.book.chapter_char_counts()
+				.map(|counts| prefix_offsets(counts.into_iter()));
+		}
+		if self.chapter_offsets.is_none() {
+			return;
+		}
+		let chapter = self.book.current_chapter();
+		if self.line_offsets.as_ref().map_or(true, |(c, _)| *c != chapter) {
+			let offsets = prefix_offsets(self.book.lines().iter().map(|line| line.len()));
+			self.line_offsets = Some((chapter, offsets));
+		}
+	}
+
+	/// stable page number of reading position, start from 1,
+	/// independent of the layout
+	pub fn current_page(&self) -> Option<usize>
+	{
+		if self.chars_per_page == 0 {
+			return None;
+		}
+		let chapter = self.book.current_chapter();
+		let chapter_offset = *self.chapter_offsets.as_ref()?.get(chapter)?;
+		let (line_chapter, line_offsets) = self.line_offsets.as_ref()?;
+		if *line_chapter != chapter {
+			return None;
 		}
+		let line_offset = *line_offsets.get(self.reading.line)?;
+		let offset = chapter_offset + line_offset + self.reading.position;
+		Some(offset / self.chars_per_page + 1)
 	}
 
-	pub fn search(&mut self, pattern: &str, context: &mut C) -> Result<()>
+	pub fn total_pages(&self) -> Option<usize>
+	{
+		if self.chars_per_page == 0 {
+			return None;
+		}
+		let total = *self.chapter_offsets.as_ref()?.last()?;
+		Some(total.div_ceil(self.chars_per_page).max(1))
+	}
+
+	/// reading position across the whole book, from 0 to 1,
+	/// by chapters and lines of current chapter
+	pub fn progress(&self) -> f64
+	{
+		let chapter_count = self.book.chapter_count().max(1);
+		let lines = self.book.lines().len().max(1);
+		let chapter_progress = self.reading.line as f64 / lines as f64;
+		((self.book.current_chapter() as f64 + chapter_progress) / chapter_count as f64)
+			.clamp(0.0, 1.0)
+	}
+
+	/// shareable reading location, book name with chapter, line, offset and percentage
+	#[cfg(feature = "gui")]
+	pub fn location(&self) -> String
+	{
+		format!("{}, chapter {}, {}:{} ({:.1}%)",
+			self.reading_book_name(),
+			self.book.current_chapter() + 1,
+			self.reading.line + 1,
+			self.reading.position,
+			self.progress() * 100.0)
+	}
+
+	/// jump to position of progress, the reverse of progress()
+	pub fn goto_percent(&mut self, percent: f64, context: &mut C) -> Result<String>
+	{
+		let chapter_count = self.book.chapter_count().max(1);
+		let position = percent.clamp(0.0, 1.0) * chapter_count as f64;
+		let chapter = (position as usize).min(chapter_count - 1);
+		if chapter != self.book.current_chapter() {
+			match self.book.goto_chapter(chapter)? {
+				Some(new_chapter) => self.reading.chapter = new_chapter,
+				None => bail!("Chapter not exists: {}", chapter + 1),
+			}
+		}
+		let lines = self.book.lines().len();
+		let line = ((position - chapter as f64) * lines as f64) as usize;
+		self.redraw_at(line.min(lines.saturating_sub(1)), 0, context);
+		Ok(self.status().to_string())
+	}
+
+	/// first match from current position, in following chapters
+	/// if none found in current one
+	pub fn search(&mut self, pattern: &str, options: &SearchOptions,
+		context: &mut C) -> Result<()>
 	{
 		self.search_pattern = String::from(pattern);
-		self.search_next(self.reading.line, self.reading.position, context)
+		self.search_options = options.clone();
+		let regex = options.build_regex(pattern)?;
+		let line = self.reading.line;
+		let offset = self.reading.position;
+		let found = search_lines(self.book.lines(), &regex);
+		if let Some(index) = found.iter()
+			.position(|(start, _)| start.line > line
+				|| (start.line == line && start.offset >= offset)) {
+			let (start, end) = &found[index];
+			self.search_matches = Some((index + 1, found.len()));
+			self.highlight = Some(HighlightInfo {
+				line: start.line,
+				start: start.offset,
+				end: end.offset,
+				mode: HighlightMode::Search,
+			});
+			self.highlight_setup(context);
+			return Ok(());
+		}
+		let Some((found_chapter, found)) = self.book
+			.search_chapter(self.reading.chapter + 1, &regex)? else {
+			return Ok(());
+		};
+		match self.book.goto_chapter(found_chapter)? {
+			Some(new_chapter) => self.reading.chapter = new_chapter,
+			None => return Ok(()),
+		}
+		let (start, end) = &found[0];
+		self.search_matches = Some((1, found.len()));
+		self.highlight = Some(HighlightInfo {
+			line: start.line,
+			start: start.offset,
+			end: end.offset,
+			mode: HighlightMode::Search,
+		});
+		self.goto_highlight(context);
+		self.redraw(context);
+		Ok(())
 	}
 
 	#[inline]
 	pub fn book_loaded(&mut self, context: &mut C)
 	{
 		self.highlight = None;
+		self.chapter_word_count = None;
 		self.render.book_loaded(self.book.as_ref(), &self.reading, context);
 	}
 
 	pub fn switch_container(&mut self, loading: BookLoadingInfo,
 		context: &mut C) -> Result<String>
 	{
-		let mut container = load_container(&self.container_manager, loading.filename())?;
+		let container = load_container(&self.container_manager, loading.filename())?;
+		self.use_container(container, loading, context)
+	}
+
+	/// switch to single book file with content read already
+	#[cfg(feature = "gui")]
+	pub fn switch_preloaded(&mut self, loading: BookLoadingInfo,
+		content: Vec<u8>, context: &mut C) -> Result<String>
+	{
+		let container = Box::new(crate::container::DummyContainer::preloaded(
+			loading.filename(), content));
+		self.use_container(container, loading, context)
+	}
+
+	fn use_container(&mut self, mut container: Box<dyn Container>,
+		loading: BookLoadingInfo, context: &mut C) -> Result<String>
+	{
 		let (book, reading) = load_book(
 			&self.container_manager,
 			&mut container, loading)?;
 		self.container = container;
 		self.book = book;
 		self.reading = reading;
+		self.reload_content_options()?;
 		self.trace.clear();
 		self.trace.push(TraceInfo { chapter: self.reading.chapter, line: self.reading.line, offset: self.reading.position });
 		self.current_trace = 0;
@@ -236,6 +486,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		let (book, reading) = load_book(&self.container_manager, &mut self.container, loading)?;
 		self.book = book;
 		self.reading = reading;
+		self.reload_content_options()?;
 		self.trace.clear();
 		self.trace.push(TraceInfo { chapter: self.reading.chapter, line: self.reading.line, offset: self.reading.position });
 		self.current_trace = 0;
@@ -244,6 +495,45 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		Ok(())
 	}
 
+	/// setup before the first drawing, no redraw
+	pub fn init_content_options(&mut self, options: ContentOptions, context: &mut C) -> Result<()>
+	{
+		self.container_manager.book_loader.set_content_options(options.clone());
+		self.content_options = options;
+		if self.reload_content_options()? {
+			self.book_loaded(context);
+		}
+		Ok(())
+	}
+
+	pub fn set_content_options(&mut self, options: ContentOptions, context: &mut C) -> Result<()>
+	{
+		self.container_manager.book_loader.set_content_options(options.clone());
+		self.content_options = options;
+		if self.reload_content_options()? {
+			self.book_loaded(context);
+			self.redraw(context);
+		}
+		Ok(())
+	}
+
+	fn reload_content_options(&mut self) -> Result<bool>
+	{
+		let options = self.reading.content_options(&self.content_options);
+		// the book may be changed too
+		self.offsets_outdated = true;
+		let visible = visible_offset(self.book.lines(), self.reading.line,
+			self.reading.position);
+		if !self.book.set_content_options(options)? {
+			return Ok(false);
+		}
+		// lines joined or split, keep reading the same text
+		let position = visible_position(self.book.lines(), visible);
+		self.reading.line = position.line;
+		self.reading.position = position.offset;
+		Ok(true)
+	}
+
 	pub fn goto_line(&mut self, line: usize, context: &mut C) -> Result<()>
 	{
 		let lines = &self.book.lines();
@@ -287,7 +577,8 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 						&reading.filename,
 						reading.inner_book - 1,
 						usize::MAX,
-						reading.font_size);
+						reading.font_size,
+						None);
 					let (book, mut new_reading) = load_book(&self.container_manager, &mut self.container, loading)?;
 					self.book = book;
 					let lines = self.book.lines();
@@ -375,6 +666,53 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		}
 	}
 
+	/// goto print page by it's label
+	pub fn goto_page(&mut self, label: &str, context: &mut C) -> Result<String>
+	{
+		let trace_info = self.book.page_position(label)
+			.ok_or(anyhow!("Page not found: {}", label))?;
+		self.do_goto_toc(trace_info, context)
+			.ok_or(anyhow!("Failed goto page: {}", label))
+	}
+
+	/// goto chapter by 1 based number, number of toc entry if by_toc,
+	/// otherwise chapter index in spine
+	pub fn goto_chapter_index(&mut self, chapter_no: usize, by_toc: bool,
+		context: &mut C) -> Result<String>
+	{
+		if chapter_no == 0 {
+			bail!("Chapter not exists: {}", chapter_no);
+		}
+		let index = chapter_no - 1;
+		if by_toc {
+			return self.goto_toc(index, context)
+				.ok_or(anyhow!("Chapter not exists: {}", chapter_no));
+		}
+		match self.book.goto_chapter(index)? {
+			Some(new_chapter) if new_chapter == index => {
+				self.reading.chapter = new_chapter;
+				self.redraw_at(0, 0, context);
+				Ok(self.status().to_string())
+			}
+			_ => bail!("Chapter not exists: {}", chapter_no),
+		}
+	}
+
+	/// goto the chapter first if any, then the anchor
+	pub fn goto_target(&mut self, target: &StartTarget, context: &mut C) -> Result<()>
+	{
+		if let Some(chapter_no) = target.chapter {
+			self.goto_chapter_index(chapter_no, false, context)?;
+		}
+		if let Some(anchor) = &target.anchor {
+			let trace_info = self.book.anchor_position(anchor)
+				.ok_or(anyhow!("Anchor not found: {}", anchor))?;
+			self.do_goto_toc(trace_info, context)
+				.ok_or(anyhow!("Failed goto anchor: {}", anchor))?;
+		}
+		Ok(())
+	}
+
 	fn do_goto_toc(&mut self, trace_info: TraceInfo, context: &mut C) -> Option<String>
 	{
 		if self.reading.chapter != trace_info.chapter {
@@ -452,6 +790,27 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		}
 	}
 
+	/// search the first line of selected text as plain text,
+	/// and goto the next occurrence after the selection
+	pub fn search_selected(&mut self, context: &mut C) -> Result<Option<&str>>
+	{
+		let (line, start, pattern) = match &self.highlight {
+			Some(HighlightInfo { mode: HighlightMode::Selection(text, ..), line, start, .. }) => {
+				let text = text.lines().next().unwrap_or("").trim();
+				if text.is_empty() {
+					return Ok(None);
+				}
+				(*line, *start, fancy_regex::escape(text).to_string())
+			}
+			_ => return Ok(None),
+		};
+		self.search_pattern = pattern;
+		// pattern escaped already
+		self.search_options = SearchOptions::default();
+		self.search_next(line, start + 1, context)?;
+		Ok(Some(&self.search_pattern))
+	}
+
 	pub fn search_again(&mut self, forward: bool, context: &mut C) -> Result<()>
 	{
 		let (line, position) = match &self.highlight {
@@ -471,7 +830,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 	fn search_next(&mut self, start_line: usize, start_position: usize, context: &mut C) -> Result<()> {
 		let book = self.book.as_ref();
 		let lines = book.lines();
-		let regex = Regex::new(&self.search_pattern)?;
+		let regex = self.search_options.build_regex(&self.search_pattern)?;
 		let mut position = start_position;
 		for idx in start_line..lines.len() {
 			let line = &lines[idx];
@@ -482,6 +841,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 					end: range.end,
 					mode: HighlightMode::Search,
 				});
+				self.search_matches = search_match_index(lines, &regex, idx, range.start);
 				self.highlight_setup(context);
 				return Ok(());
 			}
@@ -492,7 +852,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 
 	fn search_prev(&mut self, start_line: usize, start_position: usize, context: &mut C) -> Result<()> {
 		let lines = self.book.lines();
-		let regex = Regex::new(&self.search_pattern)?;
+		let regex = self.search_options.build_regex(&self.search_pattern)?;
 		for idx in (0..=start_line).rev() {
 			let range = if idx == start_line {
 				if start_position == 0 {
@@ -510,6 +870,7 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 					end: range.end,
 					mode: HighlightMode::Search,
 				});
+				self.search_matches = search_match_index(lines, &regex, idx, range.start);
 				self.highlight_setup(context);
 				return Ok(());
 			}
@@ -727,6 +1088,37 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 		self.redraw(context);
 	}
 
+	/// select word at the position, the segmenter is tried first,
+	/// such as dictionary lookup for CJK text, word boundaries otherwise
+	pub fn select_word<F>(&mut self, line: usize, offset: usize, segmenter: F,
+		context: &mut C) -> bool
+		where F: FnOnce(&Line, usize) -> Option<(usize, usize)>
+	{
+		let range = match self.book.lines().get(line) {
+			Some(text) => segmenter(text, offset)
+				.or_else(|| text.word_at_offset(offset)),
+			None => None,
+		};
+		if let Some((from, to)) = range {
+			self.select_text(Position::new(line, from), Position::new(line, to), context);
+			true
+		} else {
+			false
+		}
+	}
+
+	/// extend selection from its start to the position, start
+	/// a new selection if nothing selected
+	pub fn extend_selection(&mut self, to: Position, context: &mut C)
+	{
+		let from = match &self.highlight {
+			Some(HighlightInfo { mode: HighlightMode::Selection(..), line, start, .. }) =>
+				Position::new(*line, *start),
+			_ => to.clone(),
+		};
+		self.select_text(from, to, context);
+	}
+
 	fn highlight_setup(&mut self, context: &mut C)
 	{
 		if let Some(highlight) = &self.highlight {
@@ -747,15 +1139,23 @@ impl<C, R: Render<C> + ?Sized> Controller<C, R>
 				false
 			};
 			if !in_current_screen {
-				let position = self.render.setup_highlight(self.book.as_ref(), self.book.lines(), highlight_line, highlight_start, context);
-				self.reading.line = position.line;
-				self.reading.position = position.offset;
-				self.push_trace(false);
+				self.goto_highlight(context);
 			}
 		}
 		self.redraw(context);
 	}
 
+	/// move to the highlight line, no redraw
+	fn goto_highlight(&mut self, context: &mut C)
+	{
+		if let Some(highlight) = &self.highlight {
+			let position = self.render.setup_highlight(self.book.as_ref(), self.book.lines(), highlight.line, highlight.start, context);
+			self.reading.line = position.line;
+			self.reading.position = position.offset;
+			self.push_trace(false);
+		}
+	}
+
 	#[allow(unused)]
 	#[inline]
 	pub fn clear_highlight(&mut self, context: &mut C)
@@ -807,4 +1207,58 @@ pub fn highlight_selection(highlight: &Option<HighlightInfo>) -> Option<&str>
 	} else {
 		None
 	}
-}
\ No newline at end of file
+}
+
+/// start offset of each count, and the total at last
+fn prefix_offsets(counts: impl Iterator<Item=usize>) -> Vec<usize>
+{
+	let mut offsets = vec![];
+	let mut offset = 0;
+	for count in counts {
+		offsets.push(offset);
+		offset += count;
+	}
+	offsets.push(offset);
+	offsets
+}
+
+/// count of visible chars before the position,
+/// not changed by joining or splitting lines
+fn visible_offset(lines: &Vec<Line>, line: usize, position: usize) -> usize
+{
+	let mut count = 0;
+	for (index, text) in lines.iter().enumerate().take(line + 1) {
+		let end = if index == line { position.min(text.len()) } else { text.len() };
+		count += (0..end)
+			.filter(|offset| text.char_at(*offset).map_or(false, |ch| !ch.is_whitespace()))
+			.count();
+	}
+	count
+}
+
+/// position of the visible char after count visible chars
+fn visible_position(lines: &Vec<Line>, mut count: usize) -> Position
+{
+	for (index, text) in lines.iter().enumerate() {
+		for offset in 0..text.len() {
+			if text.char_at(offset).map_or(true, |ch| ch.is_whitespace()) {
+				continue;
+			}
+			if count == 0 {
+				return Position::new(index, offset);
+			}
+			count -= 1;
+		}
+	}
+	Position::new(lines.len().saturating_sub(1), 0)
+}
+
+/// index from 1 of the match starts at line and offset, with total matches in lines
+fn search_match_index(lines: &Vec<Line>, regex: &Regex, line: usize, offset: usize)
+	-> Option<(usize, usize)>
+{
+	let found = search_lines(lines, regex);
+	let index = found.iter()
+		.position(|(start, _)| start.line == line && start.offset == offset)?;
+	Some((index + 1, found.len()))
+}
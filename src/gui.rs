@@ -1,44 +1,54 @@
-use std::cell::{Ref, RefCell, RefMut};
+use std::borrow::Cow;
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::io;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
+use std::sync::mpsc;
+use std::sync::mpsc::TryRecvError;
+use std::thread::spawn;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{bail, Result};
-use gtk4::{AlertDialog, Align, Application, ApplicationWindow, Button, CssProvider, DropTarget, EventControllerKey, FileDialog, FileFilter, gdk, GestureClick, HeaderBar, Image, Label, Orientation, Paned, Popover, PopoverMenu, PositionType, SearchEntry, Separator, Stack, ToggleButton, Widget, Window};
+use gtk4::{AlertDialog, Align, Application, ApplicationWindow, Button, CssProvider, DropTarget, EventControllerKey, FileDialog, FileFilter, gdk, GestureClick, HeaderBar, Image, Label, Orientation, Paned, Popover, PopoverMenu, PositionType, Scale, SearchEntry, Separator, Spinner, Stack, TickCallbackId, ToggleButton, Widget, Window};
 use gtk4::gdk::{Display, DragAction, Key, ModifierType, Rectangle, Texture};
 use gtk4::gdk_pixbuf::Pixbuf;
 use gtk4::gio::{ApplicationFlags, Cancellable, File, MemoryInputStream, Menu, MenuItem, MenuModel, SimpleAction, SimpleActionGroup};
 use gtk4::glib;
-use gtk4::glib::{Bytes, closure_local, ExitCode, format_size, Variant};
-use gtk4::glib::prelude::{ObjectExt, StaticType, ToVariant};
+use gtk4::glib::{Bytes, closure_local, ControlFlow, ExitCode, timeout_add_local, Variant, VariantTy};
+use gtk4::glib::prelude::{Cast, ObjectExt, StaticType, ToVariant};
 use gtk4::graphene::Point;
-use gtk4::prelude::{ActionExt, ActionMapExt, ApplicationExt, ApplicationExtManual, BoxExt, ButtonExt, DisplayExt, DrawingAreaExt, EditableExt, EventControllerExt, FileExt, GtkApplicationExt, GtkWindowExt, IsA, NativeExt, OrientableExt, PopoverExt, SeatExt, SurfaceExt, ToggleButtonExt, WidgetExt};
+use gtk4::prelude::{ActionExt, ActionMapExt, ApplicationExt, ApplicationExtManual, BoxExt, ButtonExt, DisplayExt, DrawingAreaExt, EditableExt, EventControllerExt, FileExt, GtkApplicationExt, GtkWindowExt, IsA, NativeExt, OrientableExt, PopoverExt, RangeExt, ScaleExt, SeatExt, SurfaceExt, ToggleButtonExt, WidgetExt};
 use pangocairo::glib::Propagation;
 use pangocairo::pango::EllipsizeMode;
 use resvg::{tiny_skia, usvg};
 
-use crate::{Asset, I18n, package_name};
-use crate::book::{Book, Line};
-use crate::common::{Position, txt_lines};
-use crate::config::{BookLoadingInfo, Configuration, ReadingInfo, SidebarPosition};
-use crate::container::{BookContent, BookName, Container, load_book, load_container};
-use crate::controller::Controller;
+use crate::{Asset, description, I18n, package_name, version, version_string};
+use crate::book::{Book, DrmError, extracted_epub_container, Line, SearchOptions};
+use crate::common::{is_cjk, Position, txt_lines};
+use crate::config::{BookLoadingInfo, Configuration, ContinueReading, EmptyLaunch, ReadingInfo, SidebarPosition};
+use crate::container::{BookContent, BookName, Container, title_for_filename};
+use crate::controller::{Controller, StartTarget};
 use crate::gui::chapter_list::ChapterList;
-use crate::gui::dict::{DictionaryBook, DictionaryManager};
+use crate::dict::DictionaryBook;
+use crate::gui::dict::DictionaryManager;
 pub use crate::gui::font::HtmlFonts;
 use crate::gui::font::UserFonts;
 use crate::gui::history::HistoryList;
-use crate::gui::render::RenderContext;
+use crate::gui::render::{highlight_words, RenderContext, RenderOptions};
 use crate::gui::find_list::FindList;
+use crate::gui::notes::Notes;
 use crate::gui::settings::Settings;
 use crate::gui::view::{GuiView, update_mouse_pointer};
-use crate::open::Opener;
+use crate::open::{is_external_link, Opener};
+use crate::sync;
 
 mod render;
-mod dict;
+pub(crate) mod dict;
 mod view;
 mod math;
 mod settings;
@@ -47,6 +57,11 @@ mod font;
 mod dialogs;
 mod history;
 mod find_list;
+mod image_viewer;
+mod library;
+mod notes;
+#[cfg(feature = "opds")]
+mod catalog;
 
 const MODIFIER_NONE: ModifierType = ModifierType::empty();
 const MODIFIER_CTRL_SHIFT: ModifierType = ModifierType::CONTROL_MASK.union(ModifierType::SHIFT_MASK);
@@ -54,30 +69,43 @@ const MODIFIER_CTRL_SHIFT: ModifierType = ModifierType::CONTROL_MASK.union(Modif
 const APP_ID: &str = "net.lzrj.tbr";
 const ICON_SIZE: i32 = 32;
 const INLINE_ICON_SIZE: i32 = 16;
-const MIN_FONT_SIZE: u8 = 20;
-const MAX_FONT_SIZE: u8 = 50;
+// interval for checking the loading result
+pub(super) const LOADING_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+// delay for merging zoom steps of ctrl-scroll and pinch
+const ZOOM_DEBOUNCE: Duration = Duration::from_millis(150);
 const FONT_FILE_EXTENSIONS: [&str; 3] = ["ttf", "otf", "ttc"];
 const DICT_FILE_EXTENSIONS: [&str; 1] = ["ifo"];
 const SIDEBAR_CHAPTER_LIST_NAME: &str = "chapter_list";
 const SIDEBAR_DICT_NAME: &str = "dictionary_list";
 const SIDEBAR_FIND_NAME: &str = "find_list";
+const SIDEBAR_NOTES_NAME: &str = "notes";
 
 const OPEN_FILE_KEY: &str = "file-open";
 const OPEN_FOLDER_KEY: &str = "folder-open";
 const HISTORY_KEY: &str = "history";
 const RELOAD_KEY: &str = "reload";
 const BOOK_INFO_KEY: &str = "book-info";
+const EXPORT_ANNOTATIONS_KEY: &str = "export-annotations";
+#[cfg(feature = "opds")]
+const OPDS_CATALOG_KEY: &str = "opds-catalog";
 const SIDEBAR_KEY: &str = "sidebar";
 const THEME_KEY: &str = "dark-theme";
 const CUSTOM_COLOR_KEY: &str = "with-custom-color";
 const CUSTOM_FONT_KEY: &str = "with-custom-font";
 const CUSTOM_STYLE_KEY: &str = "custom-style";
 const SETTINGS_KEY: &str = "settings-dialog";
+const RELOAD_CONFIG_KEY: &str = "reload-config";
+const ABOUT_KEY: &str = "about";
 
 const COPY_CONTENT_KEY: &str = "copy-content";
+const COPY_IMAGE_KEY: &str = "copy-image";
 const DICT_LOOKUP_KEY: &str = "lookup-dictionary";
 
 const README_TEXT_FILENAME: &str = "readme";
+// books closed in this session, kept for reopening
+const MAX_CLOSED_BOOKS: usize = 10;
+// cross-fade duration of switching theme, in microseconds
+const THEME_TRANSITION_TIME: i64 = 150_000;
 
 type GuiController = Controller<RenderContext, GuiView>;
 type IconMap = HashMap<String, Texture>;
@@ -151,6 +179,14 @@ fn load_image(bytes: &[u8]) -> Option<Pixbuf>
 	Some(image)
 }
 
+#[inline]
+fn image_pixbuf(controller: &GuiController, line: usize, offset: usize) -> Option<Pixbuf>
+{
+	let image = controller.book.lines().get(line)?.image_at(offset)?;
+	let image_data = controller.book.image(image.href())?;
+	load_image(image_data.bytes())
+}
+
 fn custom_settings(book: &dyn Book, reading: &ReadingInfo)
 	-> (Option<bool>, Option<bool>, Option<Option<String>>)
 {
@@ -172,12 +208,21 @@ fn custom_settings(book: &dyn Book, reading: &ReadingInfo)
 	(custom_color, custom_font, custom_style)
 }
 
-fn build_ui(app: &Application, current: Option<String>,
+fn build_ui(app: &Application, current: Option<String>, target: Option<StartTarget>,
 	cfg: Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
 	-> Result<Option<GuiContext>>
 {
 	let configuration = cfg.borrow_mut();
 	let mut gui_contexts = gcs.borrow_mut();
+	let empty_launch = configuration.gui.on_empty_launch;
+	let current = match current {
+		None if gui_contexts.is_empty() && empty_launch == EmptyLaunch::LastBook =>
+			configuration.history(None, None)?
+				.into_iter()
+				.next()
+				.map(|reading| reading.filename),
+		current => current,
+	};
 	let (loading, gc_idx) = if let Some(current) = &current {
 		let current = configuration.reading(current)?;
 		let filename = current.filename();
@@ -198,8 +243,9 @@ fn build_ui(app: &Application, current: Option<String>,
 	};
 
 	let colors = configuration.curr_theme().clone();
-	let (i18n, icons, fonts, db, css_provider) = if let Some(gc) = gui_contexts.get(0) {
-		(gc.i18n.clone(), gc.icons.clone(), gc.fonts.clone(), gc.db.clone(), gc.css_provider.clone())
+	let (i18n, icons, fonts, db, css_provider, closed_books) = if let Some(gc) = gui_contexts.get(0) {
+		(gc.i18n.clone(), gc.icons.clone(), gc.fonts.clone(), gc.db.clone(), gc.css_provider.clone(),
+			gc.closed_books.clone())
 	} else {
 		let i18n = I18n::new(&configuration.gui.lang)?;
 		let i18n = Rc::new(i18n);
@@ -210,19 +256,28 @@ fn build_ui(app: &Application, current: Option<String>,
 		let db = DictionaryBook::load(&configuration.gui.dictionaries, configuration.gui.cache_dict);
 		let db = Rc::new(RefCell::new(db));
 		let css_provider = view::init_css(&colors);
-		(i18n, icons, fonts, db, css_provider)
+		(i18n, icons, fonts, db, css_provider, Default::default())
 	};
 
 	let container_manager = Default::default();
-	let (container, book, reading) = if let Some(loading) = loading {
-		let mut container = load_container(&container_manager, loading.filename())?;
-		let (book, reading) = load_book(&container_manager, &mut container, loading)?;
-		(container, book, reading)
+	let (container, book, reading, pending) = if let Some(loading) = loading {
+		// show placeholder until the book loaded in background
+		let text = i18n.args_msg("loading-book", vec![
+			("filename", title_for_filename(loading.filename())),
+		]);
+		let container: Box<dyn Container> = Box::new(ReadmeContainer::new(&text));
+		let book: Box<dyn Book> = Box::new(ReadmeBook::new(&text));
+		let reading = ReadingInfo::fake(loading.filename());
+		(container, book, reading, Some(loading))
 	} else {
-		let readme = i18n.msg("readme");
+		let readme = if empty_launch == EmptyLaunch::Blank {
+			i18n.msg("empty-launch-prompt")
+		} else {
+			i18n.msg("readme")
+		};
 		let container: Box<dyn Container> = Box::new(ReadmeContainer::new(readme.as_ref()));
 		let book: Box<dyn Book> = Box::new(ReadmeBook::new(readme.as_ref()));
-		(container, book, ReadingInfo::fake(README_TEXT_FILENAME))
+		(container, book, ReadingInfo::fake(README_TEXT_FILENAME), None)
 	};
 
 	let mut render_context = RenderContext::new(
@@ -230,12 +285,17 @@ fn build_ui(app: &Application, current: Option<String>,
 		reading.font_size,
 		reading.custom_color,
 		reading.custom_font,
-		book.leading_space(),
-		configuration.gui.strip_empty_lines,
-		configuration.gui.ignore_font_weight);
+		reading.leading_chars(configuration.gui.leading_chars, book.leading_space()),
+		RenderOptions::new(&configuration));
+	render_context.default_leading_chars = configuration.gui.leading_chars;
+	render_context.highlight_words = highlight_words(&configuration.gui.highlight_words);
+	render_context.image_interpolation = configuration.gui.image_interpolation;
+	render_context.max_image_scale = configuration.gui.image_scale_limit();
+	render_context.ignore_book_fonts = configuration.gui.ignore_book_fonts;
+	render_context.focus_mode = configuration.gui.focus_mode;
 	let view = GuiView::new(
 		"main",
-		configuration.render_han,
+		reading.render_han(configuration.render_han),
 		book.custom_fonts(),
 		fonts.clone(),
 		&mut render_context);
@@ -244,33 +304,43 @@ fn build_ui(app: &Application, current: Option<String>,
 		&configuration.gui.dictionaries,
 		configuration.gui.cache_dict,
 		configuration.gui.dict_font_size,
+		configuration.gui.scroll_step,
+		configuration.gui.page_overlap,
 		fonts.clone(),
 		&i18n,
 		&icons,
 	);
 
 	let dark_theme = configuration.dark_theme;
+	let reading_speed = configuration.reading_speed.clone();
+	let chars_per_page = configuration.chars_per_page;
+	let content_options = configuration.content_options();
 	drop(configuration);
 
 	let (custom_color, custom_font, custom_style) = custom_settings(book.as_ref(), &reading);
-	let controller = Controller::from_data(
+	let mut controller = Controller::from_data(
 		reading,
 		container_manager,
 		container,
 		book,
 		Box::new(view.clone()),
 		&mut render_context);
+	controller.reading_speed = reading_speed;
+	controller.init_content_options(content_options, &mut render_context)?;
+	controller.set_chars_per_page(chars_per_page);
 
 	let ctx = Rc::new(RefCell::new(render_context));
 	let ctrl = Rc::new(RefCell::new(controller));
 	let settings = Settings::new(gcs.clone());
-	let (gc, chapter_list_view, find_list_view, find_entry) = GuiContext::new(app, settings,
-		current, &cfg, &ctrl, &ctx, db, dm,
-		icons, i18n.clone(), fonts, css_provider);
+	let (gc, chapter_list_view, find_list_view, find_entry, notes_view)
+		= GuiContext::new(app, settings, current, &cfg, &ctrl, &ctx, db, dm,
+		icons, i18n.clone(), fonts, css_provider, closed_books);
 
 	// now setup ui
-	setup_sidebar(&gc, &view, &dict_view, chapter_list_view, &find_list_view);
+	setup_sidebar(&gc, &view, &dict_view, chapter_list_view, &find_list_view,
+		&notes_view);
 	setup_view(&gc, &view);
+	setup_dict_zoom(&gc);
 	setup_chapter_list(&gc);
 	setup_find_list(&gc);
 
@@ -283,7 +353,7 @@ fn build_ui(app: &Application, current: Option<String>,
 		search_box.connect_activate(move |entry| {
 			let search_pattern = entry.text();
 			handle(&gc, |controller, render_context| {
-				controller.search(&search_pattern, render_context)?;
+				controller.search(&search_pattern, &SearchOptions::default(), render_context)?;
 				controller.render.grab_focus();
 				Ok(())
 			});
@@ -295,10 +365,23 @@ fn build_ui(app: &Application, current: Option<String>,
 	}
 	{
 		let gc = gc.clone();
+		let view = view.clone();
 		let key_event = EventControllerKey::new();
 		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if view.link_hints_active() {
+				if key == Key::Escape {
+					view.cancel_link_hints();
+				} else if let Some(char) = key.to_unicode() {
+					view.link_hint_input(char.to_ascii_lowercase());
+				}
+				return Propagation::Stop;
+			}
 			let (key, modifier) = ignore_cap(key, modifier);
 			match (key, modifier) {
+				(Key::f, MODIFIER_NONE) => {
+					view.start_link_hints();
+					Propagation::Stop
+				}
 				(Key::space | Key::Page_Down, MODIFIER_NONE) => {
 					handle(&gc, |controller, render_context|
 						controller.next_page(render_context));
@@ -375,31 +458,11 @@ fn build_ui(app: &Application, current: Option<String>,
 					Propagation::Stop
 				}
 				(Key::equal, ModifierType::CONTROL_MASK) => {
-					apply(&gc, |controller, render_context| {
-						let reading = &mut controller.reading;
-						if reading.font_size < MAX_FONT_SIZE {
-							reading.font_size += 2;
-							controller.render.set_font_size(
-								reading.font_size,
-								controller.book.custom_fonts(),
-								render_context);
-							controller.redraw(render_context);
-						}
-					});
+					zoom_font(&gc, 1);
 					Propagation::Stop
 				}
 				(Key::minus, ModifierType::CONTROL_MASK) => {
-					apply(&gc, |controller, render_context| {
-						let reading = &mut controller.reading;
-						if reading.font_size > MIN_FONT_SIZE {
-							reading.font_size -= 2;
-							controller.render.set_font_size(
-								reading.font_size,
-								controller.book.custom_fonts(),
-								render_context);
-							controller.redraw(render_context);
-						}
-					});
+					zoom_font(&gc, -1);
 					Propagation::Stop
 				}
 				(Key::c, ModifierType::CONTROL_MASK) => {
@@ -421,11 +484,21 @@ fn build_ui(app: &Application, current: Option<String>,
 	}
 
 	setup_window(&gc, toolbar, view, search_box, find_entry);
+	if let Some(loading) = pending {
+		gc.load_in_background(loading, target);
+	} else {
+		gc.load_notes();
+		gc.check_sync_position();
+	}
 
 	{
 		let gcs = gcs.clone();
 		gc.window.connect_close_request(move |win| {
-			gcs.borrow_mut().retain(|c| c.window != *win);
+			let mut gui_contexts = gcs.borrow_mut();
+			if let Some(idx) = gui_contexts.iter().position(|c| c.window == *win) {
+				let gc = gui_contexts.remove(idx);
+				gc.book_closed();
+			}
 			Propagation::Proceed
 		});
 	}
@@ -450,6 +523,17 @@ fn copy_to_clipboard(selected_text: &str)
 	}
 }
 
+/// put the decoded image onto clipboard
+#[inline]
+fn copy_image(ctrl: &GuiController, line: usize, offset: usize)
+{
+	if let Some(pixbuf) = image_pixbuf(ctrl, line, offset) {
+		if let Some(display) = Display::default() {
+			display.clipboard().set_texture(&Texture::for_pixbuf(&pixbuf));
+		}
+	}
+}
+
 #[inline]
 fn lookup_selection(gc: &GuiContext)
 {
@@ -471,6 +555,23 @@ where
 	gc.update(&msg, ChapterListSyncMode::ReloadIfNeeded(orig_inner_book));
 }
 
+/// change font size by steps, size of step is configurable
+fn zoom_font(gc: &GuiContext, steps: i32)
+{
+	let font_size = gc.cfg().gui.zoom_font_size(gc.ctrl().reading.font_size, steps);
+	apply(gc, |controller, render_context| {
+		let reading = &mut controller.reading;
+		if font_size != reading.font_size {
+			reading.font_size = font_size;
+			controller.render.set_font_size(
+				reading.font_size,
+				controller.book.custom_fonts(),
+				render_context);
+			controller.redraw(render_context);
+		}
+	});
+}
+
 #[inline]
 fn handle<T, F>(gc: &GuiContext, f: F)
 where
@@ -560,6 +661,39 @@ fn setup_popup_menu(gc: &GuiContext, view: &GuiView) -> PopoverMenu
 	pm
 }
 
+/// popup menu for the right clicked image, located by image_target
+fn setup_image_popup_menu(gc: &GuiContext, view: &GuiView,
+	image_target: &Rc<Cell<Option<(usize, usize)>>>) -> PopoverMenu
+{
+	let action_group = SimpleActionGroup::new();
+	let menu = Menu::new();
+
+	view.insert_action_group("image-popup", Some(&action_group));
+
+	let copy_action = SimpleAction::new(COPY_IMAGE_KEY, None);
+	{
+		let gc = gc.clone();
+		let image_target = image_target.clone();
+		copy_action.connect_activate(move |_, _| {
+			if let Some((line, offset)) = image_target.get() {
+				copy_image(&gc.ctrl(), line, offset);
+			}
+		});
+	}
+	action_group.add_action(&copy_action);
+	let title = gc.i18n.msg(COPY_IMAGE_KEY);
+	let action_name = format!("image-popup.{}", COPY_IMAGE_KEY);
+	menu.append(Some(&title), Some(&action_name));
+
+	let pm = PopoverMenu::builder()
+		.has_arrow(false)
+		.position(PositionType::Bottom)
+		.menu_model(&MenuModel::from(menu))
+		.build();
+	pm.set_parent(view);
+	pm
+}
+
 fn setup_view(gc: &GuiContext, view: &GuiView)
 {
 	#[inline]
@@ -570,11 +704,17 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 		let to = Position::new(to_line, to_offset);
 		gc.ctrl_mut().select_text(from, to, &mut gc.ctx_mut());
 		if done {
-			if let Some(selected_text) = gc.ctrl().selected() {
-				if let Some(current_tab) = gc.sidebar_stack.visible_child_name() {
-					if current_tab == SIDEBAR_DICT_NAME {
-						gc.dm_mut().set_lookup(selected_text.to_owned());
-					}
+			lookup_selected(gc);
+		}
+	}
+
+	#[inline]
+	fn lookup_selected(gc: &GuiContext)
+	{
+		if let Some(selected_text) = gc.ctrl().selected() {
+			if let Some(current_tab) = gc.sidebar_stack.visible_child_name() {
+				if current_tab == SIDEBAR_DICT_NAME {
+					gc.dm_mut().set_lookup(selected_text.to_owned());
 				}
 			}
 		}
@@ -596,15 +736,15 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 	}
 
 	#[inline]
-	fn open_link(controller: &GuiController, line: usize, link_index: usize,
-		opener: &mut Opener) -> Result<()>
+	fn external_link(gc: &GuiContext, line: usize, link_index: usize) -> Option<String>
 	{
-		if let Some(line) = controller.book.lines().get(line) {
-			if let Some(link) = line.link_at(link_index) {
-				opener.open_link(link.target)?;
-			}
+		let controller = gc.ctrl();
+		let link = controller.book.lines().get(line)?.link_at(link_index)?;
+		if is_external_link(link.target) {
+			Some(link.target.to_owned())
+		} else {
+			None
 		}
-		Ok(())
 	}
 
 	view.setup_gesture();
@@ -623,19 +763,31 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 		let right_click = GestureClick::builder()
 			.button(gdk::BUTTON_SECONDARY)
 			.build();
+		#[inline]
+		fn popup_at(popup_menu: &PopoverMenu, x: f64, y: f64)
+		{
+			popup_menu.popup();
+			let (_, width, _, _) = popup_menu.measure(Orientation::Horizontal, -1);
+			let x = x as i32 + width / 2;
+			popup_menu.set_pointing_to(Some(&Rectangle::new(
+				x,
+				y as i32,
+				-1,
+				-1,
+			)));
+		}
+
 		let popup_menu = setup_popup_menu(gc, view);
+		let image_target = Rc::new(Cell::new(None));
+		let image_popup_menu = setup_image_popup_menu(gc, view, &image_target);
 		let gc = gc.clone();
+		let image_view = view.clone();
 		right_click.connect_pressed(move |_, _, x, y| {
-			if gc.ctrl().has_selection() {
-				popup_menu.popup();
-				let (_, width, _, _) = popup_menu.measure(Orientation::Horizontal, -1);
-				let x = x as i32 + width / 2;
-				popup_menu.set_pointing_to(Some(&Rectangle::new(
-					x,
-					y as i32,
-					-1,
-					-1,
-				)));
+			if let Some(target) = image_view.image_at(x, y) {
+				image_target.set(Some(target));
+				popup_at(&image_popup_menu, x, y);
+			} else if gc.ctrl().has_selection() {
+				popup_at(&popup_menu, x, y);
 			}
 		});
 		view.add_controller(right_click);
@@ -648,8 +800,12 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 			GuiView::OPEN_LINK_SIGNAL,
 			false,
 			closure_local!(move |_: GuiView, line: u64, link_index: u64| {
-				handle(&gc, |controller, render_context|
-					controller.goto_link(line as usize,	link_index as usize, render_context));
+				if let Some(url) = external_link(&gc, line as usize, link_index as usize) {
+					gc.open_external_link(url);
+				} else {
+					handle(&gc, |controller, render_context|
+						controller.goto_link(line as usize,	link_index as usize, render_context));
+				}
 	        }),
 		);
 	}
@@ -667,6 +823,32 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 		);
 	}
 
+	{
+		// view image signal, plain click on image
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::VIEW_IMAGE_SIGNAL,
+			false,
+			closure_local!(move |view: GuiView, line: u64, offset: u64| {
+				let line = line as usize;
+				let offset = offset as usize;
+				if gc.cfg().gui.external_image_viewer {
+					handle(&gc, |controller, _render_context|
+						view_image(controller, line, offset, &mut gc.opener()));
+					return;
+				}
+				let image = image_pixbuf(&gc.ctrl(), line, offset);
+				if let Some(image) = image {
+					let gc2 = gc.clone();
+					image_viewer::show_image(&view, &image, &gc.i18n, move || {
+						handle(&gc2, |controller, _render_context|
+							view_image(controller, line, offset, &mut gc2.opener()));
+					});
+				}
+	        }),
+		);
+	}
+
 	{
 		// open link external signal
 		let gc = gc.clone();
@@ -674,8 +856,9 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 			GuiView::OPEN_LINK_EXTERNAL_SIGNAL,
 			false,
 			closure_local!(move |_: GuiView, line: u64, link_index: u64| {
-				handle(&gc, |controller, _render_context|
-					open_link(controller, line as usize, link_index as usize, &mut gc.opener()))
+				if let Some(url) = external_link(&gc, line as usize, link_index as usize) {
+					gc.open_external_link(url);
+				}
 	        }),
 		);
 	}
@@ -734,18 +917,35 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 			GuiView::SELECT_WORD_SIGNAL,
 			false,
 			closure_local!(move |_: GuiView, line: u64, offset: u64| {
-				let line_no = line as usize;
-				let controller = gc.ctrl();
-				if let Some(line) = controller.book.lines().get(line_no) {
-					if let Some((from, to)) = if gc.cfg().gui.select_by_dictionary {
-						gc.db.borrow_mut().lookup_at_offset(line, offset as usize)
+				let select_by_dictionary = gc.cfg().gui.select_by_dictionary;
+				// CJK words need segmentation, always by dictionaries
+				let selected = gc.ctrl_mut().select_word(
+					line as usize,
+					offset as usize,
+					|text, offset| if select_by_dictionary
+						|| text.char_at(offset).map_or(false, is_cjk) {
+						gc.db.borrow_mut().lookup_at_offset(text, offset)
 					} else {
-						line.word_at_offset(offset as usize)
-					} {
-						drop(controller);
-						select_text(&gc, line_no, from, line_no, to, true);
-					}
-				};
+						None
+					},
+					&mut gc.ctx_mut());
+				if selected {
+					lookup_selected(&gc);
+				}
+			}),
+		);
+	}
+
+	{
+		// extend selection signal
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::EXTEND_SELECTION_SIGNAL,
+			false,
+			closure_local!(move |_: GuiView, line: u64, offset: u64| {
+				let to = Position::new(line as usize, offset as usize);
+				gc.ctrl_mut().extend_selection(to, &mut gc.ctx_mut());
+				lookup_selected(&gc);
 			}),
 		);
 	}
@@ -757,19 +957,33 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 			GuiView::SHOW_TITLE_SIGNAL,
 			false,
 			closure_local!(move |view: GuiView, show: bool, line: u64, offset: u64| {
-				if show {
-					let line_no = line as usize;
-					let controller = gc.ctrl();
-					if let Some(line) = controller.book.lines().get(line_no) {
-						let render_context = gc.ctx();
-						let char_style =  line.char_style_at(offset as usize, false, &render_context.colors);
-						if let Some(title) = char_style.title {
-							view.set_tooltip_text(Some(title));
-							return;
+				let controller = gc.ctrl();
+				let render_context = gc.ctx();
+				view.show_title(controller.book.lines(), show, line as usize,
+					offset as usize, &render_context.colors);
+			}),
+		);
+	}
+
+	{
+		// zoom signal, merge rapid steps into one font size change
+		let gc = gc.clone();
+		let pending_steps = Rc::new(Cell::new(0));
+		view.connect_closure(
+			GuiView::ZOOM_SIGNAL,
+			false,
+			closure_local!(move |_: GuiView, delta: i32| {
+				if pending_steps.get() == 0 {
+					let gc = gc.clone();
+					let pending_steps = pending_steps.clone();
+					glib::timeout_add_local_once(ZOOM_DEBOUNCE, move || {
+						let steps = pending_steps.replace(0);
+						if steps != 0 {
+							zoom_font(&gc, steps);
 						}
-					};
+					});
 				}
-				view.set_tooltip_text(None);
+				pending_steps.set(pending_steps.get() + delta);
 			}),
 		);
 	}
@@ -786,25 +1000,95 @@ fn setup_view(gc: &GuiContext, view: &GuiView)
 						handle(&gc, |controller, render_context|
 							controller.next_page(render_context));
 					} else {
-						handle(&gc, |controller, render_context|
-							controller.step_next(render_context));
+						let scroll_step = gc.cfg().gui.scroll_step.max(1);
+						handle(&gc, |controller, render_context| {
+							for _ in 0..scroll_step {
+								controller.step_next(render_context)?;
+							}
+							Ok(())
+						});
 					}
 				} else {
 					if gc.cfg().gui.scroll_for_page{
 						handle(&gc, |controller, render_context|
 							controller.prev_page(render_context));
 					} else {
-						handle(&gc, |controller, render_context|
-							controller.step_prev(render_context));
+						let scroll_step = gc.cfg().gui.scroll_step.max(1);
+						handle(&gc, |controller, render_context| {
+							for _ in 0..scroll_step {
+								controller.step_prev(render_context)?;
+							}
+							Ok(())
+						});
 					}
 				}
 	        }),
 		);
 	}
+
+	{
+		// click zone signal, only clicks not on links or images
+		let gc = gc.clone();
+		view.connect_closure(
+			GuiView::CLICK_ZONE_SIGNAL,
+			false,
+			closure_local!(move |_: GuiView, zone: i32| {
+				if !gc.cfg().gui.click_zones {
+					return;
+				}
+				// han pages go leftward
+				let zone = if gc.ctrl().render.render_han() { -zone } else { zone };
+				match zone {
+					0 => {
+						let win = &gc.window;
+						win.set_fullscreened(!win.is_fullscreened());
+					}
+					zone if zone > 0 => handle(&gc, |controller, render_context|
+						controller.next_page(render_context)),
+					_ => handle(&gc, |controller, render_context|
+						controller.prev_page(render_context)),
+				}
+			}),
+		);
+	}
+}
+
+/// show recent books instead of readme, when started without book
+fn setup_library(gc: &GuiContext, view: &GuiView, view_box: &gtk4::Box)
+{
+	if gc.current.is_some() || gc.cfg().gui.on_empty_launch != EmptyLaunch::Library {
+		return;
+	}
+	let infos = match gc.cfg().history(None, None) {
+		Ok(infos) if !infos.is_empty() => infos,
+		_ => return,
+	};
+	view.set_visible(false);
+	gc.progress_bar.set_visible(false);
+	view_box.append(&library::library_view(gc, infos));
+}
+
+/// dictionary font size changed independently from the book
+fn setup_dict_zoom(gc: &GuiContext)
+{
+	let dict_view = gc.dm().view().clone();
+	let gc = gc.clone();
+	dict_view.connect_closure(
+		GuiView::ZOOM_SIGNAL,
+		false,
+		closure_local!(move |_: GuiView, delta: i32| {
+			let orig_font_size = gc.dm().font_size();
+			let font_size = gc.cfg().gui.zoom_font_size(orig_font_size, delta);
+			if font_size != orig_font_size {
+				gc.dm_mut().set_font_size(font_size);
+			}
+		}),
+	);
 }
 
 fn setup_sidebar(gc: &GuiContext, view: &GuiView, dict_view: &gtk4::Box,
-	chapter_list_view: gtk4::Box, find_list_view: &gtk4::Box)
+	chapter_list_view: gtk4::Box, find_list_view: &gtk4::Box,
+	notes_view: &gtk4::Box)
 {
 	let i18n = &gc.i18n;
 	let stack = &gc.sidebar_stack;
@@ -817,7 +1101,25 @@ fn setup_sidebar(gc: &GuiContext, view: &GuiView, dict_view: &gtk4::Box,
 	stack.add_titled(
 		find_list_view,
 		Some(SIDEBAR_FIND_NAME), &i18n.msg("tab-find"));
-	stack.set_visible_child(&chapter_list_view);
+	stack.add_titled(
+		notes_view,
+		Some(SIDEBAR_NOTES_NAME), &i18n.msg("tab-notes"));
+	let (sidebar_open, sidebar_tab) = {
+		let configuration = gc.cfg();
+		(configuration.gui.sidebar_open, configuration.gui.sidebar_tab.clone())
+	};
+	match sidebar_tab {
+		Some(tab_name) if stack.child_by_name(&tab_name).is_some() =>
+			stack.set_visible_child_name(&tab_name),
+		_ => stack.set_visible_child(&chapter_list_view),
+	}
+	{
+		let gc = gc.clone();
+		stack.connect_visible_child_name_notify(move |stack| {
+			gc.cfg_mut().gui.sidebar_tab = stack.visible_child_name()
+				.map(|name| name.to_string());
+		});
+	}
 
 	let sidebar_tab_switch = gtk4::StackSwitcher::builder()
 		.stack(&stack)
@@ -831,8 +1133,26 @@ fn setup_sidebar(gc: &GuiContext, view: &GuiView, dict_view: &gtk4::Box,
 
 	let paned = &gc.paned;
 	paned.set_start_child(Some(&sidebar));
-	paned.set_end_child(Some(view));
-	paned.set_position(0);
+	let view_box = gtk4::Box::new(Orientation::Vertical, 0);
+	view_box.append(&gc.running_header);
+	view_box.append(view);
+	view_box.append(&gc.progress_bar);
+	{
+		let gc = gc.clone();
+		gc.progress_bar.clone().connect_change_value(move |_, _, value| {
+			handle(&gc, |controller, render_context|
+				controller.goto_percent(value, render_context));
+			Propagation::Proceed
+		});
+	}
+	setup_library(gc, view, &view_box);
+	paned.set_end_child(Some(&view_box));
+	if sidebar_open {
+		gc.sidebar_btn.set_active(true);
+		paned.set_position(gc.cfg().gui.sidebar_size as i32);
+	} else {
+		paned.set_position(0);
+	}
 
 	let gc = gc.clone();
 	paned.connect_position_notify(move |paned| {
@@ -873,12 +1193,20 @@ fn setup_chapter_list(gc1: &GuiContext)
 						gc.find_list.set_inner_book(index);
 						(false, msg)
 					}
-					Err(e) => (true, e.to_string())
+					Err(e) => (true, error_msg(&gc.i18n, &e))
 				};
 				update_title(&gc.window, &controller);
+				drop(render_context);
+				drop(controller);
+				if !error {
+					gc.sync_custom_settings();
+				}
 				update_status(error, &msg, &gc.status_bar);
 			} else if let Some(msg) = controller.goto_toc(index, &mut render_context) {
 				update_status(false, &msg, &gc.status_bar);
+				drop(render_context);
+				drop(controller);
+				gc.prefetch_chapters();
 			}
 		});
 	}
@@ -951,6 +1279,14 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 	header_bar.set_height_request(32);
 	header_bar.pack_start(&toolbar);
 	header_bar.pack_end(&gc.status_bar);
+	header_bar.pack_end(&gc.spinner);
+	{
+		// click status bar for copying the reading location
+		let status_click = GestureClick::new();
+		gc.status_bar.add_controller(status_click.clone());
+		let gc = gc.clone();
+		status_click.connect_released(move |_, _, _, _| gc.copy_location());
+	}
 	let window = &gc.window;
 	window.set_titlebar(Some(&header_bar));
 	window.set_child(Some(&gc.paned));
@@ -1017,10 +1353,27 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					search_box.select_region(0, -1);
 					Propagation::Stop
 				}
+				(Key::asterisk, _) if modifier & !ModifierType::SHIFT_MASK == MODIFIER_NONE => {
+					handle(&gc, |controller, render_context| {
+						if let Some(pattern) = controller.search_selected(render_context)? {
+							search_box.set_text(pattern);
+						}
+						Ok(())
+					});
+					Propagation::Stop
+				}
 				(Key::g, ModifierType::CONTROL_MASK) => {
 					gc.goto();
 					Propagation::Stop
 				}
+				(Key::G, MODIFIER_CTRL_SHIFT) => {
+					gc.goto_page();
+					Propagation::Stop
+				}
+				(Key::C, MODIFIER_CTRL_SHIFT) => {
+					gc.goto_chapter();
+					Propagation::Stop
+				}
 				(Key::Escape, MODIFIER_NONE) => {
 					if gc.paned.position() != 0 {
 						gc.toggle_sidebar();
@@ -1041,10 +1394,18 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					gc.open_dialog();
 					Propagation::Stop
 				}
+				(Key::R, MODIFIER_CTRL_SHIFT) => {
+					gc.reload_config();
+					Propagation::Stop
+				}
 				(Key::O, MODIFIER_CTRL_SHIFT) => {
 					gc.open_folder_dialog();
 					Propagation::Stop
 				}
+				(Key::T, MODIFIER_CTRL_SHIFT) => {
+					gc.reopen_closed();
+					Propagation::Stop
+				}
 				(Key::h, MODIFIER_NONE) => {
 					gc.show_history();
 					Propagation::Stop
@@ -1053,6 +1414,10 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					gc.switch_theme();
 					Propagation::Stop
 				}
+				(Key::z, MODIFIER_NONE) => {
+					gc.switch_focus_mode();
+					Propagation::Stop
+				}
 				(Key::T, ModifierType::SHIFT_MASK) => {
 					gc.custom_color_action.activate(None);
 					Propagation::Stop
@@ -1062,7 +1427,7 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 					Propagation::Stop
 				}
 				(Key::S, ModifierType::SHIFT_MASK) => {
-					gc.custom_style_dialog();
+					gc.custom_style_action.activate(None);
 					Propagation::Stop
 				}
 				(Key::s, ModifierType::CONTROL_MASK) => {
@@ -1097,12 +1462,19 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 		let gc = gc.clone();
 		window.connect_close_request(move |_| {
 			let mut controller = gc.ctrl_mut();
-			if controller.reading.filename != README_TEXT_FILENAME {
+			if controller.reading.filename != README_TEXT_FILENAME && !gc.loading.get() {
 				let configuration = gc.cfg_mut();
 				if let Err(e) = configuration.save_reading(&mut controller.reading) {
 					eprintln!("Failed save reading info: {}", e.to_string());
+				} else if let Some(sync_dir) = &configuration.sync_dir {
+					if let Err(e) = sync::save_position(sync_dir, &controller.reading) {
+						eprintln!("Failed save sync position: {}", e.to_string());
+					}
 				}
 			}
+			if let Err(e) = gc.notes.save() {
+				eprintln!("Failed save notes: {}", e.to_string());
+			}
 			let mut configuration = gc.cfg_mut();
 			configuration.gui.dict_font_size = gc.dm.borrow().font_size();
 			if let Err(e) = configuration.save() {
@@ -1115,12 +1487,12 @@ fn setup_window(gc: &GuiContext, toolbar: gtk4::Box, view: GuiView,
 	window.present();
 }
 
+/// switch render mode of current book only, global setting for new books
 fn switch_render(gc: &GuiContext)
 {
-	let mut configuration = gc.cfg_mut();
-	let render_han = !configuration.render_han;
-	configuration.render_han = render_han;
 	let mut controller = gc.ctrl_mut();
+	let render_han = !controller.render.render_han();
+	controller.reading.render_han = Some(render_han);
 	let mut render_context = gc.ctx_mut();
 	controller.render.reload_render(render_han, &mut render_context);
 	controller.redraw(&mut render_context);
@@ -1222,12 +1594,7 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 	where
 		F: Fn(&SimpleAction, Option<&Variant>) + 'static,
 	{
-		if let Some(active) = toggle {
-			action.set_state(&active.to_variant());
-		} else {
-			action.set_state(&false.to_variant());
-			action.set_enabled(false);
-		}
+		sync_toggle_action(action, toggle);
 		action.connect_activate(callback);
 		let title = i18n.msg(key);
 		let action_name = format!("main.{}", key);
@@ -1271,6 +1638,13 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			});
 	}
 
+	#[cfg(feature = "opds")]
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			OPDS_CATALOG_KEY, move |_, _| catalog::show(&gc));
+	}
+
 	{
 		let gc = gc.clone();
 		create_action(&section, &action_group, i18n,
@@ -1291,12 +1665,30 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 			});
 	}
 
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			EXPORT_ANNOTATIONS_KEY, move |_, _| gc.export_annotations());
+	}
+
 	{
 		let gc = gc.clone();
 		create_action(&section, &action_group, i18n,
 			SETTINGS_KEY, move |_, _| gc.show_settings());
 	}
 
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			RELOAD_CONFIG_KEY, move |_, _| gc.reload_config());
+	}
+
+	{
+		let gc = gc.clone();
+		create_action(&section, &action_group, i18n,
+			ABOUT_KEY, move |_, _| gc.about());
+	}
+
 	{
 		let action = &gc.theme_action;
 		let gc = gc.clone();
@@ -1329,9 +1721,7 @@ fn setup_main_menu(gc: &GuiContext, view: &GuiView, dark_theme: bool,
 
 	{
 		let action = &gc.custom_style_action;
-		if custom_style.is_none() {
-			action.set_enabled(false);
-		}
+		action.set_enabled(custom_style.is_some());
 		let gc = gc.clone();
 		append_action(&section, &action_group, i18n,
 			CUSTOM_STYLE_KEY, action, move |_, _| {
@@ -1370,6 +1760,14 @@ fn load_button_image(name: &str, icons: &IconMap, inline: bool) -> Image
 	image
 }
 
+/// state of toggle action, disabled if toggle not available
+#[inline]
+fn sync_toggle_action(action: &SimpleAction, toggle: Option<bool>)
+{
+	action.set_state(&toggle.unwrap_or(false).to_variant());
+	action.set_enabled(toggle.is_some());
+}
+
 #[inline]
 fn create_action(name: &str) -> SimpleAction
 {
@@ -1463,6 +1861,15 @@ struct GuiContextInner {
 	window: ApplicationWindow,
 	history_list: HistoryList,
 	status_bar: Label,
+	// spinning while loading book in background
+	spinner: Spinner,
+	// placeholder showing, reading info should not be saved
+	loading: Cell<bool>,
+	// idle worker parsing following chapters running
+	prefetching: Cell<bool>,
+	running_header: Label,
+	// seek bar of reading position in the whole book
+	progress_bar: Scale,
 	paned: Paned,
 	sidebar_stack: Stack,
 	sidebar_btn: ToggleButton,
@@ -1473,6 +1880,7 @@ struct GuiContextInner {
 	menu_btn: Button,
 	chapter_list: ChapterList,
 	find_list: FindList,
+	notes: Notes,
 	icons: Rc<IconMap>,
 	i18n: Rc<I18n>,
 	fonts: Rc<Option<UserFonts>>,
@@ -1480,6 +1888,10 @@ struct GuiContextInner {
 	file_dialog: FileDialog,
 	settings: Settings,
 	db: Rc<RefCell<DictionaryBook>>,
+	// shared by all windows, the most recently closed at last
+	closed_books: Rc<RefCell<Vec<String>>>,
+	// running cross-fade of switching theme
+	theme_transition: RefCell<Option<TickCallbackId>>,
 }
 
 enum ChapterListSyncMode {
@@ -1509,7 +1921,8 @@ impl GuiContext {
 		ctx: &Rc<RefCell<RenderContext>>, db: Rc<RefCell<DictionaryBook>>,
 		dm: Rc<RefCell<DictionaryManager>>,
 		icons: Rc<IconMap>, i18n: Rc<I18n>, fonts: Rc<Option<UserFonts>>,
-		css_provider: CssProvider) -> (Self, gtk4::Box, gtk4::Box, SearchEntry)
+		css_provider: CssProvider, closed_books: Rc<RefCell<Vec<String>>>)
+		-> (Self, gtk4::Box, gtk4::Box, SearchEntry, gtk4::Box)
 	{
 		let window = ApplicationWindow::builder()
 			.application(app)
@@ -1521,6 +1934,7 @@ impl GuiContext {
 
 		let (chapter_list, chapter_list_view) = ChapterList::create(&icons, &i18n, &ctrl);
 		let (find_list, find_list_view, find_entry) = FindList::create(&current, &i18n, &icons);
+		let (notes, notes_view) = Notes::create();
 
 		let controller = ctrl.borrow();
 		let status_msg = controller.status().to_string();
@@ -1533,6 +1947,20 @@ impl GuiContext {
 			.hexpand(true)
 			.build();
 
+		let running_header = Label::builder()
+			.label(controller.status().title.unwrap_or(""))
+			.ellipsize(EllipsizeMode::End)
+			.css_classes(vec!["dim-label"])
+			.margin_top(2)
+			.visible(cfg.borrow().gui.running_header)
+			.build();
+		controller.render.set_header(running_header.clone());
+
+		let progress_bar = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.001);
+		progress_bar.set_draw_value(false);
+		progress_bar.set_focusable(false);
+		progress_bar.set_value(controller.progress());
+
 		find_list.set_inner_book(controller.reading.inner_book);
 
 		let paned = Paned::new(Orientation::Horizontal);
@@ -1568,6 +1996,11 @@ impl GuiContext {
 			window,
 			history_list,
 			status_bar,
+			spinner: Spinner::builder().visible(false).build(),
+			loading: Cell::new(false),
+			prefetching: Cell::new(false),
+			running_header,
+			progress_bar,
 			paned,
 			sidebar_stack,
 			sidebar_btn,
@@ -1578,6 +2011,7 @@ impl GuiContext {
 			menu_btn,
 			chapter_list,
 			find_list,
+			notes,
 			icons,
 			i18n,
 			fonts,
@@ -1585,8 +2019,11 @@ impl GuiContext {
 			file_dialog,
 			settings,
 			db,
+			closed_books,
+			theme_transition: RefCell::new(None),
 		};
-		(GuiContext { inner: Rc::new(inner) }, chapter_list_view, find_list_view, find_entry)
+		(GuiContext { inner: Rc::new(inner) }, chapter_list_view, find_list_view, find_entry,
+			notes_view)
 	}
 
 	#[inline]
@@ -1687,6 +2124,33 @@ impl GuiContext {
 		}
 	}
 
+	/// remember book of the closing window for reopening
+	fn book_closed(&self)
+	{
+		let controller = self.ctrl();
+		let filename = &controller.reading.filename;
+		if filename == README_TEXT_FILENAME {
+			return;
+		}
+		let mut closed_books = self.closed_books.borrow_mut();
+		closed_books.retain(|closed| closed != filename);
+		if closed_books.len() >= MAX_CLOSED_BOOKS {
+			closed_books.remove(0);
+		}
+		closed_books.push(filename.clone());
+	}
+
+	/// reopen the most recently closed book, like a browser
+	fn reopen_closed(&self)
+	{
+		let filename = self.closed_books.borrow_mut().pop();
+		if let Some(filename) = filename {
+			if let Some(app) = self.window.application() {
+				app_open(&app, &filename);
+			}
+		}
+	}
+
 	fn open_folder_dialog(&self)
 	{
 		let dialog = FileDialog::new();
@@ -1698,13 +2162,116 @@ impl GuiContext {
 			if let Ok(file) = result {
 				if let Some(path) = file.path() {
 					if path.is_dir() {
-						gc.open_file(&path);
+						// extracted epub opened as a book, not as a folder of books
+						match extracted_epub_container(&path) {
+							Some(container) => gc.open_file(&container),
+							None => gc.open_file(&path),
+						}
 					}
 				}
 			}
 		});
 	}
 
+	/// book and container are not Send, so only read the file in
+	/// background, then parse it in main thread, packed or folder
+	/// books are read for warming up the os cache only
+	fn load_in_background(&self, loading: BookLoadingInfo, target: Option<StartTarget>)
+	{
+		self.loading.set(true);
+		self.spinner.set_visible(true);
+		self.spinner.start();
+		let filename = loading.filename().to_owned();
+		let preload = !self.ctrl().container_manager.is_container(&filename)
+			&& Path::new(&filename).is_file();
+		let (tx, rx) = mpsc::channel();
+		spawn(move || {
+			let result = if preload {
+				fs::read(&filename).map(Some)
+			} else {
+				fs::File::open(&filename)
+					.and_then(|mut file| io::copy(&mut file, &mut io::sink()))
+					.map(|_| None)
+			};
+			let _ = tx.send(result);
+		});
+		let gc = self.clone();
+		let mut loading = Some((loading, target));
+		timeout_add_local(LOADING_CHECK_INTERVAL, move || {
+			let content = match rx.try_recv() {
+				Err(TryRecvError::Empty) => return ControlFlow::Continue,
+				Ok(Ok(content)) => Ok(content),
+				Ok(Err(err)) => Err(err.into()),
+				Err(TryRecvError::Disconnected) => Ok(None),
+			};
+			if let Some((loading, target)) = loading.take() {
+				gc.book_loaded(loading, target, content);
+			}
+			ControlFlow::Break
+		});
+	}
+
+	fn book_loaded(&self, loading: BookLoadingInfo, target: Option<StartTarget>,
+		content: Result<Option<Vec<u8>>>)
+	{
+		self.spinner.stop();
+		self.spinner.set_visible(false);
+		// another book replacing the one in this window
+		if self.ctrl().reading.filename != loading.filename() {
+			self.book_closed();
+		}
+		let mut controller = self.ctrl_mut();
+		let mut render_context = self.ctx_mut();
+		let result = match content {
+			Ok(Some(content)) => controller.switch_preloaded(loading, content, &mut render_context),
+			Ok(None) => controller.switch_container(loading, &mut render_context),
+			Err(err) => Err(err),
+		};
+		match result {
+			Ok(mut msg) => {
+				if let Some(target) = &target {
+					msg = match controller.goto_target(target, &mut render_context) {
+						Ok(()) => controller.status().to_string(),
+						Err(err) => err.to_string(),
+					};
+				}
+				let font_size = controller.reading.font_size;
+				if font_size != render_context.font_size {
+					controller.render.set_font_size(
+						font_size,
+						controller.book.custom_fonts(),
+						&mut render_context);
+					controller.redraw(&mut render_context);
+				}
+				let render_han = controller.reading.render_han(self.cfg().render_han);
+				if render_han != controller.render.render_han() {
+					controller.render.reload_render(render_han, &mut render_context);
+					controller.redraw(&mut render_context);
+				}
+				drop(render_context);
+				self.find_list.set_inner_book(controller.reading.inner_book);
+				update_title(&self.window, &controller);
+				drop(controller);
+				self.loading.set(false);
+				self.sync_custom_settings();
+				self.update(&msg, ChapterListSyncMode::Reload);
+				// the target given overrides the synced position
+				if target.is_none() {
+					self.check_sync_position();
+				}
+			}
+			Err(err) => {
+				let msg = error_msg(&self.i18n, &err);
+				controller.book = Box::new(ReadmeBook::new(&msg));
+				controller.redraw(&mut render_context);
+				drop(render_context);
+				drop(controller);
+				self.loading.set(false);
+				self.error(&msg);
+			}
+		}
+	}
+
 	fn reload_book(&self)
 	{
 		let mut controller = self.ctrl_mut();
@@ -1712,13 +2279,47 @@ impl GuiContext {
 		match controller.switch_container(loading, &mut self.ctx_mut()) {
 			Ok(msg) => {
 				drop(controller);
+				self.sync_custom_settings();
 				self.chapter_list.sync_chapter_list(ChapterListSyncMode::Reload);
 				update_status(false, &msg, &self.status_bar)
 			}
-			Err(err) => self.error(&err.to_string()),
+			Err(err) => self.error(&error_msg(&self.i18n, &err)),
 		}
 	}
 
+	fn export_annotations(&self)
+	{
+		let controller = self.ctrl();
+		let book_name = controller.reading_book_name().to_owned();
+		let markdown = if let Some(selected) = controller.selected() {
+			annotations_markdown(&book_name, controller.status().title, selected)
+		} else {
+			drop(controller);
+			self.message(&self.i18n.msg("no-annotation"));
+			return;
+		};
+		drop(controller);
+
+		let dialog = FileDialog::builder()
+			.title(self.i18n.msg("export-annotations-title"))
+			.initial_name(format!("{}.md", book_name))
+			.modal(true)
+			.build();
+		let gc = self.clone();
+		dialog.save(Some(&self.window), None::<&Cancellable>, move |result| {
+			if let Ok(file) = result {
+				if let Some(path) = file.path() {
+					match fs::write(&path, &markdown) {
+						Ok(()) => gc.message(&gc.i18n.args_msg("annotations-exported", vec![
+							("path", path.to_string_lossy().to_string()),
+						])),
+						Err(err) => gc.error(&err.to_string()),
+					}
+				}
+			}
+		});
+	}
+
 	fn book_info(&self) -> Result<()>
 	{
 		#[inline]
@@ -1733,13 +2334,27 @@ impl GuiContext {
 		}
 
 		let mut text = String::new();
-		let controller = self.ctrl_mut();
+		let mut controller = self.ctrl_mut();
+		let total_word_count = controller.book.total_word_count();
 		let reading = &controller.reading;
 		let path = PathBuf::from_str(&reading.filename)?;
 		let meta = path.metadata()?;
 		let container = gtk4::Box::new(Orientation::Vertical, 10);
 		container.append(&label(&reading.filename, &mut text));
-		container.append(&label(&format_size(meta.len()), &mut text));
+		container.append(&label(&format_file_size(&self.i18n, meta.len()), &mut text));
+		let date_format = self.cfg().gui.date_format.clone();
+		if let Some(time) = meta.modified().ok()
+			.and_then(|time| format_file_time(&self.i18n, date_format.as_deref(), time)) {
+			container.append(&label(&self.i18n.args_msg("file-modified", vec![
+				("time", time),
+			]), &mut text));
+		}
+		if let Some(time) = meta.created().ok()
+			.and_then(|time| format_file_time(&self.i18n, date_format.as_deref(), time)) {
+			container.append(&label(&self.i18n.args_msg("file-created", vec![
+				("time", time),
+			]), &mut text));
+		}
 		container.append(&Separator::new(Orientation::Horizontal));
 		if let Some(book_names) = controller.container.inner_book_names() {
 			if let Some(name) = book_names.get(reading.inner_book) {
@@ -1751,6 +2366,24 @@ impl GuiContext {
 			container.append(&label(title, &mut text));
 		}
 		container.append(&label(&status.position(), &mut text));
+		if let Some(page) = status.page {
+			container.append(&label(&self.i18n.args_msg("print-page", vec![
+				("page", page),
+			]), &mut text));
+		}
+		if let Some(lang) = controller.language() {
+			container.append(&label(&self.i18n.args_msg("book-info-language", vec![
+				("lang", lang),
+			]), &mut text));
+		}
+		container.append(&label(&self.i18n.args_msg("reading-time-chapter", vec![
+			("minutes", status.reading_minutes),
+		]), &mut text));
+		if let Some(word_count) = total_word_count {
+			container.append(&label(&self.i18n.args_msg("reading-time-book", vec![
+				("minutes", word_count.minutes(&controller.reading_speed)),
+			]), &mut text));
+		}
 		let popover = Popover::builder()
 			.child(&container)
 			.build();
@@ -1791,6 +2424,7 @@ impl GuiContext {
 		};
 		self.sidebar_btn.set_active(on);
 		paned.set_position(position);
+		self.cfg_mut().gui.sidebar_open = on;
 	}
 
 	fn switch_theme(&self)
@@ -1800,10 +2434,95 @@ impl GuiContext {
 		self.theme_action.set_state(&dark_theme.to_variant());
 		configuration.dark_theme = dark_theme;
 		let mut render_context = self.ctx_mut();
-		render_context.colors = configuration.curr_theme().clone();
+		let colors = configuration.curr_theme().clone();
+		render_context.invert_images = configuration.gui.invert_images && dark_theme;
+		view::update_css(&self.css_provider, &colors);
+		if let Some(transition) = self.theme_transition.take() {
+			transition.remove();
+		}
 		let mut controller = self.ctrl_mut();
+		if !configuration.gui.smooth_theme {
+			render_context.colors = colors;
+			controller.redraw(&mut render_context);
+			return;
+		}
+
+		// fade from current colors, may be in the middle of previous transition
+		let from = render_context.colors.clone();
+		let start = glib::monotonic_time();
+		let gc = self.clone();
+		let transition = controller.render.add_tick_callback(move |_, _| {
+			let progress = (glib::monotonic_time() - start) as f32 / THEME_TRANSITION_TIME as f32;
+			let mut render_context = gc.ctx_mut();
+			render_context.colors = if progress >= 1. {
+				colors.clone()
+			} else {
+				from.mix(&colors, progress)
+			};
+			gc.ctrl_mut().redraw(&mut render_context);
+			if progress >= 1. {
+				gc.theme_transition.replace(None);
+				ControlFlow::Break
+			} else {
+				ControlFlow::Continue
+			}
+		});
+		self.theme_transition.replace(Some(transition));
+	}
+
+	/// dim lines except the paragraph at the top of the page
+	fn switch_focus_mode(&self)
+	{
+		let mut configuration = self.cfg_mut();
+		let focus_mode = !configuration.gui.focus_mode;
+		configuration.gui.focus_mode = focus_mode;
+		let mut render_context = self.ctx_mut();
+		render_context.focus_mode = focus_mode;
+		self.ctrl_mut().redraw(&mut render_context);
+	}
+
+	/// sync custom actions and render context with reading info,
+	/// after book reloaded or switched
+	fn sync_custom_settings(&self)
+	{
+		self.load_notes();
+		let mut controller = self.ctrl_mut();
+		let (custom_color, custom_font, custom_style) = custom_settings(
+			controller.book.as_ref(), &controller.reading);
+		sync_toggle_action(&self.custom_color_action, custom_color);
+		sync_toggle_action(&self.custom_font_action, custom_font);
+		self.custom_style_action.set_enabled(custom_style.is_some());
+
+		let custom_color = controller.reading.custom_color;
+		let custom_font = controller.reading.custom_font;
+		let mut render_context = self.ctx_mut();
+		if render_context.custom_color == custom_color
+			&& render_context.custom_font == custom_font {
+			return;
+		}
+		render_context.custom_color = custom_color;
+		if render_context.custom_font != custom_font {
+			controller.render.set_custom_font(
+				custom_font,
+				controller.book.custom_fonts(),
+				&mut render_context);
+		}
 		controller.redraw(&mut render_context);
-		view::update_css(&self.css_provider, &render_context.colors);
+	}
+
+	/// notes of the readme not saved
+	fn load_notes(&self)
+	{
+		let controller = self.ctrl();
+		let reading = &controller.reading;
+		let reading = if reading.filename == README_TEXT_FILENAME {
+			None
+		} else {
+			Some(reading)
+		};
+		if let Err(e) = self.notes.load(&self.cfg().notes_dir(), reading) {
+			eprintln!("Failed load notes: {}", e.to_string());
+		}
 	}
 
 	fn toggle_custom_color(&self)
@@ -1836,7 +2555,16 @@ impl GuiContext {
 		let controller = self.ctrl();
 		let reading = &controller.reading;
 		let gc = self.clone();
-		dialogs::custom_styles(&reading.custom_style, self, &self.window, move |new_style| {
+		dialogs::custom_styles(&reading.custom_style, self, &self.window, move |new_style, set_default| {
+			match set_default {
+				Some(true) => gc.cfg_mut().gui.default_style = if new_style.is_empty() {
+					None
+				} else {
+					Some(new_style.clone())
+				},
+				Some(false) => gc.cfg_mut().gui.default_style = None,
+				None => {}
+			}
 			let mut controller = gc.ctrl_mut();
 			let custom_style = if let Some(custom_style) = &controller.reading.custom_style {
 				if new_style == *custom_style {
@@ -1869,11 +2597,199 @@ impl GuiContext {
 		});
 	}
 
+	fn goto_chapter(&self)
+	{
+		let gc = self.clone();
+		dialogs::goto_chapter(self, &self.window, move |chapter_no| {
+			let by_toc = gc.cfg().chapter_number_by_toc;
+			let mut controller = gc.ctrl_mut();
+			let msg = controller.goto_chapter_index(chapter_no, by_toc,
+				gc.ctx_mut().deref_mut())?;
+			drop(controller);
+			gc.update(&msg, ChapterListSyncMode::NoReload);
+			Ok(())
+		});
+	}
+
+	fn goto_page(&self)
+	{
+		let gc = self.clone();
+		dialogs::goto_page(self, &self.window, move |label| {
+			let mut controller = gc.ctrl_mut();
+			controller.goto_page(label, gc.ctx_mut().deref_mut())?;
+			let msg = controller.status().to_string();
+			gc.message(&msg);
+			Ok(())
+		});
+	}
+
+	/// confirm before launching browser, links in untrusted book may be malicious
+	fn open_external_link(&self, url: String)
+	{
+		if self.cfg().gui.always_open_external_link {
+			if let Err(err) = self.opener().open_link(&url) {
+				self.error(&err.to_string());
+			}
+			return;
+		}
+		let i18n = &self.i18n;
+		let cancel = i18n.msg("cancel-title");
+		let always = i18n.msg("external-link-always");
+		let open = i18n.msg("external-link-open");
+		let dialog = AlertDialog::builder()
+			.modal(true)
+			.message(i18n.msg("external-link-title").as_ref())
+			.detail(&url)
+			.buttons([cancel.as_ref(), always.as_ref(), open.as_ref()])
+			.cancel_button(0)
+			.default_button(2)
+			.build();
+		let gc = self.clone();
+		dialog.choose(Some(&self.window), None::<&Cancellable>, move |result| {
+			match result {
+				Ok(1) => gc.cfg_mut().gui.always_open_external_link = true,
+				Ok(2) => {}
+				_ => return,
+			}
+			if let Err(err) = gc.opener().open_link(&url) {
+				gc.error(&err.to_string());
+			}
+		});
+	}
+
+	fn about(&self)
+	{
+		let i18n = &self.i18n;
+		let detail = [
+			i18n.args_msg("about-license", vec![("license", env!("CARGO_PKG_LICENSE"))]),
+			i18n.args_msg("about-homepage", vec![("url", env!("CARGO_PKG_HOMEPAGE"))]),
+			i18n.args_msg("about-features", vec![("features", enabled_features())]),
+		].join("\n");
+		let copy = i18n.msg("about-copy");
+		let ok = i18n.msg("ok-title");
+		let dialog = AlertDialog::builder()
+			.modal(true)
+			.message(version_string!())
+			.detail(&detail)
+			.buttons([copy.as_ref(), ok.as_ref()])
+			.cancel_button(1)
+			.default_button(1)
+			.build();
+		dialog.choose(Some(&self.window), None::<&Cancellable>, move |result| {
+			if let Ok(0) = result {
+				copy_to_clipboard(&format!("{}\n{}", version_string!(), detail));
+			}
+		});
+	}
+
+	/// offer to open the latest reading book
+	fn ask_continue_reading(&self)
+	{
+		let latest = match self.cfg().history(None, None) {
+			Ok(mut infos) if !infos.is_empty() => infos.remove(0),
+			_ => return,
+		};
+		let i18n = &self.i18n;
+		let cancel = i18n.msg("cancel-title");
+		let ok = i18n.msg("ok-title");
+		let dialog = AlertDialog::builder()
+			.modal(true)
+			.message(i18n.msg("continue-reading-title").as_ref())
+			.detail(latest.filename.as_str())
+			.buttons([cancel.as_ref(), ok.as_ref()])
+			.cancel_button(0)
+			.default_button(1)
+			.build();
+		let gc = self.clone();
+		dialog.choose(Some(&self.window), None::<&Cancellable>, move |result| {
+			if let Ok(1) = result {
+				gc.open_file(&PathBuf::from(&latest.filename));
+			}
+		});
+	}
+
+	/// ask for jump to position saved by other device
+	fn check_sync_position(&self)
+	{
+		let sync = {
+			let controller = self.ctrl();
+			if controller.reading.filename == README_TEXT_FILENAME {
+				return;
+			}
+			let configuration = self.cfg();
+			let Some(sync_dir) = &configuration.sync_dir else {
+				return;
+			};
+			match sync::newer_position(sync_dir, &controller.reading) {
+				Ok(Some(sync)) => sync,
+				Ok(None) => return,
+				Err(err) => {
+					drop(controller);
+					self.error(&err.to_string());
+					return;
+				}
+			}
+		};
+		let i18n = &self.i18n;
+		let cancel = i18n.msg("cancel-title");
+		let ok = i18n.msg("ok-title");
+		let dialog = AlertDialog::builder()
+			.modal(true)
+			.message(i18n.msg("sync-position-title").as_ref())
+			.detail(i18n.msg("sync-position-detail").as_ref())
+			.buttons([cancel.as_ref(), ok.as_ref()])
+			.cancel_button(0)
+			.default_button(1)
+			.build();
+		let gc = self.clone();
+		dialog.choose(Some(&self.window), None::<&Cancellable>, move |result| {
+			if let Ok(1) = result {
+				handle(&gc, |controller, render_context|
+					controller.goto(sync.inner_book, sync.chapter, sync.line,
+						sync.position, None, render_context));
+			}
+		});
+	}
+
 	#[inline]
 	fn update(&self, msg: &str, chapter_list_sync_mode: ChapterListSyncMode)
 	{
+		if let ChapterListSyncMode::ReloadIfNeeded(orig_inner_book) = chapter_list_sync_mode {
+			if self.ctrl().reading.inner_book != orig_inner_book {
+				self.sync_custom_settings();
+			}
+		}
 		self.message(msg);
+		self.progress_bar.set_value(self.ctrl().progress());
 		self.chapter_list.sync_chapter_list(chapter_list_sync_mode);
+		self.prefetch_chapters();
+	}
+
+	/// parse chapters following current one when idle,
+	/// so switching to them is instant
+	fn prefetch_chapters(&self)
+	{
+		if self.prefetching.replace(true) {
+			return;
+		}
+		let gc = self.clone();
+		glib::idle_add_local(move || {
+			if !gc.loading.get() && gc.ctrl_mut().book.prefetch_chapter() {
+				ControlFlow::Continue
+			} else {
+				gc.prefetching.set(false);
+				ControlFlow::Break
+			}
+		});
+	}
+
+	fn copy_location(&self)
+	{
+		let location = self.ctrl().location();
+		copy_to_clipboard(&location);
+		self.message(&self.i18n.args_msg("location-copied", vec![
+			("location", location),
+		]));
 	}
 
 	#[inline]
@@ -1893,6 +2809,63 @@ impl GuiContext {
 	{
 		self.settings.dialog(self);
 	}
+
+	fn reload_config(&self)
+	{
+		match self.settings.reload_config(self) {
+			Ok(()) => self.message(&self.i18n.msg("config-reloaded")),
+			Err(err) => self.error(&self.i18n.args_msg("failed-reload-config", vec![
+				("error", err.to_string()),
+			])),
+		}
+	}
+}
+
+fn annotations_markdown(book_name: &str, chapter_title: Option<&str>,
+	text: &str) -> String
+{
+	let mut markdown = format!("# {}\n\n", book_name);
+	if let Some(title) = chapter_title {
+		markdown.push_str(&format!("## {}\n\n", title));
+	}
+	for line in text.lines() {
+		markdown.push_str("> ");
+		markdown.push_str(line);
+		markdown.push('\n');
+	}
+	markdown
+}
+
+fn format_file_size(i18n: &I18n, size: u64) -> String
+{
+	const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+	let (size, unit) = if size < 1024 {
+		(size.to_string(), "B")
+	} else {
+		let mut value = size as f64 / 1024.;
+		let mut unit = 0;
+		while value >= 1024. && unit < UNITS.len() - 1 {
+			value /= 1024.;
+			unit += 1;
+		}
+		(format!("{:.1}", value), UNITS[unit])
+	};
+	i18n.args_msg("file-size", vec![
+		("size", size),
+		("unit", unit.to_owned()),
+	])
+}
+
+/// format time with configured format, or the one of current locale
+fn format_file_time(i18n: &I18n, format: Option<&str>, time: SystemTime) -> Option<String>
+{
+	let seconds = time.duration_since(UNIX_EPOCH).ok()?.as_secs();
+	let time = glib::DateTime::from_unix_local(seconds as i64).ok()?;
+	let format = match format {
+		Some(format) => Cow::Borrowed(format),
+		None => i18n.msg("date-time-format"),
+	};
+	Some(time.format(&format).ok()?.to_string())
 }
 
 fn update_status(error: bool, msg: &str, status_bar: &Label)
@@ -1907,10 +2880,20 @@ fn update_status(error: bool, msg: &str, status_bar: &Label)
 	status_bar.set_tooltip_text(Some(msg));
 }
 
-fn show(app: &Application, current: Option<String>,
+/// explain errors that user can do nothing about
+fn error_msg(i18n: &I18n, err: &anyhow::Error) -> String
+{
+	if err.downcast_ref::<DrmError>().is_some() {
+		i18n.msg("drm-protected").to_string()
+	} else {
+		err.to_string()
+	}
+}
+
+fn show(app: &Application, current: Option<String>, target: Option<StartTarget>,
 	cfg: &Rc<RefCell<Configuration>>, gcs: &Rc<RefCell<Vec<GuiContext>>>)
 {
-	match build_ui(app, current, cfg.clone(), gcs) {
+	match build_ui(app, current, target, cfg.clone(), gcs) {
 		Ok(Some(gc)) => {
 			// clean temp files
 			app.connect_shutdown(move |_| gc.opener().cleanup());
@@ -1956,7 +2939,8 @@ fn ignore_cap(key: Key, modifier: ModifierType) -> (Key, ModifierType)
 	}
 }
 
-pub fn start(current: Option<String>, configuration: Configuration)
+pub fn start(current: Option<String>, target: Option<StartTarget>,
+	configuration: Configuration)
 	-> Result<Option<(Option<String>, Configuration)>>
 {
 	#[cfg(unix)]
@@ -1998,19 +2982,28 @@ pub fn start(current: Option<String>, configuration: Configuration)
 				handle_signal(2, app.clone());
 				handle_signal(15, app.clone());
 			}
+			setup_remote_actions(app, &cfg, &gcs);
 			if start_without_file {
-				show(app, None, &cfg, &gcs);
+				show(app, None, None, &cfg, &gcs);
+				if cfg.borrow().continue_reading == ContinueReading::Ask {
+					// no need to ask if the last book opened already
+					if let Some(gc) = gcs.borrow().first().filter(|gc| gc.current.is_none()) {
+						gc.ask_continue_reading();
+					}
+				}
 			}
 		});
 	}
 
 	{
+		// only for the book given on command line
+		let target = Cell::new(target);
 		app.connect_open(move |app, files, _| {
 			if !files.is_empty() {
 				if let Some(path) = files[0].path() {
 					if let Some(path) = path.to_str() {
 						let current = Some(path.to_owned());
-						show(app, current, &cfg, &gcs);
+						show(app, current, target.take(), &cfg, &gcs);
 						let mut gui_contexts = gcs.borrow_mut();
 						if let Ok(idx) = get_gc(gui_contexts.as_ref(), README_TEXT_FILENAME) {
 							let gc = gui_contexts.remove(idx);
@@ -2030,6 +3023,88 @@ pub fn start(current: Option<String>, configuration: Configuration)
 	Ok(None)
 }
 
+/// application actions for controlling running tbr,
+/// exported on session bus by gio, no endpoint on platforms without d-bus, e.g.:
+/// gapplication action net.lzrj.tbr next-page
+/// gdbus call --session --dest net.lzrj.tbr --object-path /net/lzrj/tbr
+///   --method org.gtk.Actions.Activate next-page [] {}
+fn setup_remote_actions(app: &Application, cfg: &Rc<RefCell<Configuration>>,
+	gcs: &Rc<RefCell<Vec<GuiContext>>>)
+{
+	fn add_action<F>(app: &Application, gcs: &Rc<RefCell<Vec<GuiContext>>>,
+		name: &str, parameter_type: Option<&VariantTy>, f: F)
+	where
+		F: Fn(&GuiContext, Option<&Variant>) + 'static,
+	{
+		let action = SimpleAction::new(name, parameter_type);
+		let app = app.downgrade();
+		let gcs = gcs.clone();
+		action.connect_activate(move |_, parameter| {
+			if let Some(app) = app.upgrade() {
+				if let Some(gc) = active_gc(&app, &gcs) {
+					f(&gc, parameter);
+				}
+			}
+		});
+		app.add_action(&action);
+	}
+
+	add_action(app, gcs, "next-page", None, |gc, _|
+		handle(gc, |controller, render_context|
+			controller.next_page(render_context)));
+	add_action(app, gcs, "prev-page", None, |gc, _|
+		handle(gc, |controller, render_context|
+			controller.prev_page(render_context)));
+	add_action(app, gcs, "next-line", None, |gc, _|
+		handle(gc, |controller, render_context|
+			controller.step_next(render_context)));
+	add_action(app, gcs, "prev-line", None, |gc, _|
+		handle(gc, |controller, render_context|
+			controller.step_prev(render_context)));
+	add_action(app, gcs, "next-chapter", None, |gc, _|
+		handle(gc, |controller, render_context|
+			controller.switch_toc(true, render_context)));
+	add_action(app, gcs, "prev-chapter", None, |gc, _|
+		handle(gc, |controller, render_context|
+			controller.switch_toc(false, render_context)));
+	add_action(app, gcs, "goto-chapter", Some(VariantTy::INT32), |gc, parameter| {
+		if let Some(chapter_no) = parameter.and_then(|p| p.get::<i32>()) {
+			if chapter_no > 0 {
+				let by_toc = gc.cfg().chapter_number_by_toc;
+				handle(gc, |controller, render_context|
+					controller.goto_chapter_index(chapter_no as usize, by_toc, render_context));
+			}
+		}
+	});
+
+	let action = SimpleAction::new("open-file", Some(VariantTy::STRING));
+	let app_ref = app.downgrade();
+	let cfg = cfg.clone();
+	let gcs = gcs.clone();
+	action.connect_activate(move |_, parameter| {
+		if let Some(app) = app_ref.upgrade() {
+			if let Some(path) = parameter.and_then(|p| p.get::<String>()) {
+				show(&app, Some(path), None, &cfg, &gcs);
+			}
+		}
+	});
+	app.add_action(&action);
+}
+
+/// context of active window, or first window if none active
+fn active_gc(app: &Application, gcs: &Rc<RefCell<Vec<GuiContext>>>)
+	-> Option<GuiContext>
+{
+	let gui_contexts = gcs.borrow();
+	if let Some(window) = app.active_window() {
+		if let Some(gc) = gui_contexts.iter()
+			.find(|gc| gc.window.upcast_ref::<Window>() == &window) {
+			return Some(gc.clone());
+		}
+	}
+	gui_contexts.first().cloned()
+}
+
 #[cfg(unix)]
 fn handle_signal(signum: i32, app: Application)
 {
@@ -2040,6 +3115,23 @@ fn handle_signal(signum: i32, app: Application)
 	});
 }
 
+/// cargo features enabled at compile time
+fn enabled_features() -> String
+{
+	let features = [
+		("gui", cfg!(feature = "gui")),
+		("i18n", cfg!(feature = "i18n")),
+		("dict", cfg!(feature = "dict")),
+		("open", cfg!(feature = "open")),
+		("opds", cfg!(feature = "opds")),
+	];
+	features.iter()
+		.filter(|(_, enabled)| *enabled)
+		.map(|(name, _)| *name)
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
 #[inline]
 fn alert(title: &str, msg: &str, parent: &impl IsA<Window>)
 {
@@ -2062,3 +3154,43 @@ fn get_gc(gcs: &Vec<GuiContext>, filename: &str) -> core::result::Result<usize,
 {
 	gcs.binary_search_by(|gc| gc.ctrl().reading.filename.as_str().cmp(filename))
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::book::{Book, Line};
+	use crate::config::ReadingInfo;
+	use crate::gui::{custom_settings, ReadmeBook};
+
+	struct CustomizableBook {
+		lines: Vec<Line>,
+	}
+
+	impl Book for CustomizableBook {
+		fn lines(&self) -> &Vec<Line>
+		{
+			&self.lines
+		}
+
+		fn color_customizable(&self) -> bool { true }
+
+		fn fonts_customizable(&self) -> bool { true }
+
+		fn style_customizable(&self) -> bool { true }
+	}
+
+	#[test]
+	fn custom_settings_mapping()
+	{
+		let mut reading = ReadingInfo::fake("test");
+		reading.custom_color = true;
+		reading.custom_font = false;
+		reading.custom_style = Some(String::from("p { margin: 0; }"));
+
+		let book = ReadmeBook::new("readme");
+		assert_eq!(custom_settings(&book, &reading), (None, None, None));
+
+		let book = CustomizableBook { lines: vec![] };
+		assert_eq!(custom_settings(&book, &reading),
+			(Some(true), Some(false), Some(Some(String::from("p { margin: 0; }")))));
+	}
+}
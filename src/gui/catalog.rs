@@ -0,0 +1,287 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread::spawn;
+
+use gtk4::{Align, Button, EventControllerKey, glib, HeaderBar, Label, ListBox, Orientation, PolicyType, ScrolledWindow, SelectionMode, Window};
+use gtk4::gdk::Key;
+use gtk4::glib::{ControlFlow, timeout_add_local};
+use gtk4::pango::EllipsizeMode;
+use gtk4::prelude::{BoxExt, ButtonExt, GtkWindowExt, ListBoxRowExt, WidgetExt};
+
+use crate::gui::{app_open, GuiContext, LOADING_CHECK_INTERVAL, MODIFIER_NONE};
+use crate::opds::{OpdsClient, OpdsFeed, OpdsLink};
+use crate::package_name;
+
+enum Loaded {
+	Feed(String, OpdsFeed),
+	Book(PathBuf),
+	Error(String),
+}
+
+struct CatalogInner {
+	client: OpdsClient,
+	// urls of opened feeds, for going back
+	trace: Vec<String>,
+	feed: Option<OpdsFeed>,
+	loading: bool,
+}
+
+#[derive(Clone)]
+struct Catalog {
+	inner: Rc<RefCell<CatalogInner>>,
+	gc: GuiContext,
+	list: ListBox,
+	title: Label,
+	status: Label,
+	back: Button,
+}
+
+pub(super) fn show(gc: &GuiContext)
+{
+	let Some(config) = gc.cfg().gui.opds.clone() else {
+		gc.error(&gc.i18n.msg("opds-not-configured"));
+		return;
+	};
+	let i18n = &gc.i18n;
+
+	let back = Button::builder()
+		.label(i18n.msg("opds-back"))
+		.sensitive(false)
+		.build();
+	let title = Label::builder()
+		.ellipsize(EllipsizeMode::End)
+		.build();
+	let header_bar = HeaderBar::builder()
+		.title_widget(&title)
+		.build();
+	header_bar.pack_start(&back);
+
+	let list = ListBox::builder()
+		.selection_mode(SelectionMode::Single)
+		.build();
+	list.add_css_class("navigation-sidebar");
+	let status = Label::builder()
+		.halign(Align::Start)
+		.ellipsize(EllipsizeMode::End)
+		.build();
+	let main = gtk4::Box::new(Orientation::Vertical, 0);
+	main.append(&ScrolledWindow::builder()
+		.child(&list)
+		.hscrollbar_policy(PolicyType::Never)
+		.vexpand(true)
+		.build());
+	main.append(&status);
+
+	let window = Window::builder()
+		.title(i18n.msg("opds-catalog"))
+		.titlebar(&header_bar)
+		.transient_for(&gc.window)
+		.default_width(500)
+		.default_height(600)
+		.child(&main)
+		.build();
+
+	let catalog = Catalog {
+		inner: Rc::new(RefCell::new(CatalogInner {
+			client: OpdsClient::new(&config),
+			trace: vec![],
+			feed: None,
+			loading: false,
+		})),
+		gc: gc.clone(),
+		list: list.clone(),
+		title,
+		status,
+		back: back.clone(),
+	};
+	{
+		let catalog = catalog.clone();
+		list.connect_row_activated(move |_, row| catalog.activate(row.index()));
+	}
+	{
+		let catalog = catalog.clone();
+		back.connect_clicked(move |_| catalog.go_back());
+	}
+	{
+		let key_event = EventControllerKey::new();
+		let window = window.clone();
+		key_event.connect_key_pressed(move |_, key, _, modifier| {
+			if key == Key::Escape && modifier == MODIFIER_NONE {
+				window.close();
+				glib::Propagation::Stop
+			} else {
+				glib::Propagation::Proceed
+			}
+		});
+		window.add_controller(key_event);
+	}
+	window.present();
+	catalog.load_feed(config.url);
+}
+
+impl Catalog {
+	fn activate(&self, index: i32)
+	{
+		if index < 0 {
+			return;
+		}
+		let index = index as usize;
+		let inner = self.inner.borrow();
+		if inner.loading {
+			return;
+		}
+		let Some(feed) = &inner.feed else {
+			return;
+		};
+		if let Some(entry) = feed.entries.get(index) {
+			match &entry.link {
+				OpdsLink::Navigation(url) => {
+					let url = url.clone();
+					drop(inner);
+					self.load_feed(url);
+				}
+				OpdsLink::Acquisition(url, ext) => {
+					let url = url.clone();
+					let title = entry.title.clone();
+					let ext = *ext;
+					drop(inner);
+					self.download(url, title, ext);
+				}
+			}
+		} else if let Some(next) = &feed.next {
+			// the last row for more entries
+			let next = next.clone();
+			drop(inner);
+			self.load_feed(next);
+		}
+	}
+
+	fn go_back(&self)
+	{
+		let mut inner = self.inner.borrow_mut();
+		if inner.loading || inner.trace.len() < 2 {
+			return;
+		}
+		// current one
+		inner.trace.pop();
+		let url = inner.trace.pop().unwrap();
+		drop(inner);
+		self.load_feed(url);
+	}
+
+	fn load_feed(&self, url: String)
+	{
+		let client = self.start_loading("opds-loading");
+		let (tx, rx) = mpsc::channel();
+		spawn(move || {
+			let loaded = match client.feed(&url) {
+				Ok(feed) => Loaded::Feed(url, feed),
+				Err(err) => Loaded::Error(err.to_string()),
+			};
+			let _ = tx.send(loaded);
+		});
+		self.wait(rx);
+	}
+
+	fn download(&self, url: String, title: String, ext: &'static str)
+	{
+		let Some(cache_dir) = dirs::cache_dir() else {
+			self.status.set_label(&self.gc.i18n.msg("opds-no-cache-dir"));
+			return;
+		};
+		let dir = cache_dir.join(package_name!()).join("opds");
+		let client = self.start_loading("opds-downloading");
+		let (tx, rx) = mpsc::channel();
+		spawn(move || {
+			let loaded = match client.download(&url, &title, ext, &dir) {
+				Ok(path) => Loaded::Book(path),
+				Err(err) => Loaded::Error(err.to_string()),
+			};
+			let _ = tx.send(loaded);
+		});
+		self.wait(rx);
+	}
+
+	#[inline]
+	fn start_loading(&self, msg_key: &str) -> OpdsClient
+	{
+		let mut inner = self.inner.borrow_mut();
+		inner.loading = true;
+		self.back.set_sensitive(false);
+		self.status.set_label(&self.gc.i18n.msg(msg_key));
+		inner.client.clone()
+	}
+
+	fn wait(&self, rx: Receiver<Loaded>)
+	{
+		let catalog = self.clone();
+		timeout_add_local(LOADING_CHECK_INTERVAL, move || {
+			match rx.try_recv() {
+				Ok(loaded) => {
+					catalog.loaded(loaded);
+					ControlFlow::Break
+				}
+				Err(TryRecvError::Empty) => ControlFlow::Continue,
+				Err(TryRecvError::Disconnected) => {
+					catalog.loaded(Loaded::Error(String::from("Loading stopped")));
+					ControlFlow::Break
+				}
+			}
+		});
+	}
+
+	fn loaded(&self, loaded: Loaded)
+	{
+		let mut inner = self.inner.borrow_mut();
+		inner.loading = false;
+		match loaded {
+			Loaded::Feed(url, feed) => {
+				self.status.set_label("");
+				self.title.set_label(&feed.title);
+				self.list.remove_all();
+				for entry in &feed.entries {
+					let label = if let Some(author) = &entry.author {
+						format!("{} - {}", entry.title, author)
+					} else {
+						entry.title.clone()
+					};
+					let label = if let OpdsLink::Navigation(_) = entry.link {
+						format!("{} ›", label)
+					} else {
+						label
+					};
+					self.list.append(&create_row(&label));
+				}
+				if feed.next.is_some() {
+					self.list.append(&create_row(&self.gc.i18n.msg("opds-more")));
+				}
+				inner.trace.push(url);
+				inner.feed = Some(feed);
+			}
+			Loaded::Book(path) => {
+				self.status.set_label("");
+				if let Some(app) = self.gc.window.application() {
+					if let Some(path) = path.to_str() {
+						app_open(&app, path);
+					}
+				}
+			}
+			Loaded::Error(msg) => self.status.set_label(&msg),
+		}
+		self.back.set_sensitive(inner.trace.len() > 1);
+	}
+}
+
+#[inline]
+fn create_row(text: &str) -> Label
+{
+	Label::builder()
+		.label(text)
+		.halign(Align::Start)
+		.ellipsize(EllipsizeMode::End)
+		.tooltip_text(text)
+		.build()
+}
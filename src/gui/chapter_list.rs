@@ -198,22 +198,31 @@ impl ChapterList {
 		}
 	}
 
+	/// scroll only when the selected row is out of the visible area,
+	/// so reading on won't fight with browsing the list
+	fn keep_current_visible(&self)
+	{
+		let list = &self.inner.list;
+		if let Some(row) = list.selected_row() {
+			if let Some(point) = row.compute_point(list, &Point::new(0., 0.)) {
+				if let Some(adj) = list.adjustment() {
+					let (_, height) = row.preferred_size();
+					let top = point.y() as f64;
+					let bottom = top + height.height() as f64;
+					if top < adj.value() || bottom > adj.value() + adj.page_size() {
+						self.scroll_to_current();
+					}
+				}
+			}
+		}
+	}
+
 	pub(super) fn sync_chapter_list(&self, sync_mode: ChapterListSyncMode)
 	{
+		/// select the row of current toc entry, return true if selection changed
 		#[inline]
-		fn do_sync(sync_mode: ChapterListSyncMode, chapter_list: &ChapterList,
-			controller: &GuiController)
+		fn do_sync(chapter_list: &ChapterList, controller: &GuiController) -> bool
 		{
-			let inner_book = controller.reading.inner_book;
-			if match sync_mode {
-				ChapterListSyncMode::NoReload => false,
-				ChapterListSyncMode::Reload => true,
-				ChapterListSyncMode::ReloadIfNeeded(orig_inner_book) => orig_inner_book != inner_book,
-			} {
-				load_entries(chapter_list);
-				return;
-			}
-
 			let list = &chapter_list.inner.list;
 			let entries = &chapter_list.inner.rows.borrow();
 			let toc_index = controller.toc_index();
@@ -221,8 +230,8 @@ impl ChapterList {
 				let index = row.index();
 				if index >= 0 {
 					if let Some(entry) = entries.get(index as usize) {
-						if entry.index == toc_index {
-							return;
+						if !entry.book && entry.index == toc_index {
+							return false;
 						}
 					}
 				}
@@ -233,13 +242,25 @@ impl ChapterList {
 				if !entry.book && entry.index == toc_index {
 					if let Some(row) = list.row_at_index(i as i32) {
 						list.select_row(Some(&row));
+						return true;
 					}
 				}
 			}
+			false
 		}
+		let reload = match sync_mode {
+			ChapterListSyncMode::NoReload => false,
+			ChapterListSyncMode::Reload => true,
+			ChapterListSyncMode::ReloadIfNeeded(orig_inner_book) =>
+				orig_inner_book != self.ctrl().reading.inner_book,
+		};
 		self.block_reactive(true);
-		do_sync(sync_mode, &self, &self.ctrl());
-		self.scroll_to_current();
+		if reload {
+			load_entries(self);
+			self.scroll_to_current();
+		} else if do_sync(self, &self.ctrl()) {
+			self.keep_current_visible();
+		}
 		self.block_reactive(false);
 	}
 
@@ -324,6 +345,7 @@ pub fn load_entries(chapter_list: &ChapterList)
 					} else {
 						chapter_list.collapse(false);
 						chapter_list.sync_chapter_list(ChapterListSyncMode::NoReload);
+						chapter_list.scroll_to_current();
 					}
 				});
 				row.add_controller(click);
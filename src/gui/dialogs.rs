@@ -1,16 +1,20 @@
 use std::borrow::Cow;
 
 use anyhow::Result;
-use gtk4::{Align, Button, Entry, EventControllerKey, glib, Orientation, ScrolledWindow, Separator, TextBuffer, TextView, Widget, Window};
+use gtk4::{Align, Button, CheckButton, DropDown, Entry, EventControllerKey, glib, Orientation, ScrolledWindow, Separator, StringList, TextBuffer, TextView, Widget, Window};
 use gtk4::gdk::Key;
-use gtk4::prelude::{BoxExt, ButtonExt, EditableExt, EntryExt, GtkWindowExt, IsA, TextBufferExt, WidgetExt};
+use gtk4::glib::prelude::Cast;
+use gtk4::prelude::{BoxExt, ButtonExt, CheckButtonExt, EditableExt, EntryExt, GtkWindowExt, IsA, TextBufferExt, WidgetExt};
 
+use crate::config::NamedStyle;
 use crate::gui::{alert, GuiContext, MODIFIER_NONE};
 use crate::html_parser;
 
+/// callback with the style text, and Some(true) if set as default style,
+/// Some(false) if it's not default any more, None for no change
 pub(crate) fn custom_styles<F>(style: &Option<String>, gc: &GuiContext,
 	main_win: &impl IsA<Window>, callback: F)
-	where F: Fn(String) + 'static
+	where F: Fn(String, Option<bool>) + 'static
 {
 	let buf = TextBuffer::builder()
 		.enable_undo(true)
@@ -30,8 +34,106 @@ pub(crate) fn custom_styles<F>(style: &Option<String>, gc: &GuiContext,
 		.height_request(500)
 		.hexpand(true)
 		.build();
+	let i18n = &gc.i18n;
+
+	// style library, first entry is the title
+	let library_list = StringList::new(&[i18n.msg("style-library").as_ref()]);
+	for named_style in &gc.cfg().gui.styles {
+		library_list.append(&named_style.name);
+	}
+	let library_dropdown = DropDown::builder()
+		.model(&library_list)
+		.build();
+	let name_entry = Entry::builder()
+		.placeholder_text(i18n.msg("style-name-placeholder"))
+		.hexpand(true)
+		.build();
+	let save_btn = Button::builder()
+		.label(i18n.msg("style-save"))
+		.build();
+	let delete_btn = Button::builder()
+		.label(i18n.msg("style-delete"))
+		.build();
+	{
+		let gc = gc.clone();
+		let buf = buf.clone();
+		let name_entry = name_entry.clone();
+		library_dropdown.connect_selected_notify(move |dropdown| {
+			let idx = dropdown.selected() as usize;
+			if idx == 0 {
+				return;
+			}
+			if let Some(named_style) = gc.cfg().gui.styles.get(idx - 1) {
+				buf.set_text(&named_style.style);
+				name_entry.set_text(&named_style.name);
+			}
+		});
+	}
+	{
+		let gc = gc.clone();
+		let buf = buf.clone();
+		let name_entry = name_entry.clone();
+		let library_list = library_list.clone();
+		save_btn.connect_clicked(move |btn| {
+			let name = name_entry.text();
+			let name = name.trim();
+			if name.is_empty() {
+				if let Some(win) = btn.root().and_then(|root| root.downcast::<Window>().ok()) {
+					alert(&gc.i18n.msg("invalid-input-title"),
+						&gc.i18n.msg("style-name-empty"), &win);
+				}
+				return;
+			}
+			let (start, end) = buf.bounds();
+			let style = buf.text(&start, &end, true).to_string();
+			let mut configuration = gc.cfg_mut();
+			let styles = &mut configuration.gui.styles;
+			if let Some(named_style) = styles.iter_mut().find(|s| s.name == name) {
+				named_style.style = style;
+			} else {
+				styles.push(NamedStyle { name: name.to_owned(), style });
+				library_list.append(name);
+			}
+		});
+	}
+	{
+		let gc = gc.clone();
+		let name_entry = name_entry.clone();
+		let library_list = library_list.clone();
+		let library_dropdown = library_dropdown.clone();
+		delete_btn.connect_clicked(move |_| {
+			let name = name_entry.text();
+			let name = name.trim();
+			let mut configuration = gc.cfg_mut();
+			let styles = &mut configuration.gui.styles;
+			if let Some(idx) = styles.iter().position(|s| s.name == name) {
+				styles.remove(idx);
+				drop(configuration);
+				library_dropdown.set_selected(0);
+				library_list.remove(idx as u32 + 1);
+				name_entry.set_text("");
+			}
+		});
+	}
+	let library_box = gtk4::Box::new(Orientation::Horizontal, 10);
+	library_box.append(&library_dropdown);
+	library_box.append(&name_entry);
+	library_box.append(&save_btn);
+	library_box.append(&delete_btn);
+
+	let is_default = style.is_some() && *style == gc.cfg().gui.default_style;
+	let default_cb = CheckButton::builder()
+		.label(i18n.msg("style-set-default"))
+		.active(is_default)
+		.build();
+
+	let style_box = gtk4::Box::new(Orientation::Vertical, 10);
+	style_box.append(&library_box);
+	style_box.append(&scroll_view);
+	style_box.append(&default_cb);
+
 	let gc2 = gc.clone();
-	input_dialog(&scroll_view, "custom-style-dialog-title", gc, main_win, move |_, _| {
+	input_dialog(&style_box, "custom-style-dialog-title", gc, main_win, move |_, _| {
 		let (start, end) = buf.bounds();
 		let text = buf.text(&start, &end, true);
 		html_parser::parse_stylesheet(&text, true)
@@ -39,7 +141,14 @@ pub(crate) fn custom_styles<F>(style: &Option<String>, gc: &GuiContext,
 				Cow::Owned(gc2.i18n.args_msg("invalid-style", vec![
 					("error", err.to_string()),
 				])))?;
-		callback(text.to_string());
+		let set_default = if default_cb.is_active() {
+			Some(true)
+		} else if is_default {
+			Some(false)
+		} else {
+			None
+		};
+		callback(text.to_string(), set_default);
 		Ok(())
 	});
 }
@@ -65,6 +174,46 @@ pub(crate) fn goto<F>(gc: &GuiContext, main_win: &impl IsA<Window>, callback: F)
 	entry.connect_activate(move |_| ok_btn.emit_clicked());
 }
 
+#[inline]
+pub(crate) fn goto_chapter<F>(gc: &GuiContext, main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(usize) -> Result<()> + 'static
+{
+	let entry = Entry::builder()
+		.placeholder_text(gc.i18n.msg("goto-chapter-placeholder"))
+		.build();
+	let ok_btn = input_dialog(&entry, "goto-chapter-dialog-title", gc, main_win, move |gc, entry| {
+		let chapter_no = entry
+			.text()
+			.to_string()
+			.trim()
+			.parse()
+			.map_err(|_| gc.i18n.msg("invalid-format"))?;
+		callback(chapter_no)
+			.map_err(|e| Cow::Owned(e.to_string()))?;
+		Ok(())
+	});
+	entry.connect_activate(move |_| ok_btn.emit_clicked());
+}
+
+pub(crate) fn goto_page<F>(gc: &GuiContext, main_win: &impl IsA<Window>, callback: F)
+	where F: Fn(&str) -> Result<()> + 'static
+{
+	let entry = Entry::builder()
+		.placeholder_text(gc.i18n.msg("goto-page-placeholder"))
+		.build();
+	let ok_btn = input_dialog(&entry, "goto-page-dialog-title", gc, main_win, move |gc, entry| {
+		let text = entry.text();
+		let label = text.trim();
+		if label.is_empty() {
+			return Err(gc.i18n.msg("invalid-format"));
+		}
+		callback(label)
+			.map_err(|e| Cow::Owned(e.to_string()))?;
+		Ok(())
+	});
+	entry.connect_activate(move |_| ok_btn.emit_clicked());
+}
+
 fn input_dialog<F, W>(widget: &W, title: &str,
 	gc: &GuiContext, main_win: &impl IsA<Window>, callback: F) -> Button
 	where
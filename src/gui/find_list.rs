@@ -181,7 +181,7 @@ fn find_in_book(container_manager: &ContainerManager,
 	container: &mut Box<dyn Container>, filename: &str, inner_book: usize,
 	regex: &Regex, tx: &Sender<FoundEntry>, state: &Arc<Mutex<FindState>>) -> Result<(), SearchError>
 {
-	let loading = BookLoadingInfo::NewReading(&filename, inner_book, 0, 16);
+	let loading = BookLoadingInfo::NewReading(&filename, inner_book, 0, 16, None);
 	if let Ok((mut book, _)) = load_book(&container_manager, container, loading) {
 		let mut chapter = 0;
 		loop {
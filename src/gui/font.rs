@@ -11,6 +11,8 @@ use ouroboros::self_referencing;
 use crate::config::PathConfig;
 use crate::html_parser::{FontWeight, HtmlFontFaceDesc};
 
+mod woff;
+
 pub trait Fonts {
 	fn query(&self, char: char, font_size: f32, font_weight: &FontWeight,
 		font_family_names: Option<&str>) -> Option<(OutlinedGlyph, Rect)>;
@@ -102,9 +104,18 @@ pub fn user_fonts(font_paths: &Vec<PathConfig>) -> Result<Option<UserFonts>>
 					.read(true)
 					.open(&config.path) {
 					let mut buf = vec![];
-					file.read_to_end(&mut buf)?;
-					let source = fontdb::Source::Binary(Arc::new(buf));
-					db.load_font_source(source);
+					// skip the bad font, others still usable
+					let result = file.read_to_end(&mut buf)
+						.map_err(anyhow::Error::from)
+						.and_then(|_| woff::sfnt_data(buf));
+					match result {
+						Ok(buf) => {
+							let source = fontdb::Source::Binary(Arc::new(buf));
+							db.load_font_source(source);
+						}
+						Err(err) => eprintln!("Failed load font {}: {}",
+							config.path.display(), err),
+					}
 				}
 			}
 		}
@@ -155,7 +166,8 @@ impl HtmlFonts {
 			for source in face.sources {
 				match self.fonts.binary_search_by(|(key, _)| key.cmp(&source)) {
 					Ok(idx) => refs.push(idx),
-					Err(idx) => if let Some(content) = data_resolver(&source) {
+					Err(idx) => if let Some(content) = data_resolver(&source)
+						.and_then(|content| woff::sfnt_data(content).ok()) {
 						if let Ok(font) = FontVec::try_from_vec(content) {
 							self.fonts.insert(idx, (source, font));
 							for v in &mut refs {
@@ -0,0 +1,773 @@
+use std::io::Read;
+use anyhow::{anyhow, bail, Result};
+use brotli_decompressor::Decompressor;
+use flate2::read::ZlibDecoder;
+
+const WOFF_SIGNATURE: &[u8; 4] = b"wOFF";
+const WOFF2_SIGNATURE: &[u8; 4] = b"wOF2";
+const TTC_FLAVOR: u32 = 0x74746366;
+const HEAD_CHECKSUM_ADJUSTMENT: u32 = 0xB1B0AFBA;
+// no real font this large, limit memory for broken data
+const MAX_SFNT_SIZE: usize = 64 * 1024 * 1024;
+
+// composite glyph flags
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+// simple glyph flags
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+const KNOWN_TAGS: [[u8; 4]; 63] = [
+	*b"cmap", *b"head", *b"hhea", *b"hmtx", *b"maxp", *b"name", *b"OS/2",
+	*b"post", *b"cvt ", *b"fpgm", *b"glyf", *b"loca", *b"prep", *b"CFF ",
+	*b"VORG", *b"EBDT", *b"EBLC", *b"gasp", *b"hdmx", *b"kern", *b"LTSH",
+	*b"PCLT", *b"VDMX", *b"vhea", *b"vmtx", *b"BASE", *b"GDEF", *b"GPOS",
+	*b"GSUB", *b"EBSC", *b"JSTF", *b"MATH", *b"CBDT", *b"CBLC", *b"COLR",
+	*b"CPAL", *b"SVG ", *b"sbix", *b"acnt", *b"avar", *b"bdat", *b"bloc",
+	*b"bsln", *b"cvar", *b"fdsc", *b"feat", *b"fmtx", *b"fvar", *b"gvar",
+	*b"hsty", *b"just", *b"lcar", *b"mort", *b"morx", *b"opbd", *b"prop",
+	*b"trak", *b"Zapf", *b"Silf", *b"Glat", *b"Gloc", *b"Feat", *b"Sill",
+];
+
+/// convert woff/woff2 font data into sfnt font data,
+/// detected by magic bytes, other data returned as is
+pub fn sfnt_data(content: Vec<u8>) -> Result<Vec<u8>>
+{
+	match content.get(..4) {
+		Some(magic) if magic == WOFF_SIGNATURE => woff_to_sfnt(&content),
+		Some(magic) if magic == WOFF2_SIGNATURE => woff2_to_sfnt(&content),
+		_ => Ok(content),
+	}
+}
+
+struct Reader<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	#[inline]
+	fn new(data: &'a [u8]) -> Self
+	{
+		Reader { data, pos: 0 }
+	}
+
+	fn bytes(&mut self, len: usize) -> Result<&'a [u8]>
+	{
+		let end = self.pos.checked_add(len)
+			.filter(|end| *end <= self.data.len())
+			.ok_or_else(|| anyhow!("Unexpected end of font data"))?;
+		let bytes = &self.data[self.pos..end];
+		self.pos = end;
+		Ok(bytes)
+	}
+
+	#[inline]
+	fn skip(&mut self, len: usize) -> Result<()>
+	{
+		self.bytes(len)?;
+		Ok(())
+	}
+
+	#[inline]
+	fn tag(&mut self) -> Result<[u8; 4]>
+	{
+		let bytes = self.bytes(4)?;
+		Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+	}
+
+	#[inline]
+	fn u8(&mut self) -> Result<u8>
+	{
+		Ok(self.bytes(1)?[0])
+	}
+
+	#[inline]
+	fn u16(&mut self) -> Result<u16>
+	{
+		let bytes = self.bytes(2)?;
+		Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+	}
+
+	#[inline]
+	fn i16(&mut self) -> Result<i16>
+	{
+		Ok(self.u16()? as i16)
+	}
+
+	#[inline]
+	fn u32(&mut self) -> Result<u32>
+	{
+		Ok(u32::from_be_bytes(self.tag()?))
+	}
+
+	/// 255UInt16 of woff2
+	fn u255(&mut self) -> Result<u16>
+	{
+		let value = match self.u8()? {
+			253 => self.u16()?,
+			254 => 253 * 2 + self.u8()? as u16,
+			255 => 253 + self.u8()? as u16,
+			code => code as u16,
+		};
+		Ok(value)
+	}
+
+	/// UIntBase128 of woff2
+	fn base128(&mut self) -> Result<u32>
+	{
+		let mut value = 0u32;
+		for i in 0..5 {
+			let byte = self.u8()?;
+			if i == 0 && byte == 0x80 {
+				bail!("Invalid leading zero in woff2 data");
+			}
+			if value & 0xFE000000 != 0 {
+				bail!("Overflow in woff2 data");
+			}
+			value = (value << 7) | (byte & 0x7F) as u32;
+			if byte & 0x80 == 0 {
+				return Ok(value);
+			}
+		}
+		bail!("Invalid number in woff2 data")
+	}
+}
+
+#[inline]
+fn sfnt_size_limit(total_sfnt_size: u32) -> Result<usize>
+{
+	let size = total_sfnt_size as usize;
+	if size > MAX_SFNT_SIZE {
+		bail!("Font too large: {}", size);
+	}
+	Ok(size)
+}
+
+struct Table {
+	tag: [u8; 4],
+	data: Vec<u8>,
+}
+
+fn woff_to_sfnt(content: &[u8]) -> Result<Vec<u8>>
+{
+	let mut reader = Reader::new(content);
+	// signature
+	reader.skip(4)?;
+	let flavor = reader.u32()?;
+	// length
+	reader.skip(4)?;
+	let num_tables = reader.u16()?;
+	// reserved
+	reader.skip(2)?;
+	let total_sfnt_size = sfnt_size_limit(reader.u32()?)?;
+	// version, metadata and private block
+	reader.skip(2 + 2 + 4 * 5)?;
+
+	let mut tables = Vec::with_capacity(num_tables as usize);
+	for _ in 0..num_tables {
+		let tag = reader.tag()?;
+		let offset = reader.u32()? as usize;
+		let comp_length = reader.u32()? as usize;
+		let orig_length = reader.u32()? as usize;
+		// checksum, recalculated when building sfnt
+		reader.skip(4)?;
+		let mut table_reader = Reader::new(content);
+		table_reader.skip(offset)?;
+		let compressed = table_reader.bytes(comp_length)?;
+		if orig_length > total_sfnt_size {
+			bail!("Invalid woff table length");
+		}
+		let data = if comp_length < orig_length {
+			let mut data = Vec::with_capacity(orig_length);
+			ZlibDecoder::new(compressed)
+				.take(orig_length as u64 + 1)
+				.read_to_end(&mut data)?;
+			if data.len() != orig_length {
+				bail!("Invalid woff table length");
+			}
+			data
+		} else if comp_length == orig_length {
+			compressed.to_vec()
+		} else {
+			bail!("Invalid woff table length");
+		};
+		tables.push(Table { tag, data });
+	}
+	Ok(build_sfnt(flavor, tables))
+}
+
+struct Woff2Entry {
+	tag: [u8; 4],
+	transformed: bool,
+	length: usize,
+}
+
+fn woff2_to_sfnt(content: &[u8]) -> Result<Vec<u8>>
+{
+	let mut reader = Reader::new(content);
+	// signature
+	reader.skip(4)?;
+	let flavor = reader.u32()?;
+	if flavor == TTC_FLAVOR {
+		bail!("Woff2 font collection not supported");
+	}
+	// length
+	reader.skip(4)?;
+	let num_tables = reader.u16()?;
+	// reserved
+	reader.skip(2)?;
+	let total_sfnt_size = sfnt_size_limit(reader.u32()?)?;
+	let compressed_size = reader.u32()? as usize;
+	// version, metadata and private block
+	reader.skip(2 + 2 + 4 * 5)?;
+
+	let mut entries = Vec::with_capacity(num_tables as usize);
+	for _ in 0..num_tables {
+		let flags = reader.u8()?;
+		let tag = match flags & 0x3F {
+			0x3F => reader.tag()?,
+			index => KNOWN_TAGS[index as usize],
+		};
+		let version = flags >> 6;
+		let orig_length = reader.base128()? as usize;
+		// version 0 of glyf and loca is transformed, others is null transform
+		let transformed = if &tag == b"glyf" || &tag == b"loca" {
+			version == 0
+		} else {
+			version != 0
+		};
+		let length = if transformed {
+			reader.base128()? as usize
+		} else {
+			orig_length
+		};
+		entries.push(Woff2Entry { tag, transformed, length });
+	}
+
+	let compressed = reader.bytes(compressed_size)?;
+	// transformed tables never larger than the original
+	let stream_size = entries.iter()
+		.try_fold(0usize, |size, entry| size.checked_add(entry.length))
+		.filter(|size| *size <= total_sfnt_size)
+		.ok_or_else(|| anyhow!("Invalid woff2 table length"))?;
+	let mut stream = Vec::with_capacity(stream_size);
+	Decompressor::new(compressed, 4096)
+		.take(stream_size as u64)
+		.read_to_end(&mut stream)?;
+
+	let mut stream_reader = Reader::new(&stream);
+	let mut tables = Vec::with_capacity(entries.len());
+	let mut x_mins = None;
+	let mut hmtx = None;
+	for entry in &entries {
+		let data = stream_reader.bytes(entry.length)?;
+		match &entry.tag {
+			b"glyf" if entry.transformed => {
+				let (glyf, loca, mins) = reconstruct_glyf(data)?;
+				tables.push(Table { tag: *b"glyf", data: glyf });
+				tables.push(Table { tag: *b"loca", data: loca });
+				x_mins = Some(mins);
+			}
+			// rebuilt with glyf
+			b"loca" if entry.transformed => {}
+			b"hmtx" if entry.transformed => hmtx = Some(data),
+			_ => tables.push(Table { tag: entry.tag, data: data.to_vec() }),
+		}
+	}
+	if let Some(data) = hmtx {
+		let x_mins = x_mins
+			.ok_or_else(|| anyhow!("Transformed hmtx without transformed glyf"))?;
+		let hhea = tables.iter()
+			.find(|table| &table.tag == b"hhea")
+			.ok_or_else(|| anyhow!("No hhea table found"))?;
+		let mut hhea_reader = Reader::new(&hhea.data);
+		hhea_reader.skip(34)?;
+		let num_h_metrics = hhea_reader.u16()? as usize;
+		let data = reconstruct_hmtx(data, num_h_metrics, &x_mins)?;
+		tables.push(Table { tag: *b"hmtx", data });
+	}
+	Ok(build_sfnt(flavor, tables))
+}
+
+#[inline]
+fn bit_set(bitmap: &[u8], index: usize) -> bool
+{
+	bitmap[index >> 3] & (0x80 >> (index & 7)) != 0
+}
+
+/// rebuild glyf and loca table from transformed glyf,
+/// return glyf, loca and x min of all glyphs
+fn reconstruct_glyf(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>, Vec<i16>)>
+{
+	let mut reader = Reader::new(data);
+	// reserved
+	reader.skip(2)?;
+	let option_flags = reader.u16()?;
+	let num_glyphs = reader.u16()? as usize;
+	let index_format = reader.u16()?;
+	let mut sizes = [0usize; 7];
+	for size in &mut sizes {
+		*size = reader.u32()? as usize;
+	}
+	let mut n_contour_stream = Reader::new(reader.bytes(sizes[0])?);
+	let mut n_points_stream = Reader::new(reader.bytes(sizes[1])?);
+	let mut flag_stream = Reader::new(reader.bytes(sizes[2])?);
+	let mut glyph_stream = Reader::new(reader.bytes(sizes[3])?);
+	let mut composite_stream = Reader::new(reader.bytes(sizes[4])?);
+	let mut bbox_stream = Reader::new(reader.bytes(sizes[5])?);
+	let mut instruction_stream = Reader::new(reader.bytes(sizes[6])?);
+	let overlap_bitmap = if option_flags & 1 != 0 {
+		Some(reader.bytes((num_glyphs + 7) / 8)?)
+	} else {
+		None
+	};
+	let bbox_bitmap = bbox_stream.bytes(((num_glyphs + 31) / 32) * 4)?;
+
+	let mut glyf = vec![];
+	let mut offsets = Vec::with_capacity(num_glyphs + 1);
+	let mut x_mins = Vec::with_capacity(num_glyphs);
+	for index in 0..num_glyphs {
+		offsets.push(glyf.len());
+		let has_bbox = bit_set(bbox_bitmap, index);
+		let n_contours = n_contour_stream.i16()?;
+		if n_contours == 0 {
+			if has_bbox {
+				bail!("Empty glyph with bbox");
+			}
+			x_mins.push(0);
+			continue;
+		}
+		if n_contours == -1 {
+			if !has_bbox {
+				bail!("Composite glyph without bbox");
+			}
+			let bbox = bbox_stream.bytes(8)?;
+			x_mins.push(i16::from_be_bytes([bbox[0], bbox[1]]));
+			let (components, have_instructions) = composite_data(&mut composite_stream)?;
+			glyf.extend_from_slice(&n_contours.to_be_bytes());
+			glyf.extend_from_slice(bbox);
+			glyf.extend_from_slice(components);
+			if have_instructions {
+				let len = glyph_stream.u255()?;
+				glyf.extend_from_slice(&len.to_be_bytes());
+				glyf.extend_from_slice(instruction_stream.bytes(len as usize)?);
+			}
+		} else if n_contours > 0 {
+			let mut end_points = Vec::with_capacity(n_contours as usize);
+			let mut total = 0usize;
+			for _ in 0..n_contours {
+				total += n_points_stream.u255()? as usize;
+				let end_point = total.checked_sub(1)
+					.and_then(|end| u16::try_from(end).ok())
+					.ok_or_else(|| anyhow!("Invalid glyph points"))?;
+				end_points.push(end_point);
+			}
+			let mut points = Vec::with_capacity(total);
+			let (mut x, mut y) = (0i32, 0i32);
+			for _ in 0..total {
+				let flag = flag_stream.u8()?;
+				let (dx, dy) = triplet(flag & 0x7F, &mut glyph_stream)?;
+				x += dx;
+				y += dy;
+				points.push((x, y, flag & 0x80 == 0));
+			}
+			let instruction_len = glyph_stream.u255()?;
+			let bbox = if has_bbox {
+				bbox_stream.bytes(8)?.to_vec()
+			} else {
+				calc_bbox(&points)?
+			};
+			x_mins.push(i16::from_be_bytes([bbox[0], bbox[1]]));
+			let overlap = overlap_bitmap
+				.map_or(false, |bitmap| bit_set(bitmap, index));
+
+			glyf.extend_from_slice(&n_contours.to_be_bytes());
+			glyf.extend_from_slice(&bbox);
+			for end_point in end_points {
+				glyf.extend_from_slice(&end_point.to_be_bytes());
+			}
+			glyf.extend_from_slice(&instruction_len.to_be_bytes());
+			glyf.extend_from_slice(instruction_stream.bytes(instruction_len as usize)?);
+			encode_points(&points, overlap, &mut glyf)?;
+		} else {
+			bail!("Invalid number of contours: {}", n_contours);
+		}
+		// glyphs are 4 bytes aligned
+		glyf.resize((glyf.len() + 3) & !3, 0);
+	}
+	offsets.push(glyf.len());
+
+	let mut loca = vec![];
+	if index_format == 0 {
+		if glyf.len() / 2 > u16::MAX as usize {
+			bail!("Glyf too large for short loca format");
+		}
+		for offset in offsets {
+			loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes());
+		}
+	} else {
+		for offset in offsets {
+			loca.extend_from_slice(&(offset as u32).to_be_bytes());
+		}
+	}
+	Ok((glyf, loca, x_mins))
+}
+
+fn composite_data<'a>(stream: &mut Reader<'a>) -> Result<(&'a [u8], bool)>
+{
+	let start = stream.pos;
+	let mut have_instructions = false;
+	loop {
+		let flags = stream.u16()?;
+		have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+		// glyph index and arguments
+		let mut len = if flags & ARG_1_AND_2_ARE_WORDS != 0 { 6 } else { 4 };
+		if flags & WE_HAVE_A_SCALE != 0 {
+			len += 2;
+		} else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+			len += 4;
+		} else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+			len += 8;
+		}
+		stream.skip(len)?;
+		if flags & MORE_COMPONENTS == 0 {
+			break;
+		}
+	}
+	Ok((&stream.data[start..stream.pos], have_instructions))
+}
+
+fn triplet(flag: u8, stream: &mut Reader) -> Result<(i32, i32)>
+{
+	#[inline]
+	fn with_sign(flag: i32, value: i32) -> i32
+	{
+		if flag & 1 != 0 { value } else { -value }
+	}
+	let flag = flag as i32;
+	let delta = if flag < 10 {
+		let b0 = stream.u8()? as i32;
+		(0, with_sign(flag, ((flag & 14) << 7) + b0))
+	} else if flag < 20 {
+		let b0 = stream.u8()? as i32;
+		(with_sign(flag, (((flag - 10) & 14) << 7) + b0), 0)
+	} else if flag < 84 {
+		let b0 = flag - 20;
+		let b1 = stream.u8()? as i32;
+		(with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+			with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)))
+	} else if flag < 120 {
+		let b0 = flag - 84;
+		let b1 = stream.u8()? as i32;
+		let b2 = stream.u8()? as i32;
+		(with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+			with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2))
+	} else if flag < 124 {
+		let b1 = stream.u8()? as i32;
+		let b2 = stream.u8()? as i32;
+		let b3 = stream.u8()? as i32;
+		(with_sign(flag, (b1 << 4) + (b2 >> 4)),
+			with_sign(flag >> 1, ((b2 & 0x0F) << 8) + b3))
+	} else {
+		let b1 = stream.u8()? as i32;
+		let b2 = stream.u8()? as i32;
+		let b3 = stream.u8()? as i32;
+		let b4 = stream.u8()? as i32;
+		(with_sign(flag, (b1 << 8) + b2),
+			with_sign(flag >> 1, (b3 << 8) + b4))
+	};
+	Ok(delta)
+}
+
+fn calc_bbox(points: &[(i32, i32, bool)]) -> Result<Vec<u8>>
+{
+	let mut bbox = Vec::with_capacity(8);
+	if let Some((x, y, _)) = points.first() {
+		let (mut x_min, mut y_min, mut x_max, mut y_max) = (*x, *y, *x, *y);
+		for (x, y, _) in points {
+			x_min = x_min.min(*x);
+			y_min = y_min.min(*y);
+			x_max = x_max.max(*x);
+			y_max = y_max.max(*y);
+		}
+		for value in [x_min, y_min, x_max, y_max] {
+			let value = i16::try_from(value)
+				.map_err(|_| anyhow!("Glyph coordinate out of range"))?;
+			bbox.extend_from_slice(&value.to_be_bytes());
+		}
+	} else {
+		bbox.resize(8, 0);
+	}
+	Ok(bbox)
+}
+
+/// write flags and coordinates of simple glyph, without repeat flag
+fn encode_points(points: &[(i32, i32, bool)], overlap: bool, glyf: &mut Vec<u8>)
+	-> Result<()>
+{
+	#[inline]
+	fn encode(delta: i32, short_flag: u8, same_or_positive_flag: u8,
+		flag: &mut u8, coordinates: &mut Vec<u8>) -> Result<()>
+	{
+		if delta == 0 {
+			*flag |= same_or_positive_flag;
+		} else if delta.abs() <= 0xFF {
+			*flag |= short_flag;
+			if delta > 0 {
+				*flag |= same_or_positive_flag;
+			}
+			coordinates.push(delta.unsigned_abs() as u8);
+		} else {
+			let delta = i16::try_from(delta)
+				.map_err(|_| anyhow!("Glyph coordinate out of range"))?;
+			coordinates.extend_from_slice(&delta.to_be_bytes());
+		}
+		Ok(())
+	}
+
+	let mut flags = Vec::with_capacity(points.len());
+	let mut xs = vec![];
+	let mut ys = vec![];
+	let (mut last_x, mut last_y) = (0, 0);
+	for (index, (x, y, on_curve)) in points.iter().enumerate() {
+		let mut flag = if *on_curve { ON_CURVE_POINT } else { 0 };
+		if index == 0 && overlap {
+			flag |= OVERLAP_SIMPLE;
+		}
+		encode(x - last_x, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE, &mut flag, &mut xs)?;
+		encode(y - last_y, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE, &mut flag, &mut ys)?;
+		flags.push(flag);
+		last_x = *x;
+		last_y = *y;
+	}
+	glyf.extend_from_slice(&flags);
+	glyf.extend_from_slice(&xs);
+	glyf.extend_from_slice(&ys);
+	Ok(())
+}
+
+fn reconstruct_hmtx(data: &[u8], num_h_metrics: usize, x_mins: &[i16])
+	-> Result<Vec<u8>>
+{
+	let num_glyphs = x_mins.len();
+	if num_h_metrics == 0 || num_h_metrics > num_glyphs {
+		bail!("Invalid number of hmetrics: {}", num_h_metrics);
+	}
+	let mut reader = Reader::new(data);
+	let flags = reader.u8()?;
+	let mut advances = Vec::with_capacity(num_h_metrics);
+	for _ in 0..num_h_metrics {
+		advances.push(reader.u16()?);
+	}
+	let mut hmtx = Vec::with_capacity(num_h_metrics * 2 + num_glyphs * 2);
+	for (advance, x_min) in advances.iter().zip(x_mins) {
+		let lsb = if flags & 1 != 0 { *x_min } else { reader.i16()? };
+		hmtx.extend_from_slice(&advance.to_be_bytes());
+		hmtx.extend_from_slice(&lsb.to_be_bytes());
+	}
+	for x_min in &x_mins[num_h_metrics..] {
+		let lsb = if flags & 2 != 0 { *x_min } else { reader.i16()? };
+		hmtx.extend_from_slice(&lsb.to_be_bytes());
+	}
+	Ok(hmtx)
+}
+
+fn checksum(data: &[u8]) -> u32
+{
+	let mut sum = 0u32;
+	for chunk in data.chunks(4) {
+		let mut word = [0u8; 4];
+		word[..chunk.len()].copy_from_slice(chunk);
+		sum = sum.wrapping_add(u32::from_be_bytes(word));
+	}
+	sum
+}
+
+fn build_sfnt(flavor: u32, mut tables: Vec<Table>) -> Vec<u8>
+{
+	tables.sort_by(|a, b| a.tag.cmp(&b.tag));
+	let num_tables = tables.len() as u16;
+	let mut entry_selector = 0u16;
+	while (2u32 << entry_selector) <= num_tables as u32 {
+		entry_selector += 1;
+	}
+	let search_range = (1u16 << entry_selector) * 16;
+	let range_shift = num_tables * 16 - search_range;
+
+	let mut sfnt = vec![];
+	sfnt.extend_from_slice(&flavor.to_be_bytes());
+	sfnt.extend_from_slice(&num_tables.to_be_bytes());
+	sfnt.extend_from_slice(&search_range.to_be_bytes());
+	sfnt.extend_from_slice(&entry_selector.to_be_bytes());
+	sfnt.extend_from_slice(&range_shift.to_be_bytes());
+
+	let mut head_offset = None;
+	let mut offset = 12 + 16 * tables.len();
+	for table in &mut tables {
+		if &table.tag == b"head" && table.data.len() >= 12 {
+			table.data[8..12].fill(0);
+			head_offset = Some(offset);
+		}
+		sfnt.extend_from_slice(&table.tag);
+		sfnt.extend_from_slice(&checksum(&table.data).to_be_bytes());
+		sfnt.extend_from_slice(&(offset as u32).to_be_bytes());
+		sfnt.extend_from_slice(&(table.data.len() as u32).to_be_bytes());
+		offset += (table.data.len() + 3) & !3;
+	}
+	for table in &tables {
+		sfnt.extend_from_slice(&table.data);
+		sfnt.resize((sfnt.len() + 3) & !3, 0);
+	}
+	if let Some(head_offset) = head_offset {
+		let adjustment = HEAD_CHECKSUM_ADJUSTMENT.wrapping_sub(checksum(&sfnt));
+		sfnt[head_offset + 8..head_offset + 12]
+			.copy_from_slice(&adjustment.to_be_bytes());
+	}
+	sfnt
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+	use flate2::Compression;
+	use flate2::write::ZlibEncoder;
+	use crate::gui::font::woff::{build_sfnt, Reader, reconstruct_glyf, reconstruct_hmtx, sfnt_data, Table, triplet};
+
+	#[test]
+	fn test_not_woff()
+	{
+		let content = b"\x00\x01\x00\x00 plain sfnt".to_vec();
+		assert_eq!(sfnt_data(content.clone()).unwrap(), content);
+	}
+
+	#[test]
+	fn test_woff()
+	{
+		let name = b"name table content".repeat(8);
+		let post = b"post".to_vec();
+		let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+		encoder.write_all(&name).unwrap();
+		let compressed = encoder.finish().unwrap();
+
+		let mut woff = vec![];
+		woff.extend_from_slice(b"wOFF");
+		woff.extend_from_slice(&0x00010000u32.to_be_bytes());
+		woff.extend_from_slice(&0u32.to_be_bytes());
+		woff.extend_from_slice(&2u16.to_be_bytes());
+		woff.resize(44, 0);
+		woff[16..20].copy_from_slice(&1024u32.to_be_bytes());
+		let name_offset = 44 + 20 * 2;
+		let post_offset = name_offset + compressed.len();
+		for (tag, offset, comp_len, orig_len) in [
+			(b"name", name_offset, compressed.len(), name.len()),
+			(b"post", post_offset, post.len(), post.len()),
+		] {
+			woff.extend_from_slice(tag);
+			woff.extend_from_slice(&(offset as u32).to_be_bytes());
+			woff.extend_from_slice(&(comp_len as u32).to_be_bytes());
+			woff.extend_from_slice(&(orig_len as u32).to_be_bytes());
+			woff.extend_from_slice(&0u32.to_be_bytes());
+		}
+		woff.extend_from_slice(&compressed);
+		woff.extend_from_slice(&post);
+
+		let expected = build_sfnt(0x00010000, vec![
+			Table { tag: *b"post", data: post },
+			Table { tag: *b"name", data: name },
+		]);
+		assert_eq!(sfnt_data(woff).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_woff2()
+	{
+		// glyph 0 is empty, glyph 1 is a triangle (10,20) (100,20) (50,100)
+		let mut transformed_glyf = vec![0, 0, 0, 0, 0, 2, 0, 0];
+		for size in [4u32, 1, 3, 13, 0, 4, 0] {
+			transformed_glyf.extend_from_slice(&size.to_be_bytes());
+		}
+		// contours, points, flags, coordinates, bbox bitmap
+		transformed_glyf.extend_from_slice(&[0, 0, 0, 1]);
+		transformed_glyf.push(3);
+		transformed_glyf.extend_from_slice(&[0x7F, 0x7F, 0x7E]);
+		transformed_glyf.extend_from_slice(&[0, 10, 0, 20, 0, 90, 0, 0, 0, 50, 0, 80, 0]);
+		transformed_glyf.extend_from_slice(&[0, 0, 0, 0]);
+		let mut hhea = vec![0; 36];
+		hhea[34..36].copy_from_slice(&2u16.to_be_bytes());
+		// lsb of all glyphs from x min
+		let transformed_hmtx = [3, 0x01, 0xF4, 0x02, 0x58];
+
+		let mut stream = transformed_glyf.clone();
+		stream.extend_from_slice(&hhea);
+		stream.extend_from_slice(&transformed_hmtx);
+		// one uncompressed brotli meta block, then the last empty one
+		let header = (((stream.len() - 1) << 4) | (1 << 20)) as u32;
+		let mut compressed = header.to_le_bytes()[..3].to_vec();
+		compressed.extend_from_slice(&stream);
+		compressed.push(0x03);
+
+		let mut woff2 = vec![];
+		woff2.extend_from_slice(b"wOF2");
+		woff2.extend_from_slice(&0x00010000u32.to_be_bytes());
+		woff2.extend_from_slice(&0u32.to_be_bytes());
+		woff2.extend_from_slice(&4u16.to_be_bytes());
+		woff2.extend_from_slice(&0u16.to_be_bytes());
+		woff2.extend_from_slice(&1000u32.to_be_bytes());
+		woff2.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+		woff2.resize(48, 0);
+		// glyf, loca, hhea and hmtx with base128 lengths
+		woff2.extend_from_slice(&[10, 24, transformed_glyf.len() as u8]);
+		woff2.extend_from_slice(&[11, 6, 0]);
+		woff2.extend_from_slice(&[2, 36]);
+		woff2.extend_from_slice(&[3 | 0x40, 8, transformed_hmtx.len() as u8]);
+		woff2.extend_from_slice(&compressed);
+
+		let glyf = vec![
+			0, 1, 0, 10, 0, 20, 0, 100, 0, 100, 0, 2, 0, 0,
+			0x37, 0x33, 0x27, 10, 90, 50, 20, 80, 0, 0,
+		];
+		let loca = vec![0, 0, 0, 0, 0, 12];
+		let hmtx = vec![0x01, 0xF4, 0, 0, 0x02, 0x58, 0, 10];
+		let (rebuilt_glyf, rebuilt_loca, x_mins) = reconstruct_glyf(&transformed_glyf).unwrap();
+		assert_eq!(rebuilt_glyf, glyf);
+		assert_eq!(rebuilt_loca, loca);
+		assert_eq!(x_mins, vec![0, 10]);
+		assert_eq!(reconstruct_hmtx(&transformed_hmtx, 2, &x_mins).unwrap(), hmtx);
+
+		let expected = build_sfnt(0x00010000, vec![
+			Table { tag: *b"glyf", data: glyf },
+			Table { tag: *b"loca", data: loca },
+			Table { tag: *b"hhea", data: hhea },
+			Table { tag: *b"hmtx", data: hmtx },
+		]);
+		assert_eq!(sfnt_data(woff2).unwrap(), expected);
+	}
+
+	#[test]
+	fn test_woff2_numbers()
+	{
+		let data = [0x3F, 253, 0x12, 0x34, 255, 10, 254, 0, 0x81, 0x00];
+		let mut reader = Reader::new(&data);
+		assert_eq!(reader.u255().unwrap(), 0x3F);
+		assert_eq!(reader.u255().unwrap(), 0x1234);
+		assert_eq!(reader.u255().unwrap(), 263);
+		assert_eq!(reader.u255().unwrap(), 506);
+		assert_eq!(reader.base128().unwrap(), 128);
+
+		let data = [0x12, 0x34];
+		assert_eq!(triplet(1, &mut Reader::new(&data)).unwrap(), (0, 0x12));
+		assert_eq!(triplet(20, &mut Reader::new(&data)).unwrap(), (-2, -3));
+		assert_eq!(triplet(127, &mut Reader::new(&[0x12, 0x34, 0x56, 0x78])).unwrap(),
+			(0x1234, 0x5678));
+	}
+}
@@ -4,17 +4,20 @@ use std::path::PathBuf;
 use std::rc::Rc;
 use std::str::FromStr;
 
-use gtk4::{Align, EventControllerKey, glib, Label, ListBox, ListBoxRow, Orientation, Popover, SearchEntry, SelectionMode, StringList, StringObject, Widget};
-use gtk4::gdk::Key;
+use gtk4::{Align, EventControllerKey, gdk, GestureClick, glib, Label, ListBox, ListBoxRow, Orientation, Popover, PopoverMenu, PositionType, SearchEntry, SelectionMode, StringList, StringObject, Widget};
+use gtk4::gdk::{Key, Rectangle};
+use gtk4::gio::{Menu, MenuModel, SimpleAction, SimpleActionGroup};
 use gtk4::glib::markup_escape_text;
 use gtk4::pango::EllipsizeMode;
-use gtk4::prelude::{BoxExt, Cast, EditableExt, IsA, ListBoxRowExt, ListModelExt, PopoverExt, WidgetExt};
+use gtk4::prelude::{ActionMapExt, BoxExt, Cast, EditableExt, IsA, ListBoxRowExt, ListModelExt, PopoverExt, WidgetExt};
 use crate::color::Color32;
 
 use crate::config::{Configuration, match_filename, ReadingInfo};
 use crate::gui::{GuiContext, ignore_cap, MODIFIER_NONE};
 use crate::gui::view::GuiView;
 
+const OPEN_FOLDER_KEY: &str = "open-containing-folder";
+
 pub(super) struct HistoryList {
 	search: SearchEntry,
 	list_box: ListBox,
@@ -169,6 +172,27 @@ impl HistoryList {
 			let list = self.list.clone();
 			self.list_box.connect_row_activated(move |_, row| open(&gc, row.index(), &list));
 		}
+		{
+			// right click for context menu
+			let right_click = GestureClick::builder()
+				.button(gdk::BUTTON_SECONDARY)
+				.build();
+			let popup_menu = self.setup_popup_menu(gc);
+			let list_box = self.list_box.clone();
+			right_click.connect_pressed(move |_, _, x, y| {
+				if let Some(row) = list_box.row_at_y(y as i32) {
+					list_box.select_row(Some(&row));
+					popup_menu.set_pointing_to(Some(&Rectangle::new(
+						x as i32,
+						y as i32,
+						-1,
+						-1,
+					)));
+					popup_menu.popup();
+				}
+			});
+			self.list_box.add_controller(right_click);
+		}
 		{
 			let filter_pattern = self.filter_pattern.clone();
 			let gc = gc.clone();
@@ -192,6 +216,43 @@ impl HistoryList {
 		}
 	}
 
+	fn setup_popup_menu(&self, gc: &GuiContext) -> PopoverMenu
+	{
+		let action_group = SimpleActionGroup::new();
+		let menu = Menu::new();
+		self.list_box.insert_action_group("history", Some(&action_group));
+
+		let open_folder_action = SimpleAction::new(OPEN_FOLDER_KEY, None);
+		{
+			let gc = gc.clone();
+			let list_box = self.list_box.clone();
+			let list = self.list.clone();
+			open_folder_action.connect_activate(move |_, _| {
+				let Some(row) = list_box.selected_row() else {
+					return;
+				};
+				if let Some(str) = list.string(row.index() as u32) {
+					let Ok(path) = PathBuf::from_str(str.as_str());
+					if let Err(err) = gc.opener().open_path(&path) {
+						gc.error(&err.to_string());
+					}
+				}
+			});
+		}
+		action_group.add_action(&open_folder_action);
+		let title = gc.i18n.msg(OPEN_FOLDER_KEY);
+		let action_name = format!("history.{}", OPEN_FOLDER_KEY);
+		menu.append(Some(&title), Some(&action_name));
+
+		let pm = PopoverMenu::builder()
+			.has_arrow(false)
+			.position(PositionType::Bottom)
+			.menu_model(&MenuModel::from(menu))
+			.build();
+		pm.set_parent(&self.list_box);
+		pm
+	}
+
 	#[inline]
 	pub fn popup(&self, infos: Vec<ReadingInfo>)
 	{
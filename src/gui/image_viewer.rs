@@ -0,0 +1,169 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk4::{Align, Button, ContentFit, EventControllerScroll, EventControllerScrollFlags, GestureDrag, Orientation, Picture, Popover, ScrolledWindow, Widget};
+use gtk4::gdk::{ModifierType, Rectangle, Texture};
+use gtk4::gdk_pixbuf::Pixbuf;
+use gtk4::glib::Propagation;
+use gtk4::prelude::{AdjustmentExt, BoxExt, ButtonExt, GestureDragExt, IsA, PopoverExt, WidgetExt};
+
+use crate::I18n;
+
+const MIN_SCALE: f64 = 0.1;
+const MAX_SCALE: f64 = 8.0;
+const SCALE_STEP: f64 = 1.25;
+// max part of the parent widget used by viewer
+const VIEWER_RATIO: f64 = 0.9;
+const MIN_VIEWER_SIZE: i32 = 100;
+
+/// show image with full resolution in popover, zoom by ctrl+scroll or
+/// buttons, pan by dragging, open_external called for external viewer
+pub(crate) fn show_image<F>(parent: &impl IsA<Widget>, image: &Pixbuf,
+	i18n: &I18n, open_external: F)
+	where F: Fn() + 'static
+{
+	let image_width = image.width() as f64;
+	let image_height = image.height() as f64;
+	if image_width <= 0. || image_height <= 0. {
+		return;
+	}
+	let max_width = parent.width() as f64 * VIEWER_RATIO;
+	let max_height = parent.height() as f64 * VIEWER_RATIO;
+	// never enlarge small image when fitting
+	let fit_scale = (max_width / image_width)
+		.min(max_height / image_height)
+		.clamp(MIN_SCALE, 1.0);
+
+	let picture = Picture::for_paintable(&Texture::for_pixbuf(image));
+	picture.set_can_shrink(true);
+	picture.set_content_fit(ContentFit::Fill);
+	picture.set_halign(Align::Center);
+	picture.set_valign(Align::Center);
+	let scale = Rc::new(Cell::new(fit_scale));
+	resize_picture(&picture, image_width, image_height, fit_scale);
+
+	let scroll_view = ScrolledWindow::builder()
+		.child(&picture)
+		.width_request(((image_width * fit_scale) as i32).max(MIN_VIEWER_SIZE))
+		.height_request(((image_height * fit_scale) as i32).max(MIN_VIEWER_SIZE))
+		.build();
+
+	let zoom = {
+		let picture = picture.clone();
+		let scale = scale.clone();
+		Rc::new(move |new_scale: f64| {
+			let new_scale = new_scale.clamp(MIN_SCALE, MAX_SCALE);
+			scale.set(new_scale);
+			resize_picture(&picture, image_width, image_height, new_scale);
+		})
+	};
+
+	{
+		let zoom = zoom.clone();
+		let scale = scale.clone();
+		let scroll_event = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+		scroll_event.connect_scroll(move |scroll, _, y| {
+			if scroll.current_event_state().contains(ModifierType::CONTROL_MASK) {
+				if y < 0. {
+					zoom(scale.get() * SCALE_STEP);
+				} else {
+					zoom(scale.get() / SCALE_STEP);
+				}
+				Propagation::Stop
+			} else {
+				Propagation::Proceed
+			}
+		});
+		scroll_view.add_controller(scroll_event);
+	}
+	{
+		let drag = GestureDrag::new();
+		let origin = Rc::new(Cell::new((0., 0.)));
+		{
+			let origin = origin.clone();
+			let scroll_view = scroll_view.clone();
+			drag.connect_drag_begin(move |_, _, _| {
+				origin.set((scroll_view.hadjustment().value(),
+					scroll_view.vadjustment().value()));
+			});
+		}
+		let scroll_view2 = scroll_view.clone();
+		drag.connect_drag_update(move |_, x, y| {
+			let (h, v) = origin.get();
+			scroll_view2.hadjustment().set_value(h - x);
+			scroll_view2.vadjustment().set_value(v - y);
+		});
+		scroll_view.add_controller(drag);
+	}
+
+	let zoom_in_btn = viewer_button("+", &i18n.msg("image-zoom-in"));
+	{
+		let zoom = zoom.clone();
+		let scale = scale.clone();
+		zoom_in_btn.connect_clicked(move |_| zoom(scale.get() * SCALE_STEP));
+	}
+	let zoom_out_btn = viewer_button("-", &i18n.msg("image-zoom-out"));
+	{
+		let zoom = zoom.clone();
+		let scale = scale.clone();
+		zoom_out_btn.connect_clicked(move |_| zoom(scale.get() / SCALE_STEP));
+	}
+	let original_btn = viewer_button("1:1", &i18n.msg("image-original-size"));
+	{
+		let zoom = zoom.clone();
+		original_btn.connect_clicked(move |_| zoom(1.0));
+	}
+	let fit_btn = viewer_button(&i18n.msg("image-fit"), &i18n.msg("image-fit"));
+	fit_btn.connect_clicked(move |_| zoom(fit_scale));
+	let external_btn = viewer_button(&i18n.msg("image-open-external"),
+		&i18n.msg("image-open-external"));
+
+	let button_box = gtk4::Box::new(Orientation::Horizontal, 4);
+	button_box.set_halign(Align::Center);
+	button_box.append(&zoom_out_btn);
+	button_box.append(&original_btn);
+	button_box.append(&fit_btn);
+	button_box.append(&zoom_in_btn);
+	button_box.append(&external_btn);
+
+	let viewer_box = gtk4::Box::new(Orientation::Vertical, 4);
+	viewer_box.append(&scroll_view);
+	viewer_box.append(&button_box);
+
+	let popover = Popover::builder()
+		.child(&viewer_box)
+		.has_arrow(false)
+		.autohide(true)
+		.build();
+	popover.set_parent(parent);
+	popover.set_pointing_to(Some(&Rectangle::new(
+		parent.width() / 2,
+		parent.height() / 2,
+		1,
+		1,
+	)));
+	{
+		let popover = popover.clone();
+		external_btn.connect_clicked(move |_| {
+			popover.popdown();
+			open_external();
+		});
+	}
+	popover.connect_closed(|popover| popover.unparent());
+	popover.popup();
+}
+
+#[inline]
+fn resize_picture(picture: &Picture, width: f64, height: f64, scale: f64)
+{
+	picture.set_size_request((width * scale) as i32, (height * scale) as i32);
+}
+
+#[inline]
+fn viewer_button(label: &str, tooltip: &str) -> Button
+{
+	let button = Button::with_label(label);
+	button.set_tooltip_text(Some(tooltip));
+	button.set_focus_on_click(false);
+	button
+}
@@ -0,0 +1,126 @@
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::TryRecvError;
+use std::thread::spawn;
+
+use gtk4::{Align, FlowBox, Label, Orientation, Picture, ScrolledWindow, SelectionMode};
+use gtk4::gdk::Texture;
+use gtk4::gdk_pixbuf::InterpType;
+use gtk4::glib::{ControlFlow, timeout_add_local};
+use gtk4::pango::EllipsizeMode;
+use gtk4::prelude::{BoxExt, WidgetExt};
+
+use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::container::{ContainerManager, load_book, load_container};
+use crate::gui::{GuiContext, load_image, LOADING_CHECK_INTERVAL};
+
+const THUMBNAIL_WIDTH: i32 = 120;
+const THUMBNAIL_HEIGHT: i32 = 160;
+
+/// grid of recent books with cover thumbnails, click to open the book
+pub(super) fn library_view(gc: &GuiContext, infos: Vec<ReadingInfo>) -> ScrolledWindow
+{
+	let flow_box = FlowBox::builder()
+		.selection_mode(SelectionMode::None)
+		.activate_on_single_click(true)
+		.homogeneous(true)
+		.valign(Align::Start)
+		.row_spacing(10)
+		.column_spacing(10)
+		.margin_top(10)
+		.margin_bottom(10)
+		.margin_start(10)
+		.margin_end(10)
+		.build();
+	let mut entries = vec![];
+	for reading in infos {
+		let picture = Picture::builder()
+			.width_request(THUMBNAIL_WIDTH)
+			.height_request(THUMBNAIL_HEIGHT)
+			.build();
+		let name = book_name(&reading.filename);
+		let label = Label::builder()
+			.label(&name)
+			.max_width_chars(12)
+			.ellipsize(EllipsizeMode::End)
+			.tooltip_text(&reading.filename)
+			.build();
+		let entry = gtk4::Box::new(Orientation::Vertical, 4);
+		entry.append(&picture);
+		entry.append(&label);
+		flow_box.append(&entry);
+		entries.push((picture, reading));
+	}
+	{
+		let gc = gc.clone();
+		let filenames: Vec<String> = entries.iter()
+			.map(|(_, reading)| reading.filename.clone())
+			.collect();
+		flow_box.connect_child_activated(move |_, child| {
+			if let Some(filename) = filenames.get(child.index() as usize) {
+				gc.open_file(&PathBuf::from(filename));
+			}
+		});
+	}
+
+	// loading books is slow, extract covers in background
+	let (pictures, readings): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+	let (tx, rx) = mpsc::channel();
+	spawn(move || {
+		for (index, reading) in readings.into_iter().enumerate() {
+			if let Some(cover) = cover_bytes(reading) {
+				if tx.send((index, cover)).is_err() {
+					break;
+				}
+			}
+		}
+	});
+	timeout_add_local(LOADING_CHECK_INTERVAL, move || {
+		loop {
+			match rx.try_recv() {
+				Ok((index, cover)) => if let Some(texture) = cover_thumbnail(&cover) {
+					pictures[index].set_paintable(Some(&texture));
+				}
+				Err(TryRecvError::Empty) => return ControlFlow::Continue,
+				Err(TryRecvError::Disconnected) => return ControlFlow::Break,
+			}
+		}
+	});
+
+	ScrolledWindow::builder()
+		.child(&flow_box)
+		.vexpand(true)
+		.hexpand(true)
+		.build()
+}
+
+/// cover image of the book, run in worker thread
+fn cover_bytes(reading: ReadingInfo) -> Option<Vec<u8>>
+{
+	let container_manager = ContainerManager::default();
+	let mut container = load_container(&container_manager, &reading.filename).ok()?;
+	let (book, _) = load_book(&container_manager, &mut container,
+		BookLoadingInfo::History(reading)).ok()?;
+	let cover = book.cover()?;
+	Some(cover.bytes().to_vec())
+}
+
+fn cover_thumbnail(cover: &[u8]) -> Option<Texture>
+{
+	let image = load_image(cover)?;
+	let scale = f64::min(
+		THUMBNAIL_WIDTH as f64 / image.width() as f64,
+		THUMBNAIL_HEIGHT as f64 / image.height() as f64);
+	let width = ((image.width() as f64 * scale) as i32).max(1);
+	let height = ((image.height() as f64 * scale) as i32).max(1);
+	let thumbnail = image.scale_simple(width, height, InterpType::Bilinear)?;
+	Some(Texture::for_pixbuf(&thumbnail))
+}
+
+#[inline]
+fn book_name(filename: &str) -> String
+{
+	PathBuf::from(filename)
+		.file_stem()
+		.map_or_else(|| filename.to_owned(), |name| name.to_string_lossy().to_string())
+}
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use anyhow::Result;
+use gtk4::{PolicyType, ScrolledWindow, TextView, WrapMode};
+use gtk4::prelude::{TextBufferExt, TextViewExt, WidgetExt};
+use crate::config::ReadingInfo;
+use crate::sync::name_hash;
+
+struct NotesInner {
+	view: TextView,
+	// notes file of current book, None when no book to take notes for
+	file: RefCell<Option<PathBuf>>,
+}
+
+/// freeform notes of current book, saved in a sidecar file
+#[derive(Clone)]
+pub struct Notes {
+	inner: Rc<NotesInner>,
+}
+
+impl Notes {
+	pub fn create() -> (Self, gtk4::Box)
+	{
+		let view = TextView::builder()
+			.wrap_mode(WrapMode::WordChar)
+			.left_margin(4)
+			.right_margin(4)
+			.top_margin(4)
+			.bottom_margin(4)
+			.editable(false)
+			.build();
+		let container = gtk4::Box::builder()
+			.orientation(gtk4::Orientation::Vertical)
+			.vexpand(true)
+			.build();
+		container.append(&ScrolledWindow::builder()
+			.child(&view)
+			.hscrollbar_policy(PolicyType::Never)
+			.vexpand(true)
+			.build());
+		let notes = Notes {
+			inner: Rc::new(NotesInner {
+				view,
+				file: RefCell::new(None),
+			})
+		};
+		(notes, container)
+	}
+
+	/// save notes of previous book, then load notes of the reading book
+	pub fn load(&self, notes_dir: &Path, reading: Option<&ReadingInfo>) -> Result<()>
+	{
+		let file = reading.map(|reading| notes_file(notes_dir, reading));
+		if *self.inner.file.borrow() == file {
+			return Ok(());
+		}
+		let saved = self.save();
+		let text = match &file {
+			Some(file) if file.is_file() => fs::read_to_string(file)?,
+			_ => String::new(),
+		};
+		let view = &self.inner.view;
+		view.set_editable(file.is_some());
+		let buffer = view.buffer();
+		buffer.set_text(&text);
+		buffer.set_modified(false);
+		self.inner.file.replace(file);
+		saved
+	}
+
+	/// write notes only if modified, empty notes remove the file
+	pub fn save(&self) -> Result<()>
+	{
+		let buffer = self.inner.view.buffer();
+		if !buffer.is_modified() {
+			return Ok(());
+		}
+		if let Some(file) = &*self.inner.file.borrow() {
+			let (start, end) = buffer.bounds();
+			let text = buffer.text(&start, &end, false);
+			if text.is_empty() {
+				if file.is_file() {
+					fs::remove_file(file)?;
+				}
+			} else {
+				if let Some(dir) = file.parent() {
+					fs::create_dir_all(dir)?;
+				}
+				fs::write(file, text.as_str())?;
+			}
+		}
+		buffer.set_modified(false);
+		Ok(())
+	}
+}
+
+/// keyed by book path and the inner book
+#[inline]
+fn notes_file(notes_dir: &Path, reading: &ReadingInfo) -> PathBuf
+{
+	let hash = name_hash(&reading.filename);
+	notes_dir.join(format!("{:016x}-{}.txt", hash, reading.inner_book))
+}
@@ -6,13 +6,14 @@ use gtk4::pango::Layout as PangoContext;
 
 use crate::book::{Book, Line};
 use crate::color::Color32;
-use crate::common::{han_render_char, is_compact_for_han, with_leading};
+use crate::common::{han_render_char, is_compact_for_han};
 use crate::controller::HighlightInfo;
 use crate::gui::math::{Pos2, pos2, Rect, vec2};
-use crate::gui::render::{CharCell, CharDrawData, GuiRender, ImageDrawingData, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollSizing, TextDecoration, update_for_highlight, vline};
+use crate::gui::render::{calc_line_space, CharCell, CharDrawData, GuiRender, ImageDrawingData, leading_space, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollSizing, TextDecoration, highlight_word_background, highlight_word_ranges, update_for_highlight, vertical_align_shift, vline};
 use crate::gui::render::imp::draw_border;
+use crate::gui::render::wrap::LineWrap;
 use crate::html_parser;
-use crate::html_parser::{BorderLines, TextDecorationLine, TextStyle};
+use crate::html_parser::{BorderLines, TextDecorationLine, TextDecorationStyle, TextStyle};
 
 pub(super) struct GuiHanRender {
 	images: HashMap<String, ImageDrawingData>,
@@ -52,6 +53,8 @@ impl GuiRender for GuiHanRender
 		render_context.max_page_size = render_context.render_rect.width();
 		render_context.leading_space = render_context.default_font_measure.y
 			* render_context.leading_chars as f32;
+		render_context.em_space = render_context.default_font_measure.y;
+		render_context.indent_space = render_context.em_space * 2.0;
 	}
 
 	#[inline]
@@ -78,9 +81,14 @@ impl GuiRender for GuiHanRender
 			return wrapped_empty_lines;
 		}
 		let mut draw_lines = vec![];
-		let mut draw_chars = vec![];
-		let mut top = context.render_rect.min.y;
+		let word_ranges = highlight_word_ranges(text, &context.highlight_words);
+		let mut wrap = LineWrap::new();
+		let line_top = context.render_rect.min.y
+			+ context.indent_space * text.indent() as f32;
+		let mut top = line_top;
 		let max_top = context.render_rect.max.y;
+		// one cell reserved at line end for the kinsoku hanging char
+		let wrap_top = max_top - context.default_font_measure.y;
 		let mut line_size = 0.0;
 		let mut line_space = 0.0;
 		let default_size = context.default_font_measure.x;
@@ -89,7 +97,7 @@ impl GuiRender for GuiHanRender
 		let view_size = view_rect.size();
 		for i in start_offset..end_offset {
 			let char_style = text.char_style_at(i, context.custom_color, &context.colors);
-			let (cell, mut rect) = if let Some((path, size)) = self.with_image(&char_style, book, &view_size, context.font_size) {
+			let (cell, rect) = if let Some((path, size)) = self.with_image(&char_style, book, &view_size, context.font_size, context.invert_images, context.image_interpolation, context.max_image_scale) {
 				let left = self.baseline - size.x;
 				let bottom = top + size.y;
 				let rect = Rect::from_min_max(
@@ -99,10 +107,10 @@ impl GuiRender for GuiHanRender
 				let link_index = char_style.link.map(|(i, _)| i);
 				(RenderCell::Image(path, link_index), rect)
 			} else {
-				if i == 0 && with_leading(text) {
-					top = context.render_rect.min.y + context.leading_space;
+				if i == 0 {
+					top = line_top + leading_space(text, context);
 				}
-				let char = text.char_at(i).unwrap();
+				let char = text.render_char_at(i, &char_style.text_transform).unwrap();
 				let char = han_render_char(char);
 				let measures = self.get_char_measures(
 					pango,
@@ -119,8 +127,7 @@ impl GuiRender for GuiHanRender
 					(measures.size.y, 0.)
 				};
 				let mut cell_offset = vec2(-measures.draw_offset.x, y_offset);
-				let cell_size = vec2(measures.draw_size.x, char_height);
-				let color = char_style.color.clone();
+				let cell_size = vec2(measures.draw_size.x, char_height + context.char_spacing(char));
 				let mut rect = Rect::new(self.baseline - cell_size.x, top, cell_size.x, cell_size.y);
 				if let Some((range, TextStyle::Border(lines, ..))) = &char_style.border {
 					if lines.contains(BorderLines::Left) {
@@ -153,7 +160,12 @@ impl GuiRender for GuiHanRender
 					}
 				}
 
-				let background = update_for_highlight(line, i, char_style.background.clone(), &context.colors, highlight);
+				// raised to the right side in vertical text
+				cell_offset.x -= vertical_align_shift(&char_style.vertical_align, context);
+				let background = highlight_word_background(&word_ranges, i)
+					.or_else(|| char_style.background.clone());
+				let (color, background) = update_for_highlight(line, i,
+					char_style.color.clone(), background, &context.colors, highlight);
 				let cell = CharCell {
 					char,
 					font_size: measures.font_size,
@@ -170,47 +182,42 @@ impl GuiRender for GuiHanRender
 					(RenderCell::Char(cell), rect)
 				}
 			};
-			if top + rect.height() > max_top && !draw_chars.is_empty() {
+			let bottom = top + rect.height();
+			let dc = RenderChar {
+				cell,
+				offset: i,
+				rect,
+				has_title: char_style.title.is_some(),
+				synthetic: false,
+			};
+			let finished = wrap.han_push(dc, bottom > wrap_top, bottom <= max_top,
+				|dc| render_char_of(text, &dc.cell, dc.offset));
+			if let Some(draw_chars) = finished {
 				let mut render_line = RenderLine::new(line, line_size, line_space);
-				line_size = 0.0;
-				line_space = 0.0;
 				align_line(&mut render_line, draw_chars);
 				self.setup_decorations(text, &mut render_line, context);
 				self.baseline -= render_line.line_size() + render_line.line_space();
-				let line_delta = render_line.line_size() + render_line.line_space();
 				draw_lines.push(render_line);
-				draw_chars = vec![];
-				// the char wrapped to new line, so update positions
-				let y_delta = top - context.render_rect.min.y;
-				rect = Rect {
-					min: Pos2::new(rect.min.x - line_delta, rect.min.y - y_delta),
-					max: Pos2::new(rect.max.x - line_delta, rect.max.y - y_delta),
-				};
-			}
-			let rect_width = rect.width();
-			if line_size < rect_width {
-				line_size = rect_width;
-				if matches!(cell, RenderCell::Image(_, _)) {
-					let default_space = default_size / 2.0;
-					if line_space < default_space {
-						line_space = default_space;
-					}
-				} else {
-					if line_size < default_size {
-						line_size = default_size;
-					}
-					line_space = line_size / 2.0
+				line_size = 0.0;
+				line_space = 0.0;
+				// chars wrapped to new line, so update positions
+				top = line_top;
+				for dc in wrap.placed_mut() {
+					let w = dc.rect.width();
+					let h = dc.rect.height();
+					dc.rect = Rect::new(self.baseline - w, top, w, h);
+					top += h;
+					update_line_size(&mut line_size, &mut line_space, dc, text,
+						default_size, context);
 				}
+			} else {
+				let dc = wrap.placed().last().unwrap();
+				top = dc.rect.max.y;
+				update_line_size(&mut line_size, &mut line_space, dc, text,
+					default_size, context);
 			}
-			top = rect.max.y;
-			let dc = RenderChar {
-				cell,
-				offset: i,
-				rect,
-				has_title: char_style.title.is_some(),
-			};
-			draw_chars.push(dc);
 		}
+		let draw_chars = wrap.finish();
 		if draw_chars.len() > 0 {
 			let mut render_line = RenderLine::new(line, line_size, line_space);
 			align_line(&mut render_line, draw_chars);
@@ -362,11 +369,15 @@ impl GuiRender for GuiHanRender
 	{
 		let width = context.render_rect.max.x - self.baseline
 			+ context.default_font_measure.x / 2.;
+		let page_size = context.render_rect.width();
+		let (step_size, page_step) = context.scroll_steps(
+			context.default_font_measure.x, page_size);
 		ScrollSizing {
 			init_scroll_value: width,
 			full_size: width,
-			step_size: context.default_font_measure.x,
-			page_size: context.render_rect.width(),
+			step_size,
+			page_size,
+			page_step,
 		}
 	}
 
@@ -417,27 +428,21 @@ impl GuiRender for GuiHanRender
 		let mut left = min.x;
 		let mut right = rect.max.x;
 		let top = min.y;
-		let (color, padding) = match &draw_char.cell {
-			RenderCell::Image(_, _) =>
-				if let Some(color) = &decoration.color {
-					(color.clone(), context.default_font_measure.y / 4.0)
-				} else {
-					(context.colors.color.clone(), context.default_font_measure.y / 4.0)
-				},
-			RenderCell::Char(CharCell { cell_size, .. }) =>
-				if let Some(color) = &decoration.color {
-					(color.clone(), cell_size.y / 4.0)
-				} else {
-					(context.colors.color.clone(), cell_size.y / 4.0)
-				}
-			RenderCell::Link(CharCell { cell_size, .. }, _) =>
-				if let Some(color) = &decoration.color {
-					(color.clone(), cell_size.y / 4.0)
-				} else {
-					(context.colors.link.clone(), cell_size.y / 4.0)
-				}
+		let (color, padding, font_size) = match &draw_char.cell {
+			RenderCell::Image(_, _) => (
+				context.colors.color.clone(),
+				context.default_font_measure.y / 4.0,
+				context.font_size as f32),
+			RenderCell::Char(CharCell { cell_size, color, font_size, .. }) =>
+				(color.clone(), cell_size.y / 4.0, *font_size),
+			RenderCell::Link(CharCell { cell_size, font_size, .. }, _) =>
+				(context.colors.link.clone(), cell_size.y / 4.0, *font_size),
 		};
+		// color of the text if no decoration color specified
+		let color = decoration.color.clone().unwrap_or(color);
 		let margin = padding / 2.0;
+		let stroke_width = decoration.thickness.as_ref()
+			.map_or(margin / 2.0, |thickness| thickness.length_px(font_size, context.font_size as f32));
 		let draw_top = if start {
 			top + margin
 		} else {
@@ -485,7 +490,7 @@ impl GuiRender for GuiHanRender
 			style: decoration.style,
 			start_points,
 			length: draw_bottom - draw_top,
-			stroke_width: margin / 2.0,
+			stroke_width,
 			color,
 		})
 	}
@@ -553,6 +558,51 @@ impl GuiRender for GuiHanRender
 			lines,
 		});
 	}
+
+	fn footnote_separator(&self, render_context: &RenderContext) -> TextDecoration
+	{
+		let rect = &render_context.render_rect;
+		let line_size = self.default_line_size(render_context);
+		TextDecoration::Line {
+			start_points: vec![pos2(self.baseline - line_size / 2.0, rect.min.y)],
+			style: TextDecorationStyle::Solid,
+			length: rect.height() / 4.0,
+			stroke_width: line_size / 16.0,
+			color: render_context.colors.color.clone(),
+		}
+	}
+}
+
+/// char in the text for kinsoku, None for images
+#[inline]
+fn render_char_of(text: &Line, cell: &RenderCell, offset: usize) -> Option<char>
+{
+	if matches!(cell, RenderCell::Image(..)) {
+		None
+	} else {
+		text.char_at(offset)
+	}
+}
+
+/// line grows to the widest char, images with at least half default size space
+fn update_line_size(line_size: &mut f32, line_space: &mut f32, dc: &RenderChar,
+	text: &Line, default_size: f32, context: &RenderContext)
+{
+	let rect_width = dc.rect.width();
+	if *line_size < rect_width {
+		*line_size = rect_width;
+		if matches!(dc.cell, RenderCell::Image(_, _)) {
+			let default_space = default_size / 2.0;
+			if *line_space < default_space {
+				*line_space = default_space;
+			}
+		} else {
+			if *line_size < default_size {
+				*line_size = default_size;
+			}
+			*line_space = calc_line_space(text, *line_size, context)
+		}
+	}
 }
 
 fn align_line(render_line: &mut RenderLine, draw_chars: Vec<RenderChar>)
@@ -1,26 +1,47 @@
 use gtk4::cairo::{Context as CairoContext, LineJoin};
-use gtk4::gdk_pixbuf::{Colorspace, InterpType, Pixbuf};
+use gtk4::gdk_pixbuf::{Colorspace, Pixbuf};
 use gtk4::pango::ffi::PANGO_SCALE;
 use gtk4::pango::{FontDescription, Layout as PangoContext};
 use gtk4::prelude::GdkCairoContextExt;
 use gtk4::{cairo, pango};
+use gtk4::glib::Bytes;
 use indexmap::IndexSet;
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 use std::rc::Rc;
 
 use crate::book::{Book, CharStyle, Line};
 use crate::color::{Color32, Colors};
-use crate::common::{overlap_range, Position};
+use crate::common::{overlap_range, Position, with_leading};
+use crate::config::{Configuration, EmptyLines, ImageInterpolation};
 use crate::controller::{HighlightInfo, HighlightMode};
 use crate::gui::font::{Fonts, HtmlFonts, UserFonts};
 use crate::gui::load_image;
 use crate::gui::math::{pos2, vec2, Pos2, Rect, Vec2};
 use crate::html_parser;
-use crate::html_parser::{BlockStyle, BorderLines, ElementSize, FontScale, FontWeight, ImageStyle, TextDecorationStyle};
+use crate::html_parser::{BlockStyle, BorderLines, ElementSize, FontScale, FontWeight, ImageStyle, TextDecorationStyle, VerticalAlign};
 
 pub const HAN_CHAR: char = '漢';
+// spacing for dyslexia mode, in em
+const DYSLEXIA_LETTER_SPACING: f32 = 0.1;
+const DYSLEXIA_WORD_SPACING: f32 = 0.3;
+const DYSLEXIA_LINE_SPACING: f32 = 1.5;
+
+// opacity of background over lines not focused
+const FOCUS_DIM_ALPHA: f64 = 0.6;
+// baseline shift of superscript and subscript, in default font size
+const SUPERSCRIPT_SHIFT: f32 = 0.35;
+const SUBSCRIPT_SHIFT: f32 = 0.2;
+// translucent backgrounds for highlight words, readable in both themes
+const HIGHLIGHT_WORD_COLORS: [Color32; 6] = [
+	Color32::from_rgba_premultiplied(255, 215, 0, 96),
+	Color32::from_rgba_premultiplied(0, 191, 255, 96),
+	Color32::from_rgba_premultiplied(255, 105, 180, 96),
+	Color32::from_rgba_premultiplied(50, 205, 50, 96),
+	Color32::from_rgba_premultiplied(255, 140, 0, 96),
+	Color32::from_rgba_premultiplied(147, 112, 219, 96),
+];
 
 impl FontWeight {
 	#[inline]
@@ -111,6 +132,9 @@ pub struct RenderChar {
 	pub offset: usize,
 	pub rect: Rect,
 	pub has_title: bool,
+	// inserted by wrapping like hyphen, not a char of the line,
+	// offset is of the char before it
+	pub synthetic: bool,
 }
 
 #[derive(Clone)]
@@ -190,12 +214,21 @@ impl RenderLine
 		self.chars.push(render_char);
 	}
 
+	#[inline]
+	pub fn add_space(&mut self, space: f32)
+	{
+		self.line_space += space;
+	}
+
 	#[inline]
 	pub fn find<F, T>(&self, f: F) -> Option<T>
 	where
 		F: Fn(usize, &RenderChar) -> Option<T>,
 	{
 		for (index, char) in self.chars.iter().enumerate() {
+			if char.synthetic {
+				continue;
+			}
 			let found = f(index, char);
 			if found.is_some() {
 				return found;
@@ -204,6 +237,12 @@ impl RenderLine
 		None
 	}
 
+	#[inline]
+	pub fn render_chars(&self) -> &Vec<RenderChar>
+	{
+		&self.chars
+	}
+
 	#[inline]
 	pub fn first_render_char(&self) -> Option<&RenderChar>
 	{
@@ -259,7 +298,7 @@ pub struct PangoDrawData {
 	char: String,
 	font_size: u8,
 	font_weight: FontWeight,
-	font_family: Option<u16>,
+	font_family: Option<String>,
 	size: Vec2,
 	draw_offset: Pos2,
 	draw_size: Vec2,
@@ -267,13 +306,11 @@ pub struct PangoDrawData {
 
 impl PangoDrawData {
 	fn measure(char: char, font_size: f32, font_weight: &FontWeight,
-		font_family_idx: &Option<u16>, font_family_names: Option<&IndexSet<String>>,
-		layout: &PangoContext) -> Self
+		font_family: Option<&str>, layout: &PangoContext) -> Self
 	{
 		let text = char.to_string();
 		let font_size = font_size as u8;
-		let font_family_names = get_font_family_names(font_family_idx, font_family_names);
-		set_pango_font_size(font_size, &font_weight, font_family_names, layout);
+		set_pango_font_size(font_size, &font_weight, font_family, layout);
 		layout.set_text(&text);
 		let (ink_rect, logical_rect) = layout.pixel_extents();
 		let logical_x = logical_rect.x() as f32;
@@ -291,7 +328,7 @@ impl PangoDrawData {
 			char: text,
 			font_size,
 			font_weight: font_weight.clone(),
-			font_family: font_family_idx.clone(),
+			font_family: font_family.map(|name| name.to_owned()),
 			size,
 			draw_offset,
 			draw_size,
@@ -299,10 +336,9 @@ impl PangoDrawData {
 	}
 
 	fn draw(&self, cairo: &CairoContext, offset_x: f32, offset_y: f32, color: &Color32,
-		font_family_names: Option<&IndexSet<String>>, layout: &PangoContext)
+		layout: &PangoContext)
 	{
-		let font_family_names = get_font_family_names(&self.font_family, font_family_names);
-		set_pango_font_size(self.font_size, &self.font_weight, font_family_names, layout);
+		set_pango_font_size(self.font_size, &self.font_weight, self.font_family.as_deref(), layout);
 		layout.set_text(&self.char);
 
 		let x_offset = offset_x as f64;
@@ -322,12 +358,10 @@ pub struct OutlineDrawData {
 
 impl OutlineDrawData {
 	fn measure(char: char, font_size: f32, font_weight: &FontWeight,
-		font_family_idx: &Option<u16>, font_family_names: Option<&IndexSet<String>>,
-		fonts: Option<&impl Fonts>) -> Option<Self>
+		font_family: Option<&str>, fonts: Option<&impl Fonts>) -> Option<Self>
 	{
 		if let Some(fonts) = fonts {
-			let font_family_names = get_font_family_names(font_family_idx, font_family_names);
-			if let Some((outline, rect)) = fonts.query(char, font_size, font_weight, font_family_names) {
+			if let Some((outline, rect)) = fonts.query(char, font_size, font_weight, font_family) {
 				let mut points = vec![];
 				outline.draw(|_, _, a| {
 					points.push((a * 255.) as u8);
@@ -371,6 +405,38 @@ impl OutlineDrawData {
 	}
 }
 
+/// render settings for creating the context
+#[derive(Default)]
+pub struct RenderOptions {
+	pub empty_lines: EmptyLines,
+	pub ignore_font_weight: bool,
+	pub drop_cap: bool,
+	pub hyphenation: bool,
+	pub scroll_step: u8,
+	pub page_overlap: u8,
+	pub font_family: Option<String>,
+	pub dyslexia_mode: bool,
+	pub invert_images: bool,
+}
+
+impl RenderOptions {
+	pub fn new(configuration: &Configuration) -> Self
+	{
+		let gui = &configuration.gui;
+		RenderOptions {
+			empty_lines: gui.empty_lines,
+			ignore_font_weight: gui.ignore_font_weight,
+			drop_cap: gui.drop_cap,
+			hyphenation: gui.hyphenation,
+			scroll_step: gui.scroll_step,
+			page_overlap: gui.page_overlap,
+			font_family: gui.render_font_family(),
+			dyslexia_mode: gui.dyslexia_mode,
+			invert_images: gui.invert_images && configuration.dark_theme,
+		}
+	}
+}
+
 pub struct RenderContext
 {
 	pub colors: Colors,
@@ -385,12 +451,18 @@ pub struct RenderContext
 	pub custom_color: bool,
 	// use book custom font
 	pub custom_font: bool,
-	// strip empty lines
-	pub strip_empty_lines: bool,
+	// render mode of blank lines
+	pub empty_lines: EmptyLines,
 
 	pub render_rect: Rect,
 	pub leading_chars: usize,
+	// global setting of leading chars, None for book default
+	pub default_leading_chars: Option<u8>,
 	pub leading_space: f32,
+	// size of 1em along the text flow, for css lengths
+	pub em_space: f32,
+	// space for each indent level of blocks
+	pub indent_space: f32,
 	// for calculate chars in single line
 	pub max_page_size: f32,
 
@@ -399,30 +471,124 @@ pub struct RenderContext
 
 	// ignore font weight
 	pub ignore_font_weight: bool,
+	// ignore fonts embedded in books, even custom font enabled
+	pub ignore_book_fonts: bool,
+	// render large first letter of paragraph as drop cap
+	pub drop_cap: bool,
+	// break long words with hyphen at line end
+	pub hyphenation: bool,
+	// language of current book, None for unknown
+	pub language: Option<String>,
+	// lines for each scroll step
+	pub scroll_step: u8,
+	// lines keep visible when scroll by page
+	pub page_overlap: u8,
+	// forced font family for all text
+	pub font_family: Option<String>,
+	// extra space after each char and blank char, in em
+	pub letter_spacing: f32,
+	pub word_spacing: f32,
+	// scale of line height
+	pub line_spacing: f32,
+	// invert colors of images, for dark theme
+	pub invert_images: bool,
+	// dim lines except the paragraph at the top of the page
+	pub focus_mode: bool,
+	// quality of scaling images
+	pub image_interpolation: ImageInterpolation,
+	// max scale for images smaller than the view, 1 for no upscaling
+	pub max_image_scale: f32,
+	// words always highlighted, see highlight_words()
+	pub highlight_words: Vec<Vec<char>>,
 }
 
 impl RenderContext {
 	pub fn new(colors: Colors, font_size: u8, custom_color: bool, custom_font: bool,
-		leading_chars: usize, strip_empty_lines: bool, ignore_font_weight: bool)
-		-> Self
+		leading_chars: usize, options: RenderOptions) -> Self
 	{
-		RenderContext {
+		let RenderOptions {
+			empty_lines,
+			ignore_font_weight,
+			drop_cap,
+			hyphenation,
+			scroll_step,
+			page_overlap,
+			font_family,
+			dyslexia_mode,
+			invert_images,
+		} = options;
+		let mut context = RenderContext {
 			colors,
 			fonts: Rc::new(None),
 			font_size,
 			default_font_measure: Pos2::ZERO,
 			custom_color,
 			custom_font,
-			strip_empty_lines,
+			empty_lines,
 			ignore_font_weight,
+			ignore_book_fonts: false,
+			drop_cap,
+			hyphenation,
+			scroll_step,
+			page_overlap,
+			font_family,
+			letter_spacing: 0.0,
+			word_spacing: 0.0,
+			line_spacing: 1.0,
+			invert_images,
+			focus_mode: false,
+			language: None,
+			image_interpolation: Default::default(),
+			max_image_scale: 1.0,
+			highlight_words: vec![],
 			render_rect: Rect::NOTHING,
 			leading_chars,
+			default_leading_chars: None,
 			leading_space: 0.0,
+			em_space: 0.0,
+			indent_space: 0.0,
 			max_page_size: 0.0,
 			scroll_redraw_method: ScrollRedrawMethod::NoResetScroll,
+		};
+		context.set_dyslexia_mode(dyslexia_mode);
+		context
+	}
+
+	/// wider letter, word and line spacing for easier reading
+	#[inline]
+	pub fn set_dyslexia_mode(&mut self, enabled: bool)
+	{
+		if enabled {
+			self.letter_spacing = DYSLEXIA_LETTER_SPACING;
+			self.word_spacing = DYSLEXIA_WORD_SPACING;
+			self.line_spacing = DYSLEXIA_LINE_SPACING;
+		} else {
+			self.letter_spacing = 0.0;
+			self.word_spacing = 0.0;
+			self.line_spacing = 1.0;
 		}
 	}
 
+	/// extra space after the char
+	#[inline]
+	pub fn char_spacing(&self, char: char) -> f32
+	{
+		let em = self.default_font_measure.x;
+		if char == ' ' || char == '\t' || char == '\u{a0}' {
+			em * (self.letter_spacing + self.word_spacing)
+		} else {
+			em * self.letter_spacing
+		}
+	}
+
+	#[inline]
+	pub fn scroll_steps(&self, line_size: f32, page_size: f32) -> (f32, f32)
+	{
+		let step_size = line_size * self.scroll_step.max(1) as f32;
+		let page_step = page_size - line_size * self.page_overlap as f32;
+		(step_size, page_step.max(line_size))
+	}
+
 	#[inline]
 	pub fn x_padding(&self) -> f32
 	{
@@ -449,16 +615,23 @@ impl RenderContext {
 pub struct ImageDrawingData {
 	orig_width: i32,
 	orig_height: i32,
+	// decoded image, for rescaling without decoding again
+	source: Pixbuf,
 	texture: Pixbuf,
+	inverted: bool,
+	interpolation: ImageInterpolation,
 }
 
 impl ImageDrawingData {
-	/// image render size
+	/// image render size, colors and scaling quality
 	#[inline]
-	pub fn match_size(&self, width: i32, height: i32) -> bool
+	pub fn match_size(&self, width: i32, height: i32, inverted: bool,
+		interpolation: ImageInterpolation) -> bool
 	{
 		self.texture.width() == width &&
-			self.texture.height() == height
+			self.texture.height() == height &&
+			self.inverted == inverted &&
+			self.interpolation == interpolation
 	}
 }
 
@@ -476,6 +649,7 @@ pub struct ScrolledDrawData {
 pub enum ScrollRedrawMethod {
 	NoResetScroll,
 	ResetScroll,
+	// progress of the content from 0 to 1, same for both axes
 	ScrollTo(f64),
 }
 
@@ -484,6 +658,8 @@ pub struct ScrollSizing {
 	pub full_size: f32,
 	pub step_size: f32,
 	pub page_size: f32,
+	// scroll size for paging, page size without overlap
+	pub page_step: f32,
 }
 
 pub struct CharMeasures {
@@ -574,6 +750,8 @@ pub trait GuiRender {
 	fn setup_border(&self, render_line: &mut RenderLine, lines: BorderLines,
 		decoration_chars_range: Range<usize>, start: bool, end: bool,
 		color: Color32);
+	/// short line above page footnotes, in the line slot at current baseline
+	fn footnote_separator(&self, render_context: &RenderContext) -> TextDecoration;
 
 	/// for scrolling view
 	/// get redraw lines size for scrollable size measure
@@ -607,15 +785,83 @@ pub trait GuiRender {
 	}
 
 	#[inline]
-	fn try_wrap_line(&mut self, book: &dyn Book, text: &Line, line: usize,
+	fn try_wrap_line(&mut self, book: &dyn Book, lines: &[Line], line: usize,
 		start_offset: usize, end_offset: usize, highlight: &Option<HighlightInfo>,
 		pango: &PangoContext, context: &mut RenderContext) -> Vec<RenderLine>
 	{
-		if context.strip_empty_lines && text.is_blank() {
+		let text = &lines[line];
+		let skip = text.is_blank() && match context.empty_lines {
+			EmptyLines::Keep => false,
+			EmptyLines::Collapse => line > 0 && lines[line - 1].is_blank(),
+			EmptyLines::Strip => true,
+		};
+		if skip {
 			vec![]
 		} else {
-			self.wrap_line(book, text, line, start_offset, end_offset, highlight, pango, context)
+			// room before the first line for top margin
+			let margin_top = text.margin_top();
+			let spacer = if margin_top > 0.0 && start_offset == 0 {
+				let space = margin_top * context.em_space;
+				self.update_baseline_for_delta(space);
+				Some(RenderLine::new(line, 0.0, space))
+			} else {
+				None
+			};
+			let mut render_lines = self.wrap_line(book, text, line, start_offset, end_offset, highlight, pango, context);
+			if let Some(spacer) = spacer {
+				render_lines.insert(0, spacer);
+			}
+			let margin_bottom = text.margin_bottom();
+			if margin_bottom > 0.0 && end_offset >= text.len() {
+				if let Some(last) = render_lines.last_mut() {
+					let space = margin_bottom * context.em_space;
+					last.add_space(space);
+					self.update_baseline_for_delta(space);
+				}
+			}
+			render_lines
+		}
+	}
+
+	/// wrap all lines of the footnote starting at footnote_line
+	fn wrap_footnote(&mut self, book: &dyn Book, lines: &[Line], footnote_line: usize,
+		pango: &PangoContext, context: &mut RenderContext) -> Vec<RenderLine>
+	{
+		let mut render_lines = vec![];
+		let Some(serial) = lines[footnote_line].footnote() else {
+			return render_lines;
+		};
+		for index in footnote_line..lines.len() {
+			let line = &lines[index];
+			if line.footnote() != Some(serial) {
+				break;
+			}
+			let wrapped_lines = self.try_wrap_line(book, lines, index, 0, line.len(), &None, pango, context);
+			render_lines.extend(wrapped_lines);
+		}
+		render_lines
+	}
+
+	/// size of footnotes with the separator if needed,
+	/// wrapped for measuring only, baseline keeps untouched
+	fn footnotes_size(&mut self, book: &dyn Book, lines: &[Line],
+		footnote_lines: &[usize], with_separator: bool, pango: &PangoContext,
+		context: &mut RenderContext) -> f32
+	{
+		if footnote_lines.is_empty() {
+			return 0.0;
+		}
+		let mut size = 0.0;
+		for footnote_line in footnote_lines {
+			for render_line in self.wrap_footnote(book, lines, *footnote_line, pango, context) {
+				size += render_line.size();
+			}
+		}
+		self.update_baseline_for_delta(-size);
+		if with_separator {
+			size += self.default_line_size(context);
 		}
+		size
 	}
 
 	#[inline]
@@ -864,13 +1110,43 @@ pub trait GuiRender {
 
 		let mut drawn_size = 0.0;
 		let mut next = None;
+		// no page break for scrolling view
+		let paged = context.max_page_size.is_finite();
+		// first lines of footnotes referenced in this page
+		let mut footnotes = vec![];
+		let mut footnotes_size = 0.0;
+		let page_footnotes = if paged {
+			page_footnote_serials(lines)
+		} else {
+			HashSet::new()
+		};
+		// footnote reached directly, by link or search, is rendered inline
+		let inline_footnote = lines.get(reading_line).and_then(|line| line.footnote());
 		'Done:
 		for index in reading_line..lines.len() {
 			let line = &lines[index];
-			let wrapped_lines = self.try_wrap_line(book, &line, index, rc.offset, line.len(), highlight, pango, context);
+			// footnotes are rendered at bottom of pages referencing them
+			if is_page_footnote(line, &page_footnotes, inline_footnote) {
+				rc.offset = 0;
+				continue;
+			}
+			if paged && line.page_break() && !rc.render_lines.is_empty() {
+				next = Some(Position::new(index, 0));
+				break;
+			}
+			let wrapped_lines = self.try_wrap_line(book, lines, index, rc.offset, line.len(), highlight, pango, context);
 			for wrapped_line in wrapped_lines {
 				drawn_size += wrapped_line.line_size;
-				if drawn_size > context.max_page_size {
+				let new_footnotes = if paged {
+					new_footnotes(line, &wrapped_line, &footnotes)
+				} else {
+					vec![]
+				};
+				let new_footnotes_size = self.footnotes_size(book, lines,
+					&new_footnotes, footnotes.is_empty(), pango, context);
+				if drawn_size > context.max_page_size
+					|| (!rc.render_lines.is_empty()
+					&& drawn_size + footnotes_size + new_footnotes_size > context.max_page_size) {
 					next = if let Some(char) = wrapped_line.chars.first() {
 						Some(Position::new(index, char.offset))
 					} else {
@@ -882,18 +1158,54 @@ pub trait GuiRender {
 				}
 				drawn_size += wrapped_line.line_space;
 				rc.render_lines.push(wrapped_line);
+				footnotes.extend(new_footnotes);
+				footnotes_size += new_footnotes_size;
 			}
 			self.setup_line_blocks(&mut rc, index, false, context);
 			rc.offset = 0;
 		}
 		self.finalize_blocks(&mut rc, context);
+		if !footnotes.is_empty() {
+			self.place_footnotes(book, lines, &footnotes, footnotes_size,
+				&mut rc, pango, context);
+		}
 		(rc.render_lines, rc.block_borders, rc.block_backgrounds, next)
 	}
 
+	/// render footnotes at page bottom, the gap above them is
+	/// added to the last text line, for pointer positions
+	fn place_footnotes(&mut self, book: &dyn Book, lines: &[Line],
+		footnotes: &[usize], footnotes_size: f32, rc: &mut RedrawContext,
+		pango: &PangoContext, context: &mut RenderContext)
+	{
+		let text_size: f32 = rc.render_lines.iter().map(|line| line.size()).sum();
+		let gap = (context.max_page_size - text_size - footnotes_size).max(0.0);
+		let separator_size = self.default_line_size(context);
+		if let Some(last) = rc.render_lines.last_mut() {
+			last.add_space(gap + separator_size);
+		}
+		self.reset_baseline(context);
+		self.update_baseline_for_delta(text_size + gap);
+		rc.block_borders.push(self.footnote_separator(context));
+		self.update_baseline_for_delta(separator_size);
+		// notes taller than the space left are clipped
+		let available = context.max_page_size - text_size - separator_size;
+		let mut placed_size = 0.0;
+		for footnote_line in footnotes {
+			for render_line in self.wrap_footnote(book, lines, *footnote_line, pango, context) {
+				placed_size += render_line.size();
+				if placed_size > available {
+					return;
+				}
+				rc.render_lines.push(render_line);
+			}
+		}
+	}
+
 	fn draw(&self, render_lines: &[RenderLine],
 		block_borders: &[TextDecoration],
 		block_backgrounds: &[BlockBackgroundEntry],
-		font_family_names: &Option<IndexSet<String>>,
+		image_background: Option<&Color32>,
 		cairo: &CairoContext, layout: &PangoContext)
 	{
 		cairo.set_line_width(1.0);
@@ -907,7 +1219,7 @@ pub trait GuiRender {
 			for dc in &render_line.chars {
 				match &dc.cell {
 					RenderCell::Image(name, _) => {
-						self.draw_image(name, &dc.rect, cairo);
+						self.draw_image(name, &dc.rect, image_background, cairo);
 					}
 					RenderCell::Char(cell)
 					| RenderCell::Link(cell, _) => {
@@ -922,7 +1234,6 @@ pub trait GuiRender {
 								draw_data,
 								&draw_position,
 								&cell.color,
-								font_family_names,
 								layout,
 							);
 						}
@@ -945,13 +1256,27 @@ pub trait GuiRender {
 		};
 
 		let mut drawn_size = 0.0;
+		// reserve space of footnotes as the forward pagination does
+		let page_footnotes = page_footnote_serials(lines);
+		let mut footnotes = vec![];
+		let mut footnotes_size = 0.0;
 		for index in (0..=reading_line).rev() {
 			let line = &lines[index];
-			let wrapped_lines = self.try_wrap_line(book, &line, index, 0, offset, &None, pango, context);
+			if is_page_footnote(line, &page_footnotes, None) {
+				offset = usize::MAX;
+				continue;
+			}
+			let wrapped_lines = self.try_wrap_line(book, lines, index, 0, offset, &None, pango, context);
 			offset = usize::MAX;
 			for wrapped_line in wrapped_lines.iter().rev() {
+				let has_lines = drawn_size > 0.0;
 				drawn_size += wrapped_line.line_size;
-				if drawn_size > context.max_page_size {
+				let new_footnotes = new_footnotes(line, wrapped_line, &footnotes);
+				let new_footnotes_size = self.footnotes_size(book, lines,
+					&new_footnotes, footnotes.is_empty(), pango, context);
+				if drawn_size > context.max_page_size
+					|| (has_lines
+					&& drawn_size + footnotes_size + new_footnotes_size > context.max_page_size) {
 					return if let Some(char) = wrapped_line.chars.last() {
 						let offset = char.offset + 1;
 						if offset >= line.len() {
@@ -964,6 +1289,11 @@ pub trait GuiRender {
 					};
 				}
 				drawn_size += wrapped_line.line_space;
+				footnotes.extend(new_footnotes);
+				footnotes_size += new_footnotes_size;
+			}
+			if line.page_break() && drawn_size > 0.0 {
+				return Position::new(index, 0);
 			}
 		}
 		Position::new(0, 0)
@@ -973,7 +1303,7 @@ pub trait GuiRender {
 		line: usize, offset: usize, pango: &PangoContext, context: &mut RenderContext)
 		-> Position
 	{
-		let wrapped_lines = self.try_wrap_line(book, &lines[line], line, offset, usize::MAX, &None, pango, context);
+		let wrapped_lines = self.try_wrap_line(book, lines, line, offset, usize::MAX, &None, pango, context);
 		if wrapped_lines.len() > 1 {
 			if let Some(next_line_char) = wrapped_lines[1].chars.first() {
 				Position::new(line, next_line_char.offset)
@@ -996,8 +1326,7 @@ pub trait GuiRender {
 		} else {
 			(line, offset)
 		};
-		let text = &lines[line];
-		let wrapped_lines = self.try_wrap_line(book, text, line, 0, offset, &None, pango, context);
+		let wrapped_lines = self.try_wrap_line(book, lines, line, 0, offset, &None, pango, context);
 		if let Some(last_line) = wrapped_lines.last() {
 			if let Some(first_char) = last_line.chars.first() {
 				Position::new(line, first_char.offset)
@@ -1013,8 +1342,7 @@ pub trait GuiRender {
 		line: usize, start: usize, pango: &PangoContext, context: &mut RenderContext)
 		-> Position
 	{
-		let text = &lines[line];
-		let wrapped_lines = self.try_wrap_line(book, text, line, 0, start + 1, &None, pango, context);
+		let wrapped_lines = self.try_wrap_line(book, lines, line, 0, start + 1, &None, pango, context);
 		if let Some(last_line) = wrapped_lines.last() {
 			if let Some(first_char) = last_line.chars.first() {
 				Position::new(line, first_char.offset)
@@ -1027,7 +1355,8 @@ pub trait GuiRender {
 	}
 
 	fn with_image(&mut self, char_style: &CharStyle, book: &dyn Book,
-		view_size: &Vec2, font_size: u8) -> Option<(String, Pos2)>
+		view_size: &Vec2, font_size: u8, invert: bool,
+		interpolation: ImageInterpolation, max_scale: f32) -> Option<(String, Pos2)>
 	{
 		if let Some(image) = &char_style.image {
 			if let Some(data) = book.image(image.href()) {
@@ -1035,27 +1364,26 @@ pub trait GuiRender {
 				let size = match cache.entry(data.path_dup()) {
 					Entry::Occupied(o) => {
 						let image_data = o.into_mut();
-						let (render_size, _) = calc_image_size(
+						let (render_size, resize) = calc_image_size(
 							image,
 							&char_style.font_scale,
 							font_size,
 							image_data.orig_width,
 							image_data.orig_height,
-							view_size);
-						if image_data.match_size(render_size.x as i32, render_size.y as i32) {
+							view_size,
+							max_scale);
+						if image_data.match_size(render_size.x as i32, render_size.y as i32, invert, interpolation) {
 							Some(render_size)
 						} else {
-							// font size changed
-							if let Some((data, render_size)) = load_image_and_resize(image, &char_style.font_scale, font_size, view_size, data.bytes()) {
-								*image_data = data;
-								Some(render_size)
-							} else {
-								None
-							}
+							// font size, image inverting or interpolation changed
+							image_data.texture = scale_image(&image_data.source, &render_size, resize, invert, interpolation)?;
+							image_data.inverted = invert;
+							image_data.interpolation = interpolation;
+							Some(render_size)
 						}
 					}
 					Entry::Vacant(v) =>
-						if let Some((data, render_size)) = load_image_and_resize(image, &char_style.font_scale, font_size, view_size, data.bytes()) {
+						if let Some((data, render_size)) = load_image_and_resize(image, &char_style.font_scale, font_size, view_size, invert, interpolation, max_scale, data.bytes()) {
 							v.insert(data);
 							Some(render_size)
 						} else {
@@ -1071,9 +1399,17 @@ pub trait GuiRender {
 		}
 	}
 
-	fn draw_image(&self, name: &str, rect: &Rect, cairo: &CairoContext)
+	fn draw_image(&self, name: &str, rect: &Rect, background: Option<&Color32>,
+		cairo: &CairoContext)
 	{
 		if let Some(image_data) = self.image_cache().get(name) {
+			if let Some(background) = background {
+				let texture = &image_data.texture;
+				let image_rect = Rect::from_min_size(
+					rect.min,
+					Vec2::new(texture.width() as f32, texture.height() as f32));
+				draw_rect(cairo, &image_rect, 1.0, background);
+			}
 			cairo.set_source_pixbuf(&image_data.texture, rect.min.x as f64, rect.min.y as f64);
 			handle_cairo(cairo.paint());
 		}
@@ -1101,16 +1437,23 @@ pub trait GuiRender {
 		book_fonts: Option<&HtmlFonts>, render_context: &mut RenderContext) -> CharMeasures
 	{
 		const SPACE: char = ' ';
+		const NBSP: char = '\u{a0}';
 		const FULL_SPACE: char = '　';
 
 		let font_size = scale_font_size(render_context.font_size, &font_scale);
 		let font_weight = load_font_weight(&font_weight, render_context);
-		let render_fonts = if render_context.custom_font {
+		let render_fonts = if render_context.font_family.is_some() {
+			// forced font family, ignore fonts and families of book
+			font_family_idx = &None;
+			None
+		} else if render_context.custom_font && !render_context.ignore_book_fonts {
 			book_fonts
 		} else {
 			font_family_idx = &None;
 			None
 		};
+		let font_family = render_context.font_family.as_deref()
+			.or_else(|| get_font_family_names(font_family_idx, font_family_names));
 
 		if let Some(data) = self.cache_get(char, font_size, &font_weight, font_family_idx) {
 			return CharMeasures {
@@ -1123,17 +1466,17 @@ pub trait GuiRender {
 			};
 		}
 		match char {
-			SPACE => {
+			SPACE | NBSP => {
 				let measures = self.measure_char(
 					layout, 'S', font_size, font_weight, font_family_idx,
-					font_family_names, render_fonts, &render_context.fonts);
-				self.cache_insert(SPACE, font_size, &font_weight, font_family_idx, CharDrawData::Space(measures.size));
+					font_family, render_fonts, &render_context.fonts);
+				self.cache_insert(char, font_size, &font_weight, font_family_idx, CharDrawData::Space(measures.size));
 				measures
 			}
 			FULL_SPACE => {
 				let measures = self.measure_char(
 					layout, HAN_CHAR, font_size, font_weight, font_family_idx,
-					font_family_names, render_fonts, &render_context.fonts);
+					font_family, render_fonts, &render_context.fonts);
 				self.cache_insert(FULL_SPACE, font_size, &font_weight, font_family_idx, CharDrawData::Space(measures.size));
 				measures
 			}
@@ -1143,7 +1486,7 @@ pub trait GuiRender {
 				font_size,
 				font_weight,
 				font_family_idx,
-				font_family_names,
+				font_family,
 				render_fonts,
 				&render_context.fonts)
 		}
@@ -1151,7 +1494,7 @@ pub trait GuiRender {
 
 	fn measure_char(&mut self, layout: &PangoContext, char: char, font_size: f32,
 		font_weight: &FontWeight, font_family_idx: &Option<u16>,
-		font_family_names: Option<&IndexSet<String>>,
+		font_family: Option<&str>,
 		book_fonts: Option<&HtmlFonts>, fonts: &Option<UserFonts>)
 		-> CharMeasures
 	{
@@ -1159,8 +1502,7 @@ pub trait GuiRender {
 			char,
 			font_size,
 			font_weight,
-			font_family_idx,
-			font_family_names,
+			font_family,
 			book_fonts) {
 			let measures = CharMeasures {
 				size: draw_data.size,
@@ -1176,8 +1518,7 @@ pub trait GuiRender {
 			char,
 			font_size,
 			font_weight,
-			font_family_idx,
-			font_family_names,
+			font_family,
 			fonts.as_ref()) {
 			let measures = CharMeasures {
 				size: draw_data.size,
@@ -1194,8 +1535,7 @@ pub trait GuiRender {
 				char,
 				font_size,
 				font_weight,
-				font_family_idx,
-				font_family_names,
+				font_family,
 				layout);
 			let measures = CharMeasures {
 				size: draw_data.size,
@@ -1211,13 +1551,54 @@ pub trait GuiRender {
 	}
 }
 
-/// calc image render size
-/// return
-/// 1: render size
-/// 2: raise needed
+/// serials of footnotes referenced in lines, the others
+/// are not linked from this chapter and rendered inline
+fn page_footnote_serials(lines: &[Line]) -> HashSet<usize>
+{
+	let mut serials = HashSet::new();
+	for line in lines {
+		for (_, footnote_line) in line.footnote_refs() {
+			if let Some(serial) = lines.get(*footnote_line).and_then(|line| line.footnote()) {
+				serials.insert(serial);
+			}
+		}
+	}
+	serials
+}
+
+#[inline]
+fn is_page_footnote(line: &Line, page_footnotes: &HashSet<usize>,
+	inline_footnote: Option<usize>) -> bool
+{
+	match line.footnote() {
+		Some(serial) => inline_footnote != Some(serial) && page_footnotes.contains(&serial),
+		None => false,
+	}
+}
+
+/// first lines of footnotes referenced by the render line, not rendered yet
+fn new_footnotes(line: &Line, render_line: &RenderLine, footnotes: &[usize]) -> Vec<usize>
+{
+	let mut new_footnotes = vec![];
+	if render_line.chars.is_empty() {
+		return new_footnotes;
+	}
+	let range = render_line.first_offset()..=render_line.last_offset();
+	for (offset, footnote_line) in line.footnote_refs() {
+		if range.contains(offset)
+			&& !footnotes.contains(footnote_line)
+			&& !new_footnotes.contains(footnote_line) {
+			new_footnotes.push(*footnote_line);
+		}
+	}
+	new_footnotes
+}
+
+/// render size of image, and if it's resized, large image shrinks to fit
+/// the view, small image enlarges up to max_scale times
 fn calc_image_size(image_style: &ImageStyle, font_scale: &FontScale,
-	font_size: u8, orig_width: i32, orig_height: i32, view_size: &Vec2)
-	-> (Vec2, bool)
+	font_size: u8, orig_width: i32, orig_height: i32, view_size: &Vec2,
+	max_scale: f32) -> (Vec2, bool)
 {
 	#[inline]
 	fn calc_length(orig_length: i32, custom_length: &Option<ElementSize>,
@@ -1262,59 +1643,181 @@ fn calc_image_size(image_style: &ImageStyle, font_scale: &FontScale,
 	} else if relative_with_font_size {
 		(Vec2 { x: image_width, y: image_height }, true)
 	} else {
-		(Vec2 { x: image_width, y: image_height }, false)
+		// only images without custom size, keep aspect ratio
+		let scale = (width / image_width)
+			.min(height / image_height)
+			.min(max_scale);
+		if scale > 1.0 {
+			(Vec2 { x: image_width * scale, y: image_height * scale }, true)
+		} else {
+			(Vec2 { x: image_width, y: image_height }, false)
+		}
 	}
 }
 
 fn load_image_and_resize(image_style: &ImageStyle, font_scale: &FontScale,
-	font_size: u8, view_size: &Vec2, bytes: &[u8])
+	font_size: u8, view_size: &Vec2, invert: bool,
+	interpolation: ImageInterpolation, max_scale: f32, bytes: &[u8])
 	-> Option<(ImageDrawingData, Vec2)>
 {
-	let mut image = load_image(bytes)?;
-	let orig_width = image.width();
-	let orig_height = image.height();
+	let source = load_image(bytes)?;
+	let orig_width = source.width();
+	let orig_height = source.height();
 	let (render_size, resize) = calc_image_size(
 		image_style,
 		font_scale,
 		font_size,
 		orig_width,
 		orig_height,
-		view_size);
-	if resize {
-		image = image.scale_simple(render_size.x as i32, render_size.y as i32, InterpType::Nearest)?
-	}
+		view_size,
+		max_scale);
+	let texture = scale_image(&source, &render_size, resize, invert, interpolation)?;
 	Some((
 		ImageDrawingData {
 			orig_width,
 			orig_height,
-			texture: image,
+			source,
+			texture,
+			inverted: invert,
+			interpolation,
 		},
 		render_size,
 	))
 }
 
+/// scale decoded image to render size, and invert colors if needed
+fn scale_image(source: &Pixbuf, render_size: &Vec2, resize: bool, invert: bool,
+	interpolation: ImageInterpolation) -> Option<Pixbuf>
+{
+	let mut image = if resize {
+		source.scale_simple(render_size.x as i32, render_size.y as i32, interpolation.interp_type())?
+	} else {
+		source.clone()
+	};
+	if invert {
+		image = invert_image(&image);
+	}
+	Some(image)
+}
+
+/// invert rgb of pixels, alpha keeps untouched
+fn invert_image(image: &Pixbuf) -> Pixbuf
+{
+	let channels = image.n_channels() as usize;
+	let rowstride = image.rowstride() as usize;
+	let row_size = image.width() as usize * channels;
+	let mut pixels = image.read_pixel_bytes().to_vec();
+	for row in pixels.chunks_mut(rowstride) {
+		let row_size = row_size.min(row.len());
+		for pixel in row[..row_size].chunks_mut(channels) {
+			for color in pixel.iter_mut().take(3) {
+				*color = 255 - *color;
+			}
+		}
+	}
+	Pixbuf::from_bytes(
+		&Bytes::from_owned(pixels),
+		image.colorspace(),
+		image.has_alpha(),
+		image.bits_per_sample(),
+		image.width(),
+		image.height(),
+		image.rowstride())
+}
+
+/// shift of superscript or subscript, negative for raising in horizontal text
 #[inline]
-pub fn update_for_highlight(render_line: usize, offset: usize, background: Option<Color32>, colors: &Colors, highlight: &Option<HighlightInfo>) -> Option<Color32>
+pub fn vertical_align_shift(vertical_align: &Option<VerticalAlign>, context: &RenderContext) -> f32
 {
-	match highlight {
+	match vertical_align {
+		Some(VerticalAlign::Super) => -context.default_font_measure.y * SUPERSCRIPT_SHIFT,
+		Some(VerticalAlign::Sub) => context.default_font_measure.y * SUBSCRIPT_SHIFT,
+		None => 0.0,
+	}
+}
+
+#[inline]
+/// colors of highlighted char from theme, return (color, background)
+pub fn update_for_highlight(render_line: usize, offset: usize, color: Color32,
+	background: Option<Color32>, colors: &Colors, highlight: &Option<HighlightInfo>)
+	-> (Color32, Option<Color32>)
+{
+	let highlighted = match highlight {
 		Some(HighlightInfo { mode: HighlightMode::Search, line, start, end })
 		| Some(HighlightInfo { mode: HighlightMode::Link(_), line, start, end })
-		if *line == render_line && *start <= offset && *end > offset
-		=> Some(colors.highlight_background.clone()),
+		=> *line == render_line && *start <= offset && *end > offset,
 
 		Some(HighlightInfo { mode: HighlightMode::Selection(_, line2), line, start, end })
-		if (*line == render_line && *line2 == render_line && *start <= offset && *end > offset)
+		=> (*line == render_line && *line2 == render_line && *start <= offset && *end > offset)
 			|| (*line == render_line && *line2 > render_line && *start <= offset)
 			|| (*line < render_line && *line2 == render_line && *end > offset)
-			|| (*line < render_line && *line2 > render_line)
-		=> {
-			Some(colors.highlight_background.clone())
+			|| (*line < render_line && *line2 > render_line),
+
+		None => false,
+	};
+	if highlighted {
+		(colors.highlight.clone(), Some(colors.highlight_background.clone()))
+	} else {
+		(color, background)
+	}
+}
+
+/// words to highlight from configuration, empty ones ignored
+pub fn highlight_words(words: &[String]) -> Vec<Vec<char>>
+{
+	words.iter()
+		.map(|word| word.trim())
+		.filter(|word| !word.is_empty())
+		.map(|word| word.chars().collect())
+		.collect()
+}
+
+/// ranges of highlight words in the line, with color of each word
+pub fn highlight_word_ranges(text: &Line, words: &[Vec<char>]) -> Vec<(Range<usize>, Color32)>
+{
+	let mut ranges = vec![];
+	for (index, word) in words.iter().enumerate() {
+		let color = &HIGHLIGHT_WORD_COLORS[index % HIGHLIGHT_WORD_COLORS.len()];
+		for start in text.find_all(word) {
+			ranges.push((start..start + word.len(), color.clone()));
 		}
+	}
+	ranges
+}
 
-		_ => background,
+#[inline]
+pub fn highlight_word_background(ranges: &[(Range<usize>, Color32)], offset: usize) -> Option<Color32>
+{
+	ranges.iter()
+		.find(|(range, _)| range.contains(&offset))
+		.map(|(_, color)| color.clone())
+}
+
+/// space before the first char, css text-indent or default leading space
+#[inline]
+pub fn leading_space(text: &Line, context: &RenderContext) -> f32
+{
+	if let Some(text_indent) = text.text_indent() {
+		(text_indent * context.em_space).max(0.0)
+	} else if with_leading(text) {
+		context.leading_space
+	} else {
+		0.0
 	}
 }
 
+/// space after the line, honor line-height of the text if specified
+#[inline]
+pub fn calc_line_space(text: &Line, line_size: f32, context: &RenderContext) -> f32
+{
+	let line_space = if let Some(line_height) = text.line_height() {
+		line_size * (line_height - 1.0).max(0.0)
+	} else {
+		line_size / 2.0
+	};
+	line_space + line_size * (context.line_spacing - 1.0)
+}
+
 #[inline]
 fn scale_font_size(font_size: u8, scale: &FontScale) -> f32
 {
@@ -1526,6 +2029,71 @@ pub fn draw_rect(cairo: &CairoContext, rect: &Rect, stroke_width: f32, color: &C
 	handle_cairo(cairo.fill());
 }
 
+/// dim lines of other paragraphs with translucent background,
+/// the paragraph of the first line is focused
+pub fn draw_focus_dim(cairo: &CairoContext, render_lines: &[RenderLine], background: &Color32)
+{
+	let Some(focused) = render_lines.first().map(|render_line| render_line.line) else {
+		return;
+	};
+	let (x1, y1, x2, y2) = match cairo.clip_extents() {
+		Ok(extents) => extents,
+		Err(err) => {
+			eprintln!("Failed cairo call: {}", err.to_string());
+			return;
+		}
+	};
+	cairo.rectangle(x1, y1, x2 - x1, y2 - y1);
+	let mut focus_min = pos2(f32::INFINITY, f32::INFINITY);
+	let mut focus_max = pos2(f32::NEG_INFINITY, f32::NEG_INFINITY);
+	for render_line in render_lines.iter().filter(|render_line| render_line.line == focused) {
+		for render_char in &render_line.chars {
+			let rect = &render_char.rect;
+			focus_min.x = focus_min.x.min(rect.min.x);
+			focus_min.y = focus_min.y.min(rect.min.y);
+			focus_max.x = focus_max.x.max(rect.max.x);
+			focus_max.y = focus_max.y.max(rect.max.y);
+		}
+	}
+	if focus_min.x < focus_max.x && focus_min.y < focus_max.y {
+		cairo.rectangle(focus_min.x as f64, focus_min.y as f64,
+			(focus_max.x - focus_min.x) as f64, (focus_max.y - focus_min.y) as f64);
+	}
+	cairo.set_fill_rule(cairo::FillRule::EvenOdd);
+	cairo.set_source_rgba(
+		background.r() as f64 / 255.,
+		background.g() as f64 / 255.,
+		background.b() as f64 / 255.,
+		FOCUS_DIM_ALPHA);
+	handle_cairo(cairo.fill());
+	cairo.set_fill_rule(cairo::FillRule::Winding);
+}
+
+/// draw label of link hint mode, left top at position
+pub fn draw_link_hint(cairo: &CairoContext, label: &str, position: &Pos2,
+	font_size: u8, layout: &PangoContext)
+{
+	set_pango_font_size(font_size, &FontWeight::BOLD, None, layout);
+	layout.set_text(label);
+	let (_, logical_rect) = layout.pixel_extents();
+	let padding = 2.;
+	let rect = Rect::new(
+		position.x,
+		position.y,
+		logical_rect.width() as f32 + padding * 2.,
+		logical_rect.height() as f32 + padding * 2.);
+	draw_rect(cairo, &rect, 1., &Color32::KHAKI);
+	Color32::DARK_GRAY.apply(cairo);
+	cairo.rectangle(rect.min.x as f64, rect.min.y as f64,
+		rect.width() as f64, rect.height() as f64);
+	handle_cairo(cairo.stroke());
+	Color32::BLACK.apply(cairo);
+	cairo.move_to(
+		(position.x + padding - logical_rect.x() as f32) as f64,
+		(position.y + padding - logical_rect.y() as f32) as f64);
+	pangocairo::functions::show_layout(cairo, layout);
+}
+
 #[inline]
 pub fn handle_cairo<T>(result: Result<T, cairo::Error>)
 {
@@ -1549,16 +2117,14 @@ fn set_pango_font_size(font_size: u8, font_weight: &FontWeight,
 
 #[inline]
 fn draw_char(cairo: &CairoContext, draw_data: &CharDrawData, position: &Pos2,
-	color: &Color32, font_family_names: &Option<IndexSet<String>>,
-	layout: &PangoContext)
+	color: &Color32, layout: &PangoContext)
 {
 	match draw_data {
 		CharDrawData::Outline(data) => {
 			data.draw(cairo, position.x, position.y, &color);
 		}
 		CharDrawData::Pango(data) => {
-			data.draw(cairo, position.x, position.y, &color,
-				font_family_names.as_ref(), layout);
+			data.draw(cairo, position.x, position.y, &color, layout);
 		}
 		CharDrawData::Space(_) => {}
 	}
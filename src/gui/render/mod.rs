@@ -1,11 +1,16 @@
 mod imp;
 mod han;
 mod xi;
+mod wrap;
 
 pub use imp::BlockBackgroundEntry;
+pub use imp::draw_focus_dim;
+pub use imp::draw_link_hint;
+pub use imp::highlight_words;
 pub use imp::GuiRender;
 pub use imp::PointerPosition;
 pub use imp::RenderContext;
+pub use imp::RenderOptions;
 pub use imp::RenderLine;
 pub use imp::RenderCell;
 pub use imp::RenderChar;
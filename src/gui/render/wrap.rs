@@ -0,0 +1,379 @@
+/// kinsoku shori, chars not allowed at start of line
+const NOT_AT_LINE_START: &str = "、。，．,.・：；:;？！?!‼⁇⁈⁉）］｝〕〉》」』】〙〗〟’”｠»)]}ヽヾゝゞ々〻ーぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶㇰㇱㇲㇳㇴㇵㇶㇷㇸㇹㇺㇻㇼㇽㇾㇿ〜～…‥";
+/// kinsoku shori, chars not allowed at end of line
+const NOT_AT_LINE_END: &str = "（［｛〔〈《「『【〘〖〝‘“｟«([{";
+/// breaking back to the last break opportunity only for words shorter than this
+const MAX_BREAK_BACK: usize = 20;
+
+/// how to wrap when a char overflows the line end
+#[derive(Debug, PartialEq)]
+pub(super) enum LineBreak {
+	/// start next line with the overflowing char
+	Before,
+	/// move placed chars from this index to next line
+	At(usize),
+	/// let the overflowing char hang out of the line end
+	Hang,
+}
+
+/// a line may break before this char
+#[inline]
+pub(super) fn can_break_at(char: char, nowrap: bool) -> bool
+{
+	// non-breaking space and nowrap content never break
+	!nowrap && char != '\u{a0}'
+		&& (is_blank(char) || !char.is_ascii_alphanumeric())
+}
+
+#[inline]
+pub(super) fn is_blank(char: char) -> bool
+{
+	char == ' ' || char == '\t'
+}
+
+/// horizontal text, break before the overflowing char if it can break,
+/// else back to the last break opportunity, unless the word too long
+fn xi_line_break(can_break: bool, break_position: Option<usize>,
+	placed: usize) -> LineBreak
+{
+	match break_position {
+		Some(position) if !can_break
+			&& position < placed
+			&& placed <= position + MAX_BREAK_BACK => LineBreak::At(position),
+		_ => LineBreak::Before,
+	}
+}
+
+/// pick the last hyphenation point, that hyphen still fit in the line
+/// breaks: char index of placed chars, in ascending order
+/// rights: right edge of each placed char
+pub(super) fn hyphen_break(breaks: &[usize], rights: &[f32], hyphen_width: f32,
+	max_right: f32) -> Option<usize>
+{
+	breaks.iter().rev()
+		// chars after the break not placed yet
+		.filter(|index| **index > 0 && **index <= rights.len())
+		.find(|index| rights[**index - 1] + hyphen_width <= max_right)
+		.copied()
+}
+
+/// vertical text with kinsoku shori, hang the closing char out of the line end,
+/// or push the opening char at line end to next line
+/// char and last are None for images
+/// hang_fits: the char still inside the line end when hanging
+fn han_line_break(char: Option<char>, hanging: bool, hang_fits: bool,
+	last: Option<char>, placed: usize) -> LineBreak
+{
+	if hang_fits && !hanging && not_at_line_start(char) {
+		LineBreak::Hang
+	} else if placed > 1 && not_at_line_end(last) {
+		LineBreak::At(placed - 1)
+	} else {
+		LineBreak::Before
+	}
+}
+
+/// chars placed in the wrapping line, with the state to break it
+pub(super) struct LineWrap<T> {
+	placed: Vec<T>,
+	// placed chars from this index can move to next line
+	break_position: Option<usize>,
+	// one char hanging out of the line end for kinsoku
+	hanging: bool,
+}
+
+impl<T> LineWrap<T>
+{
+	pub fn new() -> Self
+	{
+		LineWrap {
+			placed: vec![],
+			break_position: None,
+			hanging: false,
+		}
+	}
+
+	#[inline]
+	pub fn placed(&self) -> &[T]
+	{
+		&self.placed
+	}
+
+	#[inline]
+	pub fn placed_mut(&mut self) -> &mut [T]
+	{
+		&mut self.placed
+	}
+
+	#[inline]
+	pub fn finish(self) -> Vec<T>
+	{
+		self.placed
+	}
+
+	/// place a char of horizontal text, return chars of the finished line
+	/// if it overflows the line end, chars moved to next line are kept placed
+	/// blank: dropped at line end
+	/// break_after: break opportunity after this char
+	/// hyphenate: with placed chars and the word start index,
+	/// return the index to break at and the hyphen
+	pub fn xi_push<H>(&mut self, item: T, overflow: bool, can_break: bool,
+		blank: bool, break_after: bool, hyphenate: H) -> Option<Vec<T>>
+	where
+		H: FnOnce(&[T], usize) -> Option<(usize, T)>,
+	{
+		let mut line = None;
+		if overflow && !self.placed.is_empty() {
+			let placed = self.placed.len();
+			let break_position = self.break_position;
+			let line_break = xi_line_break(can_break, break_position, placed);
+			// chars moved to next line contain no break opportunity
+			self.break_position = None;
+			let (position, word_start) = match line_break {
+				LineBreak::At(position) => (position, Some(position)),
+				// word too long, break it inside
+				_ if !can_break => (placed, Some(break_position.unwrap_or(0))),
+				_ => (placed, None),
+			};
+			let (position, hyphen) = match word_start
+				.and_then(|word_start| hyphenate(&self.placed, word_start)) {
+				Some((index, hyphen)) => (index, Some(hyphen)),
+				None => (position, None),
+			};
+			let carried = self.placed.split_off(position);
+			let mut finished = std::mem::replace(&mut self.placed, carried);
+			if let Some(hyphen) = hyphen {
+				finished.push(hyphen);
+			}
+			// for break char, will not print it any more
+			if blank && self.placed.is_empty() {
+				return Some(finished);
+			}
+			line = Some(finished);
+		}
+		self.placed.push(item);
+		if break_after {
+			self.break_position = Some(self.placed.len());
+		}
+		line
+	}
+
+	/// place a char of vertical text with kinsoku shori, return chars
+	/// of the finished line if it overflows the line end,
+	/// chars moved to next line are kept placed
+	/// overflow: beyond the wrapping end, which reserves room for hanging
+	/// hang_fits: still inside the real line end
+	/// char_of: the char for kinsoku, None for images
+	pub fn han_push<C>(&mut self, item: T, overflow: bool, hang_fits: bool,
+		char_of: C) -> Option<Vec<T>>
+	where
+		C: Fn(&T) -> Option<char>,
+	{
+		let mut line = None;
+		if overflow && !self.placed.is_empty() {
+			let last = self.placed.last().and_then(&char_of);
+			match han_line_break(char_of(&item), self.hanging, hang_fits, last,
+				self.placed.len()) {
+				LineBreak::Hang => self.hanging = true,
+				line_break => {
+					let position = match line_break {
+						LineBreak::At(position) => position,
+						_ => self.placed.len(),
+					};
+					let carried = self.placed.split_off(position);
+					line = Some(std::mem::replace(&mut self.placed, carried));
+					self.hanging = false;
+				}
+			}
+		}
+		self.placed.push(item);
+		line
+	}
+}
+
+#[inline]
+fn not_at_line_start(char: Option<char>) -> bool
+{
+	char.map_or(false, |char| NOT_AT_LINE_START.contains(char))
+}
+
+#[inline]
+fn not_at_line_end(char: Option<char>) -> bool
+{
+	char.map_or(false, |char| NOT_AT_LINE_END.contains(char))
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::book::ZERO_WIDTH_SPACE;
+	use crate::gui::render::wrap::{can_break_at, han_line_break, hyphen_break, is_blank, LineBreak, LineWrap};
+
+	const CJK_WIDTH: f32 = 2.0;
+
+	fn advance(char: char) -> f32
+	{
+		if char == ZERO_WIDTH_SPACE {
+			0.0
+		} else if char.is_ascii() {
+			1.0
+		} else {
+			CJK_WIDTH
+		}
+	}
+
+	/// wrap by production LineWrap, return index of each wrapped line start
+	fn xi_breaks(text: &str, nowrap: bool, width: f32) -> Vec<usize>
+	{
+		let chars: Vec<char> = text.chars().collect();
+		let mut breaks = vec![];
+		let mut wrap = LineWrap::new();
+		for (i, char) in chars.iter().enumerate() {
+			let left: f32 = wrap.placed().iter().map(|i| advance(chars[*i])).sum();
+			let blank = is_blank(*char) && !nowrap;
+			let line = wrap.xi_push(i, left + advance(*char) > width,
+				can_break_at(*char, nowrap), blank,
+				blank || *char == ZERO_WIDTH_SPACE, |_, _| None);
+			if line.is_some() {
+				if let Some(start) = wrap.placed().first() {
+					breaks.push(*start);
+				} else {
+					breaks.push(i + 1);
+				}
+			}
+		}
+		breaks
+	}
+
+	/// wrap by production LineWrap, return index of each wrapped line start
+	/// with one more cell reserved at line end for hanging
+	fn han_breaks(text: &str, height: usize) -> Vec<usize>
+	{
+		let chars: Vec<char> = text.chars().collect();
+		let mut breaks = vec![];
+		let mut wrap = LineWrap::new();
+		for i in 0..chars.len() {
+			let overflow = wrap.placed().len() + 1 > height;
+			let hang_fits = wrap.placed().len() <= height;
+			if wrap.han_push(i, overflow, hang_fits, |i| Some(chars[*i])).is_some() {
+				breaks.push(wrap.placed()[0]);
+			}
+		}
+		breaks
+	}
+
+	#[test]
+	fn test_latin_break_at_blank()
+	{
+		// word moved to next line
+		assert_eq!(xi_breaks("hello world foo", false, 8.0), vec![6, 12]);
+		// blank at line end dropped
+		assert_eq!(xi_breaks("abcd efgh", false, 4.0), vec![5]);
+	}
+
+	#[test]
+	fn test_latin_long_word()
+	{
+		// no break opportunity, break anywhere
+		assert_eq!(xi_breaks("abcdefghij", false, 4.0), vec![4, 8]);
+		// too long word not moved to next line
+		let text = format!("a {}", "b".repeat(30));
+		assert_eq!(xi_breaks(&text, false, 25.0), vec![25]);
+	}
+
+	#[test]
+	fn test_cjk_break_anywhere()
+	{
+		assert_eq!(xi_breaks("中文字符排版", false, 4.0), vec![2, 4]);
+		assert_eq!(xi_breaks("abc中文", false, 4.0), vec![3]);
+		// but latin word still moved to next line
+		assert_eq!(xi_breaks("中 abc", false, 4.0), vec![2]);
+	}
+
+	#[test]
+	fn test_nowrap()
+	{
+		assert_eq!(xi_breaks("ab cde", false, 5.0), vec![3]);
+		// no break opportunity in nowrap content, and blank kept
+		assert_eq!(xi_breaks("ab cde", true, 5.0), vec![5]);
+		assert_eq!(xi_breaks("abcde fg", true, 5.0), vec![5]);
+		assert!(!can_break_at('中', true));
+		assert!(!can_break_at('\u{a0}', false));
+	}
+
+	#[test]
+	fn test_zero_width_space()
+	{
+		let text = format!("abc{}def", ZERO_WIDTH_SPACE);
+		assert_eq!(xi_breaks(&text, false, 5.0), vec![4]);
+	}
+
+	#[test]
+	fn test_hyphen_break()
+	{
+		let rights = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+		// last break point with hyphen fit
+		assert_eq!(hyphen_break(&[2, 4], &rights, 1.0, 5.0), Some(4));
+		// hyphen not fit after 5th char, use the previous break point
+		assert_eq!(hyphen_break(&[2, 5], &rights, 1.0, 5.0), Some(2));
+		// break point not placed yet
+		assert_eq!(hyphen_break(&[3, 8], &rights, 1.0, 6.0), Some(3));
+		assert_eq!(hyphen_break(&[5], &rights, 1.0, 5.0), None);
+		assert_eq!(hyphen_break(&[], &rights, 1.0, 5.0), None);
+	}
+
+	/// hyphenate every word after its 2nd char, the hyphen as usize::MAX
+	fn hyphen_breaks(text: &str, width: usize) -> Vec<Vec<usize>>
+	{
+		let chars: Vec<char> = text.chars().collect();
+		let mut lines = vec![];
+		let mut wrap = LineWrap::new();
+		for (i, char) in chars.iter().enumerate() {
+			let overflow = wrap.placed().len() + 1 > width;
+			let blank = is_blank(*char);
+			let line = wrap.xi_push(i, overflow, can_break_at(*char, false),
+				blank, blank, |placed, word_start| {
+					let index = word_start + 2;
+					if index < placed.len() {
+						Some((index, usize::MAX))
+					} else {
+						None
+					}
+				});
+			if let Some(line) = line {
+				lines.push(line);
+			}
+		}
+		lines.push(wrap.finish());
+		lines
+	}
+
+	#[test]
+	fn test_hyphenate()
+	{
+		// word moved to next line hyphenated
+		assert_eq!(hyphen_breaks("ab cdefg", 6),
+			vec![vec![0, 1, 2, 3, 4, usize::MAX], vec![5, 6, 7]]);
+		// too long word without break opportunity hyphenated too
+		assert_eq!(hyphen_breaks("abcdefg", 4),
+			vec![vec![0, 1, usize::MAX], vec![2, 3, usize::MAX], vec![4, 5, 6]]);
+	}
+
+	#[test]
+	fn test_kinsoku()
+	{
+		// plain han text, break every 3 chars
+		assert_eq!(han_breaks("一二三四五六七", 3), vec![3, 6]);
+		// closing punctuation hang out of the line end
+		assert_eq!(han_breaks("一二三。四五", 3), vec![4]);
+		// only one char can hang
+		assert_eq!(han_breaks("一二三。」四", 3), vec![4]);
+		// opening punctuation at line end pushed to next line
+		assert_eq!(han_breaks("一二「三四五", 3), vec![2, 5]);
+		// single opening char not moved, or the line will be empty
+		assert_eq!(han_line_break(Some('三'), false, true, Some('「'), 1), LineBreak::Before);
+		// images never hang
+		assert_eq!(han_line_break(None, false, true, Some('一'), 3), LineBreak::Before);
+		// no hanging if clipped by the real line end
+		assert_eq!(han_line_break(Some('。'), false, false, Some('一'), 3), LineBreak::Before);
+	}
+}
@@ -3,34 +3,123 @@ use std::ops::Range;
 
 use gtk4::cairo::Context as CairoContext;
 use gtk4::pango::Layout as PangoContext;
+use hyphenation::{Hyphenator, Language, Load, Standard};
 
-use crate::book::{Book, Line};
+use crate::book::{Book, Line, ZERO_WIDTH_SPACE};
 use crate::color::Color32;
-use crate::common::with_leading;
+use crate::common::is_cjk;
 use crate::controller::HighlightInfo;
 use crate::gui::math::{Pos2, pos2, Rect, Vec2};
-use crate::gui::render::{CharCell, CharDrawData, GuiRender, hline, ImageDrawingData, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollSizing, TextDecoration, update_for_highlight};
+use crate::gui::render::{calc_line_space, CharCell, CharDrawData, GuiRender, hline, ImageDrawingData, leading_space, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollSizing, TextDecoration, highlight_word_background, highlight_word_ranges, update_for_highlight, vertical_align_shift};
 use crate::gui::render::imp::draw_border;
+use crate::gui::render::wrap::{can_break_at, hyphen_break, is_blank, LineWrap};
 use crate::html_parser;
-use crate::html_parser::{BorderLines, TextDecorationLine, TextStyle};
+use crate::html_parser::{BorderLines, TextDecorationLine, TextDecorationStyle, TextStyle};
+use crate::package_name;
+
+// first letter at least this times larger than the next one will be drop cap
+const DROP_CAP_MIN_SCALE: f32 = 1.8;
 
 pub(super) struct GuiXiRender {
 	images: HashMap<String, ImageDrawingData>,
 	baseline: f32,
 	outline_draw_cache: HashMap<u64, CharDrawData>,
+	// hyphenation dictionary for the book language, None for not supported
+	hyphenator: Option<(String, Option<Standard>)>,
 }
 
 impl GuiXiRender
 {
 	pub fn new() -> Self
 	{
-		GuiXiRender { images: HashMap::new(), baseline: 0.0, outline_draw_cache: HashMap::new() }
+		GuiXiRender {
+			images: HashMap::new(),
+			baseline: 0.0,
+			outline_draw_cache: HashMap::new(),
+			hyphenator: None,
+		}
+	}
+
+	/// load once for each book language
+	fn load_hyphenator(&mut self, context: &RenderContext) -> Option<&Standard>
+	{
+		let lang = context.language.as_deref()?;
+		if self.hyphenator.as_ref().map_or(true, |(curr, _)| curr != lang) {
+			let dictionary = hyphenation_language(lang)
+				.and_then(load_dictionary);
+			self.hyphenator = Some((lang.to_owned(), dictionary));
+		}
+		self.hyphenator.as_ref()?.1.as_ref()
+	}
+
+	/// break the word exceeding line end by hyphenation,
+	/// return index of draw_chars to break at, and the hyphen char
+	fn hyphenate(&mut self, book: &dyn Book, text: &Line, draw_chars: &[RenderChar],
+		word_start: usize, offset: usize, max_left: f32, pango: &PangoContext,
+		context: &mut RenderContext) -> Option<(usize, RenderChar)>
+	{
+		let start_offset = draw_chars.get(word_start)?.offset;
+		let mut end_offset = offset;
+		while let Some(ch) = text.char_at(end_offset) {
+			if !is_word_char(ch) {
+				break;
+			}
+			end_offset += 1;
+		}
+		let word: String = (start_offset..end_offset)
+			.filter_map(|i| text.char_at(i))
+			.collect();
+		if !word.chars().all(is_word_char) {
+			return None;
+		}
+		let breaks = self.load_hyphenator(context)?.hyphenate(&word).breaks;
+
+		let last = draw_chars.last()?;
+		let (RenderCell::Char(last_cell) | RenderCell::Link(last_cell, _)) = &last.cell else {
+			return None;
+		};
+		let char_style = text.char_style_at(last.offset, context.custom_color, &context.colors);
+		let measures = self.get_char_measures(
+			pango,
+			'-',
+			&char_style.font_scale,
+			&char_style.font_weight,
+			&char_style.font_family,
+			book.font_family_names(),
+			book.custom_fonts(),
+			context);
+		let breaks: Vec<usize> = breaks.iter()
+			.map(|byte_index| word_start + word[..*byte_index].chars().count())
+			.collect();
+		let rights: Vec<f32> = draw_chars.iter()
+			.map(|dc| dc.rect.max.x)
+			.collect();
+		let index = hyphen_break(&breaks, &rights, measures.size.x, max_left)?;
+		let prev = &draw_chars[index - 1];
+		let hyphen = RenderChar {
+			cell: RenderCell::Char(CharCell {
+				char: '-',
+				font_size: measures.font_size,
+				font_weight: measures.font_weight,
+				font_family: measures.font_family_idx,
+				color: last_cell.color.clone(),
+				background: None,
+				cell_offset: Vec2::ZERO,
+				cell_size: measures.size,
+			}),
+			offset: prev.offset,
+			rect: Rect::new(prev.rect.max.x, prev.rect.min.y, measures.size.x, measures.size.y),
+			has_title: false,
+			synthetic: true,
+		};
+		Some((index, hyphen))
 	}
 
 	/// align chars and calculate line size and space,
 	/// and reset context.line_base
+	/// drop cap char will not be aligned, and not count in line size
 	fn push_line(&self, draw_lines: &mut Vec<RenderLine>,
-		draw_chars: Vec<RenderChar>, text: &Line,
+		draw_chars: Vec<RenderChar>, drop_cap: Option<RenderChar>, text: &Line,
 		line: usize, context: &RenderContext, mut baseline: f32) -> f32
 	{
 		let mut line_size = 0.0;
@@ -49,13 +138,16 @@ impl GuiXiRender
 					if line_size < default_size {
 						line_size = default_size;
 					}
-					line_space = line_size / 2.0
+					line_space = calc_line_space(text, line_size, context)
 				}
 			}
 		}
 		let bottom = baseline + line_size;
 		baseline = baseline + line_size + line_space;
 		let mut render_line = RenderLine::new(line, line_size, line_space);
+		if let Some(drop_cap) = drop_cap {
+			render_line.push(drop_cap);
+		}
 		// align to bottom
 		for mut dc in draw_chars {
 			let rect = &mut dc.rect;
@@ -92,6 +184,8 @@ impl GuiRender for GuiXiRender
 		render_context.max_page_size = render_context.render_rect.height();
 		render_context.leading_space = render_context.default_font_measure.x
 			* render_context.leading_chars as f32;
+		render_context.em_space = render_context.default_font_measure.x;
+		render_context.indent_space = render_context.em_space * 2.0;
 	}
 
 	#[inline]
@@ -118,16 +212,22 @@ impl GuiRender for GuiXiRender
 			return wrapped_empty_lines;
 		}
 		let mut draw_lines = vec![];
-		let mut draw_chars = vec![];
-		let mut break_position = None;
+		let word_ranges = highlight_word_ranges(text, &context.highlight_words);
+		let mut wrap = LineWrap::new();
 
-		let mut left = context.render_rect.min.x;
+		let line_left = context.render_rect.min.x
+			+ context.indent_space * text.indent() as f32;
+		let mut left = line_left;
+		let with_drop_cap = start_offset == 0 && is_drop_cap(text, context);
+		let mut drop_cap = None;
+		let mut drop_cap_right = line_left;
+		let mut drop_cap_bottom = self.baseline;
 		let max_left = context.render_rect.max.x;
 		let view_rect = &context.render_rect;
 		let view_size = view_rect.size();
 		for i in start_offset..end_offset {
 			let char_style = text.char_style_at(i, context.custom_color, &context.colors);
-			let (cell, mut rect, is_blank_char, can_break) = if let Some((path, size)) = self.with_image(&char_style, book, &view_size, context.font_size) {
+			let (cell, rect, is_blank_char, can_break) = if let Some((path, size)) = self.with_image(&char_style, book, &view_size, context.font_size, context.invert_images, context.image_interpolation, context.max_image_scale) {
 				let bottom = self.baseline + size.y;
 				let right = left + size.x;
 				let rect = Rect::from_min_max(
@@ -137,10 +237,10 @@ impl GuiRender for GuiXiRender
 				let link_index = char_style.link.map(|(i, _)| i);
 				(RenderCell::Image(path, link_index), rect, false, true)
 			} else {
-				if i == 0 && with_leading(text) {
-					left += context.leading_space;
+				if i == 0 && !with_drop_cap {
+					left += leading_space(text, context);
 				}
-				let char = text.char_at(i).unwrap();
+				let char = text.render_char_at(i, &char_style.text_transform).unwrap();
 				let measures = self.get_char_measures(
 					pango,
 					char,
@@ -151,10 +251,18 @@ impl GuiRender for GuiXiRender
 					book.custom_fonts(),
 					context);
 
-				let mut rect = Rect::new(left, self.baseline, measures.size.x, measures.size.y);
-				let color = char_style.color.clone();
-				let background = update_for_highlight(line, i, char_style.background.clone(), &context.colors, highlight);
-				let cell_offset = if let Some((range, TextStyle::Border(lines, ..))) = &char_style.border {
+				let mut cell_size = measures.size;
+				cell_size.x += context.char_spacing(char);
+				if char == ZERO_WIDTH_SPACE {
+					// break opportunity only, never visible
+					cell_size.x = 0.0;
+				}
+				let mut rect = Rect::new(left, self.baseline, cell_size.x, cell_size.y);
+				let background = highlight_word_background(&word_ranges, i)
+					.or_else(|| char_style.background.clone());
+				let (color, background) = update_for_highlight(line, i,
+					char_style.color.clone(), background, &context.colors, highlight);
+				let mut cell_offset = if let Some((range, TextStyle::Border(lines, ..))) = &char_style.border {
 					if lines.contains(BorderLines::Left) {
 						if lines.contains(BorderLines::Right) {
 							let draw_width = measures.size.x;
@@ -196,7 +304,8 @@ impl GuiRender for GuiXiRender
 				} else {
 					Vec2::ZERO
 				};
-				let blank_char = char == ' ' || char == '\t';
+				cell_offset.y += vertical_align_shift(&char_style.vertical_align, context);
+				let blank_char = is_blank(char);
 				let cell = CharCell {
 					char: if blank_char { ' ' } else { char },
 					font_size: measures.font_size,
@@ -205,91 +314,90 @@ impl GuiRender for GuiXiRender
 					color,
 					background,
 					cell_offset,
-					cell_size: measures.size,
+					cell_size,
 				};
 				let render_cell = if let Some((link_index, _)) = char_style.link {
 					RenderCell::Link(cell, link_index)
 				} else {
 					RenderCell::Char(cell)
 				};
-				(render_cell, rect, blank_char, blank_char || !char.is_ascii_alphanumeric())
+				let can_break = can_break_at(char, char_style.nowrap);
+				(render_cell, rect, blank_char && !char_style.nowrap, can_break)
 			};
-			let draw_height = rect.height();
 			let draw_width = rect.width();
 
-			if left + draw_width > max_left && !draw_chars.is_empty() {
-				left = context.render_rect.min.x;
-				// for unicode, can_break, or prev break not exists, or breaking content too long
-				if can_break || break_position.is_none()
-					|| draw_chars.len() > break_position.unwrap() + 20
-					|| break_position.unwrap() >= draw_chars.len() {
-					self.baseline = self.push_line(
-						&mut draw_lines,
-						draw_chars,
-						text,
-						line,
-						context,
-						self.baseline);
-					draw_chars = vec![];
-					break_position = None;
-					// for break char, will not print it any more
-					// skip it for line break
-					if is_blank_char {
-						continue;
-					}
-					rect = Rect {
-						min: Pos2::new(left, self.baseline),
-						max: Pos2::new(left + draw_width, draw_height + self.baseline),
-					};
-				} else {
-					let break_draw_chars = if let Some(break_position) = break_position {
-						draw_chars.drain(break_position..).collect()
-					} else {
-						vec![]
-					};
-					self.baseline = self.push_line(
-						&mut draw_lines,
-						draw_chars,
-						text,
-						line,
-						context,
-						self.baseline);
-					draw_chars = break_draw_chars;
-					for draw_char in &mut draw_chars {
-						let w = draw_char.rect.width();
-						let h = draw_char.rect.height();
-						draw_char.rect = Rect {
-							min: Pos2::new(left, self.baseline),
-							max: Pos2::new(left + w, self.baseline + h),
-						};
-						left += w;
-					}
-					rect = Rect {
-						min: Pos2::new(left, self.baseline),
-						max: Pos2::new(left + draw_width, draw_height + self.baseline),
-					}
-				}
+			if i == 0 && with_drop_cap {
+				drop_cap_right = left + draw_width + context.default_font_measure.x / 2.;
+				drop_cap_bottom = self.baseline + rect.height();
+				left = drop_cap_right;
+				drop_cap = Some(RenderChar {
+					cell,
+					offset: i,
+					rect,
+					has_title: char_style.title.is_some(),
+					synthetic: false,
+				});
+				continue;
 			}
-			left += draw_width;
-			draw_chars.push(RenderChar {
+
+			let overflow = left + draw_width > max_left;
+			let break_after = is_blank_char || text.char_at(i) == Some(ZERO_WIDTH_SPACE);
+			let dc = RenderChar {
 				cell,
 				offset: i,
 				rect,
 				has_title: char_style.title.is_some(),
-			});
-			if is_blank_char {
-				break_position = Some(draw_chars.len());
+				synthetic: false,
+			};
+			let finished = wrap.xi_push(dc, overflow, can_break, is_blank_char,
+				break_after, |draw_chars, word_start| if context.hyphenation {
+					self.hyphenate(book, text, draw_chars, word_start, i,
+						max_left, pango, context)
+				} else {
+					None
+				});
+			if let Some(draw_chars) = finished {
+				self.baseline = self.push_line(
+					&mut draw_lines,
+					draw_chars,
+					drop_cap.take(),
+					text,
+					line,
+					context,
+					self.baseline);
+				left = if self.baseline < drop_cap_bottom { drop_cap_right } else { line_left };
+				// chars wrapped to new line, so update positions
+				for draw_char in wrap.placed_mut() {
+					let w = draw_char.rect.width();
+					let h = draw_char.rect.height();
+					draw_char.rect = Rect {
+						min: Pos2::new(left, self.baseline),
+						max: Pos2::new(left + w, self.baseline + h),
+					};
+					left += w;
+				}
+			} else {
+				left += draw_width;
 			}
 		}
+		let draw_chars = wrap.finish();
 		if draw_chars.len() > 0 {
 			self.baseline = self.push_line(
 				&mut draw_lines,
 				draw_chars,
+				drop_cap.take(),
 				text,
 				line,
 				context,
 				self.baseline);
 		}
+		if self.baseline < drop_cap_bottom {
+			// paragraph shorter than the drop cap
+			if let Some(last) = draw_lines.last_mut() {
+				last.add_space(drop_cap_bottom - self.baseline);
+				self.baseline = drop_cap_bottom;
+			}
+		}
 		return draw_lines;
 	}
 
@@ -438,27 +546,21 @@ impl GuiRender for GuiXiRender
 		let min = &rect.min;
 		let left = min.x;
 		let mut top = min.y;
-		let (color, padding) = match &draw_char.cell {
-			RenderCell::Image(_, _) =>
-				if let Some(color) = &decoration.color {
-					(color.clone(), context.default_font_measure.x / 4.0)
-				} else {
-					(context.colors.color.clone(), context.default_font_measure.x / 4.0)
-				},
-			RenderCell::Char(CharCell { cell_size, .. }) =>
-				if let Some(color) = &decoration.color {
-					(color.clone(), cell_size.x / 4.0)
-				} else {
-					(context.colors.color.clone(), cell_size.x / 4.0)
-				}
-			RenderCell::Link(CharCell { cell_size, .. }, _) =>
-				if let Some(color) = &decoration.color {
-					(color.clone(), cell_size.x / 4.0)
-				} else {
-					(context.colors.link.clone(), cell_size.x / 4.0)
-				}
+		let (color, padding, font_size) = match &draw_char.cell {
+			RenderCell::Image(_, _) => (
+				context.colors.color.clone(),
+				context.default_font_measure.x / 4.0,
+				context.font_size as f32),
+			RenderCell::Char(CharCell { cell_size, color, font_size, .. }) =>
+				(color.clone(), cell_size.x / 4.0, *font_size),
+			RenderCell::Link(CharCell { cell_size, font_size, .. }, _) =>
+				(context.colors.link.clone(), cell_size.x / 4.0, *font_size),
 		};
+		// color of the text if no decoration color specified
+		let color = decoration.color.clone().unwrap_or(color);
 		let margin = padding / 2.0;
+		let stroke_width = decoration.thickness.as_ref()
+			.map_or(margin / 2.0, |thickness| thickness.length_px(font_size, context.font_size as f32));
 		let draw_left = if start {
 			left + margin
 		} else {
@@ -499,7 +601,7 @@ impl GuiRender for GuiXiRender
 			style: decoration.style,
 			start_points,
 			length: draw_right - draw_left,
-			stroke_width: margin / 2.0,
+			stroke_width,
 			color,
 		});
 	}
@@ -557,15 +659,32 @@ impl GuiRender for GuiXiRender
 		});
 	}
 
+	fn footnote_separator(&self, render_context: &RenderContext) -> TextDecoration
+	{
+		let rect = &render_context.render_rect;
+		let line_size = self.default_line_size(render_context);
+		TextDecoration::Line {
+			start_points: vec![pos2(rect.min.x, self.baseline + line_size / 2.0)],
+			style: TextDecorationStyle::Solid,
+			length: rect.width() / 4.0,
+			stroke_width: line_size / 16.0,
+			color: render_context.colors.color.clone(),
+		}
+	}
+
 	fn scroll_size(&self, context: &mut RenderContext) -> ScrollSizing
 	{
 		let height = self.baseline - context.render_rect.min.y
 			+ context.default_font_measure.y / 2.;
+		let page_size = context.render_rect.height();
+		let (step_size, page_step) = context.scroll_steps(
+			context.default_font_measure.y, page_size);
 		ScrollSizing {
 			init_scroll_value: 0.,
 			full_size: height,
-			step_size: context.default_font_measure.y,
-			page_size: context.render_rect.height(),
+			step_size,
+			page_size,
+			page_step,
 		}
 	}
 
@@ -602,3 +721,74 @@ impl GuiRender for GuiXiRender
 		mouse_pos.y += scroll_value;
 	}
 }
+
+/// first letter of paragraph much larger than the following text
+fn is_drop_cap(text: &Line, context: &RenderContext) -> bool
+{
+	if !context.drop_cap || text.len() < 2 {
+		return false;
+	}
+	match (text.char_at(0), text.char_at(1)) {
+		(Some(first), Some(next)) if first.is_alphanumeric() && !next.is_whitespace() => {}
+		_ => return false,
+	}
+	let first_style = text.char_style_at(0, context.custom_color, &context.colors);
+	if first_style.image.is_some() {
+		return false;
+	}
+	let next_style = text.char_style_at(1, context.custom_color, &context.colors);
+	first_style.font_scale.scale(1.)
+		>= next_style.font_scale.scale(1.) * DROP_CAP_MIN_SCALE
+}
+
+#[inline]
+fn is_word_char(ch: char) -> bool
+{
+	ch.is_alphabetic() && !is_cjk(ch)
+}
+
+/// only en-us is embedded, others are loaded from
+/// hyphenation/<code>.standard.bincode in config dir
+fn load_dictionary(language: Language) -> Option<Standard>
+{
+	if language == Language::EnglishUS {
+		return Standard::from_embedded(language).ok();
+	}
+	let path = dirs::config_dir()?
+		.join(package_name!())
+		.join("hyphenation")
+		.join(format!("{}.standard.bincode", language.code()));
+	Standard::from_path(language, path).ok()
+}
+
+/// map book language code to hyphenation dictionary
+fn hyphenation_language(lang: &str) -> Option<Language>
+{
+	let lang = lang.to_ascii_lowercase().replace('_', "-");
+	let mut parts = lang.split('-');
+	let primary = parts.next()?;
+	let region = parts.next();
+	let language = match primary {
+		"en" => if region == Some("gb") { Language::EnglishGB } else { Language::EnglishUS },
+		"de" => Language::German1996,
+		"fr" => Language::French,
+		"es" => Language::Spanish,
+		"it" => Language::Italian,
+		"pt" => Language::Portuguese,
+		"nl" => Language::Dutch,
+		"ru" => Language::Russian,
+		"uk" => Language::Ukrainian,
+		"pl" => Language::Polish,
+		"cs" => Language::Czech,
+		"hu" => Language::Hungarian,
+		"sv" => Language::Swedish,
+		"da" => Language::Danish,
+		"nb" | "no" => Language::NorwegianBokmal,
+		"fi" => Language::Finnish,
+		"tr" => Language::Turkish,
+		"el" => Language::GreekMono,
+		"la" => Language::Latin,
+		_ => return None,
+	};
+	Some(language)
+}
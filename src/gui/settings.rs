@@ -2,6 +2,8 @@ use std::cell::RefCell;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+use anyhow::Result;
+use encoding_rs::Encoding;
 use gtk4::{AlertDialog, Align, ApplicationWindow, Button, CheckButton, ColorDialog, ColorDialogButton, DropDown, Entry, EventControllerKey, FileDialog, FileFilter, glib, Label, ListBox, ListBoxRow, Orientation, PolicyType, ScrolledWindow, SelectionMode, Separator, StringList, Window};
 use gtk4::gdk::Key;
 use gtk4::gio::{Cancellable, File, ListStore};
@@ -11,15 +13,36 @@ use gtk4::prelude::{BoxExt, ButtonExt, CheckButtonExt, EditableExt, FileExt, Gtk
 use gtk4::subclass::prelude::ObjectSubclassIsExt;
 use crate::color::Color32;
 
-use crate::config::{Configuration, PathConfig, SidebarPosition};
-use crate::gui::{alert, create_button, DICT_FILE_EXTENSIONS, font, FONT_FILE_EXTENSIONS, GuiContext, IconMap, MAX_FONT_SIZE, MIN_FONT_SIZE, MODIFIER_NONE, set_sidebar_position, sidebar_updated};
+use crate::config::{Configuration, EmptyLaunch, EmptyLines, ImageInterpolation, PathConfig, RawConfig, SidebarPosition};
+use crate::gui::{alert, create_button, DICT_FILE_EXTENSIONS, font, FONT_FILE_EXTENSIONS, GuiContext, IconMap, MODIFIER_NONE, set_sidebar_position, sidebar_updated, view};
 use crate::gui::font::UserFonts;
+use crate::gui::render::highlight_words;
 use crate::I18n;
 
 const SIDEBAR_POSITIONS: [SidebarPosition; 2] = [
 	SidebarPosition::Left,
 	SidebarPosition::Top,
 ];
+const EMPTY_LINES_MODES: [EmptyLines; 3] = [
+	EmptyLines::Keep,
+	EmptyLines::Collapse,
+	EmptyLines::Strip,
+];
+const EMPTY_LAUNCH_MODES: [EmptyLaunch; 4] = [
+	EmptyLaunch::Readme,
+	EmptyLaunch::Library,
+	EmptyLaunch::LastBook,
+	EmptyLaunch::Blank,
+];
+const IMAGE_INTERPOLATIONS: [ImageInterpolation; 3] = [
+	ImageInterpolation::Nearest,
+	ImageInterpolation::Bilinear,
+	ImageInterpolation::Hyper,
+];
+// first entry for book default or global setting
+const LEADING_CHARS_OPTIONS: [Option<u8>; 4] = [None, Some(0), Some(1), Some(2)];
+// first entry for global setting
+const REFLOW_TEXT_OPTIONS: [Option<bool>; 3] = [None, Some(true), Some(false)];
 
 pub(super) struct Settings {
 	gcs: Rc<RefCell<Vec<GuiContext>>>,
@@ -37,10 +60,129 @@ impl Settings {
 	{
 		let gcs = self.gcs.clone();
 		let gc2 = gc.clone();
-		show(&gc.cfg, &gc.window, &gc.i18n, &gc.icons, move |params, new_fonts| {
+		let (book_leading_chars, book_reflow_text, book_language, metadata_language,
+			book_encoding) = {
+			let controller = gc.ctrl();
+			(controller.reading.leading_chars, controller.reading.reflow_text,
+				controller.reading.language.clone(),
+				controller.book.language().map(|lang| lang.to_owned()),
+				// hide the encoding setting for books ignoring it
+				if controller.book.encoding_overridable() {
+					Some(controller.reading.encoding.clone())
+				} else {
+					None
+				})
+		};
+		let gc3 = gc.clone();
+		let preview_empty_lines = move |empty_lines| {
+			super::apply(&gc3, |controller, render_context| {
+				if render_context.empty_lines != empty_lines {
+					render_context.empty_lines = empty_lines;
+					controller.redraw(render_context);
+				}
+			});
+		};
+		show(&gc.cfg, &gc.window, &gc.i18n, &gc.icons, book_leading_chars,
+			book_reflow_text, book_language, metadata_language, book_encoding,
+			preview_empty_lines, move |params, new_fonts| {
 			apply_settings(&gcs, params, new_fonts, &gc2)
 		});
 	}
+
+	/// read config file again, and apply it to all windows like settings dialog does
+	pub fn reload_config(&self, gc: &GuiContext) -> Result<()>
+	{
+		let raw_config = gc.cfg().read_file()?;
+		let new_fonts = if paths_modified(&gc.cfg().gui.fonts, &raw_config.gui.fonts) {
+			Some(font::user_fonts(&raw_config.gui.fonts)?)
+		} else {
+			None
+		};
+		let (book_leading_chars, book_reflow_text, book_language, book_encoding) = {
+			let controller = gc.ctrl();
+			(controller.reading.leading_chars, controller.reading.reflow_text,
+				controller.reading.language.clone(), controller.reading.encoding.clone())
+		};
+		let dark_theme = gc.cfg().dark_theme;
+		let params = config_params(&raw_config, dark_theme, book_leading_chars,
+			book_reflow_text, book_language, book_encoding);
+		apply_settings(&self.gcs, params, new_fonts, gc);
+
+		// options not in settings dialog
+		let content_options = gc.cfg().content_options();
+		gc.cfg_mut().reload(raw_config);
+		let new_content_options = gc.cfg().content_options();
+		let mut err = None;
+		if new_content_options != content_options {
+			// one failed window won't stop applying to others
+			for gc in self.gcs.borrow().iter() {
+				let mut render_context = gc.ctx_mut();
+				if let Err(e) = gc.ctrl_mut().set_content_options(new_content_options.clone(), &mut render_context) {
+					err.get_or_insert(e);
+				}
+			}
+		}
+		if let Some(err) = err {
+			Err(err)
+		} else {
+			Ok(())
+		}
+	}
+}
+
+fn config_params(raw_config: &RawConfig, dark_theme: bool,
+	book_leading_chars: Option<u8>, book_reflow_text: Option<bool>,
+	book_language: Option<String>, book_encoding: Option<String>) -> SettingsParam
+{
+	let gui = &raw_config.gui;
+	let colors = gui.curr_colors(dark_theme);
+	SettingsParam {
+		render_han: raw_config.render_han,
+		locale: &gui.lang,
+		fonts: gui.fonts.clone(),
+		dictionaries: gui.dictionaries.clone(),
+		cache_dict: gui.cache_dict,
+		ignore_font_weight: gui.ignore_font_weight,
+		ignore_book_fonts: gui.ignore_book_fonts,
+		empty_lines: gui.empty_lines,
+		scroll_for_page: gui.scroll_for_page,
+		click_zones: gui.click_zones,
+		smooth_theme: gui.smooth_theme,
+		drop_cap: gui.drop_cap,
+		hyphenation: gui.hyphenation,
+		show_page_numbers: raw_config.show_page_numbers,
+		strip_invisible_chars: raw_config.strip_invisible_chars,
+		page_footnotes: gui.page_footnotes,
+		reflow_text: raw_config.reflow_text,
+		book_reflow_text,
+		always_open_external_link: gui.always_open_external_link,
+		external_image_viewer: gui.external_image_viewer,
+		on_empty_launch: gui.on_empty_launch,
+		leading_chars: gui.leading_chars,
+		book_leading_chars,
+		book_language,
+		book_encoding,
+		chapter_number_by_toc: raw_config.chapter_number_by_toc,
+		running_header: gui.running_header,
+		font_family: gui.font_family.clone(),
+		highlight_words: gui.highlight_words.clone(),
+		dyslexia_mode: gui.dyslexia_mode,
+		focus_mode: gui.focus_mode,
+		invert_images: gui.invert_images,
+		image_interpolation: gui.image_interpolation,
+		upscale_images: gui.upscale_images,
+		default_font_size: gui.default_font_size,
+		sidebar_position: &gui.sidebar_position,
+		select_by_dictionary: gui.select_by_dictionary,
+		color_color: colors.color.clone(),
+		color_background: colors.background.clone(),
+		color_highlight: colors.highlight.clone(),
+		color_highlight_background: colors.highlight_background.clone(),
+		color_link: colors.link.clone(),
+		color_matched: colors.matched_color.clone(),
+		color_matched_background: colors.matched_background.clone(),
+		color_image_background: colors.image_background.clone(),
+	}
 }
 
 struct SettingsParam<'a> {
@@ -50,8 +192,36 @@ struct SettingsParam<'a> {
 	dictionaries: Vec<PathConfig>,
 	cache_dict: bool,
 	ignore_font_weight: bool,
-	strip_empty_lines: bool,
+	ignore_book_fonts: bool,
+	empty_lines: EmptyLines,
 	scroll_for_page: bool,
+	click_zones: bool,
+	smooth_theme: bool,
+	drop_cap: bool,
+	hyphenation: bool,
+	show_page_numbers: bool,
+	strip_invisible_chars: bool,
+	page_footnotes: bool,
+	reflow_text: bool,
+	book_reflow_text: Option<bool>,
+	always_open_external_link: bool,
+	external_image_viewer: bool,
+	on_empty_launch: EmptyLaunch,
+	leading_chars: Option<u8>,
+	book_leading_chars: Option<u8>,
+	// language override of current book, None for the book's metadata
+	book_language: Option<String>,
+	// text encoding of current book, None for detecting
+	book_encoding: Option<String>,
+	chapter_number_by_toc: bool,
+	running_header: bool,
+	font_family: Option<String>,
+	highlight_words: Vec<String>,
+	dyslexia_mode: bool,
+	focus_mode: bool,
+	invert_images: bool,
+	image_interpolation: ImageInterpolation,
+	upscale_images: bool,
 	default_font_size: u8,
 	sidebar_position: &'a SidebarPosition,
 	select_by_dictionary: bool,
@@ -62,6 +232,7 @@ struct SettingsParam<'a> {
 	color_link: Color32,
 	color_matched: Color32,
 	color_matched_background: Color32,
+	color_image_background: Option<Color32>,
 }
 
 #[inline]
@@ -93,9 +264,39 @@ fn append_color_btn(title: &str, color: Color32, color_dialog: &ColorDialog,
 	btn
 }
 
-fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
-	i18n: &Rc<I18n>, icons: &Rc<IconMap>, apply: F) -> Window
-	where F: Fn(SettingsParam, Option<Option<UserFonts>>) + 'static
+fn append_leading_chars_dropdown(title: &str, default_title: &str,
+	leading_chars: Option<u8>, settings: &gtk4::Box, i18n: &I18n) -> DropDown
+{
+	let list = StringList::default();
+	for entry in LEADING_CHARS_OPTIONS {
+		match entry {
+			None => list.append(&i18n.msg(default_title)),
+			Some(0) => list.append(&i18n.msg("leading-chars-off")),
+			Some(chars) => list.append(&chars.to_string()),
+		}
+	}
+	let selected = LEADING_CHARS_OPTIONS.iter()
+		.position(|entry| *entry == leading_chars)
+		.unwrap_or(0);
+	let dropdown = DropDown::builder()
+		.model(&list)
+		.selected(selected as u32)
+		.build();
+	let lc_box = gtk4::Box::new(Orientation::Horizontal, 10);
+	lc_box.append(&title_label(&i18n.msg(title)));
+	lc_box.append(&dropdown);
+	settings.append(&lc_box);
+	dropdown
+}
+
+fn show<P, F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
+	i18n: &Rc<I18n>, icons: &Rc<IconMap>, book_leading_chars: Option<u8>,
+	book_reflow_text: Option<bool>, book_language: Option<String>,
+	metadata_language: Option<String>, book_encoding: Option<Option<String>>,
+	preview_empty_lines: P, apply: F) -> Window
+	where
+		P: Fn(EmptyLines) + 'static,
+		F: Fn(SettingsParam, Option<Option<UserFonts>>) + 'static
 {
 	let dialog = Window::builder()
 		.title(i18n.msg("settings-dialog-title"))
@@ -120,6 +321,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 	dialog.set_child(Some(&dialog_box));
 
 	let configuration = cfg.borrow();
+	let (min_font_size, max_font_size) = configuration.gui.font_size_range();
 
 	let locale_dropdown = {
 		let locale_box = gtk4::Box::new(Orientation::Horizontal, 0);
@@ -165,14 +367,162 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		&i18n.msg("ignore-font-weight"),
 		configuration.gui.ignore_font_weight,
 		&settings);
-	let strip_empty_lines_cb = append_checkbox(
-		&i18n.msg("strip-empty-lines"),
-		configuration.gui.strip_empty_lines,
+	let ignore_book_fonts_cb = append_checkbox(
+		&i18n.msg("ignore-book-fonts"),
+		configuration.gui.ignore_book_fonts,
 		&settings);
+	let empty_lines_dropdown = {
+		let empty_lines_list = StringList::default();
+		for mode in EMPTY_LINES_MODES {
+			empty_lines_list.append(&i18n.msg(mode.i18n_key()));
+		}
+		let current = EMPTY_LINES_MODES.iter()
+			.position(|mode| *mode == configuration.gui.empty_lines)
+			.unwrap_or(0);
+		let dropdown = DropDown::builder()
+			.model(&empty_lines_list)
+			.selected(current as u32)
+			.build();
+		let el_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		el_box.append(&title_label(&i18n.msg("empty-lines")));
+		el_box.append(&dropdown);
+		settings.append(&el_box);
+		dropdown
+	};
+	// redraw immediately for tuning, restored when dialog closed
+	let preview_empty_lines = Rc::new(preview_empty_lines);
+	{
+		let preview_empty_lines = preview_empty_lines.clone();
+		empty_lines_dropdown.connect_selected_notify(move |dropdown| {
+			preview_empty_lines(EMPTY_LINES_MODES[dropdown.selected() as usize]);
+		});
+	}
 	let scroll_for_page_cb = append_checkbox(
 		&i18n.msg("scroll-for-page"),
 		configuration.gui.scroll_for_page,
 		&settings);
+	let click_zones_cb = append_checkbox(
+		&i18n.msg("click-zones"),
+		configuration.gui.click_zones,
+		&settings);
+	let smooth_theme_cb = append_checkbox(
+		&i18n.msg("smooth-theme"),
+		configuration.gui.smooth_theme,
+		&settings);
+	let drop_cap_cb = append_checkbox(
+		&i18n.msg("drop-cap"),
+		configuration.gui.drop_cap,
+		&settings);
+	let hyphenation_cb = append_checkbox(
+		&i18n.msg("hyphenation"),
+		configuration.gui.hyphenation,
+		&settings);
+	let show_page_numbers_cb = append_checkbox(
+		&i18n.msg("show-page-numbers"),
+		configuration.show_page_numbers,
+		&settings);
+	let strip_invisible_chars_cb = append_checkbox(
+		&i18n.msg("strip-invisible-chars"),
+		configuration.strip_invisible_chars,
+		&settings);
+	let page_footnotes_cb = append_checkbox(
+		&i18n.msg("page-footnotes"),
+		configuration.gui.page_footnotes,
+		&settings);
+	let reflow_text_cb = append_checkbox(
+		&i18n.msg("reflow-text"),
+		configuration.reflow_text,
+		&settings);
+	let book_reflow_text_dropdown = {
+		let list = StringList::default();
+		for entry in REFLOW_TEXT_OPTIONS {
+			match entry {
+				None => list.append(&i18n.msg("reflow-text-global")),
+				Some(true) => list.append(&i18n.msg("reflow-text-on")),
+				Some(false) => list.append(&i18n.msg("reflow-text-off")),
+			}
+		}
+		let selected = REFLOW_TEXT_OPTIONS.iter()
+			.position(|entry| *entry == book_reflow_text)
+			.unwrap_or(0);
+		let dropdown = DropDown::builder()
+			.model(&list)
+			.selected(selected as u32)
+			.build();
+		let rt_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		rt_box.append(&title_label(&i18n.msg("book-reflow-text")));
+		rt_box.append(&dropdown);
+		settings.append(&rt_box);
+		dropdown
+	};
+	let always_open_external_link_cb = append_checkbox(
+		&i18n.msg("always-open-external-link"),
+		configuration.gui.always_open_external_link,
+		&settings);
+	let external_image_viewer_cb = append_checkbox(
+		&i18n.msg("external-image-viewer"),
+		configuration.gui.external_image_viewer,
+		&settings);
+	let empty_launch_dropdown = {
+		let empty_launch_list = StringList::default();
+		for mode in EMPTY_LAUNCH_MODES {
+			empty_launch_list.append(&i18n.msg(mode.i18n_key()));
+		}
+		let current = EMPTY_LAUNCH_MODES.iter()
+			.position(|mode| *mode == configuration.gui.on_empty_launch)
+			.unwrap_or(0);
+		let dropdown = DropDown::builder()
+			.model(&empty_launch_list)
+			.selected(current as u32)
+			.build();
+		let el_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		el_box.append(&title_label(&i18n.msg("empty-launch")));
+		el_box.append(&dropdown);
+		settings.append(&el_box);
+		dropdown
+	};
+	let dyslexia_mode_cb = append_checkbox(
+		&i18n.msg("dyslexia-mode"),
+		configuration.gui.dyslexia_mode,
+		&settings);
+	let focus_mode_cb = append_checkbox(
+		&i18n.msg("focus-mode"),
+		configuration.gui.focus_mode,
+		&settings);
+	let invert_images_cb = append_checkbox(
+		&i18n.msg("invert-images"),
+		configuration.gui.invert_images,
+		&settings);
+	let upscale_images_cb = append_checkbox(
+		&i18n.msg("upscale-images"),
+		configuration.gui.upscale_images,
+		&settings);
+	let image_interpolation_dropdown = {
+		let interpolation_list = StringList::default();
+		for interpolation in IMAGE_INTERPOLATIONS {
+			interpolation_list.append(&i18n.msg(interpolation.i18n_key()));
+		}
+		let current = IMAGE_INTERPOLATIONS.iter()
+			.position(|interpolation| *interpolation == configuration.gui.image_interpolation)
+			.unwrap_or(0);
+		let dropdown = DropDown::builder()
+			.model(&interpolation_list)
+			.selected(current as u32)
+			.build();
+		let ii_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		ii_box.append(&title_label(&i18n.msg("image-interpolation")));
+		ii_box.append(&dropdown);
+		settings.append(&ii_box);
+		dropdown
+	};
+	let chapter_number_by_toc_cb = append_checkbox(
+		&i18n.msg("chapter-number-by-toc"),
+		configuration.chapter_number_by_toc,
+		&settings);
+	let running_header_cb = append_checkbox(
+		&i18n.msg("running-header"),
+		configuration.gui.running_header,
+		&settings);
 
 	let sidebar_position_dropdown = {
 		let sidebar_position_box = gtk4::Box::new(Orientation::Horizontal, 0);
@@ -197,6 +547,47 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		sidebar_position_dropdown
 	};
 
+	let leading_chars_dropdown = append_leading_chars_dropdown(
+		"leading-chars",
+		"leading-chars-book-default",
+		configuration.gui.leading_chars,
+		&settings,
+		i18n);
+	let book_leading_chars_dropdown = append_leading_chars_dropdown(
+		"book-leading-chars",
+		"leading-chars-global",
+		book_leading_chars,
+		&settings,
+		i18n);
+	let book_language_entry = {
+		let placeholder = match &metadata_language {
+			Some(lang) => i18n.args_msg("book-language-detected", vec![("lang", lang.as_str())]),
+			None => i18n.msg("book-language-unknown").to_string(),
+		};
+		let entry = Entry::builder()
+			.text(book_language.as_deref().unwrap_or(""))
+			.placeholder_text(placeholder)
+			.hexpand(true)
+			.build();
+		let bl_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		bl_box.append(&title_label(&i18n.msg("book-language")));
+		bl_box.append(&entry);
+		settings.append(&bl_box);
+		entry
+	};
+	let book_encoding_entry = book_encoding.as_ref().map(|book_encoding| {
+		let entry = Entry::builder()
+			.text(book_encoding.as_deref().unwrap_or(""))
+			.placeholder_text(i18n.msg("book-encoding-detect"))
+			.hexpand(true)
+			.build();
+		let be_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		be_box.append(&title_label(&i18n.msg("book-encoding")));
+		be_box.append(&entry);
+		settings.append(&be_box);
+		entry
+	});
+
 	let font_size_entry = {
 		let entry = Entry::builder()
 			.text(&format!("{}", configuration.gui.default_font_size))
@@ -206,13 +597,39 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		fs_box.append(&title_label(&i18n.msg("default-font-size")));
 		fs_box.append(&entry);
 		fs_box.append(&Label::builder()
-			.label(&format!("({} - {})", MIN_FONT_SIZE, MAX_FONT_SIZE))
+			.label(&format!("({} - {})", min_font_size, max_font_size))
 			.build());
 
 		settings.append(&fs_box);
 		entry
 	};
 
+	let font_family_entry = {
+		let entry = Entry::builder()
+			.text(configuration.gui.font_family.as_deref().unwrap_or(""))
+			.placeholder_text(i18n.msg("font-family-placeholder"))
+			.hexpand(true)
+			.build();
+		let ff_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		ff_box.append(&title_label(&i18n.msg("font-family")));
+		ff_box.append(&entry);
+		settings.append(&ff_box);
+		entry
+	};
+
+	let highlight_words_entry = {
+		let entry = Entry::builder()
+			.text(configuration.gui.highlight_words.join(", "))
+			.placeholder_text(i18n.msg("highlight-words-placeholder"))
+			.hexpand(true)
+			.build();
+		let hw_box = gtk4::Box::new(Orientation::Horizontal, 10);
+		hw_box.append(&title_label(&i18n.msg("highlight-words")));
+		hw_box.append(&entry);
+		settings.append(&hw_box);
+		entry
+	};
+
 	let colors = configuration.gui.
 		curr_colors(configuration.dark_theme);
 	let color_dialog = ColorDialog::new();
@@ -258,6 +675,16 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 		&color_dialog,
 		&settings,
 		i18n);
+	let image_background_cb = append_checkbox(
+		&i18n.msg("image-background"),
+		colors.image_background.is_some(),
+		&settings);
+	let color_image_background_btn = append_color_btn(
+		"color-image-background",
+		colors.image_background.clone().unwrap_or(Color32::WHITE),
+		&color_dialog,
+		&settings,
+		i18n);
 
 	let font_list = {
 		let title = i18n.msg("font-files");
@@ -393,7 +820,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				.to_string()
 				.trim()
 				.parse() {
-				if default_font_size < MIN_FONT_SIZE || default_font_size > MAX_FONT_SIZE {
+				if default_font_size < min_font_size || default_font_size > max_font_size {
 					alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-default-font-size"), &dialog);
 					return;
 				}
@@ -411,8 +838,60 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 					.locale
 			};
 			let ignore_font_weight = ignore_font_weight_cb.is_active();
-			let strip_empty_lines = strip_empty_lines_cb.is_active();
+			let ignore_book_fonts = ignore_book_fonts_cb.is_active();
+			let empty_lines = EMPTY_LINES_MODES[empty_lines_dropdown.selected() as usize];
 			let scroll_for_page = scroll_for_page_cb.is_active();
+			let click_zones = click_zones_cb.is_active();
+			let smooth_theme = smooth_theme_cb.is_active();
+			let drop_cap = drop_cap_cb.is_active();
+			let hyphenation = hyphenation_cb.is_active();
+			let show_page_numbers = show_page_numbers_cb.is_active();
+			let strip_invisible_chars = strip_invisible_chars_cb.is_active();
+			let page_footnotes = page_footnotes_cb.is_active();
+			let reflow_text = reflow_text_cb.is_active();
+			let book_reflow_text = REFLOW_TEXT_OPTIONS[book_reflow_text_dropdown.selected() as usize];
+			let always_open_external_link = always_open_external_link_cb.is_active();
+			let external_image_viewer = external_image_viewer_cb.is_active();
+			let on_empty_launch = EMPTY_LAUNCH_MODES[empty_launch_dropdown.selected() as usize];
+			let leading_chars = LEADING_CHARS_OPTIONS[leading_chars_dropdown.selected() as usize];
+			let book_leading_chars = LEADING_CHARS_OPTIONS[book_leading_chars_dropdown.selected() as usize];
+			let book_language = {
+				let text = book_language_entry.text();
+				let text = text.trim();
+				if text.is_empty() { None } else { Some(text.to_owned()) }
+			};
+			let book_encoding = if let Some(entry) = &book_encoding_entry {
+				let text = entry.text();
+				let text = text.trim();
+				if text.is_empty() {
+					None
+				} else if Encoding::for_label(text.as_bytes()).is_some() {
+					Some(text.to_owned())
+				} else {
+					alert(&i18n.msg("alert-error-title"), &i18n.msg("invalid-encoding"), &dialog);
+					return;
+				}
+			} else {
+				book_encoding.clone().flatten()
+			};
+			let chapter_number_by_toc = chapter_number_by_toc_cb.is_active();
+			let running_header = running_header_cb.is_active();
+			let dyslexia_mode = dyslexia_mode_cb.is_active();
+			let focus_mode = focus_mode_cb.is_active();
+			let invert_images = invert_images_cb.is_active();
+			let image_interpolation = IMAGE_INTERPOLATIONS[image_interpolation_dropdown.selected() as usize];
+			let upscale_images = upscale_images_cb.is_active();
+			let font_family = {
+				let text = font_family_entry.text();
+				let text = text.trim();
+				if text.is_empty() { None } else { Some(text.to_owned()) }
+			};
+			let highlight_words = highlight_words_entry.text()
+				.split(',')
+				.map(|word| word.trim())
+				.filter(|word| !word.is_empty())
+				.map(|word| word.to_owned())
+				.collect();
 			let fonts = collect_path_list(&font_list, |path|
 				path.exists() && path.is_file());
 			let dictionaries = collect_path_list(&dict_list, |path|
@@ -449,6 +928,11 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 			let color_link = Color32::from(color_link_btn.rgba());
 			let color_matched = Color32::from(color_matched_btn.rgba());
 			let color_matched_background = Color32::from(color_matched_background_btn.rgba());
+			let color_image_background = if image_background_cb.is_active() {
+				Some(Color32::from(color_image_background_btn.rgba()))
+			} else {
+				None
+			};
 			let params = SettingsParam {
 				render_han,
 				locale,
@@ -456,8 +940,34 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				dictionaries,
 				cache_dict,
 				ignore_font_weight,
-				strip_empty_lines,
+				ignore_book_fonts,
+				empty_lines,
 				scroll_for_page,
+				click_zones,
+				smooth_theme,
+				drop_cap,
+				hyphenation,
+				show_page_numbers,
+				strip_invisible_chars,
+				page_footnotes,
+				reflow_text,
+				book_reflow_text,
+				always_open_external_link,
+				external_image_viewer,
+				on_empty_launch,
+				leading_chars,
+				book_leading_chars,
+				book_language,
+				book_encoding,
+				chapter_number_by_toc,
+				running_header,
+				font_family,
+				highlight_words,
+				dyslexia_mode,
+				focus_mode,
+				invert_images,
+				image_interpolation,
+				upscale_images,
 				default_font_size,
 				sidebar_position,
 				select_by_dictionary,
@@ -468,6 +978,7 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 				color_link,
 				color_matched,
 				color_matched_background,
+				color_image_background,
 			};
 			apply(params, new_fonts);
 			dialog.close();
@@ -513,6 +1024,14 @@ fn show<F>(cfg: &Rc<RefCell<Configuration>>, window: &ApplicationWindow,
 	}
 	dialog.add_controller(key_event);
 
+	{
+		let cfg = cfg.clone();
+		dialog.connect_close_request(move |_| {
+			// settings applied already if confirmed
+			preview_empty_lines(cfg.borrow().gui.empty_lines);
+			glib::Propagation::Proceed
+		});
+	}
 	dialog.present();
 	dialog
 }
@@ -604,8 +1123,10 @@ fn create_list_row(obj: &Object, i18n: &I18n, icons: &IconMap, list: &ListStore)
 		.expect("Needs to be PathConfigEntry");
 	let config = entry.imp().path.borrow();
 	let remove_btn = create_button("remove.svg", Some(&i18n.msg("remove-title")), icons, true);
+	let move_up_btn = create_button("move_up.svg", Some(&i18n.msg("move-up-title")), icons, true);
 	let entry_box = gtk4::Box::new(Orientation::Horizontal, 10);
 	entry_box.append(&remove_btn);
+	entry_box.append(&move_up_btn);
 	let checkbox = append_checkbox(&path_str(&config.path), config.enabled, &entry_box);
 	let row = ListBoxRow::new();
 	row.set_child(Some(&entry_box));
@@ -626,6 +1147,19 @@ fn create_list_row(obj: &Object, i18n: &I18n, icons: &IconMap, list: &ListStore)
 			}
 		});
 	}
+	{
+		// order of list is the order of loading and lookup
+		let row = row.clone();
+		let list = list.clone();
+		let obj = obj.clone();
+		move_up_btn.connect_clicked(move |_| {
+			let idx = row.index();
+			if idx > 0 {
+				list.remove(idx as u32);
+				list.insert(idx as u32 - 1, &obj);
+			}
+		});
+	}
 	row
 }
 
@@ -680,17 +1214,99 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 	};
 
 	configuration.gui.scroll_for_page = params.scroll_for_page;
+	configuration.gui.click_zones = params.click_zones;
+	configuration.gui.smooth_theme = params.smooth_theme;
 	configuration.gui.default_font_size = params.default_font_size;
 	configuration.gui.select_by_dictionary = params.select_by_dictionary;
+	configuration.gui.always_open_external_link = params.always_open_external_link;
+	configuration.gui.external_image_viewer = params.external_image_viewer;
+	configuration.gui.on_empty_launch = params.on_empty_launch;
+	configuration.chapter_number_by_toc = params.chapter_number_by_toc;
 
-	if configuration.gui.ignore_font_weight != params.ignore_font_weight {
+	let font_options_changed = if configuration.gui.ignore_font_weight != params.ignore_font_weight
+		|| configuration.gui.ignore_book_fonts != params.ignore_book_fonts {
 		configuration.gui.ignore_font_weight = params.ignore_font_weight;
+		configuration.gui.ignore_book_fonts = params.ignore_book_fonts;
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	if configuration.gui.empty_lines != params.empty_lines {
+		configuration.gui.empty_lines = params.empty_lines;
+		redraw = true;
+	};
+	if configuration.gui.drop_cap != params.drop_cap {
+		configuration.gui.drop_cap = params.drop_cap;
 		redraw = true;
 	};
-	if configuration.gui.strip_empty_lines != params.strip_empty_lines {
-		configuration.gui.strip_empty_lines = params.strip_empty_lines;
+	if configuration.gui.hyphenation != params.hyphenation {
+		configuration.gui.hyphenation = params.hyphenation;
+		redraw = true;
+	};
+	let orig_font_family = configuration.gui.render_font_family();
+	configuration.gui.font_family = params.font_family.clone();
+	let dyslexia_mode_changed = if configuration.gui.dyslexia_mode != params.dyslexia_mode {
+		configuration.gui.dyslexia_mode = params.dyslexia_mode;
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	if configuration.gui.focus_mode != params.focus_mode {
+		configuration.gui.focus_mode = params.focus_mode;
+		redraw = true;
+	}
+	let font_family_changed = if configuration.gui.render_font_family() != orig_font_family {
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	if configuration.gui.invert_images != params.invert_images {
+		configuration.gui.invert_images = params.invert_images;
+		redraw = true;
+	};
+	if configuration.gui.image_interpolation != params.image_interpolation {
+		configuration.gui.image_interpolation = params.image_interpolation;
+		redraw = true;
+	};
+	if configuration.gui.upscale_images != params.upscale_images {
+		configuration.gui.upscale_images = params.upscale_images;
+		redraw = true;
+	};
+	let highlight_words_changed = if configuration.gui.highlight_words != params.highlight_words {
+		configuration.gui.highlight_words = params.highlight_words;
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	if configuration.gui.running_header != params.running_header {
+		configuration.gui.running_header = params.running_header;
+		for gc in gui_contexts.iter() {
+			gc.running_header.set_visible(params.running_header);
+		}
 		redraw = true;
 	};
+	let content_options_changed = if configuration.show_page_numbers != params.show_page_numbers
+		|| configuration.strip_invisible_chars != params.strip_invisible_chars
+		|| configuration.gui.page_footnotes != params.page_footnotes
+		|| configuration.reflow_text != params.reflow_text {
+		configuration.show_page_numbers = params.show_page_numbers;
+		configuration.strip_invisible_chars = params.strip_invisible_chars;
+		configuration.gui.page_footnotes = params.page_footnotes;
+		configuration.reflow_text = params.reflow_text;
+		true
+	} else {
+		false
+	};
+	let book_reflow_text_changed = if gc.ctrl().reading.reflow_text != params.book_reflow_text {
+		gc.ctrl_mut().reading.reflow_text = params.book_reflow_text;
+		true
+	} else {
+		false
+	};
 	if configuration.gui.sidebar_position != *params.sidebar_position {
 		configuration.gui.sidebar_position = params.sidebar_position.clone();
 		set_sidebar_position(gc, &configuration.gui.sidebar_position);
@@ -701,12 +1317,43 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 		}
 	}
 
+	let leading_chars_changed = if configuration.gui.leading_chars != params.leading_chars {
+		configuration.gui.leading_chars = params.leading_chars;
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	let book_leading_chars_changed = if gc.ctrl().reading.leading_chars != params.book_leading_chars {
+		gc.ctrl_mut().reading.leading_chars = params.book_leading_chars;
+		redraw = true;
+		true
+	} else {
+		false
+	};
+	// decoded when loading, so reload the book
+	let book_encoding_changed = if gc.ctrl().reading.encoding != params.book_encoding {
+		gc.ctrl_mut().reading.encoding = params.book_encoding.clone();
+		true
+	} else {
+		false
+	};
+	let book_language_changed = if gc.ctrl().reading.language != params.book_language {
+		gc.ctrl_mut().reading.language = params.book_language.clone();
+		redraw = true;
+		true
+	} else {
+		false
+	};
+
 	if new_fonts.is_some() {
 		redraw = true;
 	}
 
 	let colors_changed = apply_colors(&mut configuration, &params, gc);
 	if colors_changed {
+		// css provider shared by all windows
+		view::update_css(&gc.css_provider, configuration.curr_theme());
 		redraw = true;
 	}
 
@@ -726,6 +1373,8 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 		}
 	}
 
+	// per book setting only for book in this window
+	let this_window = gc.window.clone();
 	if redraw {
 		let (set_fonts, fonts_data) = if let Some(new_fonts) = new_fonts {
 			let fonts_data = Rc::new(new_fonts);
@@ -738,7 +1387,8 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 			let mut render_context = gc.ctx_mut();
 			let mut controller = gc.ctrl_mut();
 			if reload_render {
-				controller.render.reload_render(configuration.render_han, &mut render_context);
+				let render_han = controller.reading.render_han(configuration.render_han);
+				controller.render.reload_render(render_han, &mut render_context);
 			}
 			if set_fonts {
 				gc.dm_mut().set_fonts(fonts_data.clone());
@@ -747,11 +1397,56 @@ fn apply_settings(gcs: &Rc<RefCell<Vec<GuiContext>>>, params: SettingsParam,
 			if colors_changed {
 				render_context.colors = configuration.gui.curr_colors(configuration.dark_theme).clone();
 			}
-			render_context.ignore_font_weight = params.ignore_font_weight;
-			render_context.strip_empty_lines = params.strip_empty_lines;
+			render_context.invert_images = configuration.gui.invert_images
+				&& configuration.dark_theme;
+			render_context.image_interpolation = configuration.gui.image_interpolation;
+			render_context.max_image_scale = configuration.gui.image_scale_limit();
+			if dyslexia_mode_changed {
+				render_context.set_dyslexia_mode(params.dyslexia_mode);
+			}
+			render_context.focus_mode = configuration.gui.focus_mode;
+			if font_family_changed {
+				controller.render.set_font_family(configuration.gui.render_font_family(),
+					controller.book.custom_fonts(), &mut render_context);
+			}
+			if highlight_words_changed {
+				render_context.highlight_words = highlight_words(&configuration.gui.highlight_words);
+			}
+			if font_options_changed {
+				controller.render.set_font_options(params.ignore_font_weight,
+					params.ignore_book_fonts, controller.book.custom_fonts(),
+					&mut render_context);
+			}
+			render_context.empty_lines = params.empty_lines;
+			render_context.drop_cap = params.drop_cap;
+			render_context.hyphenation = params.hyphenation;
+			if leading_chars_changed
+				|| ((book_leading_chars_changed || book_language_changed) && gc.window == this_window) {
+				render_context.default_leading_chars = configuration.gui.leading_chars;
+				controller.book_loaded(&mut render_context);
+			}
 			controller.redraw(&mut render_context);
 		}
 	}
+	if content_options_changed || book_reflow_text_changed || book_language_changed {
+		let content_options = configuration.content_options();
+		for gc in gui_contexts.iter() {
+			if !content_options_changed && gc.window != this_window {
+				continue;
+			}
+			let mut render_context = gc.ctx_mut();
+			let result = gc.ctrl_mut()
+				.set_content_options(content_options.clone(), &mut render_context);
+			if let Err(err) = result {
+				gc.error(&err.to_string());
+			}
+		}
+	}
+	if book_encoding_changed {
+		// reloading the book reads the configuration
+		drop(configuration);
+		gc.reload_book();
+	}
 }
 
 #[inline]
@@ -781,6 +1476,10 @@ fn apply_colors(configuration: &mut Configuration, params: &SettingsParam,
 		colors.link = params.color_link.clone();
 		redraw = true;
 	};
+	if colors.image_background != params.color_image_background {
+		colors.image_background = params.color_image_background.clone();
+		redraw = true;
+	};
 	let mut matched_color_changed = false;
 	if colors.matched_color != params.color_matched {
 		colors.matched_color = params.color_matched.clone();
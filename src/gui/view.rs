@@ -1,7 +1,8 @@
+use std::cell::Cell;
 use std::rc::Rc;
 
 use glib::Object;
-use gtk4::{CssProvider, EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, gdk, GestureClick, GestureDrag, glib};
+use gtk4::{CssProvider, EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, gdk, GestureClick, GestureDrag, GestureZoom, glib, Label};
 use gtk4::gdk::{Display, ModifierType};
 use gtk4::pango::Layout as PangoContext;
 use gtk4::prelude::{EventControllerExt, GestureDragExt, GestureExt, ObjectExt, WidgetExt};
@@ -16,9 +17,13 @@ use crate::controller::{HighlightInfo, Render};
 use crate::gui::font::UserFonts;
 use crate::gui::HtmlFonts;
 use crate::gui::math::{Pos2, pos2};
-use crate::gui::render::RenderContext;
+use crate::gui::render::{RenderCell, RenderContext};
 
 const MIN_TEXT_SELECT_DISTANCE: f32 = 4.0;
+// scale change of pinch gesture for one zoom step
+const ZOOM_STEP_SCALE: f64 = 1.15;
+// chars for labels of link hint mode
+const LINK_HINT_CHARS: &str = "asdfghjkl";
 
 pub enum ScrollPosition {
 	LineNext,
@@ -34,6 +39,7 @@ pub enum ClickTarget {
 	Link(usize, usize),
 	ExternalLink(usize, usize),
 	Image(usize, usize),
+	ViewImage(usize, usize),
 	Char(usize, usize),
 	None,
 }
@@ -59,6 +65,7 @@ impl Render<RenderContext> for GuiView {
 		-> Option<Position>
 	{
 		let next = self.imp().redraw(book, lines, line, offset, highlight, context, &self.get_pango());
+		self.update_header(book.title(line, offset));
 		self.queue_draw();
 		next
 	}
@@ -96,13 +103,17 @@ impl GuiView {
 	pub const WIDGET_NAME: &'static str = "book-view";
 	pub const OPEN_LINK_SIGNAL: &'static str = "open-link";
 	pub const OPEN_IMAGE_EXTERNAL_SIGNAL: &'static str = "open-image-external";
+	pub const VIEW_IMAGE_SIGNAL: &'static str = "view-image";
 	pub const OPEN_LINK_EXTERNAL_SIGNAL: &'static str = "open-link-external";
 	pub const SELECTING_TEXT_SIGNAL: &'static str = "select-text";
 	pub const TEXT_SELECTED_SIGNAL: &'static str = "text-selected";
 	pub const CLEAR_SELECTION_SIGNAL: &'static str = "clear-selection";
 	pub const SCROLL_SIGNAL: &'static str = "scroll";
+	pub const CLICK_ZONE_SIGNAL: &'static str = "click-zone";
 	pub const SELECT_WORD_SIGNAL: &'static str = "select-word";
+	pub const EXTEND_SELECTION_SIGNAL: &'static str = "extend-selection";
 	pub const SHOW_TITLE_SIGNAL: &'static str = "title";
+	pub const ZOOM_SIGNAL: &'static str = "zoom";
 
 	pub fn new(instance_name: &str, render_han: bool, book_fonts: Option<&HtmlFonts>,
 		user_fonts: Rc<Option<UserFonts>>, render_context: &mut RenderContext) -> Self
@@ -189,8 +200,29 @@ impl GuiView {
 						&(line as u64),
 						&(offset as u64),
 					]),
-					ClickTarget::None | ClickTarget::Char(..) =>
-						view.emit_by_name::<()>(GuiView::CLEAR_SELECTION_SIGNAL, &[]),
+					ClickTarget::ViewImage(line, offset) => view.emit_by_name::<()>(GuiView::VIEW_IMAGE_SIGNAL, &[
+						&(line as u64),
+						&(offset as u64),
+					]),
+					// shift+click for extending selection, even across pages
+					ClickTarget::Char(line, offset) if state.eq(&ModifierType::SHIFT_MASK) =>
+						view.emit_by_name::<()>(GuiView::EXTEND_SELECTION_SIGNAL, &[
+							&(line as u64),
+							&(offset as u64),
+						]),
+					ClickTarget::None | ClickTarget::Char(..) => {
+						view.emit_by_name::<()>(GuiView::CLEAR_SELECTION_SIGNAL, &[]);
+						// -1 for left third, 1 for right third, 0 for middle
+						let width = view.width() as f64;
+						let zone: i32 = if x < width / 3. {
+							-1
+						} else if x > width * 2. / 3. {
+							1
+						} else {
+							0
+						};
+						view.emit_by_name::<()>(GuiView::CLICK_ZONE_SIGNAL, &[&zone]);
+					}
 				}
 			} else if n_press == 2 {
 				gesture.set_state(gtk4::EventSequenceState::Claimed);
@@ -217,13 +249,40 @@ impl GuiView {
 		if !self.scrollable() {
 			let scroll_event = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
 			let view = self.clone();
-			scroll_event.connect_scroll(move |_, _, y| {
+			scroll_event.connect_scroll(move |scroll, _, y| {
 				view.grab_focus();
-				let delta = if y > 0. { 1 } else { -1 };
-				view.emit_by_name::<()>(GuiView::SCROLL_SIGNAL, &[&delta]);
+				if scroll.current_event_state().contains(ModifierType::CONTROL_MASK) {
+					// scroll up for zoom in
+					let delta = if y < 0. { 1 } else { -1 };
+					view.emit_by_name::<()>(GuiView::ZOOM_SIGNAL, &[&delta]);
+				} else {
+					let delta = if y > 0. { 1 } else { -1 };
+					view.emit_by_name::<()>(GuiView::SCROLL_SIGNAL, &[&delta]);
+				}
 				glib::Propagation::Stop
 			});
 			self.add_controller(scroll_event);
+
+			let zoom_gesture = GestureZoom::new();
+			// scale of last emitted zoom step
+			let last_scale = Rc::new(Cell::new(1.0));
+			{
+				let last_scale = last_scale.clone();
+				zoom_gesture.connect_begin(move |_, _| last_scale.set(1.0));
+			}
+			let view = self.clone();
+			zoom_gesture.connect_scale_changed(move |_, scale| {
+				let delta = if scale > last_scale.get() * ZOOM_STEP_SCALE {
+					1
+				} else if scale < last_scale.get() / ZOOM_STEP_SCALE {
+					-1
+				} else {
+					return;
+				};
+				last_scale.set(scale);
+				view.emit_by_name::<()>(GuiView::ZOOM_SIGNAL, &[&delta]);
+			});
+			self.add_controller(zoom_gesture);
 		}
 	}
 
@@ -237,6 +296,12 @@ impl GuiView {
 		layout
 	}
 
+	#[inline]
+	pub fn render_han(&self) -> bool
+	{
+		self.imp().render_han.get()
+	}
+
 	#[inline]
 	pub fn reload_render(&self, render_han: bool, render_context: &mut RenderContext)
 	{
@@ -267,6 +332,20 @@ impl GuiView {
 		self.imp().set_custom_font(custom_font, book_fonts, &self.get_pango(), render_context);
 	}
 
+	#[inline]
+	pub fn set_font_family(&self, font_family: Option<String>, book_fonts: Option<&HtmlFonts>, render_context: &mut RenderContext)
+	{
+		self.imp().set_font_family(font_family, book_fonts, &self.get_pango(), render_context);
+	}
+
+	#[inline]
+	pub fn set_font_options(&self, ignore_font_weight: bool, ignore_book_fonts: bool,
+		book_fonts: Option<&HtmlFonts>, render_context: &mut RenderContext)
+	{
+		self.imp().set_font_options(ignore_font_weight, ignore_book_fonts, book_fonts,
+			&self.get_pango(), render_context);
+	}
+
 	#[inline(always)]
 	pub fn scroll_pos(&self) -> f64
 	{
@@ -286,6 +365,91 @@ impl GuiView {
 	{
 		self.imp().calc_selection(original_pos, current_pos)
 	}
+
+	/// line and char offset of the image at the widget position
+	pub fn image_at(&self, x: f64, y: f64) -> Option<(usize, usize)>
+	{
+		self.imp().pointer_info(pos2(x as f32, y as f32), |info| {
+			let (line, dc) = info?;
+			if matches!(dc.cell, RenderCell::Image(_, _)) {
+				Some((line.line(), dc.offset))
+			} else {
+				None
+			}
+		})
+	}
+
+	/// label for running header, showing title of current page
+	#[inline]
+	pub fn set_header(&self, header: Label)
+	{
+		self.imp().header.replace(Some(header));
+	}
+
+	#[inline]
+	fn update_header(&self, title: Option<&str>)
+	{
+		if let Some(header) = self.imp().header.borrow().as_ref() {
+			if header.is_visible() {
+				header.set_text(title.unwrap_or(""));
+			}
+		}
+	}
+
+	/// enter link hint mode, label all visible links,
+	/// return false if no link visible
+	pub fn start_link_hints(&self) -> bool
+	{
+		let started = self.imp().start_link_hints();
+		if started {
+			self.queue_draw();
+		}
+		started
+	}
+
+	#[inline]
+	pub fn link_hints_active(&self) -> bool
+	{
+		self.imp().link_hints_active()
+	}
+
+	#[inline]
+	pub fn cancel_link_hints(&self)
+	{
+		self.imp().cancel_link_hints();
+		self.queue_draw();
+	}
+
+	/// input char for hint label, open the link when label matched
+	pub fn link_hint_input(&self, char: char)
+	{
+		let found = self.imp().link_hint_input(char);
+		self.queue_draw();
+		if let Some((line, link_index)) = found {
+			self.emit_by_name::<()>(GuiView::OPEN_LINK_SIGNAL, &[
+				&(line as u64),
+				&(link_index as u64),
+			]);
+		}
+	}
+
+	/// show title attribute of the hovered char as tooltip,
+	/// called by handler of SHOW_TITLE_SIGNAL
+	pub fn show_title(&self, lines: &Vec<Line>, show: bool, line: usize,
+		offset: usize, colors: &Colors)
+	{
+		let title = if show {
+			lines.get(line)
+				.and_then(|line| line.char_style_at(offset, false, colors).title)
+				.map(|title| title.as_str())
+		} else {
+			None
+		};
+		// reset same text will hide the shown tooltip
+		if self.tooltip_text().as_deref() != title {
+			self.set_tooltip_text(title);
+		}
+	}
 }
 
 mod imp {
@@ -311,8 +475,9 @@ mod imp {
 	use crate::controller::HighlightInfo;
 	use crate::gui::font::{HtmlFonts, UserFonts};
 	use crate::gui::math::{Pos2, Rect};
-	use crate::gui::render::{BlockBackgroundEntry, create_render, GuiRender, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollRedrawMethod, TextDecoration};
-	use crate::gui::view::{ClickTarget, MIN_TEXT_SELECT_DISTANCE, ScrollPosition};
+	use crate::color::Color32;
+	use crate::gui::render::{BlockBackgroundEntry, create_render, draw_focus_dim, draw_link_hint, GuiRender, PointerPosition, RenderCell, RenderChar, RenderContext, RenderLine, ScrolledDrawData, ScrollRedrawMethod, TextDecoration};
+	use crate::gui::view::{ClickTarget, link_hint_labels, MIN_TEXT_SELECT_DISTANCE, progress_value, ScrollPosition};
 
 	#[derive(Properties)]
 	#[properties(wrapper_type = super::GuiView)]
@@ -330,6 +495,7 @@ mod imp {
 		render_han: Cell<bool>,
 		data: RefCell<GuiViewData>,
 		render: RefCell<Box<dyn GuiRender>>,
+		pub(super) header: RefCell<Option<Label>>,
 	}
 
 	impl Default for GuiView {
@@ -348,8 +514,13 @@ mod imp {
 					block_borders: vec![],
 					draw_data: None,
 					font_family_names: None,
+					link_hints: vec![],
+					hint_input: String::new(),
+					focus_dim: None,
+					image_background: None,
 				}),
 				render: RefCell::new(create_render(false)),
+				header: RefCell::new(None),
 			}
 		}
 	}
@@ -361,6 +532,20 @@ mod imp {
 		block_borders: Vec<TextDecoration>,
 		draw_data: Option<ScrolledDrawData>,
 		font_family_names: Option<IndexSet<String>>,
+		link_hints: Vec<LinkHint>,
+		hint_input: String,
+		// background color dimming lines not focused, None if focus mode off
+		focus_dim: Option<Color32>,
+		// filled behind images of the theme, None for not filled
+		image_background: Option<Color32>,
+	}
+
+	struct LinkHint {
+		label: String,
+		line: usize,
+		link_index: usize,
+		position: Pos2,
+		font_size: u8,
 	}
 
 	#[glib::object_subclass]
@@ -403,6 +588,13 @@ mod imp {
 						])
 						.run_last()
 						.build(),
+					Signal::builder(super::GuiView::VIEW_IMAGE_SIGNAL)
+						.param_types([
+							<u64>::static_type(),
+							<u64>::static_type(),
+						])
+						.run_last()
+						.build(),
 					Signal::builder(super::GuiView::SELECTING_TEXT_SIGNAL)
 						.param_types([
 							<u64>::static_type(),
@@ -431,12 +623,31 @@ mod imp {
 						])
 						.run_last()
 						.build(),
+					Signal::builder(super::GuiView::EXTEND_SELECTION_SIGNAL)
+						.param_types([
+							<u64>::static_type(),
+							<u64>::static_type(),
+						])
+						.run_last()
+						.build(),
 					Signal::builder(super::GuiView::SCROLL_SIGNAL)
 						.param_types([
 							<i32>::static_type(),
 						])
 						.run_last()
 						.build(),
+					Signal::builder(super::GuiView::CLICK_ZONE_SIGNAL)
+						.param_types([
+							<i32>::static_type(),
+						])
+						.run_last()
+						.build(),
+					Signal::builder(super::GuiView::ZOOM_SIGNAL)
+						.param_types([
+							<i32>::static_type(),
+						])
+						.run_last()
+						.build(),
 					Signal::builder(super::GuiView::SHOW_TITLE_SIGNAL)
 						.param_types([
 							<bool>::static_type(),
@@ -471,13 +682,23 @@ mod imp {
 					&data.block_borders,
 					&data.block_backgrounds)
 			};
+			let pango = self.obj().get_pango();
 			render.draw(
 				render_lines,
 				block_borders,
 				block_backgrounds,
-				&data.font_family_names,
+				data.image_background.as_ref(),
 				&cairo,
-				&self.obj().get_pango());
+				&pango);
+			if let Some(background) = &data.focus_dim {
+				draw_focus_dim(&cairo, render_lines, background);
+			}
+			for hint in &data.link_hints {
+				if hint.label.starts_with(&data.hint_input) {
+					draw_link_hint(&cairo, &hint.label, &hint.position,
+						hint.font_size, &pango);
+				}
+			}
 		}
 	}
 
@@ -560,7 +781,10 @@ mod imp {
 		{
 			context.custom_font = reading.custom_font;
 			context.custom_color = reading.custom_color;
-			context.leading_chars = book.leading_space();
+			context.leading_chars = reading.leading_chars(
+				context.default_leading_chars,
+				book.leading_space());
+			context.language = reading.language(book).map(|lang| lang.to_owned());
 			let mut render = self.render.borrow_mut();
 			render.image_cache_mut().clear();
 			render.apply_font_modified(book.custom_fonts(), pango, context);
@@ -585,8 +809,15 @@ mod imp {
 				let (render_lines, block_borders, block_backgrounds, next) = render.gui_redraw(book, lines, line, offset, highlight,
 					pango, context);
 				data.render_lines = render_lines;
+				data.link_hints.clear();
 				data.block_borders = block_borders;
 				data.block_backgrounds = block_backgrounds;
+				data.focus_dim = if context.focus_mode {
+					Some(context.colors.background.clone())
+				} else {
+					None
+				};
+				data.image_background = context.colors.image_background.clone();
 				next
 			}
 		}
@@ -641,16 +872,23 @@ mod imp {
 				&mut render,
 			);
 
+			let render_han = self.render_han.get();
 			self.adjustment(|adjustment| {
 				let value = match &render_context.scroll_redraw_method {
 					ScrollRedrawMethod::ResetScroll => sizing.init_scroll_value as f64,
 					ScrollRedrawMethod::NoResetScroll => adjustment.value(),
-					ScrollRedrawMethod::ScrollTo(value) => *value,
+					ScrollRedrawMethod::ScrollTo(progress) => progress_value(
+						*progress,
+						render_han,
+						sizing.full_size as f64,
+						sizing.page_size as f64),
 				};
 				let mut data = self.data.borrow_mut();
 				data.render_lines = lines;
+				data.link_hints.clear();
 				data.block_borders = block_borders;
 				data.block_backgrounds = block_backgrounds;
+				data.image_background = render_context.colors.image_background.clone();
 				let draw_data = render.visible_scrolling(
 					value as f32, sizing.full_size,
 					&render_context.render_rect, &data.render_lines);
@@ -663,15 +901,94 @@ mod imp {
 					0.,
 					sizing.full_size as f64,
 					sizing.step_size as f64,
-					sizing.page_size as f64,
+					sizing.page_step as f64,
 					sizing.page_size as f64,
 				);
 			})
 		}
 
+		pub(super) fn start_link_hints(&self) -> bool
+		{
+			let mut data = self.data.borrow_mut();
+			let range = match &data.draw_data {
+				Some(draw_data) => draw_data.range.clone(),
+				None => 0..data.render_lines.len(),
+			};
+			let mut links: Vec<(usize, usize, Pos2, u8)> = vec![];
+			for render_line in &data.render_lines[range] {
+				let line = render_line.line();
+				for dc in render_line.render_chars() {
+					if let RenderCell::Link(cell, link_index) = &dc.cell {
+						// link may be wrapped into multiple render lines
+						if links.iter().any(|(l, i, ..)| *l == line && i == link_index) {
+							continue;
+						}
+						let font_size = (cell.font_size * 0.75).max(8.) as u8;
+						links.push((line, *link_index, dc.rect.min, font_size));
+					}
+				}
+			}
+			if links.is_empty() {
+				return false;
+			}
+			let labels = link_hint_labels(links.len());
+			data.link_hints = links.into_iter()
+				.zip(labels)
+				.map(|((line, link_index, position, font_size), label)| LinkHint {
+					label,
+					line,
+					link_index,
+					position,
+					font_size,
+				})
+				.collect();
+			data.hint_input.clear();
+			true
+		}
+
+		#[inline]
+		pub(super) fn link_hints_active(&self) -> bool
+		{
+			!self.data.borrow().link_hints.is_empty()
+		}
+
+		#[inline]
+		pub(super) fn cancel_link_hints(&self)
+		{
+			let mut data = self.data.borrow_mut();
+			data.link_hints.clear();
+			data.hint_input.clear();
+		}
+
+		pub(super) fn link_hint_input(&self, char: char) -> Option<(usize, usize)>
+		{
+			let mut data = self.data.borrow_mut();
+			data.hint_input.push(char);
+			let input = &data.hint_input;
+			let found = data.link_hints.iter()
+				.find(|hint| hint.label == *input)
+				.map(|hint| (hint.line, hint.link_index));
+			if found.is_some()
+				|| !data.link_hints.iter().any(|hint| hint.label.starts_with(input.as_str())) {
+				data.link_hints.clear();
+				data.hint_input.clear();
+			}
+			found
+		}
+
+		/// progress of the scrolled content on the active axis,
+		/// so the place kept when switching between han and xi
 		pub(super) fn scroll_pos(&self) -> Option<f64>
 		{
-			self.adjustment(|adjustment| Some(adjustment.value()))
+			let render_han = self.render_han.get();
+			self.adjustment(|adjustment| {
+				let range = adjustment.upper() - adjustment.page_size();
+				if range <= 0. {
+					return Some(0.);
+				}
+				let progress = adjustment.value() / range;
+				Some(if render_han { 1. - progress } else { progress })
+			})
 		}
 
 		pub(super) fn scroll_to(&self, position: ScrollPosition)
@@ -734,6 +1051,23 @@ mod imp {
 			}
 		}
 
+		pub(super) fn set_font_family(&self, font_family: Option<String>, book_fonts: Option<&HtmlFonts>,
+			pango: &PangoContext, render_context: &mut RenderContext)
+		{
+			render_context.font_family = font_family;
+			let mut render = self.render.borrow_mut();
+			render.apply_font_modified(book_fonts, pango, render_context);
+		}
+
+		pub(super) fn set_font_options(&self, ignore_font_weight: bool, ignore_book_fonts: bool,
+			book_fonts: Option<&HtmlFonts>, pango: &PangoContext, render_context: &mut RenderContext)
+		{
+			render_context.ignore_font_weight = ignore_font_weight;
+			render_context.ignore_book_fonts = ignore_book_fonts;
+			let mut render = self.render.borrow_mut();
+			render.apply_font_modified(book_fonts, pango, render_context);
+		}
+
 		pub fn resized(&self, width: i32, height: i32, render_context: &mut RenderContext)
 		{
 			render_context.update_render_rect(width as f32, height as f32);
@@ -886,7 +1220,7 @@ mod imp {
 						} else if let Some(link_index) = link_index {
 							ClickTarget::Link(line.line(), link_index)
 						} else {
-							return None;
+							ClickTarget::ViewImage(line.line(), dc.offset)
 						}
 					RenderCell::Char(_) =>
 						ClickTarget::Char(line.line(), dc.offset),
@@ -900,12 +1234,10 @@ mod imp {
 		{
 			let name = match dc.cell {
 				RenderCell::Char(_) => None,
-				RenderCell::Image(_, link_index) => if state.eq(&ModifierType::CONTROL_MASK) {
-					Some("zoom-in")
-				} else if link_index.is_some() {
+				RenderCell::Image(_, link_index) => if link_index.is_some() && !state.eq(&ModifierType::CONTROL_MASK) {
 					Some("pointer")
 				} else {
-					None
+					Some("zoom-in")
 				}
 				RenderCell::Link(_, _) => Some("pointer"),
 			};
@@ -923,13 +1255,8 @@ mod imp {
 		{
 			let name = match dc.cell {
 				RenderCell::Char(_) => None,
-				RenderCell::Image(_, None) => if state.eq(&ModifierType::CONTROL_MASK) {
-					Some("pointer")
-				} else {
-					None
-				}
 				RenderCell::Link(_, _) |
-				RenderCell::Image(_, Some(_)) => Some("pointer"),
+				RenderCell::Image(_, _) => Some("pointer"),
 			};
 			name.unwrap_or("default")
 		}
@@ -963,6 +1290,39 @@ mod imp {
 	}
 }
 
+/// scroll value of the progress, han starts from the right
+#[inline]
+fn progress_value(progress: f64, render_han: bool, full_size: f64, page_size: f64) -> f64
+{
+	let range = (full_size - page_size).max(0.);
+	if render_han {
+		range * (1. - progress)
+	} else {
+		range * progress
+	}
+}
+
+/// labels with same length for link hints, in home row chars
+fn link_hint_labels(count: usize) -> Vec<String>
+{
+	let chars: Vec<char> = LINK_HINT_CHARS.chars().collect();
+	let base = chars.len();
+	let mut len = 1;
+	let mut capacity = base;
+	while capacity < count {
+		capacity *= base;
+		len += 1;
+	}
+	(0..count).map(|mut index| {
+		let mut label = vec![chars[0]; len];
+		for pos in (0..len).rev() {
+			label[pos] = chars[index % base];
+			index /= base;
+		}
+		label.into_iter().collect()
+	}).collect()
+}
+
 pub fn init_css(colors: &Colors) -> CssProvider
 {
 	let css_provider = CssProvider::new();
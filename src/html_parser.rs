@@ -13,28 +13,65 @@ use lightningcss::declaration::DeclarationBlock;
 use lightningcss::properties::{border, font, Property};
 use lightningcss::properties::border::{Border, BorderSideWidth};
 use lightningcss::properties::display::{Display, DisplayKeyword, DisplayOutside, DisplayPair};
-use lightningcss::properties::font::{AbsoluteFontWeight, FontFamily, FontSize, FontWeight as CssFontWeight};
+use lightningcss::properties::font::{AbsoluteFontWeight, FontFamily, FontSize, FontWeight as CssFontWeight, LineHeight};
 use lightningcss::properties::size::Size;
-use lightningcss::properties::text::{TextDecoration as CssTextDecoration, TextDecorationLine as CssTextDecorationLine, TextDecorationStyle as CssTextDecorationStyle};
+use lightningcss::properties::text::{TextDecoration as CssTextDecoration, TextDecorationLine as CssTextDecorationLine, TextDecorationStyle as CssTextDecorationStyle, TextDecorationThickness, TextTransform as CssTextTransform, TextTransformCase, WhiteSpace};
 use lightningcss::rules::{CssRule, font_face};
 use lightningcss::rules::font_face::FontFaceProperty;
-use lightningcss::stylesheet::{ParserOptions, StyleSheet};
+use lightningcss::stylesheet::{ParserOptions, PrinterOptions, StyleSheet};
 use lightningcss::traits::Parse;
 use lightningcss::values;
 use lightningcss::values::color::CssColor;
-use lightningcss::values::length::{Length, LengthPercentage, LengthValue};
+use lightningcss::values::length::{Length, LengthPercentage, LengthPercentageOrAuto, LengthValue};
 use lightningcss::values::percentage;
 use markup5ever::{LocalName, Namespace, Prefix, QualName};
 use roxmltree::{Document, ParsingOptions};
 use scraper::{Html, Node, Selector};
 use scraper::node::Element;
 
-use crate::book::{EMPTY_CHAPTER_CONTENT, IMAGE_CHAR, Line};
+use crate::book::{EMPTY_CHAPTER_CONTENT, IMAGE_CHAR, Line, ZERO_WIDTH_SPACE};
 use crate::color::Color32;
-use crate::common::Position;
+use crate::common::{Position, strip_invisible_chars};
 
 const DEFAULT_FONT_WEIGHT: u16 = 400;
 const DEFAULT_FONT_SIZE: f32 = 16.0;
+pub const DEFAULT_TAB_WIDTH: u8 = 4;
+const DETAILS_OPENED_MARKER: char = '▾';
+
+/// options changing parsed content, can be switched by user
+#[derive(Clone, PartialEq)]
+pub struct ContentOptions {
+	/// keep print page numbers marked by epub:type="pagebreak"
+	pub show_page_numbers: bool,
+	/// remove soft hyphens and zero width spaces
+	pub strip_invisible_chars: bool,
+	/// spaces for expanding tab in preformatted text
+	pub tab_width: u8,
+	/// join hard wrapped lines of plain text into paragraphs
+	pub reflow_text: bool,
+	/// font families for text in languages, keyed by lowercase language tag
+	pub lang_fonts: HashMap<String, String>,
+	/// mark epub:type="footnote" blocks for rendering at page bottom
+	pub page_footnotes: bool,
+	/// language set by user for the book, None for the book's metadata
+	pub language: Option<String>,
+}
+
+impl Default for ContentOptions {
+	#[inline]
+	fn default() -> Self
+	{
+		ContentOptions {
+			show_page_numbers: false,
+			strip_invisible_chars: false,
+			tab_width: DEFAULT_TAB_WIDTH,
+			reflow_text: false,
+			lang_fonts: HashMap::new(),
+			page_footnotes: false,
+			language: None,
+		}
+	}
+}
 
 pub struct HtmlParseOptions<'a> {
 	html: &'a str,
@@ -42,6 +79,8 @@ pub struct HtmlParseOptions<'a> {
 	resolver: Option<&'a dyn HtmlResolver>,
 	custom_title: Option<String>,
 	dark_mode: bool,
+	content_options: ContentOptions,
+	language: Option<&'a str>,
 }
 
 impl<'a> HtmlParseOptions<'a> {
@@ -54,6 +93,8 @@ impl<'a> HtmlParseOptions<'a> {
 			resolver: None,
 			custom_title: None,
 			dark_mode: false,
+			content_options: Default::default(),
+			language: None,
 		}
 	}
 	pub fn with_font_family(mut self, font_family: &'a mut IndexSet<String>) -> Self
@@ -66,6 +107,17 @@ impl<'a> HtmlParseOptions<'a> {
 		self.resolver = Some(resolver);
 		self
 	}
+	pub fn with_content_options(mut self, content_options: ContentOptions) -> Self
+	{
+		self.content_options = content_options;
+		self
+	}
+	/// language of the book, used for quotation marks
+	pub fn with_language(mut self, language: &'a str) -> Self
+	{
+		self.language = Some(language);
+		self
+	}
 	#[allow(unused)]
 	pub fn with_custom_title(mut self, custom_title: String) -> Self
 	{
@@ -155,6 +207,44 @@ bitflags! {
 	}
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum VerticalAlign {
+	Super,
+	Sub,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TextTransform {
+	Uppercase,
+	Lowercase,
+	Capitalize,
+}
+
+impl TextTransform {
+	/// transform char for rendering, prev is the char before it in the line
+	/// only single char mappings applied, so no locale specified handling
+	pub fn apply(&self, ch: char, prev: Option<char>) -> char
+	{
+		match self {
+			TextTransform::Uppercase => single_char(ch.to_uppercase(), ch),
+			TextTransform::Lowercase => single_char(ch.to_lowercase(), ch),
+			TextTransform::Capitalize => match prev {
+				Some(prev) if prev.is_alphanumeric() || prev == '\'' || prev == '’' => ch,
+				_ => single_char(ch.to_uppercase(), ch),
+			}
+		}
+	}
+}
+
+#[inline]
+fn single_char(mut mapped: impl Iterator<Item=char>, orig: char) -> char
+{
+	match (mapped.next(), mapped.next()) {
+		(Some(ch), None) => ch,
+		_ => orig,
+	}
+}
+
 pub enum BlockStyle {
 	Border { range: Range<usize>, lines: BorderLines, color: Option<Color32> },
 	Background { range: Range<usize>, color: Color32 },
@@ -162,9 +252,13 @@ pub enum BlockStyle {
 
 #[derive(Clone, Debug)]
 pub struct TextDecoration {
+	/// empty for color or thickness only decoration, lines from outer one
 	pub line: TextDecorationLine,
 	pub style: TextDecorationStyle,
+	/// None for color of the text
 	pub color: Option<Color32>,
+	/// None for default thickness
+	pub thickness: Option<ElementSize>,
 }
 
 impl TextDecoration {
@@ -174,6 +268,19 @@ impl TextDecoration {
 			line,
 			style: TextDecorationStyle::Solid,
 			color: None,
+			thickness: None,
+		}
+	}
+
+	/// take properties not specified in this decoration from the original one
+	fn merge(self, orig: &TextDecoration) -> Self
+	{
+		let color = self.color.or_else(|| orig.color.clone());
+		let thickness = self.thickness.or_else(|| orig.thickness.clone());
+		if self.line.is_empty() {
+			Self { line: orig.line, style: orig.style, color, thickness }
+		} else {
+			Self { line: self.line, style: self.style, color, thickness }
 		}
 	}
 }
@@ -201,6 +308,17 @@ impl ElementSize {
 	{
 		self.relative
 	}
+	/// px of length relative to the font size of the text or the root one
+	#[cfg(feature = "gui")]
+	#[inline]
+	pub fn length_px(&self, font_size: f32, root_font_size: f32) -> f32
+	{
+		if self.relative {
+			self.scale.0 * font_size
+		} else {
+			self.scale.0 * root_font_size
+		}
+	}
 	#[inline]
 	pub fn to_px(&self, font_scale: &FontScale, font_size: f32) -> f32
 	{
@@ -248,6 +366,12 @@ pub enum TextStyle {
 	Color(Color32),
 	BackgroundColor(Color32),
 	Title(String),
+	TextTransform(TextTransform),
+	/// white-space: nowrap, avoid line breaking inside
+	NoWrap,
+	/// <mark>, highlighted with theme color if no background specified
+	Mark,
+	VerticalAlign(VerticalAlign),
 }
 
 impl TextStyle {
@@ -265,6 +389,10 @@ impl TextStyle {
 			TextStyle::Color(_) => 8,
 			TextStyle::BackgroundColor(_) => 9,
 			TextStyle::Title(_) => 10,
+			TextStyle::TextTransform(_) => 11,
+			TextStyle::NoWrap => 12,
+			TextStyle::Mark => 13,
+			TextStyle::VerticalAlign(_) => 14,
 		}
 	}
 }
@@ -275,6 +403,14 @@ enum ParseTag {
 	Width(ElementSize),
 	Height(ElementSize),
 	Paragraph,
+	LineHeight(f32),
+	// in em
+	TextIndent(f32),
+	// top and bottom in em, None for not specified
+	Margin(Option<f32>, Option<f32>),
+	// start a new page before the element
+	PageBreak,
+	ListStyle(ListStyleType),
 	Hidden,
 }
 
@@ -287,6 +423,11 @@ impl ParseTag {
 			ParseTag::Paragraph => 1000,
 			ParseTag::Width(_) => 1001,
 			ParseTag::Height(_) => 1002,
+			ParseTag::LineHeight(_) => 1003,
+			ParseTag::TextIndent(_) => 1004,
+			ParseTag::Margin(..) => 1005,
+			ParseTag::PageBreak => 1006,
+			ParseTag::ListStyle(_) => 1007,
 			ParseTag::Hidden => 9999,
 		}
 	}
@@ -306,6 +447,57 @@ impl ParseTag {
 	}
 }
 
+/// marker style of ordered list items
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ListStyleType {
+	Decimal,
+	LowerAlpha,
+	UpperAlpha,
+	LowerRoman,
+	UpperRoman,
+	None,
+}
+
+impl ListStyleType {
+	fn parse(value: &str) -> Option<Self>
+	{
+		let style = match value {
+			"decimal" | "1" => ListStyleType::Decimal,
+			"lower-alpha" | "lower-latin" | "a" => ListStyleType::LowerAlpha,
+			"upper-alpha" | "upper-latin" | "A" => ListStyleType::UpperAlpha,
+			"lower-roman" | "i" => ListStyleType::LowerRoman,
+			"upper-roman" | "I" => ListStyleType::UpperRoman,
+			"none" => ListStyleType::None,
+			_ => return None,
+		};
+		Some(style)
+	}
+
+	/// marker text of item with the number, like "iv."
+	fn marker(&self, number: usize) -> Option<String>
+	{
+		let text = match self {
+			ListStyleType::None => return None,
+			ListStyleType::Decimal => number.to_string(),
+			ListStyleType::LowerAlpha => alpha_number(number)
+				.unwrap_or_else(|| number.to_string()),
+			ListStyleType::UpperAlpha => alpha_number(number)
+				.map_or_else(|| number.to_string(), |text| text.to_uppercase()),
+			ListStyleType::LowerRoman => roman_number(number)
+				.map_or_else(|| number.to_string(), |text| text.to_lowercase()),
+			ListStyleType::UpperRoman => roman_number(number)
+				.unwrap_or_else(|| number.to_string()),
+		};
+		Some(format!("{}.", text))
+	}
+}
+
+// ordered list being converted, for markers of its items
+struct OrderedList {
+	style: ListStyleType,
+	next: usize,
+}
+
 // style with !important or not
 #[derive(Clone, Debug)]
 struct LeveledParseTag(ParseTag, bool);
@@ -417,7 +609,6 @@ pub struct HtmlContent {
 impl HtmlContent
 {
 	#[inline]
-	#[allow(unused)]
 	pub fn empty() -> Self
 	{
 		HtmlContent {
@@ -471,6 +662,19 @@ pub struct HtmlParser<'a> {
 	font_face_map: HashMap<&'a str, Option<String>>,
 	styles: Vec<StyleDescription>,
 	dark_mode: bool,
+	content_options: ContentOptions,
+	indent: u8,
+	line_height: Option<f32>,
+	text_indent: Option<f32>,
+	language: Option<String>,
+	// nesting level of <q>
+	quote_level: usize,
+	// the innermost list, None for unordered or not in list
+	ordered_list: Option<OrderedList>,
+	// inside <pre>, keep white spaces and line breaks
+	preformatted: bool,
+	// count of footnotes, for serial of the next one
+	footnotes: usize,
 
 	title: Option<String>,
 	lines: Vec<Line>,
@@ -622,6 +826,8 @@ impl<'a> HtmlParser<'a> {
 			}
 		}
 
+		setup_footnote_refs(lines, &self.id_map);
+
 		let block_styles = if self.block_styles.is_empty() {
 			None
 		} else {
@@ -656,10 +862,18 @@ impl<'a> HtmlParser<'a> {
 	{
 		match node.value() {
 			Node::Text(contents) => {
-				let string = contents.text.to_string();
+				let string = if self.content_options.strip_invisible_chars {
+					strip_invisible_chars(&contents.text)
+				} else {
+					contents.text.to_string()
+				};
+				if self.preformatted {
+					self.push_preformatted(&string);
+					return;
+				}
 				let text = string.trim_matches(|c: char| c.is_ascii_whitespace());
-				let line = self.lines.last_mut().unwrap();
 				if text.len() > 0 {
+					let line = self.last_line();
 					if line.len() > 0
 						&& line.char_at(line.len() - 1).unwrap().is_ascii_alphanumeric()
 						&& text.chars().next().unwrap().is_ascii_alphanumeric() {
@@ -669,6 +883,11 @@ impl<'a> HtmlParser<'a> {
 				}
 			}
 			Node::Element(element) => {
+				// footnote starts at new line, for it's rendered apart
+				let footnote = self.content_options.page_footnotes && is_footnote(element);
+				if footnote {
+					self.new_line();
+				}
 				let position = Position::new(
 					self.lines.len() - 1,
 					self.lines.last().unwrap().len());
@@ -686,6 +905,28 @@ impl<'a> HtmlParser<'a> {
 				if force_paragraph {
 					self.new_line();
 				}
+				if remove_tag(&mut element_tags, ParseTag::PageBreak).is_some() {
+					self.new_line();
+					self.last_line().set_page_break(true);
+				}
+				let orig_line_height = self.line_height;
+				if let Some(LeveledParseTag(ParseTag::LineHeight(line_height), _)) =
+					remove_tag(&mut element_tags, ParseTag::LineHeight(0.)) {
+					self.line_height = Some(line_height);
+				}
+				let orig_text_indent = self.text_indent;
+				if let Some(LeveledParseTag(ParseTag::TextIndent(text_indent), _)) =
+					remove_tag(&mut element_tags, ParseTag::TextIndent(0.)) {
+					self.text_indent = Some(text_indent);
+				}
+				if let Some(tag) = self.lang_font(element) {
+					insert_or_replace_tag(&mut element_tags, tag, false);
+				}
+				let margin = remove_tag(&mut element_tags, ParseTag::Margin(None, None));
+				let list_style = match remove_tag(&mut element_tags, ParseTag::ListStyle(ListStyleType::None)) {
+					Some(LeveledParseTag(ParseTag::ListStyle(style), _)) => Some(style),
+					_ => None,
+				};
 				match element.name.local {
 					local_name!("title") => self.load_title(node),
 					local_name!("script") => {}
@@ -703,6 +944,19 @@ impl<'a> HtmlParser<'a> {
 							false);
 						self.convert_node_children(node.children());
 					}
+					local_name!("abbr") | local_name!("acronym") => {
+						// dotted underline for expandable abbreviation, expansion shown as title
+						if element.attr("title").is_some() {
+							let abbr = TextDecoration {
+								line: TextDecorationLine::Underline,
+								style: TextDecorationStyle::Dotted,
+								color: None,
+								thickness: None,
+							};
+							unique_and_insert_tag(&mut element_tags, ParseTag::Style(TextStyle::Decoration(abbr)));
+						}
+						self.convert_node_children(node.children());
+					}
 					local_name!("div") => {
 						self.newline_for_class(element);
 						self.convert_node_children(node.children());
@@ -732,6 +986,26 @@ impl<'a> HtmlParser<'a> {
 						unique_and_insert_font_size(&mut element_tags, 1, false);
 						self.new_paragraph(node);
 					}
+					local_name!("nobr") => {
+						insert_or_replace_tag(&mut element_tags, ParseTag::Style(TextStyle::NoWrap), false);
+						self.convert_node_children(node.children());
+					}
+					local_name!("mark") => {
+						if !find_tag(&element_tags, ParseTag::Style(TextStyle::BackgroundColor(Color32::BLACK))) {
+							insert_or_replace_tag(&mut element_tags, ParseTag::Style(TextStyle::Mark), false);
+						}
+						self.convert_node_children(node.children());
+					}
+					local_name!("sup") => {
+						unique_and_insert_tag(&mut element_tags, ParseTag::Style(TextStyle::VerticalAlign(VerticalAlign::Super)));
+						unique_and_insert_font_size(&mut element_tags, 2, true);
+						self.convert_node_children(node.children());
+					}
+					local_name!("sub") => {
+						unique_and_insert_tag(&mut element_tags, ParseTag::Style(TextStyle::VerticalAlign(VerticalAlign::Sub)));
+						unique_and_insert_font_size(&mut element_tags, 2, true);
+						self.convert_node_children(node.children());
+					}
 					local_name!("small") => {
 						unique_and_insert_font_size(&mut element_tags, 2, true);
 						self.convert_node_children(node.children());
@@ -740,16 +1014,92 @@ impl<'a> HtmlParser<'a> {
 						unique_and_insert_font_size(&mut element_tags, 4, true);
 						self.convert_node_children(node.children());
 					}
+					local_name!("pre") => {
+						let orig_preformatted = self.preformatted;
+						self.preformatted = true;
+						self.new_paragraph(node);
+						self.preformatted = orig_preformatted;
+					}
+					local_name!("summary") => {
+						// details are always expanded, mark the summary as an opened disclosure
+						insert_or_replace_tag(
+							&mut element_tags,
+							ParseTag::Style(TextStyle::FontWeight(FontWeightValue::Bolder)),
+							false);
+						self.new_line();
+						let line = self.last_line();
+						line.push(DETAILS_OPENED_MARKER);
+						line.push(' ');
+						self.convert_node_children(node.children());
+						self.new_line();
+					}
+					local_name!("blockquote")
+					| local_name!("dd") => {
+						let orig_indent = self.indent;
+						self.indent = self.indent.saturating_add(1);
+						self.new_paragraph(node);
+						self.indent = orig_indent;
+					}
 					local_name!("p")
-					| local_name!("blockquote")
 					| local_name!("table")
 					| local_name!("tr")
 					| local_name!("dt")
-					| local_name!("li") => self.new_paragraph(node),
+					| local_name!("details") => self.new_paragraph(node),
+					local_name!("ol") => {
+						let style = list_style
+							.or_else(|| ListStyleType::parse(element.attr("type")?.trim()))
+							.unwrap_or(ListStyleType::Decimal);
+						let next = element.attr("start")
+							.and_then(|start| start.trim().parse().ok())
+							.unwrap_or(1);
+						let orig_list = self.ordered_list.replace(OrderedList { style, next });
+						self.new_paragraph(node);
+						self.ordered_list = orig_list;
+					}
+					local_name!("ul") | local_name!("menu") => {
+						let orig_list = self.ordered_list.take();
+						self.new_paragraph(node);
+						self.ordered_list = orig_list;
+					}
+					local_name!("li") => {
+						let marker = if let Some(list) = &mut self.ordered_list {
+							if let Some(value) = element.attr("value")
+								.and_then(|value| value.trim().parse().ok()) {
+								list.next = value;
+							}
+							let style = list_style
+								.or_else(|| ListStyleType::parse(element.attr("type")?.trim()))
+								.unwrap_or(list.style);
+							let marker = style.marker(list.next);
+							list.next += 1;
+							marker
+						} else {
+							None
+						};
+						self.new_line();
+						if let Some(marker) = marker {
+							let line = self.last_line();
+							line.concat(&marker);
+							line.push(' ');
+						}
+						self.convert_node_children(node.children());
+						self.new_line();
+					}
 					local_name!("br") => {
 						self.new_line();
 						self.convert_node_children(node.children());
 					}
+					local_name!("wbr") => self.last_line().push(ZERO_WIDTH_SPACE),
+					local_name!("q") => {
+						let language = element.attr("lang")
+							.or(self.language.as_deref());
+						let (open, close) = quotation_marks(language, self.quote_level);
+						self.last_line().push(open);
+						self.quote_level += 1;
+						self.convert_node_children(node.children());
+						self.quote_level -= 1;
+						self.last_line().push(close);
+					}
 					local_name!("font") => {
 						if let Some(level_text) = element.attr("size") {
 							if let Ok(level) = level_text.parse::<u8>() {
@@ -766,11 +1116,13 @@ impl<'a> HtmlParser<'a> {
 						self.convert_node_children(node.children());
 					}
 					local_name!("a") => {
-						if let Some(href) = element.attr("href") {
+						// <a> in svg links by xlink:href, usually wrapping an <image>
+						if let Some(href) = element.attr("href").or_else(|| xlink_href(element)) {
 							let a = TextDecoration {
 								line: TextDecorationLine::Underline,
 								style: TextDecorationStyle::Solid,
 								color: None,
+								thickness: None,
 							};
 							unique_and_insert_tag(&mut element_tags, ParseTag::Style(TextStyle::Decoration(a)));
 							insert_or_replace_tag(&mut element_tags, ParseTag::Style(TextStyle::Link(href.to_string())), false);
@@ -783,12 +1135,7 @@ impl<'a> HtmlParser<'a> {
 						}
 					}
 					local_name!("image") => {
-						let name = QualName::new(
-							Some(Prefix::from("xlink")),
-							Namespace::from("http://www.w3.org/1999/xlink"),
-							LocalName::from("href"));
-						let href = element.attrs.get(&name).map(Deref::deref);
-						if let Some(href) = href {
+						if let Some(href) = xlink_href(element) {
 							self.add_image(href, &element_tags);
 						}
 					}
@@ -799,6 +1146,11 @@ impl<'a> HtmlParser<'a> {
 				if force_paragraph {
 					self.new_line();
 				}
+				self.line_height = orig_line_height;
+				self.text_indent = orig_text_indent;
+				if let Some(LeveledParseTag(ParseTag::Margin(top, bottom), _)) = margin {
+					self.apply_margin(&position, top, bottom);
+				}
 				if !element_tags.is_empty() {
 					let lines = &self.lines;
 					// only for new lines
@@ -821,6 +1173,16 @@ impl<'a> HtmlParser<'a> {
 						break;
 					}
 				}
+				if footnote {
+					self.new_line();
+					let serial = self.footnotes;
+					self.footnotes += 1;
+					// the last line is the empty one for following content
+					let end = self.lines.len() - 1;
+					for line in &mut self.lines[position.line..end] {
+						line.set_footnote(Some(serial));
+					}
+				}
 			}
 			Node::Document {} => self.convert_node_children(node.children()),
 			_ => {}
@@ -854,6 +1216,11 @@ impl<'a> HtmlParser<'a> {
 			let tag = ParseTag::Style(TextStyle::Title(title.to_string()));
 			insert_or_replace_tag(&mut element_tags, tag, false);
 		}
+		if element.attr("hidden").is_some()
+			|| element.attr("aria-hidden").map_or(false, |v| v.trim().eq_ignore_ascii_case("true"))
+			|| (!self.content_options.show_page_numbers && is_page_break(element)) {
+			insert_or_replace_tag(&mut element_tags, ParseTag::Hidden, false);
+		}
 		element_tags
 	}
 
@@ -870,12 +1237,55 @@ impl<'a> HtmlParser<'a> {
 				_ => {}
 			}
 		}
-		let line = self.lines.last_mut().unwrap();
+		let line = self.last_line();
 		let start = line.len();
 		line.push(IMAGE_CHAR);
 		line.push_style(TextStyle::Image(ImageStyle::new(href, width, height)), start..start + 1);
 	}
 
+	/// the last line for appending content,
+	/// setup block attributes if it's empty
+	#[inline]
+	fn last_line(&mut self) -> &mut Line
+	{
+		let line = self.lines.last_mut().unwrap();
+		if line.is_empty() {
+			line.set_indent(self.indent);
+			line.set_line_height(self.line_height);
+			line.set_text_indent(self.text_indent);
+		}
+		line
+	}
+
+	/// only for block started at new line, top margin is
+	/// converted to bottom margin of the previous block,
+	/// or kept on the first line if no previous block
+	fn apply_margin(&mut self, position: &Position, top: Option<f32>, bottom: Option<f32>)
+	{
+		if position.offset != 0 {
+			return;
+		}
+		let lines = &mut self.lines;
+		let Some(first) = (position.line..lines.len()).find(|idx| !lines[*idx].is_empty()) else {
+			return;
+		};
+		if let Some(top) = top {
+			if let Some(prev) = lines[..first].iter_mut().rev().find(|line| !line.is_empty()) {
+				prev.add_margin_bottom(top);
+			} else {
+				lines[first].add_margin_top(top);
+			}
+		}
+		if let Some(bottom) = bottom {
+			// block should end with new line
+			if lines.last().map_or(false, |line| line.is_empty()) {
+				if let Some(last) = lines.iter_mut().rev().find(|line| !line.is_empty()) {
+					last.add_margin_bottom(bottom);
+				}
+			}
+		}
+	}
+
 	fn newline_for_class(&mut self, element: &Element)
 	{
 		if !self.lines.last().unwrap().is_empty() {
@@ -909,6 +1319,27 @@ impl<'a> HtmlParser<'a> {
 		}
 	}
 
+	/// keep line breaks and spaces, expand tabs to tab stops
+	fn push_preformatted(&mut self, text: &str)
+	{
+		let tab_width = self.content_options.tab_width.max(1) as usize;
+		for (index, text) in text.split('\n').enumerate() {
+			if index > 0 {
+				self.new_line();
+			}
+			let line = self.last_line();
+			for ch in text.chars() {
+				match ch {
+					'\t' => for _ in 0..tab_width - line.len() % tab_width {
+						line.push(' ');
+					}
+					'\r' => {}
+					_ => line.push(ch),
+				}
+			}
+		}
+	}
+
 	#[inline]
 	fn new_paragraph(&mut self, child: NodeRef<Node>)
 	{
@@ -955,6 +1386,20 @@ impl<'a> HtmlParser<'a> {
 			Property::FontFamily(families) => self.font_family(families),
 			Property::TextDecorationLine(line, _) => Some(ParseTag::Style(TextStyle::Decoration(TextDecoration::line((*line).into())))),
 			Property::TextDecoration(decoration, _) => Some(self.text_decoration(decoration)),
+			Property::TextDecorationColor(color, _) => {
+				let decoration = TextDecoration {
+					color: Some(self.css_color(color)?),
+					..TextDecoration::line(TextDecorationLine::empty())
+				};
+				Some(ParseTag::Style(TextStyle::Decoration(decoration)))
+			}
+			Property::TextDecorationThickness(thickness) => {
+				let decoration = TextDecoration {
+					thickness: Some(decoration_thickness(thickness)?),
+					..TextDecoration::line(TextDecorationLine::empty())
+				};
+				Some(ParseTag::Style(TextStyle::Decoration(decoration)))
+			}
 			Property::Color(color) => Some(ParseTag::Style(TextStyle::Color(self.css_color(color)?))),
 			Property::BackgroundColor(color) => Some(ParseTag::Style(TextStyle::BackgroundColor(self.css_color(color)?))),
 			Property::Background(bg) => Some(ParseTag::Style(TextStyle::BackgroundColor(self.css_color(&bg[0].color)?))),
@@ -962,10 +1407,41 @@ impl<'a> HtmlParser<'a> {
 			Property::Display(Display::Keyword(DisplayKeyword::None)) => Some(ParseTag::Hidden),
 			Property::Width(size) => Some(ParseTag::Width(image_size(size)?)),
 			Property::Height(size) => Some(ParseTag::Height(image_size(size)?)),
+			Property::TextTransform(transform) => text_transform(transform),
+			Property::VerticalAlign(align) => vertical_align(align),
+			Property::WhiteSpace(WhiteSpace::NoWrap | WhiteSpace::Pre) => Some(ParseTag::Style(TextStyle::NoWrap)),
+			Property::LineHeight(line_height) => Some(ParseTag::LineHeight(line_height_scale(line_height)?)),
+			Property::TextIndent(text_indent) => Some(ParseTag::TextIndent(em_length(&text_indent.value)?)),
+			Property::Margin(margin) => margin_tag(em_length_or_auto(&margin.top), em_length_or_auto(&margin.bottom)),
+			Property::MarginTop(top) => margin_tag(em_length_or_auto(top), None),
+			Property::MarginBottom(bottom) => margin_tag(None, em_length_or_auto(bottom)),
+			// not supported by lightningcss, parsed as unknown properties
+			Property::Custom(custom) if matches!(custom.name.as_ref(), "page-break-before" | "break-before") =>
+				page_break(property),
+			Property::ListStyleType(_) | Property::ListStyle(..) => list_style_type(property),
 			_ => None,
 		}
 	}
 
+	/// configured font family for lang or xml:lang of the element,
+	/// full language tag first, then the primary subtag
+	fn lang_font(&mut self, element: &Element) -> Option<ParseTag>
+	{
+		let lang_fonts = &self.content_options.lang_fonts;
+		if lang_fonts.is_empty() {
+			return None;
+		}
+		let lang = element.attrs()
+			.find(|(name, _)| *name == "lang" || *name == "xml:lang")
+			.map(|(_, value)| value.trim().to_lowercase())?;
+		let family = lang_fonts.get(&lang).or_else(|| {
+			let primary = lang.split(['-', '_']).next()?;
+			lang_fonts.get(primary)
+		})?;
+		let (idx, _) = self.font_families.as_mut()?.insert_full(family.clone());
+		Some(ParseTag::Style(TextStyle::FontFamily(idx as u16)))
+	}
+
 	#[inline]
 	fn font_family(&mut self, families: &Vec<FontFamily>) -> Option<ParseTag>
 	{
@@ -1026,7 +1502,8 @@ impl<'a> HtmlParser<'a> {
 		let style = decoration.style.into();
 		let line = decoration.line.into();
 		let color = self.css_color(&decoration.color);
-		let decoration = TextDecoration { line, style, color };
+		let thickness = decoration_thickness(&decoration.thickness);
+		let decoration = TextDecoration { line, style, color, thickness };
 		ParseTag::Style(TextStyle::Decoration(decoration))
 	}
 
@@ -1111,6 +1588,63 @@ fn replace_font_size(tags: &mut LeveledParseTagSet, font_level: u8, relative: bo
 
 const DIV_PUSH_CLASSES: [&str; 3] = ["contents", "toc", "mulu"];
 
+#[inline]
+fn page_break(property: &Property) -> Option<ParseTag>
+{
+	let value = property.value_to_css_string(PrinterOptions::default()).ok()?;
+	match value.trim() {
+		"always" | "page" | "left" | "right" | "recto" | "verso" => Some(ParseTag::PageBreak),
+		_ => None,
+	}
+}
+
+/// list-style-type or keyword of list-style shorthand
+#[inline]
+fn list_style_type(property: &Property) -> Option<ParseTag>
+{
+	let value = property.value_to_css_string(PrinterOptions::default()).ok()?;
+	let style = value
+		.split_ascii_whitespace()
+		.find_map(ListStyleType::parse)?;
+	Some(ParseTag::ListStyle(style))
+}
+
+/// bijective base 26 like spreadsheet columns, a to z, then aa
+fn alpha_number(mut number: usize) -> Option<String>
+{
+	if number == 0 {
+		return None;
+	}
+	let mut chars = vec![];
+	while number > 0 {
+		number -= 1;
+		chars.push((b'a' + (number % 26) as u8) as char);
+		number /= 26;
+	}
+	Some(chars.iter().rev().collect())
+}
+
+/// roman numerals for 1 to 3999
+fn roman_number(mut number: usize) -> Option<String>
+{
+	const NUMERALS: [(usize, &str); 13] = [
+		(1000, "M"), (900, "CM"), (500, "D"), (400, "CD"),
+		(100, "C"), (90, "XC"), (50, "L"), (40, "XL"),
+		(10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+	];
+	if number == 0 || number >= 4000 {
+		return None;
+	}
+	let mut text = String::new();
+	for (value, numeral) in NUMERALS {
+		while number >= value {
+			text.push_str(numeral);
+			number -= value;
+		}
+	}
+	Some(text)
+}
+
 #[inline]
 fn style_parse_options<'a>() -> ParserOptions<'a, 'a>
 {
@@ -1123,6 +1657,15 @@ fn insert_or_replace_tag(styles: &mut LeveledParseTagSet, tag: ParseTag, importa
 {
 	match styles.binary_search_by(|s| s.0.cmp(&tag)) {
 		Ok(idx) => if important || !styles[idx].1 {
+			// margin top and bottom, decoration line, color and thickness
+			// may come from different properties
+			let tag = match (tag, &styles[idx].0) {
+				(ParseTag::Margin(top, bottom), ParseTag::Margin(orig_top, orig_bottom)) =>
+					ParseTag::Margin(top.or(*orig_top), bottom.or(*orig_bottom)),
+				(ParseTag::Style(TextStyle::Decoration(decoration)), ParseTag::Style(TextStyle::Decoration(orig))) =>
+					ParseTag::Style(TextStyle::Decoration(decoration.merge(orig))),
+				(tag, _) => tag,
+			};
 			styles[idx] = LeveledParseTag(tag, important);
 		}
 		Err(idx) => styles.insert(idx, LeveledParseTag(tag, important)),
@@ -1148,8 +1691,16 @@ fn find_tag(tags: &LeveledParseTagSet, tag: ParseTag) -> bool
 #[inline]
 fn unique_and_insert_tag(tags: &mut LeveledParseTagSet, tag: ParseTag)
 {
-	if let Err(idx) = tags.binary_search_by(|s| s.0.cmp(&tag)) {
-		tags.insert(idx, LeveledParseTag(tag, false));
+	match tags.binary_search_by(|s| s.0.cmp(&tag)) {
+		// color or thickness only decoration, lines taken from the inserting one
+		Ok(idx) => if let (ParseTag::Style(TextStyle::Decoration(orig)), ParseTag::Style(TextStyle::Decoration(decoration)))
+			= (&tags[idx].0, &tag) {
+			if orig.line.is_empty() {
+				let decoration = orig.clone().merge(decoration);
+				tags[idx].0 = ParseTag::Style(TextStyle::Decoration(decoration));
+			}
+		}
+		Err(idx) => tags.insert(idx, LeveledParseTag(tag, false)),
 	}
 }
 
@@ -1207,6 +1758,16 @@ fn font_size(size: &FontSize) -> ParseTag
 	ParseTag::Style(style)
 }
 
+#[inline]
+fn decoration_thickness(thickness: &TextDecorationThickness) -> Option<ElementSize>
+{
+	match thickness {
+		TextDecorationThickness::LengthPercentage(lp) => Some(length_percentage(lp)),
+		TextDecorationThickness::Auto |
+		TextDecorationThickness::FromFont => None,
+	}
+}
+
 fn image_size(size: &Size) -> Option<ElementSize>
 {
 	let es = match size {
@@ -1222,6 +1783,130 @@ fn image_size(size: &Size) -> Option<ElementSize>
 	Some(es)
 }
 
+/// line height as multiple of font size, None for normal
+fn line_height_scale(line_height: &LineHeight) -> Option<f32>
+{
+	match line_height {
+		LineHeight::Normal => None,
+		LineHeight::Number(number) => Some(*number),
+		LineHeight::Length(LengthPercentage::Dimension(lv)) =>
+			Some(length_value(lv, DEFAULT_FONT_SIZE).0),
+		LineHeight::Length(LengthPercentage::Percentage(percentage::Percentage(p))) =>
+			Some(*p),
+		LineHeight::Length(LengthPercentage::Calc(_)) => None,
+	}
+}
+
+/// length in em, percentage not supported
+#[inline]
+fn em_length(length: &LengthPercentage) -> Option<f32>
+{
+	match length {
+		LengthPercentage::Dimension(lv) => Some(length_value(lv, DEFAULT_FONT_SIZE).0),
+		LengthPercentage::Percentage(_) | LengthPercentage::Calc(_) => None,
+	}
+}
+
+#[inline]
+fn em_length_or_auto(length: &LengthPercentageOrAuto) -> Option<f32>
+{
+	match length {
+		LengthPercentageOrAuto::LengthPercentage(length) => em_length(length),
+		LengthPercentageOrAuto::Auto => None,
+	}
+}
+
+#[inline]
+fn margin_tag(top: Option<f32>, bottom: Option<f32>) -> Option<ParseTag>
+{
+	if top.is_none() && bottom.is_none() {
+		None
+	} else {
+		Some(ParseTag::Margin(top, bottom))
+	}
+}
+
+/// footnote or rearnote block, like <aside epub:type="footnote">
+#[inline]
+fn is_footnote(element: &Element) -> bool
+{
+	element.attr("epub:type")
+		.map_or(false, |t| t.split_ascii_whitespace()
+			.any(|t| t == "footnote" || t == "rearnote"))
+		|| matches!(element.attr("role"), Some("doc-footnote") | Some("doc-endnote"))
+}
+
+/// record links targeting footnotes in the same chapter,
+/// with the first line of the footnote
+fn setup_footnote_refs(lines: &mut Vec<Line>, id_map: &HashMap<String, Position>)
+{
+	let mut refs = vec![];
+	for (index, line) in lines.iter().enumerate() {
+		if line.footnote().is_some() {
+			continue;
+		}
+		line.link_iter(true, |link| {
+			let anchor = link.target.rsplit_once('#').map(|(_, anchor)| anchor);
+			if let Some(position) = anchor.and_then(|anchor| id_map.get(anchor)) {
+				if let Some(serial) = lines.get(position.line).and_then(|line| line.footnote()) {
+					let mut footnote_line = position.line;
+					while footnote_line > 0 && lines[footnote_line - 1].footnote() == Some(serial) {
+						footnote_line -= 1;
+					}
+					refs.push((index, link.range.start, footnote_line));
+				}
+			}
+			(false, None::<()>)
+		});
+	}
+	for (index, offset, footnote_line) in refs {
+		lines[index].add_footnote_ref(offset, footnote_line);
+	}
+}
+
+/// href attribute in xlink namespace, used by svg elements
+#[inline]
+fn xlink_href(element: &Element) -> Option<&str>
+{
+	let name = QualName::new(
+		Some(Prefix::from("xlink")),
+		Namespace::from("http://www.w3.org/1999/xlink"),
+		LocalName::from("href"));
+	element.attrs.get(&name).map(Deref::deref)
+}
+
+/// print page number marker, like <span epub:type="pagebreak" title="12"/>
+#[inline]
+fn is_page_break(element: &Element) -> bool
+{
+	element.attr("epub:type")
+		.map_or(false, |t| t.split_ascii_whitespace().any(|t| t == "pagebreak"))
+		|| element.attr("role") == Some("doc-pagebreak")
+}
+
+#[inline]
+fn text_transform(transform: &CssTextTransform) -> Option<ParseTag>
+{
+	let transform = match transform.case {
+		TextTransformCase::None => return None,
+		TextTransformCase::Uppercase => TextTransform::Uppercase,
+		TextTransformCase::Lowercase => TextTransform::Lowercase,
+		TextTransformCase::Capitalize => TextTransform::Capitalize,
+	};
+	Some(ParseTag::Style(TextStyle::TextTransform(transform)))
+}
+
+#[inline]
+fn vertical_align(align: &font::VerticalAlign) -> Option<ParseTag>
+{
+	let align = match align {
+		font::VerticalAlign::Keyword(font::VerticalAlignKeyword::Super) => VerticalAlign::Super,
+		font::VerticalAlign::Keyword(font::VerticalAlignKeyword::Sub) => VerticalAlign::Sub,
+		_ => return None,
+	};
+	Some(ParseTag::Style(TextStyle::VerticalAlign(align)))
+}
+
 #[inline]
 fn border_width(width: &BorderSideWidth) -> bool
 {
@@ -1371,10 +2056,38 @@ pub fn parse_stylesheet(css: &str, strict: bool) -> Result<StyleSheet>
 		.map_err(|err| anyhow!("{}",err.to_string()))
 }
 
+/// quotation marks of language for <q>, alternate for nested level
+fn quotation_marks(language: Option<&str>, level: usize) -> (char, char)
+{
+	let language = language.unwrap_or("").to_lowercase();
+	let primary = language.split(['-', '_']).next().unwrap_or("");
+	let (outer, inner) = match primary {
+		"de" | "cs" | "sk" | "sl" | "lt" | "is" =>
+			(('„', '“'), ('‚', '‘')),
+		"fr" | "ru" | "uk" | "be" | "es" | "it" | "pt" | "ca" | "el" | "no" | "nb" | "nn" =>
+			(('«', '»'), ('“', '”')),
+		"pl" | "hu" | "ro" | "nl" | "bg" =>
+			(('„', '”'), ('«', '»')),
+		"sv" | "fi" =>
+			(('”', '”'), ('’', '’')),
+		"ja" =>
+			(('「', '」'), ('『', '』')),
+		"zh" if language.contains("tw") || language.contains("hk") || language.contains("hant") =>
+			(('「', '」'), ('『', '』')),
+		_ => (('“', '”'), ('‘', '’')),
+	};
+	if level % 2 == 0 { outer } else { inner }
+}
+
 pub fn parse(options: HtmlParseOptions) -> Result<(HtmlContent, Vec<HtmlFontFaceDesc>)>
 {
 	let html = Html::parse_document(&options.html);
 	let stylesheets = load_stylesheets(&html, options.resolver);
+	let language = options.language
+		.map(|language| language.trim())
+		.filter(|language| !language.is_empty())
+		.or_else(|| html.root_element().attr("lang"))
+		.map(|language| language.to_owned());
 
 	let mut parser = HtmlParser {
 		resolver: options.resolver,
@@ -1384,6 +2097,15 @@ pub fn parse(options: HtmlParseOptions) -> Result<(HtmlContent, Vec<HtmlFontFace
 		font_face_map: Default::default(),
 		styles: vec![],
 		dark_mode: options.dark_mode,
+		content_options: options.content_options,
+		indent: 0,
+		line_height: None,
+		text_indent: None,
+		language,
+		quote_level: 0,
+		ordered_list: None,
+		preformatted: false,
+		footnotes: 0,
 
 		title: None,
 		lines: vec![Line::default()],
@@ -1440,3 +2162,103 @@ pub fn parse(options: HtmlParseOptions) -> Result<(HtmlContent, Vec<HtmlFontFace
 		id_map,
 	}, font_faces))
 }
+
+#[cfg(test)]
+mod tests {
+	use crate::book::{IMAGE_CHAR, Line};
+	use crate::html_parser::{alpha_number, parse, roman_number, ContentOptions, HtmlParseOptions};
+
+	/// link index and target of the first image char in lines
+	fn image_link(lines: &Vec<Line>) -> Option<(usize, String)>
+	{
+		for line in lines {
+			let Some(offset) = (0..line.len()).find(|idx| line.char_at(*idx) == Some(IMAGE_CHAR)) else {
+				continue;
+			};
+			line.image_at(offset)?;
+			return line.link_iter(true, |link| if link.range.contains(&offset) {
+				(true, Some((link.index, link.target.to_owned())))
+			} else {
+				(false, None)
+			});
+		}
+		None
+	}
+
+	#[test]
+	fn test_linked_image()
+	{
+		let html = r#"<html><body>
+<p>Contents</p>
+<div><a href="chapter1.html"><img src="cover.png"/></a></div>
+</body></html>"#;
+		let (content, _) = parse(HtmlParseOptions::new(html)).unwrap();
+		let (_, target) = image_link(content.lines()).unwrap();
+		assert_eq!(target, "chapter1.html");
+
+		let html = r#"<html><body>
+<svg xmlns:xlink="http://www.w3.org/1999/xlink">
+<a xlink:href="chapter1.html"><image xlink:href="cover.png"/></a>
+</svg>
+</body></html>"#;
+		let (content, _) = parse(HtmlParseOptions::new(html)).unwrap();
+		let (_, target) = image_link(content.lines()).unwrap();
+		assert_eq!(target, "chapter1.html");
+
+		let html = r#"<html><body><img src="cover.png"/></body></html>"#;
+		let (content, _) = parse(HtmlParseOptions::new(html)).unwrap();
+		assert!(image_link(content.lines()).is_none());
+	}
+
+	#[test]
+	fn test_page_footnotes()
+	{
+		let html = r##"<html><body>
+<p>Text with a note<a epub:type="noteref" href="#n1">1</a>.</p>
+<p>More text</p>
+<aside epub:type="footnote" id="n1"><p>The note</p></aside>
+</body></html>"##;
+		let content_options = ContentOptions { page_footnotes: true, ..Default::default() };
+		let (content, _) = parse(HtmlParseOptions::new(html)
+			.with_content_options(content_options)).unwrap();
+		let lines = content.lines();
+		let note_line = lines.iter()
+			.position(|line| line.to_string() == "The note")
+			.unwrap();
+		let text_line = &lines[lines.iter()
+			.position(|line| line.to_string().starts_with("Text"))
+			.unwrap()];
+		assert_eq!(lines[note_line].footnote(), Some(0));
+		assert_eq!(text_line.footnote(), None);
+		assert_eq!(text_line.footnote_refs(), &vec![(16, note_line)]);
+
+		// kept in text flow without the option
+		let (content, _) = parse(HtmlParseOptions::new(html)).unwrap();
+		assert!(content.lines().iter()
+			.all(|line| line.footnote().is_none() && line.footnote_refs().is_empty()));
+	}
+
+	#[test]
+	fn test_list_markers()
+	{
+		assert_eq!(alpha_number(1).unwrap(), "a");
+		assert_eq!(alpha_number(26).unwrap(), "z");
+		assert_eq!(alpha_number(28).unwrap(), "ab");
+		assert_eq!(roman_number(4).unwrap(), "IV");
+		assert_eq!(roman_number(1994).unwrap(), "MCMXCIV");
+		assert!(roman_number(0).is_none());
+
+		let html = r#"<html><body>
+<ol style="list-style-type: lower-roman"><li>One</li><li>Two</li></ol>
+<ol type="A" start="3"><li>Three</li><li value="10">Ten</li></ol>
+<ol><li>First<ul><li>Bullet</li></ul></li><li>Second</li></ol>
+</body></html>"#;
+		let (content, _) = parse(HtmlParseOptions::new(html)).unwrap();
+		let lines: Vec<String> = content.lines().iter()
+			.map(|line| line.to_string())
+			.filter(|line| !line.is_empty())
+			.collect();
+		assert_eq!(lines, vec!["i. One", "ii. Two", "C. Three", "J. Ten",
+			"1. First", "Bullet", "2. Second"]);
+	}
+}
@@ -1,9 +1,15 @@
 use cursive::Cursive;
+use cursive::event::EventResult;
 use cursive::event::Key::Esc;
-use cursive::traits::Scrollable;
-use cursive::views::{Dialog, OnEventView, SelectView};
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::view::SizeConstraint;
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, SelectView};
 use crate::terminal::Listable;
 
+const LIST_VIEW_NAME: &str = "list";
+const LIST_FILTER_NAME: &str = "list_filter";
+
+/// select from list, j/k for moving, / for filtering by title
 pub(crate) fn list_dialog<'a, F, I, T: Listable>(title: &str, iterator: I, current_value: usize, callback: F) -> OnEventView<Dialog>
 	where
 		F: Fn(&mut Cursive, usize) + 'static,
@@ -15,19 +21,53 @@ pub(crate) fn list_dialog<'a, F, I, T: Listable>(title: &str, iterator: I, curre
 			callback(s, *v);
 		});
 	let mut selected = 0;
+	let mut items = vec![];
 	for (idx, info) in iterator.enumerate() {
 		let id = info.id();
 		select_view.add_item(info.title(), id);
+		items.push((info.title().to_owned(), id));
 		if current_value == id {
 			selected = idx;
 		}
 	}
-	let mut scroll_view = select_view
+	let select_view = OnEventView::new(select_view
 		.selected(selected)
+		.with_name(LIST_VIEW_NAME))
+		.on_pre_event_inner('j', |v, _| {
+			let callback = v.get_mut().select_down(1);
+			Some(EventResult::Consumed(Some(callback)))
+		})
+		.on_pre_event_inner('k', |v, _| {
+			let callback = v.get_mut().select_up(1);
+			Some(EventResult::Consumed(Some(callback)))
+		});
+	let mut scroll_view = select_view
 		.scrollable()
 		.show_scrollbars(false);
 	scroll_view.scroll_to_important_area();
-	let dialog = OnEventView::new(Dialog::around(scroll_view).title(title))
+
+	let filter_view = EditView::new()
+		.on_edit(move |s, pattern, _| {
+			let pattern = pattern.trim().to_lowercase();
+			s.call_on_name(LIST_VIEW_NAME, |view: &mut SelectView<usize>| {
+				view.clear();
+				for (title, id) in &items {
+					if pattern.is_empty() || title.to_lowercase().contains(&pattern) {
+						view.add_item(title.as_str(), *id);
+					}
+				}
+			});
+		})
+		.on_submit(|s, _| {
+			s.focus_name(LIST_VIEW_NAME).unwrap();
+		})
+		.with_name(LIST_FILTER_NAME)
+		.resized(SizeConstraint::Full, SizeConstraint::Fixed(1));
+	let layout = LinearLayout::vertical()
+		.child(scroll_view)
+		.child(filter_view);
+	let dialog = OnEventView::new(Dialog::around(layout).title(title))
+		.on_event('/', |s| { s.focus_name(LIST_FILTER_NAME).unwrap(); })
 		.on_event('q', |s| { s.pop_layer(); })
 		.on_event(Esc, |s| { s.pop_layer(); });
 	dialog
@@ -5,15 +5,17 @@ extern crate core;
 extern crate markup5ever;
 
 use std::env;
+use std::io::{BufWriter, stdout, Write};
 use anyhow::{anyhow, Result};
 use clap::Parser;
 use dirs::{cache_dir, config_dir};
 use rust_embed::RustEmbed;
 
-use crate::book::BookLoader;
-use crate::common::Position;
-use crate::config::load_config;
-use crate::container::ContainerManager;
+use crate::book::{Book, BookLoader};
+use crate::common::{Position, TraceInfo};
+use crate::config::{BookLoadingInfo, load_config};
+use crate::container::{ContainerManager, load_book, load_container};
+use crate::controller::StartTarget;
 #[cfg(feature = "i18n")]
 use crate::i18n::I18n;
 
@@ -24,6 +26,8 @@ mod book;
 mod html_parser;
 mod container;
 mod controller;
+#[cfg(feature = "dict")]
+mod dict;
 #[cfg(feature = "gui")]
 mod gui;
 #[cfg(feature = "i18n")]
@@ -31,7 +35,10 @@ mod i18n;
 mod color;
 #[cfg(feature = "open")]
 mod open;
+#[cfg(feature = "opds")]
+mod opds;
 mod config;
+mod sync;
 mod xhtml;
 
 const TBR_BOOK_ENV_KEY: &str = "TBR_BOOK";
@@ -60,9 +67,35 @@ struct Cli {
 	#[clap(
 		short,
 		long,
+		conflicts_with = "gui",
 		help = "Using terminal to read e-book, by default if gui exists, tbr will using gui view."
 	)]
 	terminal: bool,
+	#[cfg(feature = "gui")]
+	#[clap(
+		short,
+		long,
+		help = "Using gui view to read e-book, even if terminal is preferred in config."
+	)]
+	gui: bool,
+	#[clap(
+		short,
+		long,
+		help = "Print plain text of the e-book to stdout and exit."
+	)]
+	dump: bool,
+	#[clap(
+		short,
+		long,
+		help = "Open at the chapter of this number, start from 1. Only dump this chapter with --dump."
+	)]
+	chapter: Option<usize>,
+	#[clap(
+		short,
+		long,
+		help = "Open at the element of this id, may be prefixed by the file like \"text/ch1.xhtml#id\". Dump from it with --dump."
+	)]
+	anchor: Option<String>,
 	filename: Option<String>,
 }
 
@@ -78,6 +111,10 @@ struct Asset;
 
 fn main() -> Result<()> {
 	let cli = Cli::parse();
+	if cli.dump {
+		let filename = cli.filename.ok_or(anyhow!("No file to dump."))?;
+		return dump(&filename, cli.chapter, cli.anchor.as_deref());
+	}
 	let config_dir = match config_dir() {
 		None => return Err(anyhow!("Can not find config dir.")),
 		Some(x) => x.join(package_name!()),
@@ -99,15 +136,76 @@ fn main() -> Result<()> {
 		config_file,
 		&config_dir,
 		&cache_dir)?;
+	let target = if cli.chapter.is_some() || cli.anchor.is_some() {
+		Some(StartTarget { chapter: cli.chapter, anchor: cli.anchor })
+	} else {
+		None
+	};
 	#[cfg(feature = "gui")]
-	if !cli.terminal {
-		if let Some((curr, c)) = gui::start(current, configuration)? {
+	if cli.gui || (!cli.terminal && !configuration.gui.prefer_terminal) {
+		if let Some((curr, c)) = gui::start(current, target.clone(), configuration)? {
 			current = curr;
 			configuration = c;
 		} else {
 			return Ok(());
 		}
 	}
-	terminal::start(current, configuration, config_dir)?;
+	terminal::start(current, target, configuration, config_dir)?;
 	Ok(())
 }
+
+fn dump(filename: &str, chapter: Option<usize>, anchor: Option<&str>) -> Result<()>
+{
+	let mut out = BufWriter::new(stdout().lock());
+	if chapter.is_some() || anchor.is_some() {
+		let (mut book, trace) = target_position(filename, chapter, anchor)?;
+		if book.current_chapter() != trace.chapter {
+			book.goto_chapter(trace.chapter)?;
+		}
+		for line in book.lines().iter().skip(trace.line) {
+			writeln!(out, "{}", line)?;
+		}
+		out.flush()?;
+		return Ok(());
+	}
+	let container_manager = ContainerManager::default();
+	let mut container = load_container(&container_manager, filename)?;
+	let book_count = container.inner_book_names().map_or(1, |names| names.len());
+	for inner_book in 0..book_count {
+		let loading = BookLoadingInfo::NewReading(filename, inner_book, 0, 0, None);
+		let (mut book, _) = load_book(&container_manager, &mut container, loading)?;
+		loop {
+			for line in book.lines() {
+				writeln!(out, "{}", line)?;
+			}
+			let next = book.current_chapter() + 1;
+			if book.goto_chapter(next)?.is_none() {
+				break;
+			}
+		}
+	}
+	out.flush()?;
+	Ok(())
+}
+
+/// load the book at the chapter, then locate the anchor if any
+fn target_position(filename: &str, chapter: Option<usize>,
+	anchor: Option<&str>) -> Result<(Box<dyn Book>, TraceInfo)>
+{
+	let chapter_index = match chapter {
+		Some(0) => return Err(anyhow!("Chapter number start from 1.")),
+		Some(chapter) => chapter - 1,
+		None => 0,
+	};
+	let container_manager = ContainerManager::default();
+	let mut container = load_container(&container_manager, filename)?;
+	let loading = BookLoadingInfo::NewReading(filename, 0, chapter_index, 0, None);
+	let (mut book, _) = load_book(&container_manager, &mut container, loading)?;
+	let trace = if let Some(anchor) = anchor {
+		book.anchor_position(anchor)
+			.ok_or_else(|| anyhow!("Anchor not found: {}", anchor))?
+	} else {
+		TraceInfo { chapter: book.current_chapter(), line: 0, offset: 0 }
+	};
+	Ok((book, trace))
+}
@@ -0,0 +1,240 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use roxmltree::{Document, Node};
+
+use crate::config::OpdsConfiguration;
+
+const ACQUISITION_REL_PREFIX: &str = "http://opds-spec.org/acquisition";
+const ATOM_TYPE: &str = "application/atom+xml";
+// formats can be opened, in preferred order
+const SUPPORTED_TYPES: [(&str, &str); 4] = [
+	("application/epub+zip", "epub"),
+	("text/html", "html"),
+	("application/xhtml+xml", "xhtml"),
+	("text/plain", "txt"),
+];
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub enum OpdsLink {
+	/// nested catalog
+	Navigation(String),
+	/// downloadable book, with url and file extension
+	Acquisition(String, &'static str),
+}
+
+pub struct OpdsEntry {
+	pub title: String,
+	pub author: Option<String>,
+	pub link: OpdsLink,
+}
+
+pub struct OpdsFeed {
+	pub title: String,
+	pub entries: Vec<OpdsEntry>,
+	pub next: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct OpdsClient {
+	authorization: Option<String>,
+}
+
+impl OpdsClient {
+	pub fn new(config: &OpdsConfiguration) -> Self
+	{
+		let authorization = config.username.as_ref().map(|username| {
+			let password = config.password.as_ref().map_or("", |p| p.as_str());
+			let credential = format!("{}:{}", username, password);
+			format!("Basic {}", base64(credential.as_bytes()))
+		});
+		OpdsClient { authorization }
+	}
+
+	pub fn feed(&self, url: &str) -> Result<OpdsFeed>
+	{
+		let text = self.get(url)?.into_string()?;
+		parse_feed(url, &text)
+	}
+
+	/// download book into the folder, return the saved file path
+	pub fn download(&self, url: &str, title: &str, ext: &str, dir: &Path)
+		-> Result<PathBuf>
+	{
+		fs::create_dir_all(dir)?;
+		let path = dir.join(format!("{}.{}", file_name(title), ext));
+		let mut reader = self.get(url)?.into_reader();
+		let mut file = File::create(&path)?;
+		if let Err(err) = io::copy(&mut reader, &mut file) {
+			drop(file);
+			let _ = fs::remove_file(&path);
+			return Err(err.into());
+		}
+		Ok(path)
+	}
+
+	#[inline]
+	fn get(&self, url: &str) -> Result<ureq::Response>
+	{
+		let mut request = ureq::get(url);
+		if let Some(authorization) = &self.authorization {
+			request = request.set("Authorization", authorization);
+		}
+		Ok(request.call()?)
+	}
+}
+
+fn parse_feed(url: &str, text: &str) -> Result<OpdsFeed>
+{
+	let doc = Document::parse(text)?;
+	let feed = doc.root_element();
+	if feed.tag_name().name() != "feed" {
+		return Err(anyhow!("Invalid OPDS feed: {}", url));
+	}
+	let title = child_text(&feed, "title").unwrap_or_else(|| url.to_owned());
+	let mut next = None;
+	let mut entries = vec![];
+	for child in feed.children() {
+		match child.tag_name().name() {
+			"link" if child.attribute("rel") == Some("next") =>
+				if let Some(href) = child.attribute("href") {
+					next = Some(resolve_url(url, href));
+				}
+			"entry" => if let Some(entry) = parse_entry(url, &child) {
+				entries.push(entry);
+			}
+			_ => {}
+		}
+	}
+	Ok(OpdsFeed { title, entries, next })
+}
+
+fn parse_entry(url: &str, entry: &Node) -> Option<OpdsEntry>
+{
+	let title = child_text(entry, "title")?;
+	let author = entry.children()
+		.find(|n| n.tag_name().name() == "author")
+		.and_then(|author| child_text(&author, "name"));
+	let mut navigation = None;
+	let mut acquisition: Option<(usize, &str)> = None;
+	for link in entry.children().filter(|n| n.tag_name().name() == "link") {
+		let Some(href) = link.attribute("href") else {
+			continue;
+		};
+		let link_type = link.attribute("type").unwrap_or("");
+		let rel = link.attribute("rel").unwrap_or("");
+		if rel.starts_with(ACQUISITION_REL_PREFIX) {
+			if let Some(idx) = SUPPORTED_TYPES.iter()
+				.position(|(mime, _)| link_type.starts_with(mime)) {
+				if acquisition.map_or(true, |(prev, _)| idx < prev) {
+					acquisition = Some((idx, href));
+				}
+			}
+		} else if navigation.is_none() && link_type.starts_with(ATOM_TYPE) {
+			navigation = Some(href);
+		}
+	}
+	let link = if let Some((idx, href)) = acquisition {
+		OpdsLink::Acquisition(resolve_url(url, href), SUPPORTED_TYPES[idx].1)
+	} else {
+		OpdsLink::Navigation(resolve_url(url, navigation?))
+	};
+	Some(OpdsEntry { title, author, link })
+}
+
+#[inline]
+fn child_text(node: &Node, name: &str) -> Option<String>
+{
+	let text = node.children()
+		.find(|n| n.tag_name().name() == name)?
+		.text()?
+		.trim();
+	if text.is_empty() {
+		None
+	} else {
+		Some(text.to_owned())
+	}
+}
+
+fn resolve_url(base: &str, href: &str) -> String
+{
+	if href.starts_with("http://") || href.starts_with("https://") {
+		return href.to_owned();
+	}
+	let host_end = base.find("://")
+		.and_then(|idx| base[idx + 3..].find('/').map(|i| idx + 3 + i))
+		.unwrap_or(base.len());
+	if href.starts_with("//") {
+		let scheme_end = base.find("://").map_or(0, |idx| idx + 1);
+		format!("{}{}", &base[..scheme_end], href)
+	} else if href.starts_with('/') {
+		format!("{}{}", &base[..host_end], href)
+	} else {
+		let path = &base[host_end..];
+		let path = path.split(|c| c == '?' || c == '#').next().unwrap_or("");
+		let dir_end = path.rfind('/').map_or(0, |idx| idx + 1);
+		let dir = if dir_end == 0 { "/" } else { &path[..dir_end] };
+		format!("{}{}{}", &base[..host_end], dir, href)
+	}
+}
+
+#[inline]
+fn file_name(title: &str) -> String
+{
+	let name: String = title.chars()
+		.map(|ch| if ch.is_control() || "/\\:*?\"<>|".contains(ch) { '_' } else { ch })
+		.collect();
+	let name = name.trim();
+	if name.is_empty() {
+		String::from("book")
+	} else {
+		name.to_owned()
+	}
+}
+
+fn base64(bytes: &[u8]) -> String
+{
+	let mut text = String::with_capacity((bytes.len() + 2) / 3 * 4);
+	for chunk in bytes.chunks(3) {
+		let b0 = chunk[0] as u32;
+		let b1 = chunk.get(1).map_or(0, |b| *b as u32);
+		let b2 = chunk.get(2).map_or(0, |b| *b as u32);
+		let n = (b0 << 16) | (b1 << 8) | b2;
+		for i in 0..4 {
+			if i > chunk.len() {
+				text.push('=');
+			} else {
+				let idx = (n >> (18 - i * 6)) & 0x3f;
+				text.push(BASE64_CHARS[idx as usize] as char);
+			}
+		}
+	}
+	text
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::opds::{base64, resolve_url};
+
+	#[test]
+	fn test_resolve_url()
+	{
+		let base = "https://example.com/opds/root.xml?page=1";
+		assert_eq!(resolve_url(base, "https://other.org/a"), "https://other.org/a");
+		assert_eq!(resolve_url(base, "/books/1"), "https://example.com/books/1");
+		assert_eq!(resolve_url(base, "new.xml"), "https://example.com/opds/new.xml");
+		assert_eq!(resolve_url(base, "//cdn.org/b.epub"), "https://cdn.org/b.epub");
+		assert_eq!(resolve_url("https://example.com", "a.xml"), "https://example.com/a.xml");
+	}
+
+	#[test]
+	fn test_base64()
+	{
+		assert_eq!(base64(b"user:pass"), "dXNlcjpwYXNz");
+		assert_eq!(base64(b"ab"), "YWI=");
+		assert_eq!(base64(b"a"), "YQ==");
+	}
+}
@@ -1,6 +1,6 @@
 use std::{env, fs};
-use std::path::PathBuf;
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 
@@ -30,12 +30,21 @@ impl Opener {
 
 	pub fn open_link(&mut self, url: &str) -> Result<()>
 	{
-		if url.starts_with("http://") || url.starts_with("https://") {
+		if is_external_link(url) {
 			open::that(url)?;
 		}
 		Ok(())
 	}
 
+	/// reveal the file in system file browser
+	pub fn open_path(&mut self, path: &Path) -> Result<()>
+	{
+		if !path.exists() {
+			return Err(anyhow!("File not exists: {}", path.display()));
+		}
+		reveal(path)
+	}
+
 	/// impl Drop not called on exit, so need call this manually
 	pub fn cleanup(&mut self)
 	{
@@ -47,6 +56,48 @@ impl Opener {
 	}
 }
 
+/// link to outside of the book, should be opened by system
+#[inline]
+pub fn is_external_link(url: &str) -> bool
+{
+	let url = url.trim_start();
+	["http://", "https://", "mailto:"].iter()
+		.any(|scheme| url.get(..scheme.len())
+			.map_or(false, |prefix| prefix.eq_ignore_ascii_case(scheme)))
+}
+
+#[cfg(target_os = "windows")]
+#[inline]
+fn reveal(path: &Path) -> Result<()>
+{
+	let mut arg = std::ffi::OsString::from("/select,");
+	arg.push(path);
+	std::process::Command::new("explorer").arg(arg).spawn()?;
+	Ok(())
+}
+
+#[cfg(target_os = "macos")]
+#[inline]
+fn reveal(path: &Path) -> Result<()>
+{
+	std::process::Command::new("open").arg("-R").arg(path).spawn()?;
+	Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+#[inline]
+fn reveal(path: &Path) -> Result<()>
+{
+	// no standard way to select file, so just open the folder
+	let folder = if path.is_dir() {
+		path
+	} else {
+		path.parent().ok_or(anyhow!("No parent folder: {}", path.display()))?
+	};
+	open::that(folder)?;
+	Ok(())
+}
+
 fn create_tmp(tmp_dir: &PathBuf, ext: &str, bytes: &[u8]) -> Result<PathBuf>
 {
 	loop {
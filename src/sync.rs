@@ -0,0 +1,103 @@
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::ReadingInfo;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+// only the leading content hashed, for large books
+const HASH_PREFIX_SIZE: u64 = 1024 * 1024;
+
+/// reading position shared with other devices by the sync folder,
+/// file name is hash of book content and size, so the book path not matters
+#[derive(Serialize, Deserialize)]
+pub struct SyncPosition {
+	pub inner_book: usize,
+	pub chapter: usize,
+	pub line: usize,
+	pub position: usize,
+	pub ts: u64,
+}
+
+pub fn save_position(sync_dir: &Path, reading: &ReadingInfo) -> Result<()>
+{
+	let Some(path) = sync_file(sync_dir, &reading.filename)? else {
+		return Ok(());
+	};
+	let sync = SyncPosition {
+		inner_book: reading.inner_book,
+		chapter: reading.chapter,
+		line: reading.line,
+		position: reading.position,
+		ts: reading.ts(),
+	};
+	fs::create_dir_all(sync_dir)?;
+	let text = serde_json::to_string(&sync)?;
+	fs::write(path, text)?;
+	Ok(())
+}
+
+/// position saved by other device, only if newer then local reading
+pub fn newer_position(sync_dir: &Path, reading: &ReadingInfo)
+	-> Result<Option<SyncPosition>>
+{
+	let Some(path) = sync_file(sync_dir, &reading.filename)? else {
+		return Ok(None);
+	};
+	if !path.is_file() {
+		return Ok(None);
+	}
+	let text = fs::read_to_string(path)?;
+	let sync: SyncPosition = serde_json::from_str(&text)?;
+	if sync.ts <= reading.ts() {
+		return Ok(None);
+	}
+	if sync.inner_book == reading.inner_book
+		&& sync.chapter == reading.chapter
+		&& sync.line == reading.line
+		&& sync.position == reading.position {
+		return Ok(None);
+	}
+	Ok(Some(sync))
+}
+
+#[inline]
+fn sync_file(sync_dir: &Path, filename: &str) -> Result<Option<PathBuf>>
+{
+	let path = Path::new(filename);
+	// folder books not supported
+	if !path.is_file() {
+		return Ok(None);
+	}
+	// mtime not kept when copied to other device
+	let size = fs::metadata(path)?.len();
+	let mut prefix = vec![];
+	File::open(path)?.take(HASH_PREFIX_SIZE).read_to_end(&mut prefix)?;
+	let hash = fnv_update(FNV_OFFSET, &prefix);
+	let hash = fnv_update(hash, &size.to_le_bytes());
+	Ok(Some(sync_dir.join(format!("{:016x}.json", hash))))
+}
+
+/// FNV-1a of the name, for files keyed by it but not the content
+#[cfg(feature = "gui")]
+#[inline]
+pub fn name_hash(name: &str) -> u64
+{
+	fnv_update(FNV_OFFSET, name.as_bytes())
+}
+
+/// FNV-1a, stable for all platforms and builds
+#[inline]
+fn fnv_update(mut hash: u64, bytes: &[u8]) -> u64
+{
+	for byte in bytes {
+		hash ^= *byte as u64;
+		hash = hash.wrapping_mul(FNV_PRIME);
+	}
+	hash
+}
@@ -9,17 +9,22 @@ use cursive::event::Key::Esc;
 use cursive::theme::{Error, load_theme_file, load_toml, Theme};
 use cursive::traits::Resizable;
 use cursive::view::{Nameable, SizeConstraint};
-use cursive::views::{EditView, LinearLayout, OnEventView, TextView, ViewRef};
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, TextView, ViewRef};
 
 use view::ReadingView;
 
 use crate::{Asset, description, version, version_string};
 use crate::config::{BookLoadingInfo, Configuration};
+use crate::controller::StartTarget;
 use crate::list::{list_dialog, ListIterator};
+use crate::sync;
+use crate::sync::SyncPosition;
 use crate::terminal::input_method::{InputMethod, setup_im};
 
 pub mod view;
 mod input_method;
+#[cfg(feature = "dict")]
+mod dict;
 
 const STATUS_VIEW_NAME: &str = "status";
 const TEXT_VIEW_NAME: &str = "text";
@@ -28,6 +33,9 @@ const INPUT_VIEW_NAME: &str = "input";
 const INPUT_LAYOUT_NAME: &str = "input_layout";
 const SEARCH_LABEL_TEXT: &str = "Search: ";
 const GOTO_LABEL_TEXT: &str = "Goto line: ";
+const GOTO_PAGE_LABEL_TEXT: &str = "Goto page: ";
+const GOTO_CHAPTER_LABEL_TEXT: &str = "Goto chapter: ";
+const PROGRESS_VIEW_WIDTH: usize = 25;
 
 struct Themes {
 	bright: Theme,
@@ -50,6 +58,8 @@ struct TerminalContext {
 	configuration: Configuration,
 	themes: Themes,
 	im: Option<Box<dyn InputMethod>>,
+	#[cfg(feature = "dict")]
+	dictionary: Option<dict::DictionaryLookup>,
 }
 
 pub trait Listable {
@@ -71,8 +81,22 @@ impl<'a> Listable for (&'a str, usize) {
 	}
 }
 
-pub fn start(current: Option<String>, mut configuration: Configuration,
-	config_dir: PathBuf) -> Result<()>
+impl Listable for (String, usize) {
+	#[inline]
+	fn title(&self) -> &str
+	{
+		&self.0
+	}
+
+	#[inline]
+	fn id(&self) -> usize
+	{
+		self.1
+	}
+}
+
+pub fn start(current: Option<String>, target: Option<StartTarget>,
+	mut configuration: Configuration, config_dir: PathBuf) -> Result<()>
 {
 	let current = current.ok_or(anyhow!("No file to open."))?;
 	println!("Loading {} ...", current);
@@ -81,23 +105,57 @@ pub fn start(current: Option<String>, mut configuration: Configuration,
 	let themes = load_themes(&config_dir)?;
 	let theme = themes.get(configuration.dark_theme);
 	app.set_theme(theme.clone());
-	let reading_view = ReadingView::new(configuration.render_han, loading)?;
+	let mut reading_view = ReadingView::new(
+		configuration.render_han,
+		loading,
+		configuration.reading_speed.clone(),
+		configuration.chars_per_page,
+		configuration.content_options())?;
+	// the target given overrides the synced position
+	let sync_position = if let Some(target) = &target {
+		reading_view.goto_target(target)?;
+		None
+	} else if let Some(sync_dir) = &configuration.sync_dir {
+		match sync::newer_position(sync_dir, &reading_view.reading_info()) {
+			Ok(sync_position) => sync_position,
+			Err(e) => {
+				eprintln!("Failed load sync position: {}", e.to_string());
+				None
+			}
+		}
+	} else {
+		None
+	};
 	// turn off ime at start
 	let im = setup_im();
-	app.set_user_data(TerminalContext { current, configuration, themes, im });
+	app.set_user_data(TerminalContext {
+		current,
+		configuration,
+		themes,
+		im,
+		#[cfg(feature = "dict")]
+		dictionary: None,
+	});
 	let status_view = LinearLayout::horizontal()
 		.child(TextView::new(&reading_view.status_msg())
 			.no_wrap()
 			.with_name(STATUS_VIEW_NAME)
 			.resized(SizeConstraint::Full, SizeConstraint::Fixed(1)))
+		.child(reading_view.progress_view()
+			.resized(SizeConstraint::Fixed(PROGRESS_VIEW_WIDTH), SizeConstraint::Fixed(1)))
 		.with_name(STATUS_LAYOUT_NAME);
+	let text_view = OnEventView::new(reading_view.with_name(TEXT_VIEW_NAME).full_screen());
+	#[cfg(feature = "dict")]
+	let text_view = text_view.on_event('d', |s| dict::lookup_dictionary(s));
 	let layout = LinearLayout::vertical()
-		.child(OnEventView::new(reading_view.with_name(TEXT_VIEW_NAME).full_screen())
+		.child(text_view
 			.on_event('/', |s| setup_search_view(s))
 			.on_event(Event::CtrlChar('x'), |s| switch_render(s))
 			.on_event('q', |s| s.quit())
 			.on_event('v', |s| update_status(s, version_string!()))
 			.on_event('g', |s| goto_line(s))
+			.on_event('p', |s| goto_page(s))
+			.on_event('C', |s| goto_chapter(s))
 			.on_event('b', |s| select_book(s))
 			.on_event('h', |s| select_history(s))
 			.on_event('t', |s| switch_theme(s))
@@ -112,7 +170,11 @@ pub fn start(current: Option<String>, mut configuration: Configuration,
 					return;
 				}
 				let toc_index = reading_view.toc_index();
-				let dialog = list_dialog("Select TOC", option.unwrap(), toc_index, move |s, new_index| {
+				// indent entries by toc level
+				let entries = option.unwrap().map(|info| (
+					format!("{}{}", "  ".repeat(info.level.saturating_sub(1)), info.title),
+					info.index));
+				let dialog = list_dialog("Select TOC", entries, toc_index, move |s, new_index| {
 					let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
 					if toc_index != new_index {
 						if let Some(status) = reading_view.goto_toc(new_index) {
@@ -124,6 +186,9 @@ pub fn start(current: Option<String>, mut configuration: Configuration,
 			}))
 		.child(status_view);
 	app.add_fullscreen_layer(layout);
+	if let Some(sync_position) = sync_position {
+		app.add_layer(sync_position_dialog(sync_position));
+	}
 	app.run();
 	let reading_view: ViewRef<ReadingView> = app.find_name(TEXT_VIEW_NAME).unwrap();
 	let mut reading_now = reading_view.reading_info();
@@ -131,9 +196,29 @@ pub fn start(current: Option<String>, mut configuration: Configuration,
 	configuration = controller_context.configuration;
 	configuration.save_reading(&mut reading_now)?;
 	configuration.save()?;
+	if let Some(sync_dir) = &configuration.sync_dir {
+		if let Err(e) = sync::save_position(sync_dir, &reading_now) {
+			eprintln!("Failed save sync position: {}", e.to_string());
+		}
+	}
 	Ok(())
 }
 
+fn sync_position_dialog(sync: SyncPosition) -> Dialog
+{
+	Dialog::text("A newer reading position saved by other device found, jump to it?")
+		.title("Reading position synced")
+		.button("Ok", move |s| {
+			s.pop_layer();
+			let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+			let msg = reading_view.goto(sync.inner_book, sync.chapter, sync.line, sync.position)
+				.unwrap_or_else(|e| e.to_string());
+			drop(reading_view);
+			update_status(s, &msg);
+		})
+		.dismiss_button("Cancel")
+}
+
 pub(crate) fn update_status_callback(status: String) -> Callback {
 	Callback::from_fn(move |s| {
 		update_status(s, &status);
@@ -142,11 +227,7 @@ pub(crate) fn update_status_callback(status: String) -> Callback {
 
 fn switch_render(s: &mut Cursive) {
 	let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
-	s.with_user_data(|controller_context: &mut TerminalContext| {
-		let configuration = &mut controller_context.configuration;
-		configuration.render_han = !configuration.render_han;
-		reading_view.switch_render(configuration.render_han);
-	});
+	reading_view.switch_render();
 }
 
 fn select_book(s: &mut Cursive) {
@@ -250,6 +331,34 @@ fn goto_line(app: &mut Cursive) {
 	}, |_| {});
 }
 
+fn goto_page(app: &mut Cursive) {
+	setup_input_view(app, GOTO_PAGE_LABEL_TEXT, "", |s, label| {
+		if let Some(label) = label {
+			let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+			let msg = reading_view.goto_page(label.trim())?;
+			drop(reading_view);
+			update_status(s, &msg);
+		}
+		Ok(())
+	}, |_| {});
+}
+
+fn goto_chapter(app: &mut Cursive) {
+	setup_input_view(app, GOTO_CHAPTER_LABEL_TEXT, "", |s, chapter_no| {
+		if let Some(chapter_no) = chapter_no {
+			let chapter_no = chapter_no.trim().parse::<usize>()?;
+			let by_toc = s.with_user_data(|context: &mut TerminalContext|
+				context.configuration.chapter_number_by_toc)
+				.unwrap_or(false);
+			let mut reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+			let msg = reading_view.goto_chapter_index(chapter_no, by_toc)?;
+			drop(reading_view);
+			update_status(s, &msg);
+		}
+		Ok(())
+	}, |_| {});
+}
+
 fn setup_search_view(app: &mut Cursive) {
 	fn set_im_active(s: &mut Cursive, active: Option<bool>, update_restore: bool)
 	{
@@ -0,0 +1,153 @@
+use cursive::Cursive;
+use cursive::event::{Event, Key};
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::views::{Dialog, EditView, LinearLayout, OnEventView, ScrollView, TextView, ViewRef};
+
+use crate::book::Book;
+use crate::dict::DictionaryBook;
+use crate::terminal::{TerminalContext, TEXT_VIEW_NAME, update_status};
+use crate::terminal::view::ReadingView;
+
+const DICT_INPUT_NAME: &str = "dict_input";
+const DICT_DEFINITION_NAME: &str = "dict_definition";
+const NO_DEFINITION: &str = "No definition in dictionary";
+const NO_DICTIONARY: &str = "No dictionary configured";
+
+/// dictionary book with looked up words for back/forward
+pub(super) struct DictionaryLookup {
+	db: DictionaryBook,
+	words: Vec<String>,
+	current_index: Option<usize>,
+	definition: String,
+}
+
+impl DictionaryLookup {
+	#[inline]
+	fn new(db: DictionaryBook) -> Self
+	{
+		DictionaryLookup { db, words: vec![], current_index: None, definition: String::new() }
+	}
+
+	fn push_word(&mut self, word: String) -> Option<(String, String)>
+	{
+		let current_index = if let Some(mut current_index) = self.current_index {
+			if word != self.words[current_index] {
+				current_index += 1;
+				self.words.drain(current_index..);
+				self.words.push(word);
+			}
+			current_index
+		} else {
+			self.words.push(word);
+			0
+		};
+		self.lookup(current_index)
+	}
+
+	fn switch_word(&mut self, forward: bool) -> Option<(String, String)>
+	{
+		let current_index = self.current_index?;
+		let new_index = if forward {
+			if current_index + 1 < self.words.len() {
+				current_index + 1
+			} else {
+				return None;
+			}
+		} else {
+			current_index.checked_sub(1)?
+		};
+		self.lookup(new_index)
+	}
+
+	fn lookup(&mut self, index: usize) -> Option<(String, String)>
+	{
+		let word = self.words.get(index)?;
+		self.db.lookup(word, NO_DEFINITION);
+		self.definition = self.db.lines()
+			.iter()
+			.map(|line| line.to_string())
+			.collect::<Vec<_>>()
+			.join("\n");
+		self.current_index = Some(index);
+		Some((word.clone(), self.definition.clone()))
+	}
+
+	#[inline]
+	fn current(&self) -> Option<(String, String)>
+	{
+		let word = self.words.get(self.current_index?)?;
+		Some((word.clone(), self.definition.clone()))
+	}
+}
+
+/// open dictionary panel, dictionaries are loaded at first use
+pub(super) fn lookup_dictionary(s: &mut Cursive)
+{
+	let reading_view: ViewRef<ReadingView> = s.find_name(TEXT_VIEW_NAME).unwrap();
+	let search_pattern = reading_view.search_pattern().to_owned();
+	drop(reading_view);
+	let current = s.with_user_data(|context: &mut TerminalContext| {
+		if context.dictionary.is_none() {
+			let (dictionaries, cache_dict) = context.configuration.dictionaries();
+			if !dictionaries.iter().any(|config| config.enabled) {
+				return None;
+			}
+			let db = DictionaryBook::load(dictionaries, cache_dict);
+			context.dictionary = Some(DictionaryLookup::new(db));
+		}
+		let dictionary = context.dictionary.as_ref()?;
+		Some(dictionary.current().unwrap_or((search_pattern, String::new())))
+	}).flatten();
+	let (word, definition) = match current {
+		Some(current) => current,
+		None => {
+			update_status(s, NO_DICTIONARY);
+			return;
+		}
+	};
+
+	let input_view = EditView::new()
+		.content(word)
+		.on_submit(|s, word| {
+			let word = word.trim();
+			if !word.is_empty() {
+				let word = word.to_owned();
+				show_definition(s, |dictionary| dictionary.push_word(word));
+			}
+		})
+		.with_name(DICT_INPUT_NAME);
+	let definition_view = TextView::new(definition)
+		.scrollable()
+		.with_name(DICT_DEFINITION_NAME)
+		.min_size((60, 20));
+	let dialog = Dialog::around(LinearLayout::vertical()
+		.child(input_view)
+		.child(definition_view))
+		.title("Dictionary")
+		.button("Back", |s| show_definition(s, |dictionary| dictionary.switch_word(false)))
+		.button("Forward", |s| show_definition(s, |dictionary| dictionary.switch_word(true)))
+		.dismiss_button("Close");
+	let dialog = OnEventView::new(dialog)
+		.on_event(Key::Esc, |s| { s.pop_layer(); })
+		.on_event(Event::Alt(Key::Left), |s| show_definition(s, |dictionary| dictionary.switch_word(false)))
+		.on_event(Event::Alt(Key::Right), |s| show_definition(s, |dictionary| dictionary.switch_word(true)));
+	s.add_layer(dialog);
+	s.focus_name(DICT_INPUT_NAME).unwrap();
+}
+
+fn show_definition<F>(s: &mut Cursive, f: F)
+	where F: FnOnce(&mut DictionaryLookup) -> Option<(String, String)>
+{
+	let result = s.with_user_data(|context: &mut TerminalContext|
+		f(context.dictionary.as_mut()?))
+		.flatten();
+	if let Some((word, definition)) = result {
+		s.call_on_name(DICT_INPUT_NAME, |view: &mut EditView| {
+			view.set_content(word);
+		});
+		s.call_on_name(DICT_DEFINITION_NAME, |view: &mut ScrollView<TextView>| {
+			view.get_inner_mut().set_content(definition);
+			view.scroll_to_top();
+		});
+	}
+}
@@ -1,14 +1,17 @@
+use std::cell::Cell;
+use std::rc::Rc;
 use anyhow::Result;
 use cursive::{Printer, Vec2, View, XY};
 use cursive::event::{Event, EventResult, Key, MouseButton, MouseEvent};
 use cursive::theme::{ColorStyle, PaletteColor};
 
 
-use crate::book::{Book, Line};
+use crate::book::{Book, Line, SearchOptions};
 use crate::common::{char_width, Position};
-use crate::config::{BookLoadingInfo, ReadingInfo};
+use crate::config::{BookLoadingInfo, ReadingInfo, ReadingSpeed};
 use crate::container::Container;
-use crate::controller::{Controller, HighlightInfo, HighlightMode, Render};
+use crate::controller::{Controller, HighlightInfo, HighlightMode, Render, StartTarget};
+use crate::html_parser::ContentOptions;
 use crate::terminal::update_status_callback;
 use crate::terminal::view::han::Han;
 use crate::terminal::view::xi::Xi;
@@ -16,9 +19,15 @@ use crate::terminal::view::xi::Xi;
 mod han;
 mod xi;
 
+const PROGRESS_PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
 pub struct ReadingView {
 	controller: Controller<RenderContext, dyn TerminalRender>,
 	render_context: RenderContext,
+	// shared with progress view, updated when drawing
+	progress: Rc<Cell<f64>>,
+	// global setting, for books without their own
+	render_han: bool,
 
 	search_color: ColorStyle,
 	link_color: ColorStyle,
@@ -137,8 +146,38 @@ fn load_render(render_han: bool) -> Box<dyn TerminalRender> {
 	}
 }
 
+/// bar of whole book reading progress with percentage
+pub struct ProgressView {
+	progress: Rc<Cell<f64>>,
+}
+
+impl View for ProgressView {
+	fn draw(&self, printer: &Printer) {
+		let progress = self.progress.get();
+		let label = format!(" {:>3}%", (progress * 100.0).round() as usize);
+		let width = printer.size.x.saturating_sub(label.len());
+		let eighths = (progress * (width * 8) as f64) as usize;
+		let mut bar = String::with_capacity(width * 3);
+		for idx in 0..width {
+			let filled = eighths.saturating_sub(idx * 8).min(8);
+			bar.push(match filled {
+				0 => '░',
+				8 => '█',
+				_ => PROGRESS_PARTIAL_BLOCKS[filled - 1],
+			});
+		}
+		printer.print((0, 0), &bar);
+		printer.print((width, 0), &label);
+	}
+
+	fn required_size(&mut self, constraint: Vec2) -> Vec2 {
+		Vec2::new(constraint.x, 1)
+	}
+}
+
 impl View for ReadingView {
 	fn draw(&self, printer: &Printer) {
+		self.progress.set(self.controller.progress());
 		let context = &self.render_context;
 		let mut xy = XY { x: 0, y: 0 };
 		let mut tmp = [0u8; 4];
@@ -184,18 +223,31 @@ impl View for ReadingView {
 }
 
 impl ReadingView {
-	pub(crate) fn new(render_han: bool, reading: BookLoadingInfo) -> Result<ReadingView> {
-		let render: Box<dyn TerminalRender> = load_render(render_han);
+	pub(crate) fn new(render_han: bool, reading: BookLoadingInfo,
+		reading_speed: ReadingSpeed, chars_per_page: usize,
+		content_options: ContentOptions) -> Result<ReadingView> {
+		let book_render_han = match &reading {
+			BookLoadingInfo::ChangeInnerBook(reading)
+			| BookLoadingInfo::History(reading)
+			| BookLoadingInfo::Reload(reading) => reading.render_han(render_han),
+			BookLoadingInfo::NewReading(..) => render_han,
+		};
+		let render: Box<dyn TerminalRender> = load_render(book_render_han);
 		let mut render_context = RenderContext::new();
-		let controller = Controller::new(
+		let mut controller = Controller::new(
 			reading,
+			content_options,
 			render,
 			&mut render_context)?;
+		controller.reading_speed = reading_speed;
+		controller.set_chars_per_page(chars_per_page);
 		let link_color = ColorStyle::new(ColorStyle::secondary().front, PaletteColor::Background);
 		let highlight_link_color = ColorStyle::new(ColorStyle::secondary().front, ColorStyle::highlight().back);
 		Ok(ReadingView {
 			controller,
 			render_context,
+			progress: Rc::new(Cell::new(0.0)),
+			render_han,
 
 			search_color: ColorStyle::highlight(),
 			link_color,
@@ -210,6 +262,12 @@ impl ReadingView {
 		self.controller.reading_info().clone()
 	}
 
+	#[inline]
+	pub fn progress_view(&self) -> ProgressView
+	{
+		ProgressView { progress: self.progress.clone() }
+	}
+
 	#[inline]
 	pub fn status_msg(&self) -> String
 	{
@@ -244,7 +302,19 @@ impl ReadingView {
 	#[inline]
 	pub fn switch_container(&mut self, loading: BookLoadingInfo) -> Result<String>
 	{
-		self.controller.switch_container(loading, &mut self.render_context)
+		let was_han = self.controller.reading.render_han(self.render_han);
+		let status = self.controller.switch_container(loading, &mut self.render_context)?;
+		let render_han = self.controller.reading.render_han(self.render_han);
+		if render_han != was_han {
+			self.load_render(render_han);
+		}
+		Ok(status)
+	}
+
+	#[inline]
+	pub fn goto_target(&mut self, target: &StartTarget) -> Result<()>
+	{
+		self.controller.goto_target(target, &mut self.render_context)
 	}
 
 	#[inline]
@@ -253,10 +323,30 @@ impl ReadingView {
 		self.controller.goto_line(line, &mut self.render_context)
 	}
 
+	#[inline]
+	pub fn goto(&mut self, inner_book: usize, chapter: usize, line: usize,
+		position: usize) -> Result<String>
+	{
+		self.controller.goto(inner_book, chapter, line, position, None,
+			&mut self.render_context)
+	}
+
+	#[inline]
+	pub fn goto_chapter_index(&mut self, chapter_no: usize, by_toc: bool) -> Result<String>
+	{
+		self.controller.goto_chapter_index(chapter_no, by_toc, &mut self.render_context)
+	}
+
+	#[inline]
+	pub fn goto_page(&mut self, label: &str) -> Result<String>
+	{
+		self.controller.goto_page(label, &mut self.render_context)
+	}
+
 	#[inline]
 	pub fn search(&mut self, pattern: &str) -> Result<()>
 	{
-		self.controller.search(pattern, &mut self.render_context)
+		self.controller.search(pattern, &SearchOptions::default(), &mut self.render_context)
 	}
 
 	#[inline]
@@ -271,7 +361,14 @@ impl ReadingView {
 		self.controller.goto_toc(toc_index, &mut self.render_context)
 	}
 
-	pub(crate) fn switch_render(&mut self, render_han: bool) {
+	/// toggle render for current book only
+	pub(crate) fn switch_render(&mut self) {
+		let render_han = !self.controller.reading.render_han(self.render_han);
+		self.controller.reading.render_han = Some(render_han);
+		self.load_render(render_han);
+	}
+
+	fn load_render(&mut self, render_han: bool) {
 		self.controller.render = load_render(render_han);
 		self.controller.render.resized(&self.render_context);
 		self.controller.redraw(&mut self.render_context);
@@ -94,9 +94,9 @@ impl TerminalRender for Han
 }
 
 impl Render<RenderContext> for Han {
-	fn book_loaded(&mut self, book: &dyn Book, _reading: &ReadingInfo, context: &mut RenderContext)
+	fn book_loaded(&mut self, book: &dyn Book, reading: &ReadingInfo, context: &mut RenderContext)
 	{
-		context.leading_space = book.leading_space();
+		context.leading_space = reading.leading_chars(None, book.leading_space());
 	}
 
 	fn redraw(&mut self, _book: &dyn Book, lines: &Vec<Line>, new_line: usize,
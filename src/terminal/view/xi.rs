@@ -1,4 +1,4 @@
-use crate::book::{Book, Line};
+use crate::book::{Book, Line, ZERO_WIDTH_SPACE};
 use crate::common::{char_width, with_leading};
 use crate::config::ReadingInfo;
 use crate::controller::HighlightInfo;
@@ -11,9 +11,9 @@ pub struct Xi {}
 impl TerminalRender for Xi {}
 
 impl Render<RenderContext> for Xi {
-	fn book_loaded(&mut self, book: &dyn Book, _reading: &ReadingInfo, context: &mut RenderContext)
+	fn book_loaded(&mut self, book: &dyn Book, reading: &ReadingInfo, context: &mut RenderContext)
 	{
-		context.leading_space = book.leading_space();
+		context.leading_space = reading.leading_chars(None, book.leading_space());
 	}
 
 	fn redraw(&mut self, _book: &dyn Book, lines: &Vec<Line>, line: usize,
@@ -246,6 +246,9 @@ impl Xi
 					None => DrawChar::new(*char, DrawCharMode::Plain),
 				};
 				print_line.push(dc);
+				if *char == ZERO_WIDTH_SPACE {
+					break_position = Some(position + 1);
+				}
 			}
 			position += 1;
 		}
@@ -266,7 +269,7 @@ impl Xi
 
 #[cfg(test)]
 mod tests {
-	use crate::book::{Book, Line};
+	use crate::book::{Book, Line, ZERO_WIDTH_SPACE};
 	use crate::terminal::view::{DrawChar, DrawCharMode, Render, RenderContext};
 	use crate::terminal::view::xi::{fill_print_line, Xi};
 
@@ -436,4 +439,26 @@ mod tests {
 			assert_eq!(line.len(), result_line.len());
 		}
 	}
+
+	#[test]
+	fn test_wrap_at_zero_width_space() {
+		let text = format!("{}{}{}", "a".repeat(70), ZERO_WIDTH_SPACE, "b".repeat(20));
+		let lines = vec![Line::new(&text)];
+		let mut context = RenderContext {
+			width: TEST_WIDTH,
+			height: 5,
+			print_lines: vec![],
+			leading_space: 0,
+		};
+		let book: Box<dyn Book> = Box::new(DummyBook { lines });
+		let mut xi = Xi {};
+		xi.redraw(book.as_ref(), &book.lines(), 0, 0, &None, &mut context);
+
+		// break after the zero width space, not in the middle of b
+		assert_eq!(context.print_lines[0][70].char, ZERO_WIDTH_SPACE);
+		assert_eq!(context.print_lines[0][71].char, ' ');
+		assert_eq!(context.print_lines[1][0].char, 'b');
+		assert_eq!(context.print_lines[1][19].char, 'b');
+		assert_eq!(context.print_lines[1][20].char, ' ');
+	}
 }